@@ -0,0 +1,286 @@
+//! `db`: whole-database operations
+//! across configured backends.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use clap::Subcommand;
+use pulsewire_core::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig
+};
+use pulsewire_core::infra::db_copy::{
+  self,
+  Store
+};
+use pulsewire_core::infra::{
+  backup,
+  database
+};
+
+#[derive(Subcommand)]
+
+pub enum DbCommand {
+  /// Copy all fetcher and server
+  /// tables into an empty database,
+  /// keeping ids. Stop the fetcher and
+  /// server on both sides first.
+  Copy {
+    /// Config of the database to read.
+    #[arg(long)]
+    from:       PathBuf,
+    /// Config of the database to
+    /// write; migrated before copying.
+    #[arg(long)]
+    to:         PathBuf,
+    /// Rows per batch.
+    #[arg(
+      long,
+      default_value_t = 1000
+    )]
+    batch_size: usize
+  },
+  /// Back up the configured database
+  /// into a directory: a snapshot for
+  /// SQLite, a logical export of the
+  /// fetcher and server tables for
+  /// Postgres, plus a manifest.
+  Backup {
+    /// Path to config.toml.
+    config_path: PathBuf,
+    /// Directory to create; must be
+    /// missing or empty.
+    #[arg(long)]
+    out:         PathBuf,
+    /// Gzip the data files.
+    #[arg(long)]
+    compress:    bool
+  },
+  /// Restore a backup directory into
+  /// the configured database after
+  /// checking its manifest. Stop the
+  /// fetcher and server first.
+  Restore {
+    /// Path to config.toml.
+    config_path: PathBuf,
+    /// Backup directory.
+    #[arg(long)]
+    from:        PathBuf,
+    /// Overwrite an existing SQLite
+    /// database file.
+    #[arg(long)]
+    replace:     bool
+  }
+}
+
+pub async fn run(
+  command: DbCommand
+) -> Result<(), String> {
+  match command {
+    | DbCommand::Copy {
+      from,
+      to,
+      batch_size
+    } => {
+      copy(from, to, batch_size).await
+    }
+    | DbCommand::Backup {
+      config_path,
+      out,
+      compress
+    } => {
+      let app =
+        load(&config_path).await?;
+
+      let manifest = backup::backup(
+        &app, &out, compress, progress
+      )
+      .await?;
+
+      report(&manifest);
+
+      println!(
+        "ok: backed up schema version \
+         {} to {}",
+        manifest.schema_version,
+        out.display()
+      );
+
+      Ok(())
+    }
+    | DbCommand::Restore {
+      config_path,
+      from,
+      replace
+    } => {
+      let app =
+        load(&config_path).await?;
+
+      let manifest = backup::restore(
+        &app, &from, replace, progress
+      )
+      .await?;
+
+      report(&manifest);
+
+      println!(
+        "ok: restored {} into {}; row \
+         counts verified",
+        from.display(),
+        config_path.display()
+      );
+
+      Ok(())
+    }
+  }
+}
+
+fn progress(
+  table: &str,
+  rows: u64
+) {
+  eprint!(
+    "\r{:<48}",
+    format!("{table}: {rows} rows")
+  );
+}
+
+fn report(
+  manifest: &backup::BackupManifest
+) {
+  eprintln!();
+
+  for table in &manifest.tables {
+    println!(
+      "{:<24}  {:>10}",
+      table.name, table.rows
+    );
+  }
+}
+
+async fn load(
+  path: &Path
+) -> Result<AppConfig, String> {
+  let LoadedConfig {
+    app, ..
+  } = ConfigLoader::load(path)
+    .await
+    .map_err(|e| e.to_string())?;
+
+  Ok(app)
+}
+
+fn same_database(
+  a: &AppConfig,
+  b: &AppConfig
+) -> bool {
+  match (a.db_dialect, b.db_dialect) {
+    | (
+      SqlDialect::Sqlite,
+      SqlDialect::Sqlite
+    ) => {
+      std::path::absolute(
+        &a.sqlite_path
+      )
+      .ok()
+        == std::path::absolute(
+          &b.sqlite_path
+        )
+        .ok()
+    }
+    | (
+      SqlDialect::Postgres,
+      SqlDialect::Postgres
+    ) => {
+      a.postgres.host == b.postgres.host
+        && a.postgres.port
+          == b.postgres.port
+        && a.postgres.database
+          == b.postgres.database
+        && a.postgres.schema
+          == b.postgres.schema
+    }
+    | _ => false
+  }
+}
+
+async fn copy(
+  from: PathBuf,
+  to: PathBuf,
+  batch_size: usize
+) -> Result<(), String> {
+  let source = load(&from).await?;
+  let target = load(&to).await?;
+
+  if same_database(&source, &target) {
+    return Err(
+      "--from and --to point at the \
+       same database"
+        .to_string()
+    );
+  }
+
+  // Both sides must be on this
+  // binary's schema for the column
+  // mapping to hold; the source is
+  // never migrated implicitly.
+  let status = database::create_repo(
+    source.db_dialect,
+    &source
+  )
+  .await?
+  .schema_status()
+  .await?;
+
+  status.check()?;
+
+  if status.pending().next().is_some() {
+    return Err(format!(
+      "source schema has pending \
+       migrations; run `migrate up \
+       {}` first",
+      from.display()
+    ));
+  }
+
+  database::create_repo(
+    target.db_dialect,
+    &target
+  )
+  .await?
+  .migrate(
+    &target.timezone,
+    target.default_poll_seconds
+  )
+  .await?;
+
+  let report = db_copy::copy(
+    &Store::open(&source).await?,
+    &Store::open(&target).await?,
+    batch_size.max(1),
+    progress
+  )
+  .await?;
+
+  eprintln!();
+
+  for (table, rows) in &report.tables {
+    println!("{table:<24}  {rows:>10}");
+  }
+
+  println!(
+    "ok: copied {} tables from {} to \
+     {}; row counts verified",
+    report.tables.len(),
+    from.display(),
+    to.display()
+  );
+
+  Ok(())
+}
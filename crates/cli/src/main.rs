@@ -1,13 +1,33 @@
+mod db;
+mod migrate;
+mod refresh;
+mod simulate;
+
 use std::path::PathBuf;
 
 use clap::{
   Parser,
   Subcommand
 };
+use pulsewire_core::app::health::{
+  day_key,
+  local_day,
+  rollup_day
+};
+use pulsewire_core::app::reparse::reparse;
 use pulsewire_core::infra::config::{
   ConfigLoader,
   LoadedConfig
 };
+use pulsewire_core::infra::database;
+use pulsewire_core::infra::time::parse_time_arg;
+use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::ports::clock::Clock;
+use pulsewire_core::ports::repo::{
+  HealthQuery,
+  HealthScope,
+  PayloadFilter
+};
 
 #[derive(Parser)]
 #[command(
@@ -45,7 +65,116 @@ enum Command {
     /// actions.
     #[arg(long)]
     confirm:     bool
-  }
+  },
+  /// Replay archived payload bodies
+  /// through the current parser and
+  /// upsert their items.
+  Reparse {
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>,
+    /// Only payloads of this feed id.
+    #[arg(long)]
+    feed:        Option<String>,
+    /// Only payloads of feeds in this
+    /// category.
+    #[arg(long)]
+    category:    Option<String>,
+    /// Fetched at or after (RFC 3339,
+    /// YYYY-MM-DD or epoch millis).
+    #[arg(long)]
+    since:       Option<String>,
+    /// Fetched before (RFC 3339,
+    /// YYYY-MM-DD or epoch millis).
+    #[arg(long)]
+    until:       Option<String>,
+    /// Report what would change
+    /// without writing.
+    #[arg(long)]
+    dry_run:     bool
+  },
+  /// Show daily feed/domain health
+  /// rollups, newest first.
+  Health {
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>,
+    /// `feed` or `domain`.
+    #[arg(
+      long,
+      default_value = "feed"
+    )]
+    scope:       String,
+    /// Only this feed id or domain.
+    #[arg(long)]
+    subject:     Option<String>,
+    /// First day (RFC 3339,
+    /// YYYY-MM-DD or epoch millis).
+    #[arg(long)]
+    since:       Option<String>,
+    /// Last day, inclusive.
+    #[arg(long)]
+    until:       Option<String>,
+    #[arg(long, default_value_t = 50)]
+    limit:       i64,
+    /// Recompute the rollups for the
+    /// selected days before printing.
+    #[arg(long)]
+    refresh:     bool
+  },
+  /// Ask the running fetcher to GET a
+  /// feed now.
+  Refresh {
+    /// Feed id to fetch.
+    feed_id:     String,
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>,
+    /// Wait up to this many seconds
+    /// for the outcome.
+    #[arg(
+      long,
+      value_name = "SECONDS"
+    )]
+    wait:        Option<u64>
+  },
+  /// Show or apply database schema
+  /// migrations.
+  Migrate {
+    /// `status` or `up`.
+    #[arg(value_enum)]
+    action:      migrate::Action,
+    /// `fetcher` or `server`; the
+    /// server applies its own, so only
+    /// `status` works for it.
+    #[arg(
+      long,
+      value_enum,
+      default_value = "fetcher"
+    )]
+    target:      migrate::Target,
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>
+  },
+  /// Whole-database operations.
+  Db {
+    #[command(subcommand)]
+    command: db::DbCommand
+  },
+  /// Run the scheduler over virtual
+  /// days against a scripted origin and
+  /// report requests, detection latency
+  /// and per-domain load.
+  Simulate(simulate::SimulateArgs)
 }
 
 #[tokio::main]
@@ -119,11 +248,319 @@ async fn main() -> Result<(), String> {
         "ok: cleaned local artifacts"
       );
     }
+    | Command::Reparse {
+      config_path,
+      feed,
+      category,
+      since,
+      until,
+      dry_run
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let LoadedConfig {
+        app, ..
+      } = ConfigLoader::load(&cfg_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+      let filter = PayloadFilter {
+        feed_id: feed,
+        category,
+        since_ms: since
+          .map(|s| {
+            parse_time_arg(
+              &s,
+              &app.timezone
+            )
+          })
+          .transpose()?,
+        until_ms: until
+          .map(|s| {
+            parse_time_arg(
+              &s,
+              &app.timezone
+            )
+          })
+          .transpose()?
+      };
+
+      let repo = database::create_repo(
+        app.db_dialect,
+        &app
+      )
+      .await?;
+
+      repo
+        .migrate(
+          &app.timezone,
+          app.default_poll_seconds
+        )
+        .await?;
+
+      let report = reparse(
+        &app,
+        repo.as_ref(),
+        &filter,
+        dry_run
+      )
+      .await?;
+
+      let verb = if dry_run {
+        "would change"
+      } else {
+        "changed"
+      };
+
+      for c in &report.changes {
+        println!(
+          "payload {} ({}): {verb} \
+           +{} inserted, ~{} updated",
+          c.payload_id,
+          c.feed_id,
+          c.inserted,
+          c.updated
+        );
+      }
+
+      println!(
+        "ok: {} payloads, {} \
+         inserted, {} updated, {} \
+         unchanged, {} missing \
+         bodies, {} parse errors{}",
+        report.payloads,
+        report.inserted,
+        report.updated,
+        report.unchanged,
+        report.missing_bodies,
+        report.parse_errors,
+        if dry_run {
+          " (dry run)"
+        } else {
+          ""
+        }
+      );
+    }
+    | Command::Health {
+      config_path,
+      scope,
+      subject,
+      since,
+      until,
+      limit,
+      refresh
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let LoadedConfig {
+        app, ..
+      } = ConfigLoader::load(&cfg_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+      let to_day = |raw: String| {
+        parse_time_arg(
+          &raw,
+          &app.timezone
+        )
+        .map(|ms| {
+          local_day(ms, &app.timezone)
+        })
+      };
+
+      let since = since
+        .map(to_day)
+        .transpose()?;
+
+      let until = until
+        .map(to_day)
+        .transpose()?;
+
+      let repo = database::create_repo(
+        app.db_dialect,
+        &app
+      )
+      .await?;
+
+      repo
+        .migrate(
+          &app.timezone,
+          app.default_poll_seconds
+        )
+        .await?;
+
+      if refresh {
+        let now_ms = SystemClock
+          .now_epoch_ms()
+          .await;
+
+        let today = local_day(
+          now_ms,
+          &app.timezone
+        );
+
+        let last =
+          until.unwrap_or(today);
+
+        let mut day =
+          since.unwrap_or(last);
+
+        while day <= last {
+          rollup_day(
+            &app,
+            repo.as_ref(),
+            day,
+            now_ms
+          )
+          .await?;
+
+          let Some(next) =
+            day.succ_opt()
+          else {
+            break;
+          };
+
+          day = next;
+        }
+      }
+
+      let rows = repo
+        .health_rollups(&HealthQuery {
+          scope: HealthScope::parse(
+            &scope
+          )?,
+          subject,
+          since_day: since.map(day_key),
+          until_day: until.map(day_key),
+          limit
+        })
+        .await?;
+
+      println!(
+        "{:<10}  {:<24}  {:>7}  {:>7}  \
+         {:>7}  {:>7}  {:>7}  {:>7}  \
+         errors",
+        "day",
+        scope,
+        "fetches",
+        "ok",
+        "p50_ms",
+        "p95_ms",
+        "changes",
+        "items"
+      );
+
+      for r in &rows {
+        let errors = r
+          .error_counts
+          .iter()
+          .map(|(k, v)| {
+            format!("{k}={v}")
+          })
+          .collect::<Vec<_>>()
+          .join(",");
+
+        println!(
+          "{:<10}  {:<24}  {:>7}  \
+           {:>7}  {:>7}  {:>7}  {:>7}  \
+           {:>7}  {}",
+          r.day,
+          r.subject,
+          r.fetches,
+          r.successes,
+          fmt_ms(r.p50_latency_ms),
+          fmt_ms(r.p95_latency_ms),
+          r.changes,
+          r.items,
+          if errors.is_empty() {
+            "-".to_string()
+          } else {
+            errors
+          }
+        );
+      }
+    }
+    | Command::Refresh {
+      feed_id,
+      config_path,
+      wait
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let LoadedConfig {
+        app, ..
+      } = ConfigLoader::load(&cfg_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+      let repo = database::create_repo(
+        app.db_dialect,
+        &app
+      )
+      .await?;
+
+      repo
+        .migrate(
+          &app.timezone,
+          app.default_poll_seconds
+        )
+        .await?;
+
+      refresh::run(
+        &app,
+        repo.as_ref(),
+        &feed_id,
+        wait
+      )
+      .await?;
+    }
+    | Command::Migrate {
+      action,
+      target,
+      config_path
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let LoadedConfig {
+        app, ..
+      } = ConfigLoader::load(&cfg_path)
+        .await
+        .map_err(|e| e.to_string())?;
+
+      let repo = database::create_repo(
+        app.db_dialect,
+        &app
+      )
+      .await?;
+
+      migrate::run(
+        &app,
+        repo.as_ref(),
+        action,
+        target
+      )
+      .await?;
+    }
+    | Command::Db {
+      command
+    } => db::run(command).await?,
+    | Command::Simulate(args) => {
+      simulate::run(args).await?
+    }
   }
 
   Ok(())
 }
 
+fn fmt_ms(ms: Option<i64>) -> String {
+  ms.map(|v| v.to_string())
+    .unwrap_or_else(|| "-".to_string())
+}
+
 fn pick_config_path(
   arg: Option<PathBuf>
 ) -> PathBuf {
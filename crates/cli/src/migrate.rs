@@ -0,0 +1,156 @@
+//! `migrate`: inspect or apply the
+//! fetcher database's schema
+//! migrations, or inspect the server's.
+
+use clap::ValueEnum;
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::database::server_schema_migrations;
+use pulsewire_core::infra::time::format_epoch_ms;
+use pulsewire_core::ports::repo::{
+  Repo,
+  SchemaMigration
+};
+
+#[derive(Clone, Copy, ValueEnum)]
+
+pub enum Action {
+  /// List applied and pending
+  /// migrations.
+  Status,
+  /// Apply pending migrations.
+  Up
+}
+
+/// Whose migration table to read.
+#[derive(Clone, Copy, ValueEnum)]
+
+pub enum Target {
+  /// `schema_migrations`.
+  Fetcher,
+  /// `server_schema_migrations`.
+  Server
+}
+
+pub async fn run(
+  app: &AppConfig,
+  repo: &dyn Repo,
+  action: Action,
+  target: Target
+) -> Result<(), String> {
+  match (target, action) {
+    | (
+      Target::Fetcher,
+      Action::Status
+    ) => status(app, repo).await,
+    | (Target::Fetcher, Action::Up) => {
+      up(app, repo).await
+    }
+    | (
+      Target::Server,
+      Action::Status
+    ) => server_status(app).await,
+    | (Target::Server, Action::Up) => {
+      Err(
+        "server migrations ship with \
+         pulsewire-server, which \
+         applies them when it starts"
+          .to_string()
+      )
+    }
+  }
+}
+
+async fn status(
+  app: &AppConfig,
+  repo: &dyn Repo
+) -> Result<(), String> {
+  let status =
+    repo.schema_status().await?;
+
+  for m in &status.migrations {
+    print_row(app, m);
+  }
+
+  println!(
+    "schema version {} (binary \
+     supports {})",
+    status.current(),
+    status.latest
+  );
+
+  status.check()
+}
+
+async fn up(
+  app: &AppConfig,
+  repo: &dyn Repo
+) -> Result<(), String> {
+  let before =
+    repo.schema_status().await?;
+
+  before.check()?;
+
+  let pending =
+    before.pending().count();
+
+  repo
+    .migrate(
+      &app.timezone,
+      app.default_poll_seconds
+    )
+    .await?;
+
+  println!(
+    "ok: applied {pending} \
+     migrations, schema at version {}",
+    repo
+      .schema_status()
+      .await?
+      .current()
+  );
+
+  Ok(())
+}
+
+/// The server's pending steps are only
+/// known to its binary, so this lists
+/// what it has applied.
+async fn server_status(
+  app: &AppConfig
+) -> Result<(), String> {
+  let applied =
+    server_schema_migrations(app)
+      .await?;
+
+  for m in &applied {
+    print_row(app, m);
+  }
+
+  println!(
+    "server schema version {}",
+    applied
+      .last()
+      .map_or(0, |m| m.version)
+  );
+
+  Ok(())
+}
+
+fn print_row(
+  app: &AppConfig,
+  m: &SchemaMigration
+) {
+  let applied = m
+    .applied_at_ms
+    .map(|ms| {
+      format_epoch_ms(ms, &app.timezone)
+    })
+    .unwrap_or_else(|| {
+      "pending".to_string()
+    });
+
+  println!(
+    "{:>4}  {:<24}  {applied}",
+    m.version, m.name
+  );
+}
@@ -0,0 +1,123 @@
+//! `refresh`: queue an immediate fetch
+//! for a running fetcher to pick up.
+
+use std::time::Duration;
+
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::ports::clock::Clock;
+use pulsewire_core::ports::repo::{
+  FetchRequest,
+  FetchRequestStatus,
+  Repo
+};
+
+const POLL: Duration =
+  Duration::from_secs(1);
+
+/// Queues the request and, with
+/// `wait_seconds`, polls until the
+/// fetcher reports an outcome. A failed
+/// fetch or a timeout is an error.
+pub async fn run(
+  app: &AppConfig,
+  repo: &dyn Repo,
+  feed_id: &str,
+  wait_seconds: Option<u64>
+) -> Result<(), String> {
+  let now_ms =
+    SystemClock.now_epoch_ms().await;
+
+  let id = repo
+    .enqueue_fetch_request(
+      feed_id,
+      Some("cli"),
+      now_ms,
+      &app.timezone
+    )
+    .await?;
+
+  println!(
+    "ok: refresh request {id} queued \
+     for {feed_id}"
+  );
+
+  let Some(wait_seconds) = wait_seconds
+  else {
+    return Ok(());
+  };
+
+  let deadline =
+    tokio::time::Instant::now()
+      + Duration::from_secs(
+        wait_seconds
+      );
+
+  loop {
+    let request = repo
+      .fetch_request(id)
+      .await?
+      .ok_or_else(|| {
+        format!(
+          "refresh request {id} \
+           disappeared"
+        )
+      })?;
+
+    if request.status.is_finished() {
+      print_outcome(&request);
+
+      return match request.status {
+        | FetchRequestStatus::Done => {
+          Ok(())
+        }
+        | _ => {
+          Err(format!(
+            "refresh of {feed_id} \
+             failed"
+          ))
+        }
+      };
+    }
+
+    if tokio::time::Instant::now()
+      >= deadline
+    {
+      return Err(format!(
+        "refresh request {id} still \
+         {} after {wait_seconds}s; is \
+         the fetcher running?",
+        request.status.as_str()
+      ));
+    }
+
+    tokio::time::sleep(POLL).await;
+  }
+}
+
+fn print_outcome(
+  request: &FetchRequest
+) {
+  let took_ms = request
+    .finished_at_ms
+    .zip(request.started_at_ms)
+    .map(|(f, s)| f - s);
+
+  println!(
+    "{}: status={} http={} took_ms={} \
+     error={}",
+    request.feed_id,
+    request.status.as_str(),
+    request
+      .http_status
+      .map(|s| s.to_string())
+      .unwrap_or_else(|| "-".into()),
+    took_ms
+      .map(|ms| ms.to_string())
+      .unwrap_or_else(|| "-".into()),
+    request
+      .error
+      .as_deref()
+      .unwrap_or("-")
+  );
+}
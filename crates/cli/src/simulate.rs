@@ -0,0 +1,316 @@
+//! `simulate`: run the scheduler over
+//! virtual days against a scripted
+//! origin and report the load and
+//! detection latency it would produce.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use pulsewire_core::app::health::{
+  day_bounds,
+  local_day
+};
+use pulsewire_core::app::simulate::{
+  self,
+  Latency,
+  Scenario,
+  SimReport,
+  SimulationPlan
+};
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig
+};
+use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::infra::time::{
+  format_epoch_ms,
+  parse_time_arg
+};
+use pulsewire_core::ports::clock::Clock;
+
+#[derive(Args)]
+
+pub struct SimulateArgs {
+  /// Path to config.toml (defaults to
+  /// CONFIG_PATH or
+  /// crates/fetcher/res/config.toml).
+  config_path: Option<PathBuf>,
+  /// Scenario TOML with change rates,
+  /// error rates and latencies
+  /// (`[defaults]`,
+  /// `[domains.<name>]`,
+  /// `[feeds.<id>]`).
+  #[arg(long)]
+  scenario:    Option<PathBuf>,
+  /// Virtual days to run.
+  #[arg(long, default_value_t = 7)]
+  days:        u64,
+  /// Seed for jitter and the scripted
+  /// origin.
+  #[arg(long, default_value_t = 1)]
+  seed:        u64,
+  /// Virtual start (RFC 3339,
+  /// YYYY-MM-DD or epoch millis);
+  /// defaults to local midnight today.
+  #[arg(long)]
+  start:       Option<String>,
+  /// Only feeds in this category.
+  #[arg(long)]
+  category:    Option<String>,
+  /// Print only the domain table and
+  /// totals.
+  #[arg(long)]
+  summary:     bool
+}
+
+pub async fn run(
+  args: SimulateArgs
+) -> Result<(), String> {
+  let cfg_path =
+    super::pick_config_path(
+      args.config_path
+    );
+
+  let LoadedConfig {
+    app,
+    feeds,
+    categories
+  } = ConfigLoader::load(&cfg_path)
+    .await
+    .map_err(|e| e.to_string())?;
+
+  let scenario = match &args.scenario {
+    | Some(path) => {
+      Scenario::load(path)?
+    }
+    | None => Scenario::default()
+  };
+
+  let start_ms = match &args.start {
+    | Some(raw) => {
+      parse_time_arg(
+        raw,
+        &app.timezone
+      )?
+    }
+    | None => {
+      let now_ms = SystemClock
+        .now_epoch_ms()
+        .await;
+
+      day_bounds(
+        local_day(
+          now_ms,
+          &app.timezone
+        ),
+        &app.timezone
+      )
+      .0
+    }
+  };
+
+  let keep = |category: &String| {
+    args
+      .category
+      .as_ref()
+      .is_none_or(|c| c == category)
+  };
+
+  let categories = categories
+    .into_iter()
+    .map(|c| c.name)
+    .filter(|name| keep(name))
+    .collect::<Vec<_>>();
+
+  let feeds = feeds
+    .into_iter()
+    .filter(|f| keep(&f.category))
+    .collect::<Vec<_>>();
+
+  if feeds.is_empty() {
+    return Err(
+      "no feeds to simulate"
+        .to_string()
+    );
+  }
+
+  let plan = SimulationPlan {
+    start_ms,
+    days: args.days,
+    seed: args.seed
+  };
+
+  let zone = app.timezone;
+
+  let report =
+    tokio::task::spawn_blocking(
+      move || {
+        simulate::run(
+          app, categories, feeds,
+          &scenario, plan
+        )
+      }
+    )
+    .await
+    .map_err(|e| {
+      format!(
+        "simulation task failed: {e}"
+      )
+    })??;
+
+  println!(
+    "simulated {} to {} (seed {})",
+    format_epoch_ms(
+      report.start_ms,
+      &zone
+    ),
+    format_epoch_ms(
+      report.end_ms,
+      &zone
+    ),
+    report.seed
+  );
+
+  print_report(&report, args.summary);
+
+  Ok(())
+}
+
+fn print_report(
+  report: &SimReport,
+  summary: bool
+) {
+  if !summary {
+    println!(
+      "{:<24}  {:<24}  {:>6}  {:>6}  \
+       {:>6}  {:>6}  {:>7}  {:>8}  \
+       {:>8}  {:>8}  {:>8}",
+      "feed",
+      "domain",
+      "head",
+      "get",
+      "304",
+      "errors",
+      "changes",
+      "detected",
+      "p50",
+      "p95",
+      "max"
+    );
+
+    for f in &report.feeds {
+      println!(
+        "{:<24}  {:<24}  {:>6}  {:>6}  \
+         {:>6}  {:>6}  {:>7}  {:>8}  \
+         {:>8}  {:>8}  {:>8}{}",
+        f.feed_id,
+        f.domain,
+        f.heads,
+        f.gets,
+        f.not_modified,
+        f.errors,
+        f.changes,
+        f.detected,
+        fmt_span(f.detection.p50_ms),
+        fmt_span(f.detection.p95_ms),
+        fmt_span(f.detection.max_ms),
+        if f.parked {
+          "  parked"
+        } else {
+          ""
+        }
+      );
+    }
+
+    println!();
+  }
+
+  println!(
+    "{:<24}  {:>8}  {:>8}  {:>9}  \
+     {:>9}",
+    "domain",
+    "requests",
+    "per_day",
+    "peak_hour",
+    "in_flight"
+  );
+
+  for d in &report.domains {
+    println!(
+      "{:<24}  {:>8}  {:>8.1}  {:>9}  \
+       {:>9}",
+      d.domain,
+      d.requests,
+      d.requests_per_day,
+      d.peak_hour,
+      d.peak_in_flight
+    );
+  }
+
+  let requests: u64 = report
+    .feeds
+    .iter()
+    .map(|f| f.requests())
+    .sum();
+
+  let changes: u64 = report
+    .feeds
+    .iter()
+    .map(|f| f.changes)
+    .sum();
+
+  let detected: u64 = report
+    .feeds
+    .iter()
+    .map(|f| f.detected)
+    .sum();
+
+  let parked = report
+    .feeds
+    .iter()
+    .filter(|f| f.parked)
+    .count();
+
+  let Latency {
+    p50_ms,
+    p95_ms,
+    max_ms
+  } = report.detection;
+
+  println!(
+    "ok: {requests} requests, \
+     {detected}/{changes} changes \
+     detected (p50 {}, p95 {}, max \
+     {}), {parked} feeds parked",
+    fmt_span(p50_ms),
+    fmt_span(p95_ms),
+    fmt_span(max_ms)
+  );
+}
+
+/// `45s`, `12m30s` or `3h05m`.
+fn fmt_span(ms: Option<i64>) -> String {
+  let Some(ms) = ms else {
+    return "-".to_string();
+  };
+
+  let secs = ms / 1000;
+
+  match secs {
+    | s if s < 60 => format!("{s}s"),
+    | s if s < 3600 => {
+      format!(
+        "{}m{:02}s",
+        s / 60,
+        s % 60
+      )
+    }
+    | s => {
+      format!(
+        "{}h{:02}m",
+        s / 3600,
+        s % 3600 / 60
+      )
+    }
+  }
+}
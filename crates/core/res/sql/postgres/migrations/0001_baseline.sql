@@ -10,14 +10,18 @@ CREATE TABLE IF NOT EXISTS feeds(
   category TEXT NOT NULL REFERENCES categories(name),
   base_poll_seconds BIGINT NOT NULL,
   tags TEXT[] NULL,
+  dataset TEXT NULL,
+  priority BIGINT NOT NULL DEFAULT 1,
   created_at TIMESTAMPTZ NOT NULL
 );
 
 ALTER TABLE feeds ADD COLUMN IF NOT EXISTS category TEXT;
 ALTER TABLE feeds ADD COLUMN IF NOT EXISTS tags TEXT[];
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS dataset TEXT;
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS priority BIGINT NOT NULL DEFAULT 1;
 
 CREATE TABLE IF NOT EXISTS feed_state_history(
-  id BIGSERIAL PRIMARY KEY,
+  id BIGSERIAL,
   feed_id TEXT NOT NULL REFERENCES feeds(id),
   recorded_at TIMESTAMPTZ NOT NULL,
   phase TEXT NOT NULL,
@@ -34,11 +38,13 @@ CREATE TABLE IF NOT EXISTS feed_state_history(
   next_action_at TIMESTAMPTZ NOT NULL,
   jitter_seconds BIGINT NOT NULL,
   note TEXT NULL,
-  consecutive_error_count BIGINT NOT NULL DEFAULT 0
-);
+  consecutive_error_count BIGINT NOT NULL DEFAULT 0,
+  get_only_since TIMESTAMPTZ NULL,
+  PRIMARY KEY (id, recorded_at)
+) PARTITION BY RANGE (recorded_at);
 
 CREATE TABLE IF NOT EXISTS fetch_events(
-  id BIGSERIAL PRIMARY KEY,
+  id BIGSERIAL,
   feed_id TEXT NOT NULL REFERENCES feeds(id),
   event_time TIMESTAMPTZ NOT NULL,
   method TEXT NOT NULL,
@@ -47,8 +53,9 @@ CREATE TABLE IF NOT EXISTS fetch_events(
   latency_ms BIGINT NULL,
   backoff_index BIGINT NOT NULL,
   scheduled_next_action_at TIMESTAMPTZ NOT NULL,
-  debug TEXT NULL
-);
+  debug TEXT NULL,
+  PRIMARY KEY (id, event_time)
+) PARTITION BY RANGE (event_time);
 
 CREATE TABLE IF NOT EXISTS feed_payloads(
   id BIGSERIAL PRIMARY KEY,
@@ -80,7 +87,8 @@ CREATE TABLE IF NOT EXISTS feed_state_current(
   next_action_at TIMESTAMPTZ NOT NULL,
   jitter_seconds BIGINT NOT NULL,
   note TEXT NULL,
-  consecutive_error_count BIGINT NOT NULL DEFAULT 0
+  consecutive_error_count BIGINT NOT NULL DEFAULT 0,
+  get_only_since TIMESTAMPTZ NULL
 );
 
 CREATE INDEX IF NOT EXISTS idx_feed_state_current_next_action
@@ -115,3 +123,104 @@ CREATE TABLE IF NOT EXISTS error_feeds(
 
 ALTER TABLE feed_state_current ADD COLUMN IF NOT EXISTS consecutive_error_count BIGINT NOT NULL DEFAULT 0;
 ALTER TABLE feed_state_history ADD COLUMN IF NOT EXISTS consecutive_error_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE feed_state_current ADD COLUMN IF NOT EXISTS get_only_since TIMESTAMPTZ;
+ALTER TABLE feed_state_history ADD COLUMN IF NOT EXISTS get_only_since TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS dataset_blobs(
+  content_hash TEXT PRIMARY KEY,
+  body BYTEA NOT NULL,
+  size_bytes BIGINT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dataset_versions(
+  id BIGSERIAL PRIMARY KEY,
+  feed_id TEXT NOT NULL REFERENCES feeds(id),
+  fetched_at TIMESTAMPTZ NOT NULL,
+  content_hash TEXT NOT NULL REFERENCES dataset_blobs(content_hash),
+  row_count BIGINT NOT NULL,
+  added_count BIGINT NOT NULL,
+  removed_count BIGINT NOT NULL,
+  changed_count BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_dataset_versions_feed
+ON dataset_versions(feed_id, fetched_at);
+
+CREATE TABLE IF NOT EXISTS payload_archive(
+  content_hash TEXT PRIMARY KEY,
+  encoding TEXT NOT NULL,
+  body BYTEA NULL,
+  location TEXT NULL,
+  raw_size BIGINT NOT NULL,
+  stored_size BIGINT NOT NULL,
+  first_seen_at TIMESTAMPTZ NOT NULL,
+  last_seen_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_payload_archive_last_seen
+ON payload_archive(last_seen_at);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_events_time
+ON fetch_events(event_time);
+
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_feed_time
+ON feed_payloads(feed_id, fetched_at);
+
+CREATE TABLE IF NOT EXISTS feed_health_daily(
+  day DATE NOT NULL,
+  scope TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  fetches BIGINT NOT NULL,
+  successes BIGINT NOT NULL,
+  error_counts JSONB NOT NULL,
+  p50_latency_ms BIGINT NULL,
+  p95_latency_ms BIGINT NULL,
+  changes BIGINT NOT NULL,
+  items BIGINT NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (day, scope, subject)
+);
+
+CREATE TABLE IF NOT EXISTS feed_leases(
+  feed_id TEXT PRIMARY KEY,
+  owner TEXT NULL,
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS domain_tokens(
+  domain TEXT NOT NULL,
+  slot INTEGER NOT NULL,
+  holder TEXT NULL,
+  expires_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (domain, slot)
+);
+
+CREATE INDEX IF NOT EXISTS idx_domain_tokens_holder
+ON domain_tokens(holder);
+
+CREATE TABLE IF NOT EXISTS fetch_requests(
+  id BIGSERIAL PRIMARY KEY,
+  feed_id TEXT NOT NULL,
+  requested_by TEXT NULL,
+  requested_at TIMESTAMPTZ NOT NULL,
+  status TEXT NOT NULL,
+  started_at TIMESTAMPTZ NULL,
+  finished_at TIMESTAMPTZ NULL,
+  http_status INTEGER NULL,
+  error TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_requests_status
+ON fetch_requests(status, id);
+
+CREATE TABLE IF NOT EXISTS paused_targets(
+  scope TEXT NOT NULL,
+  name TEXT NOT NULL,
+  reason TEXT NULL,
+  paused_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY(scope, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_events_feed
+ON fetch_events(feed_id, id);
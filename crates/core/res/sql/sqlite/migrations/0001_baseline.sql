@@ -10,6 +10,8 @@ CREATE TABLE IF NOT EXISTS feeds(
   category TEXT NOT NULL REFERENCES categories(name),
   base_poll_seconds INTEGER NOT NULL,
   tags TEXT NULL,
+  dataset TEXT NULL,
+  priority INTEGER NOT NULL DEFAULT 1,
   created_at_ms INTEGER NOT NULL
 );
 
@@ -31,7 +33,8 @@ CREATE TABLE IF NOT EXISTS feed_state_history(
   next_action_at_ms INTEGER NOT NULL,
   jitter_seconds INTEGER NOT NULL,
   note TEXT NULL,
-  consecutive_error_count INTEGER NOT NULL DEFAULT 0
+  consecutive_error_count INTEGER NOT NULL DEFAULT 0,
+  get_only_since_ms INTEGER NULL
 );
 
 CREATE TABLE IF NOT EXISTS fetch_events(
@@ -77,7 +80,8 @@ CREATE TABLE IF NOT EXISTS feed_state_current(
   next_action_at_ms INTEGER NOT NULL,
   jitter_seconds INTEGER NOT NULL,
   note TEXT NULL,
-  consecutive_error_count INTEGER NOT NULL DEFAULT 0
+  consecutive_error_count INTEGER NOT NULL DEFAULT 0,
+  get_only_since_ms INTEGER NULL
 );
 
 CREATE INDEX IF NOT EXISTS idx_feed_state_current_next_action
@@ -109,3 +113,85 @@ CREATE INDEX IF NOT EXISTS idx_feed_items_payload ON feed_items(payload_id);
 CREATE INDEX IF NOT EXISTS idx_feed_items_feed ON feed_items(feed_id);
 CREATE INDEX IF NOT EXISTS idx_feeds_domain ON feeds(domain);
 CREATE INDEX IF NOT EXISTS idx_feeds_category ON feeds(category);
+
+CREATE TABLE IF NOT EXISTS dataset_blobs(
+  content_hash TEXT PRIMARY KEY,
+  body BLOB NOT NULL,
+  size_bytes INTEGER NOT NULL,
+  created_at_ms INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dataset_versions(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  feed_id TEXT NOT NULL REFERENCES feeds(id),
+  fetched_at_ms INTEGER NOT NULL,
+  content_hash TEXT NOT NULL REFERENCES dataset_blobs(content_hash),
+  row_count INTEGER NOT NULL,
+  added_count INTEGER NOT NULL,
+  removed_count INTEGER NOT NULL,
+  changed_count INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_dataset_versions_feed
+ON dataset_versions(feed_id, fetched_at_ms);
+
+CREATE TABLE IF NOT EXISTS payload_archive(
+  content_hash TEXT PRIMARY KEY,
+  encoding TEXT NOT NULL,
+  body BLOB NULL,
+  location TEXT NULL,
+  raw_size INTEGER NOT NULL,
+  stored_size INTEGER NOT NULL,
+  first_seen_at_ms INTEGER NOT NULL,
+  last_seen_at_ms INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_payload_archive_last_seen
+ON payload_archive(last_seen_at_ms);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_events_time
+ON fetch_events(event_time_ms);
+
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_feed_time
+ON feed_payloads(feed_id, fetched_at_ms);
+
+CREATE TABLE IF NOT EXISTS feed_health_daily(
+  day TEXT NOT NULL,
+  scope TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  fetches INTEGER NOT NULL,
+  successes INTEGER NOT NULL,
+  error_counts TEXT NOT NULL,
+  p50_latency_ms INTEGER NULL,
+  p95_latency_ms INTEGER NULL,
+  changes INTEGER NOT NULL,
+  items INTEGER NOT NULL,
+  updated_at_ms INTEGER NOT NULL,
+  PRIMARY KEY (day, scope, subject)
+);
+
+CREATE TABLE IF NOT EXISTS fetch_requests(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  feed_id TEXT NOT NULL,
+  requested_by TEXT NULL,
+  requested_at_ms INTEGER NOT NULL,
+  status TEXT NOT NULL,
+  started_at_ms INTEGER NULL,
+  finished_at_ms INTEGER NULL,
+  http_status INTEGER NULL,
+  error TEXT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_requests_status
+ON fetch_requests(status, id);
+
+CREATE TABLE IF NOT EXISTS paused_targets(
+  scope TEXT NOT NULL,
+  name TEXT NOT NULL,
+  reason TEXT NULL,
+  paused_at_ms INTEGER NOT NULL,
+  PRIMARY KEY(scope, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_fetch_events_feed
+ON fetch_events(feed_id, id);
@@ -0,0 +1,461 @@
+//! Raw payload archive: keeps gzip
+//! compressed response bodies (in the
+//! DB or a directory) so parser issues
+//! can be replayed against the exact
+//! bytes received, and prunes bodies
+//! past the retention window. Each
+//! write to the directory storage gets
+//! its own file name, so a prune
+//! unlinking a deleted row's file never
+//! removes the body of a row inserted
+//! again meanwhile.
+
+use std::io::{
+  Read,
+  Write
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tracing::{
+  info,
+  warn
+};
+
+use crate::domain::model::{
+  AppConfig,
+  ArchiveStorage
+};
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  ArchivedPayload,
+  Repo
+};
+
+const ENCODING_GZIP: &str = "gzip";
+
+const PRUNE_INTERVAL: Duration =
+  Duration::from_secs(3600);
+
+const PRUNE_BATCH: i64 = 500;
+
+const DAY_MS: i64 = 86_400_000;
+
+/// Archives `body` under `hash` unless
+/// it is already stored, in which case
+/// only its last-seen time moves.
+pub async fn archive_body<R>(
+  cfg: &AppConfig,
+  repo: &Arc<R>,
+  hash: &str,
+  body: &[u8],
+  now_ms: i64
+) -> Result<(), String>
+where
+  R: Repo + ?Sized
+{
+  let started = Instant::now();
+
+  let seen = repo
+    .touch_archived_payload(
+      hash,
+      now_ms,
+      &cfg.timezone
+    )
+    .await;
+
+  metrics::record_db_time(
+    "touch_archived_payload",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  if seen? {
+    return Ok(());
+  }
+
+  let compressed = gzip(body)?;
+
+  let stored_size =
+    compressed.len() as i64;
+
+  let (stored, location) =
+    match &cfg.archive.storage {
+      | ArchiveStorage::Database => {
+        (Some(compressed), None)
+      }
+      | ArchiveStorage::Directory(
+        dir
+      ) => {
+        let rel =
+          relative_path(hash, now_ms);
+
+        write_file(
+          &dir.join(&rel),
+          &compressed
+        )
+        .await?;
+
+        (None, Some(rel))
+      }
+    };
+
+  let payload = ArchivedPayload {
+    content_hash: hash.to_string(),
+    encoding: ENCODING_GZIP.to_string(),
+    body: stored,
+    location,
+    raw_size: body.len() as i64,
+    stored_size
+  };
+
+  let started = Instant::now();
+
+  let res = repo
+    .insert_archived_payload(
+      &payload,
+      now_ms,
+      &cfg.timezone
+    )
+    .await;
+
+  metrics::record_db_time(
+    "insert_archived_payload",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  res?;
+
+  if let (
+    ArchiveStorage::Directory(dir),
+    Some(rel)
+  ) = (
+    &cfg.archive.storage,
+    payload.location.as_deref()
+  ) {
+    discard_if_lost(
+      repo, dir, hash, rel
+    )
+    .await;
+  }
+
+  Ok(())
+}
+
+/// Removes the file just written when
+/// a concurrent write of the same body
+/// inserted its row first.
+async fn discard_if_lost<R>(
+  repo: &Arc<R>,
+  dir: &Path,
+  hash: &str,
+  rel: &str
+) where
+  R: Repo + ?Sized
+{
+  let kept = match repo
+    .archived_payload(hash)
+    .await
+  {
+    | Ok(kept) => kept,
+    | Err(e) => {
+      warn!(error = %e, "Failed to check archived payload file");
+
+      return;
+    }
+  };
+
+  if kept.is_some_and(|p| {
+    p.location.as_deref() == Some(rel)
+  }) {
+    return;
+  }
+
+  if let Err(e) =
+    tokio::fs::remove_file(
+      dir.join(rel)
+    )
+    .await
+  {
+    warn!(path = %rel, error = %e, "Failed to remove archived payload file");
+  }
+}
+
+/// Returns the decompressed body for
+/// `hash`, or `None` when it was never
+/// archived (or has been pruned).
+pub async fn load_body<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  hash: &str
+) -> Result<Option<Vec<u8>>, String>
+where
+  R: Repo + ?Sized
+{
+  let Some(payload) =
+    repo.archived_payload(hash).await?
+  else {
+    return Ok(None);
+  };
+
+  let compressed = match (
+    payload.body,
+    payload.location
+  ) {
+    | (Some(body), _) => body,
+    | (None, Some(rel)) => {
+      let ArchiveStorage::Directory(
+        dir
+      ) = &cfg.archive.storage
+      else {
+        return Err(format!(
+          "archived payload {hash} is \
+           stored on disk but \
+           archive.storage is not \
+           'directory'"
+        ));
+      };
+
+      tokio::fs::read(dir.join(&rel))
+        .await
+        .map_err(|e| {
+          format!(
+            "read archived payload \
+             {rel}: {e}"
+          )
+        })?
+    }
+    | (None, None) => {
+      return Err(format!(
+        "archived payload {hash} has \
+         no body"
+      ));
+    }
+  };
+
+  if payload.encoding != ENCODING_GZIP {
+    return Err(format!(
+      "unsupported archive encoding \
+       '{}'",
+      payload.encoding
+    ));
+  }
+
+  gunzip(&compressed).map(Some)
+}
+
+/// Deletes archived bodies not seen
+/// within the retention window, in
+/// batches. Returns the number pruned.
+pub async fn prune_once<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  now_ms: i64
+) -> Result<u64, String>
+where
+  R: Repo + ?Sized
+{
+  let retention_days =
+    cfg.archive.retention_days;
+
+  if retention_days == 0 {
+    return Ok(0);
+  }
+
+  let cutoff_ms = now_ms
+    .saturating_sub(
+      (retention_days as i64)
+        .saturating_mul(DAY_MS)
+    );
+
+  let mut total = 0u64;
+
+  loop {
+    let started = Instant::now();
+
+    let pruned = repo
+      .prune_archived_payloads(
+        cutoff_ms,
+        PRUNE_BATCH,
+        &cfg.timezone
+      )
+      .await;
+
+    metrics::record_db_time(
+      "prune_archived_payloads",
+      started.elapsed().as_millis()
+        as u64
+    );
+
+    let pruned = pruned?;
+
+    if let ArchiveStorage::Directory(
+      dir
+    ) = &cfg.archive.storage
+    {
+      for p in &pruned {
+        let Some(rel) =
+          p.location.as_deref()
+        else {
+          continue;
+        };
+
+        if let Err(e) =
+          tokio::fs::remove_file(
+            dir.join(rel)
+          )
+          .await
+        {
+          warn!(path = %rel, error = %e, "Failed to remove archived payload file");
+        }
+      }
+    }
+
+    total += pruned.len() as u64;
+
+    if (pruned.len() as i64)
+      < PRUNE_BATCH
+    {
+      break;
+    }
+  }
+
+  Ok(total)
+}
+
+/// Runs `prune_once` hourly; only
+/// spawned when the archive is enabled
+/// with a retention window.
+pub async fn prune_forever<R, C>(
+  cfg: Arc<AppConfig>,
+  repo: Arc<R>,
+  clock: Arc<C>
+) -> Result<(), String>
+where
+  R: Repo + ?Sized,
+  C: Clock
+{
+  let mut interval =
+    tokio::time::interval(
+      PRUNE_INTERVAL
+    );
+
+  loop {
+    interval.tick().await;
+
+    let now_ms =
+      clock.now_epoch_ms().await;
+
+    match prune_once(
+      &cfg,
+      repo.as_ref(),
+      now_ms
+    )
+    .await
+    {
+      | Ok(0) => {}
+      | Ok(pruned) => {
+        info!(
+          pruned,
+          retention_days =
+            cfg.archive.retention_days,
+          "Pruned archived payloads"
+        );
+      }
+      | Err(e) => {
+        warn!(error = %e, "Archive prune failed");
+      }
+    }
+  }
+}
+
+/// Sharded by hash; the time and a
+/// random suffix make every write a
+/// new file.
+fn relative_path(
+  hash: &str,
+  now_ms: i64
+) -> String {
+  let shard =
+    hash.get(..2).unwrap_or("00");
+
+  let nonce = rand::random::<u32>();
+
+  let name = format!(
+    "{hash}-{now_ms}-{nonce:08x}"
+  );
+
+  format!("{shard}/{name}.gz")
+}
+
+async fn write_file(
+  path: &Path,
+  bytes: &[u8]
+) -> Result<(), String> {
+  if let Some(parent) = path.parent() {
+    tokio::fs::create_dir_all(parent)
+      .await
+      .map_err(|e| {
+        format!(
+          "archive dir create error: \
+           {e}"
+        )
+      })?;
+  }
+
+  let tmp = path.with_extension("tmp");
+
+  tokio::fs::write(&tmp, bytes)
+    .await
+    .map_err(|e| {
+    format!("archive write error: {e}")
+  })?;
+
+  tokio::fs::rename(&tmp, path)
+    .await
+    .map_err(|e| {
+      format!(
+        "archive rename error: {e}"
+      )
+    })
+}
+
+fn gzip(
+  bytes: &[u8]
+) -> Result<Vec<u8>, String> {
+  let mut enc = GzEncoder::new(
+    Vec::new(),
+    Compression::default()
+  );
+
+  enc.write_all(bytes).map_err(
+    |e| format!("gzip error: {e}")
+  )?;
+
+  enc.finish().map_err(|e| {
+    format!("gzip error: {e}")
+  })
+}
+
+fn gunzip(
+  bytes: &[u8]
+) -> Result<Vec<u8>, String> {
+  let mut out = Vec::new();
+
+  GzDecoder::new(bytes)
+    .read_to_end(&mut out)
+    .map_err(|e| {
+      format!("gunzip error: {e}")
+    })?;
+
+  Ok(out)
+}
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
+use super::scheduler::head_mode::HeadModes;
+use super::write_behind::WriteBehind;
 use crate::domain::model::AppConfig;
+use crate::infra::admin::Controls;
 use crate::ports::clock::Clock;
 use crate::ports::http::Http;
 use crate::ports::random::RandomSource;
@@ -8,8 +11,13 @@ use crate::ports::repo::Repo;
 
 /// Bundles the runtime dependencies the
 /// scheduler needs (configuration,
-/// persistence, HTTP client, clock, and
-/// randomness source).
+/// persistence and its write-behind
+/// buffer, HTTP client, clock, and
+/// randomness source), plus the
+/// in-memory state its loops share:
+/// operator controls, which the admin
+/// API serves too, and the per-domain
+/// HEAD view.
 
 pub struct AppContext<R, H, C, G>
 where
@@ -18,11 +26,14 @@ where
   C: Clock,
   G: RandomSource
 {
-  pub cfg:   Arc<AppConfig>,
-  pub repo:  Arc<R>,
-  pub http:  Arc<H>,
-  pub clock: Arc<C>,
-  pub rng:   Arc<G>
+  pub cfg:        Arc<AppConfig>,
+  pub repo:       Arc<R>,
+  pub http:       Arc<H>,
+  pub clock:      Arc<C>,
+  pub rng:        Arc<G>,
+  pub writes:     WriteBehind,
+  pub controls:   Arc<Controls>,
+  pub head_modes: Arc<HeadModes>
 }
 
 impl<R, H, C, G> Clone
@@ -35,11 +46,24 @@ where
 {
   fn clone(&self) -> Self {
     Self {
-      cfg:   Arc::clone(&self.cfg),
-      repo:  Arc::clone(&self.repo),
-      http:  Arc::clone(&self.http),
-      clock: Arc::clone(&self.clock),
-      rng:   Arc::clone(&self.rng)
+      cfg:        Arc::clone(&self.cfg),
+      repo:       Arc::clone(
+        &self.repo
+      ),
+      http:       Arc::clone(
+        &self.http
+      ),
+      clock:      Arc::clone(
+        &self.clock
+      ),
+      rng:        Arc::clone(&self.rng),
+      writes:     self.writes.clone(),
+      controls:   Arc::clone(
+        &self.controls
+      ),
+      head_modes: Arc::clone(
+        &self.head_modes
+      )
     }
   }
 }
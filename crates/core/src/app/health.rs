@@ -0,0 +1,362 @@
+//! Daily health rollups: per-feed and
+//! per-domain fetch counts, errors by
+//! kind, latency percentiles, content
+//! changes, and items ingested, derived
+//! from `fetch_events` and
+//! `feed_payloads`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use chrono::{
+  Days,
+  NaiveDate,
+  TimeZone
+};
+use chrono_tz::Tz;
+use tracing::{
+  debug,
+  warn
+};
+
+use crate::domain::model::AppConfig;
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  HealthEvent,
+  HealthPayload,
+  HealthRollup,
+  HealthScope,
+  Repo
+};
+
+#[derive(Default)]
+
+struct Acc {
+  fetches:   i64,
+  successes: i64,
+  errors:    BTreeMap<String, i64>,
+  latencies: Vec<i64>,
+  changes:   i64,
+  items:     i64
+}
+
+impl Acc {
+  fn event(
+    &mut self,
+    e: &HealthEvent
+  ) {
+    self.fetches += 1;
+
+    match &e.error_kind {
+      | None => self.successes += 1,
+      | Some(kind) => {
+        *self
+          .errors
+          .entry(error_kind_name(kind))
+          .or_default() += 1;
+      }
+    }
+
+    if let Some(ms) = e.latency_ms {
+      self.latencies.push(ms);
+    }
+  }
+
+  fn payload(
+    &mut self,
+    p: &HealthPayload
+  ) {
+    if p.changed {
+      self.changes += 1;
+
+      self.items += p.item_count;
+    }
+  }
+
+  fn finish(
+    mut self,
+    day: &str,
+    scope: HealthScope,
+    subject: String
+  ) -> HealthRollup {
+    self.latencies.sort_unstable();
+
+    HealthRollup {
+      day: day.to_string(),
+      scope,
+      subject,
+      fetches: self.fetches,
+      successes: self.successes,
+      error_counts: self.errors,
+      p50_latency_ms: percentile(
+        &self.latencies,
+        50
+      ),
+      p95_latency_ms: percentile(
+        &self.latencies,
+        95
+      ),
+      changes: self.changes,
+      items: self.items
+    }
+  }
+}
+
+/// Stored error kinds are `Debug`
+/// strings; `Http4xx(404)` groups
+/// under `Http4xx`.
+fn error_kind_name(
+  raw: &str
+) -> String {
+  raw
+    .split('(')
+    .next()
+    .unwrap_or(raw)
+    .to_string()
+}
+
+/// Nearest-rank percentile over sorted
+/// values.
+pub(crate) fn percentile(
+  sorted: &[i64],
+  pct: usize
+) -> Option<i64> {
+  if sorted.is_empty() {
+    return None;
+  }
+
+  let rank =
+    (pct * sorted.len()).div_ceil(100);
+
+  sorted
+    .get(rank.saturating_sub(1))
+    .copied()
+}
+
+/// Groups one day's events and payloads
+/// into feed rows followed by domain
+/// rows, each sorted by subject. Only
+/// payloads whose content changed count
+/// towards `changes` and `items`.
+pub fn build_rollups(
+  day: &str,
+  events: &[HealthEvent],
+  payloads: &[HealthPayload]
+) -> Vec<HealthRollup> {
+  let mut feeds: BTreeMap<String, Acc> =
+    BTreeMap::new();
+
+  let mut domains: BTreeMap<
+    String,
+    Acc
+  > = BTreeMap::new();
+
+  for e in events {
+    feeds
+      .entry(e.feed_id.clone())
+      .or_default()
+      .event(e);
+
+    domains
+      .entry(e.domain.clone())
+      .or_default()
+      .event(e);
+  }
+
+  for p in payloads {
+    feeds
+      .entry(p.feed_id.clone())
+      .or_default()
+      .payload(p);
+
+    domains
+      .entry(p.domain.clone())
+      .or_default()
+      .payload(p);
+  }
+
+  let mut rows = Vec::with_capacity(
+    feeds.len() + domains.len()
+  );
+
+  for (subject, acc) in feeds {
+    rows.push(acc.finish(
+      day,
+      HealthScope::Feed,
+      subject
+    ));
+  }
+
+  for (subject, acc) in domains {
+    rows.push(acc.finish(
+      day,
+      HealthScope::Domain,
+      subject
+    ));
+  }
+
+  rows
+}
+
+/// Epoch-millis bounds of a local
+/// calendar day, `[start, end)`.
+pub fn day_bounds(
+  day: NaiveDate,
+  zone: &Tz
+) -> (i64, i64) {
+  let start_of = |d: NaiveDate| {
+    let midnight = d
+      .and_hms_opt(0, 0, 0)
+      .unwrap_or_default();
+
+    zone
+      .from_local_datetime(&midnight)
+      .earliest()
+      .map(|t| t.timestamp_millis())
+      .unwrap_or_else(|| {
+        midnight
+          .and_utc()
+          .timestamp_millis()
+      })
+  };
+
+  let next = day
+    .checked_add_days(Days::new(1))
+    .unwrap_or(day);
+
+  (start_of(day), start_of(next))
+}
+
+/// `YYYY-MM-DD`, the stored form of a
+/// rollup day.
+pub fn day_key(
+  day: NaiveDate
+) -> String {
+  day.format("%Y-%m-%d").to_string()
+}
+
+pub fn local_day(
+  ms: i64,
+  zone: &Tz
+) -> NaiveDate {
+  zone
+    .timestamp_millis_opt(ms)
+    .single()
+    .map(|t| t.date_naive())
+    .unwrap_or_default()
+}
+
+/// Recomputes and replaces the rollups
+/// for one local day. Returns the
+/// number of rows stored.
+pub async fn rollup_day<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  day: NaiveDate,
+  now_ms: i64
+) -> Result<usize, String>
+where
+  R: Repo + ?Sized
+{
+  let (start_ms, end_ms) =
+    day_bounds(day, &cfg.timezone);
+
+  let started = Instant::now();
+
+  let events = repo
+    .health_events(
+      start_ms,
+      end_ms,
+      &cfg.timezone
+    )
+    .await?;
+
+  let payloads = repo
+    .health_payloads(
+      start_ms,
+      end_ms,
+      &cfg.timezone
+    )
+    .await?;
+
+  let day_str = day_key(day);
+
+  let rows = build_rollups(
+    &day_str, &events, &payloads
+  );
+
+  repo
+    .replace_health_rollups(
+      &day_str,
+      &rows,
+      now_ms,
+      &cfg.timezone
+    )
+    .await?;
+
+  metrics::record_db_time(
+    "health_rollup",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  Ok(rows.len())
+}
+
+/// Refreshes today's and yesterday's
+/// rollups every
+/// `health.interval_seconds`, so events
+/// landing just before midnight are
+/// still counted.
+pub async fn run_forever<R, C>(
+  cfg: Arc<AppConfig>,
+  repo: Arc<R>,
+  clock: Arc<C>
+) -> Result<(), String>
+where
+  R: Repo + ?Sized,
+  C: Clock
+{
+  let mut interval =
+    tokio::time::interval(
+      Duration::from_secs(
+        cfg.health.interval_seconds
+      )
+    );
+
+  loop {
+    interval.tick().await;
+
+    let now_ms =
+      clock.now_epoch_ms().await;
+
+    let today =
+      local_day(now_ms, &cfg.timezone);
+
+    for day in [
+      today.pred_opt().unwrap_or(today),
+      today
+    ] {
+      match rollup_day(
+        &cfg,
+        repo.as_ref(),
+        day,
+        now_ms
+      )
+      .await
+      {
+        | Ok(rows) => {
+          debug!(%day, rows, "Health rollups refreshed");
+        }
+        | Err(e) => {
+          warn!(%day, error = %e, "Health rollup failed");
+        }
+      }
+    }
+  }
+}
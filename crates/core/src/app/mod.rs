@@ -1,5 +1,12 @@
 //! Application layer wiring and the
 //! scheduler loop.
 
+pub mod archive;
 pub mod context;
+pub mod health;
+pub mod partitions;
+pub mod reparse;
+pub mod retention;
 pub mod scheduler;
+pub mod simulate;
+pub mod write_behind;
@@ -0,0 +1,146 @@
+//! Partition maintenance for the
+//! Postgres history tables: keeps
+//! monthly partitions created ahead of
+//! time and drops those retention has
+//! fully expired, instead of deleting
+//! their rows one batch at a time.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{
+  info,
+  warn
+};
+
+use super::retention::scope_for;
+use crate::domain::model::{
+  AppConfig,
+  RetentionConfig
+};
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  PartitionPlan,
+  PruneTarget,
+  Repo
+};
+
+/// How often partitions are checked.
+const MAINTENANCE_INTERVAL: Duration =
+  Duration::from_secs(3600);
+
+/// Time before which every row of
+/// `target` has expired in all of
+/// `categories`: the oldest of their
+/// age cutoffs. `None` when retention
+/// is off or any category keeps rows
+/// without an age limit.
+pub fn drop_cutoff(
+  cfg: &RetentionConfig,
+  categories: &[String],
+  target: PruneTarget,
+  now_ms: i64
+) -> Option<i64> {
+  if !cfg.enabled
+    || categories.is_empty()
+  {
+    return None;
+  }
+
+  categories
+    .iter()
+    .map(|category| {
+      scope_for(
+        cfg, category, target, now_ms
+      )
+      .and_then(|scope| scope.cutoff_ms)
+    })
+    .collect::<Option<Vec<_>>>()?
+    .into_iter()
+    .min()
+}
+
+pub fn plan_for(
+  cfg: &AppConfig,
+  categories: &[String],
+  now_ms: i64
+) -> PartitionPlan {
+  PartitionPlan {
+    now_ms,
+    premake_months: cfg
+      .partitions
+      .premake_months,
+    convert_existing: cfg
+      .partitions
+      .convert_existing,
+    fetch_events_before_ms: drop_cutoff(
+      &cfg.retention,
+      categories,
+      PruneTarget::FetchEvents,
+      now_ms
+    ),
+    state_history_before_ms:
+      drop_cutoff(
+        &cfg.retention,
+        categories,
+        PruneTarget::StateHistory,
+        now_ms
+      )
+  }
+}
+
+/// Runs a maintenance pass every
+/// `MAINTENANCE_INTERVAL`; only spawned
+/// on Postgres.
+pub async fn run_forever<R, C>(
+  cfg: Arc<AppConfig>,
+  repo: Arc<R>,
+  clock: Arc<C>,
+  categories: Vec<String>
+) -> Result<(), String>
+where
+  R: Repo + ?Sized,
+  C: Clock
+{
+  let mut interval =
+    tokio::time::interval(
+      MAINTENANCE_INTERVAL
+    );
+
+  loop {
+    interval.tick().await;
+
+    let plan = plan_for(
+      &cfg,
+      &categories,
+      clock.now_epoch_ms().await
+    );
+
+    match repo
+      .maintain_partitions(&plan)
+      .await
+    {
+      | Ok(report)
+        if report
+          .created
+          .is_empty()
+          && report
+            .dropped
+            .is_empty()
+          && report
+            .converted
+            .is_empty() => {}
+      | Ok(report) => {
+        info!(
+          created = ?report.created,
+          dropped = ?report.dropped,
+          converted = ?report.converted,
+          "Maintained partitions"
+        );
+      }
+      | Err(e) => {
+        warn!(error = %e, "Partition maintenance failed");
+      }
+    }
+  }
+}
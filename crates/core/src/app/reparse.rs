@@ -0,0 +1,299 @@
+//! Replays archived payload bodies
+//! through the current feed parser and
+//! upserts the resulting items in
+//! place, matched by item identity.
+
+use std::collections::HashMap;
+
+use tracing::warn;
+
+use super::archive::load_body;
+use crate::domain::model::AppConfig;
+use crate::feed::parser::{
+  self,
+  FeedItem
+};
+use crate::ports::repo::{
+  PayloadFilter,
+  Repo,
+  StoredItem
+};
+
+const PAGE_SIZE: i64 = 200;
+
+#[derive(Debug, Clone)]
+
+pub struct PayloadChange {
+  pub payload_id: i64,
+  pub feed_id:    String,
+  pub inserted:   usize,
+  pub updated:    usize
+}
+
+#[derive(Debug, Clone, Default)]
+
+pub struct ReparseReport {
+  pub payloads:       u64,
+  pub missing_bodies: u64,
+  pub parse_errors:   u64,
+  pub inserted:       u64,
+  pub updated:        u64,
+  pub unchanged:      u64,
+  pub changes: Vec<PayloadChange>
+}
+
+/// What applying a fresh parse to the
+/// stored items of one payload would
+/// do.
+#[derive(Debug, Default)]
+
+pub struct ItemPlan {
+  pub inserts:   Vec<FeedItem>,
+  pub updates:   Vec<StoredItem>,
+  pub unchanged: usize
+}
+
+/// Items are matched by guid, then
+/// link, then title, each stored item
+/// at most once; a weaker key only
+/// matches when the stronger keys both
+/// items carry agree. Stored items with
+/// no match are left untouched.
+pub fn plan_items(
+  existing: &[StoredItem],
+  parsed: &[FeedItem]
+) -> ItemPlan {
+  let mut by_key: HashMap<
+    String,
+    Vec<usize>
+  > = HashMap::new();
+
+  for (i, stored) in
+    existing.iter().enumerate()
+  {
+    for key in item_keys(&stored.item) {
+      by_key
+        .entry(key)
+        .or_default()
+        .push(i);
+    }
+  }
+
+  let mut taken =
+    vec![false; existing.len()];
+
+  let mut plan = ItemPlan::default();
+
+  for item in parsed {
+    let matched = item_keys(item)
+      .iter()
+      .find_map(|k| {
+        by_key
+          .get(k)?
+          .iter()
+          .copied()
+          .find(|&i| {
+            !taken[i]
+              && compatible(
+                &existing[i].item,
+                item
+              )
+          })
+      });
+
+    let Some(i) = matched else {
+      plan.inserts.push(item.clone());
+
+      continue;
+    };
+
+    taken[i] = true;
+
+    let stored = &existing[i];
+
+    if stored.item == *item {
+      plan.unchanged += 1;
+    } else {
+      plan.updates.push(StoredItem {
+        id:   stored.id,
+        item: item.clone()
+      });
+    }
+  }
+
+  plan
+}
+
+/// Every identity key `item` carries,
+/// strongest first.
+fn item_keys(
+  item: &FeedItem
+) -> Vec<String> {
+  let guid = item
+    .guid
+    .as_ref()
+    .map(|g| format!("g:{g}"));
+
+  let link = item
+    .link
+    .as_ref()
+    .map(|l| format!("l:{l}"));
+
+  let title = item
+    .title
+    .as_ref()
+    .map(|t| format!("t:{t}"));
+
+  [guid, link, title]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Two items with different guids, or
+/// different links, are never the same
+/// item.
+fn compatible(
+  stored: &FeedItem,
+  item: &FeedItem
+) -> bool {
+  let agree =
+    |a: &Option<String>,
+     b: &Option<String>| {
+      a.is_none()
+        || b.is_none()
+        || a == b
+    };
+
+  agree(&stored.guid, &item.guid)
+    && agree(&stored.link, &item.link)
+}
+
+/// Walks archived payloads matching
+/// `filter`; with `dry_run` nothing is
+/// written and the report describes
+/// what would change.
+pub async fn reparse<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  filter: &PayloadFilter,
+  dry_run: bool
+) -> Result<ReparseReport, String>
+where
+  R: Repo + ?Sized
+{
+  let mut report =
+    ReparseReport::default();
+
+  let mut after_id = 0i64;
+
+  loop {
+    let refs = repo
+      .archived_payload_refs(
+        filter,
+        after_id,
+        PAGE_SIZE,
+        &cfg.timezone
+      )
+      .await?;
+
+    let Some(last) = refs.last() else {
+      break;
+    };
+
+    after_id = last.id;
+
+    for p in &refs {
+      report.payloads += 1;
+
+      // A body that cannot be read is
+      // as good as missing; the rest of
+      // the run goes on.
+      let body = match load_body(
+        cfg,
+        repo,
+        &p.content_hash
+      )
+      .await
+      {
+        | Ok(Some(body)) => body,
+        | Ok(None) => {
+          report.missing_bodies += 1;
+
+          continue;
+        }
+        | Err(e) => {
+          warn!(payload_id = p.id, feed_id = %p.feed_id, error = %e, "Archived body unreadable");
+
+          report.missing_bodies += 1;
+
+          continue;
+        }
+      };
+
+      let parsed = match parser::parse(
+        &body
+      ) {
+        | Ok(parsed) => parsed,
+        | Err(e) => {
+          warn!(payload_id = p.id, feed_id = %p.feed_id, error = %e, "Reparse failed");
+
+          report.parse_errors += 1;
+
+          continue;
+        }
+      };
+
+      let existing = repo
+        .payload_items(p.id)
+        .await?;
+
+      let plan = plan_items(
+        &existing,
+        &parsed.items
+      );
+
+      report.inserted +=
+        plan.inserts.len() as u64;
+
+      report.updated +=
+        plan.updates.len() as u64;
+
+      report.unchanged +=
+        plan.unchanged as u64;
+
+      if plan.inserts.is_empty()
+        && plan.updates.is_empty()
+      {
+        continue;
+      }
+
+      report.changes.push(
+        PayloadChange {
+          payload_id: p.id,
+          feed_id:    p.feed_id.clone(),
+          inserted:   plan
+            .inserts
+            .len(),
+          updated:    plan
+            .updates
+            .len()
+        }
+      );
+
+      if !dry_run {
+        repo
+          .apply_payload_items(
+            p.id,
+            &p.feed_id,
+            &plan.inserts,
+            &plan.updates,
+            &cfg.timezone
+          )
+          .await?;
+      }
+    }
+  }
+
+  Ok(report)
+}
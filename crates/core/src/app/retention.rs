@@ -0,0 +1,216 @@
+//! Retention for history tables:
+//! deletes fetch events, state history,
+//! and payloads (with their items) past
+//! each category's age and row limits,
+//! in small batches so scheduler ticks
+//! keep getting DB time.
+
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use tracing::{
+  info,
+  warn
+};
+
+use crate::domain::model::{
+  AppConfig,
+  RetentionConfig,
+  RetentionLimits
+};
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  PruneScope,
+  PruneTarget,
+  Repo
+};
+
+const DAY_MS: i64 = 86_400_000;
+
+/// Pause between batches so long
+/// prunes yield to fetch writes.
+const BATCH_PAUSE: Duration =
+  Duration::from_millis(50);
+
+/// Items precede payloads so a payload
+/// is only removed once its items are.
+const TARGETS: [PruneTarget; 4] = [
+  PruneTarget::FetchEvents,
+  PruneTarget::StateHistory,
+  PruneTarget::Items,
+  PruneTarget::Payloads
+];
+
+/// Builds the delete scope for one
+/// category and target; `None` when the
+/// policy keeps everything.
+pub fn scope_for(
+  cfg: &RetentionConfig,
+  category: &str,
+  target: PruneTarget,
+  now_ms: i64
+) -> Option<PruneScope> {
+  let policy = cfg.policy_for(category);
+
+  let limits: RetentionLimits =
+    match target {
+      | PruneTarget::FetchEvents => {
+        policy.fetch_events
+      }
+      | PruneTarget::StateHistory => {
+        policy.state_history
+      }
+      | PruneTarget::Items
+      | PruneTarget::Payloads => {
+        policy.payloads
+      }
+    };
+
+  if limits.is_unbounded() {
+    return None;
+  }
+
+  Some(PruneScope {
+    category:      category.to_string(),
+    cutoff_ms:     limits
+      .max_age_days
+      .map(|days| {
+        now_ms.saturating_sub(
+          (days as i64)
+            .saturating_mul(DAY_MS)
+        )
+      }),
+    keep_per_feed: limits
+      .max_rows_per_feed
+      .map(|n| n as i64),
+    limit:         cfg.batch_size
+      as i64,
+    server_schema: cfg
+      .server_schema
+      .clone()
+  })
+}
+
+/// One full pass over every category
+/// and table. Returns rows deleted per
+/// target, in `TARGETS` order.
+pub async fn prune_once<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  categories: &[String],
+  now_ms: i64
+) -> Result<[u64; 4], String>
+where
+  R: Repo + ?Sized
+{
+  let mut totals = [0u64; 4];
+
+  for category in categories {
+    for (idx, target) in
+      TARGETS.iter().enumerate()
+    {
+      let Some(scope) = scope_for(
+        &cfg.retention,
+        category,
+        *target,
+        now_ms
+      ) else {
+        continue;
+      };
+
+      loop {
+        let started = Instant::now();
+
+        let deleted = repo
+          .prune_rows(
+            *target,
+            &scope,
+            &cfg.timezone
+          )
+          .await;
+
+        metrics::record_db_time(
+          "prune_rows",
+          started.elapsed().as_millis()
+            as u64
+        );
+
+        let deleted = deleted?;
+
+        totals[idx] += deleted;
+
+        if deleted < scope.limit as u64
+        {
+          break;
+        }
+
+        tokio::time::sleep(BATCH_PAUSE)
+          .await;
+      }
+    }
+  }
+
+  Ok(totals)
+}
+
+/// Runs `prune_once` every
+/// `retention.interval_seconds`; only
+/// spawned when retention is enabled.
+pub async fn run_forever<R, C>(
+  cfg: Arc<AppConfig>,
+  repo: Arc<R>,
+  clock: Arc<C>,
+  categories: Vec<String>
+) -> Result<(), String>
+where
+  R: Repo + ?Sized,
+  C: Clock
+{
+  let mut interval =
+    tokio::time::interval(
+      Duration::from_secs(
+        cfg.retention.interval_seconds
+      )
+    );
+
+  loop {
+    interval.tick().await;
+
+    let now_ms =
+      clock.now_epoch_ms().await;
+
+    match prune_once(
+      &cfg,
+      repo.as_ref(),
+      &categories,
+      now_ms
+    )
+    .await
+    {
+      | Ok([0, 0, 0, 0]) => {}
+      | Ok(
+        [
+          events,
+          history,
+          items,
+          payloads
+        ]
+      ) => {
+        info!(
+          events,
+          history,
+          items,
+          payloads,
+          "Pruned history rows"
+        );
+      }
+      | Err(e) => {
+        warn!(error = %e, "Retention prune failed");
+      }
+    }
+  }
+}
@@ -2,39 +2,55 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use tracing::{
+  debug,
   error,
   warn
 };
 
 use super::concurrency::ConcurrencyGuards;
+use super::dataset::ingest_dataset;
+use super::head_mode::HeadModes;
+use crate::app::archive::archive_body;
+use crate::app::write_behind::WriteBehind;
 use crate::domain::hashing::sha256_hex;
 use crate::domain::link_state::{
   LinkPhase,
-  LinkState
+  LinkState,
+  head_unreliable
 };
 use crate::domain::model::AppConfig;
+use crate::domain::poll_window::adjust_next_action;
 use crate::feed;
 use crate::infra::metrics;
 use crate::ports::http::Http;
-use crate::ports::repo::Repo;
+use crate::ports::repo::{
+  PendingEvent,
+  PendingState,
+  Repo
+};
 
+/// Returns when the feed is next due,
+/// or `None` once it has been moved to
+/// `error_feeds`.
 pub async fn do_head<R, H>(
   cfg: &AppConfig,
   repo: &Arc<R>,
+  writes: &WriteBehind,
   http: &Arc<H>,
   concurrency: &ConcurrencyGuards,
+  head_modes: &HeadModes,
   feed: &crate::domain::model::FeedConfig,
   mut state: LinkState,
   now_ms: i64,
   rand: f64,
   record_history: bool
-) -> Result<(), String>
+) -> Result<Option<i64>, String>
 where
   R: Repo + ?Sized,
   H: Http
 {
   let _permit = concurrency
-    .permit(&feed.domain)
+    .permit(&feed.domain, feed.priority)
     .await;
 
   let _inflight =
@@ -57,7 +73,7 @@ where
     res.error.is_none()
   );
 
-  let updated =
+  let mut updated =
     LinkState::apply_head_result(
       state,
       res.clone(),
@@ -65,50 +81,56 @@ where
       rand
     );
 
-  let started = Instant::now();
-
-  let event_res = repo
-    .insert_event(
-      &feed.id,
-      "HEAD",
-      res.status.map(|s| s as i64),
-      res.error,
-      Some(res.latency_ms as i64),
-      updated.backoff_index as i64,
-      updated.next_action_at_ms,
-      updated.note.as_deref(),
-      &cfg.timezone
-    )
-    .await;
-
-  metrics::record_db_time(
-    "insert_event",
-    started.elapsed().as_millis()
-      as u64
+  apply_windows(
+    cfg,
+    feed,
+    &mut updated,
+    now_ms,
+    rand
   );
 
-  event_res?;
-
-  let started = Instant::now();
-
-  let state_res = repo
-    .insert_state(
-      &updated,
-      now_ms,
-      &cfg.timezone,
+  head_modes
+    .record(&feed.domain, &updated);
+
+  writes
+    .event(PendingEvent {
+      feed_id: feed.id.clone(),
+      event_time_ms: now_ms
+        + res.latency_ms as i64,
+      method: "HEAD".to_string(),
+      status: res
+        .status
+        .map(|s| s as i64),
+      error_kind: res.error,
+      latency_ms: Some(
+        res.latency_ms as i64
+      ),
+      backoff_index: updated
+        .backoff_index
+        as i64,
+      scheduled_next_action_at_ms:
+        updated.next_action_at_ms,
+      debug: updated.note.clone()
+    })
+    .await?;
+
+  writes
+    .state(PendingState {
+      state: updated.clone(),
+      recorded_at_ms: now_ms,
       record_history
-    )
-    .await;
+    })
+    .await?;
 
-  metrics::record_db_time(
-    "insert_state",
-    started.elapsed().as_millis()
-      as u64
-  );
-
-  state_res?;
+  let max_errors = cfg
+    .scheduling
+    .policy_for(&feed.category)
+    .max_consecutive_errors;
 
+  // A refused HEAD only switches the
+  // feed to GETs.
   if is_immediate_error(cfg, res.status)
+    && !head_unreliable(&res)
   {
     error!(
         feed_id = %feed.id,
@@ -137,15 +159,16 @@ where
     );
 
     err_res?;
-  } else if cfg.max_consecutive_errors
-    > 0
+
+    return Ok(None);
+  } else if max_errors > 0
     && updated.consecutive_error_count
-      >= cfg.max_consecutive_errors
+      >= max_errors
   {
     error!(
         feed_id = %feed.id,
         errors = updated.consecutive_error_count,
-        max_errors = cfg.max_consecutive_errors,
+        max_errors,
         "Feed reached max consecutive errors"
     );
 
@@ -170,14 +193,18 @@ where
     );
 
     err_res?;
+
+    return Ok(None);
   }
 
-  Ok(())
+  Ok(Some(updated.next_action_at_ms))
 }
 
+/// Same contract as `do_head`.
 pub async fn do_get<R, H>(
   cfg: &AppConfig,
   repo: &Arc<R>,
+  writes: &WriteBehind,
   http: &Arc<H>,
   concurrency: &ConcurrencyGuards,
   feed: &crate::domain::model::FeedConfig,
@@ -185,20 +212,33 @@ pub async fn do_get<R, H>(
   now_ms: i64,
   rand: f64,
   record_history: bool
-) -> Result<(), String>
+) -> Result<Option<i64>, String>
 where
   R: Repo + ?Sized,
   H: Http
 {
   let _permit = concurrency
-    .permit(&feed.domain)
+    .permit(&feed.domain, feed.priority)
     .await;
 
   state.phase = LinkPhase::NeedsGet;
 
   tracing::debug!(feed_id = %feed.id, url = %feed.url, "GET request start");
 
-  let res = http.get(&feed.url).await;
+  let res = if state
+    .get_only_since_ms
+    .is_some()
+  {
+    http
+      .get_conditional(
+        &feed.url,
+        state.etag.as_deref(),
+        state.last_modified_ms
+      )
+      .await
+  } else {
+    http.get(&feed.url).await
+  };
 
   metrics::record_http_result(
     "get",
@@ -208,13 +248,37 @@ where
     res.error.is_none()
   );
 
-  let body_changed = res
+  // A 304 to a conditional GET has an
+  // empty body, and an origin ignoring
+  // the validators resends the stored
+  // one; neither is a change.
+  let hash = res
     .body
     .as_ref()
-    .map(|b| !b.is_empty())
-    .unwrap_or(false);
+    .filter(|b| !b.is_empty())
+    .map(|b| sha256_hex(b));
+
+  let body_changed = match &hash {
+    | Some(hash) => {
+      let started = Instant::now();
+
+      let latest_res = repo
+        .latest_content_hash(&feed.id)
+        .await;
+
+      metrics::record_db_time(
+        "latest_content_hash",
+        started.elapsed().as_millis()
+          as u64
+      );
 
-  let updated =
+      latest_res?.as_deref()
+        != Some(hash.as_str())
+    }
+    | None => false
+  };
+
+  let mut updated =
     LinkState::apply_get_result(
       state,
       res.clone(),
@@ -223,82 +287,102 @@ where
       rand
     );
 
-  let started = Instant::now();
-
-  let event_res = repo
-    .insert_event(
-      &feed.id,
-      "GET",
-      res.status.map(|s| s as i64),
-      res.error,
-      Some(res.latency_ms as i64),
-      updated.backoff_index as i64,
-      updated.next_action_at_ms,
-      updated.note.as_deref(),
-      &cfg.timezone
-    )
-    .await;
-
-  metrics::record_db_time(
-    "insert_event",
-    started.elapsed().as_millis()
-      as u64
+  apply_windows(
+    cfg,
+    feed,
+    &mut updated,
+    now_ms,
+    rand
   );
 
-  event_res?;
-
-  if let Some(body) = res.body.as_ref()
+  writes
+    .event(PendingEvent {
+      feed_id: feed.id.clone(),
+      event_time_ms: now_ms
+        + res.latency_ms as i64,
+      method: "GET".to_string(),
+      status: res
+        .status
+        .map(|s| s as i64),
+      error_kind: res.error,
+      latency_ms: Some(
+        res.latency_ms as i64
+      ),
+      backoff_index: updated
+        .backoff_index
+        as i64,
+      scheduled_next_action_at_ms:
+        updated.next_action_at_ms,
+      debug: updated.note.clone()
+    })
+    .await?;
+
+  if let (Some(body), Some(hash)) =
+    (res.body.as_ref(), hash.as_ref())
   {
-    let hash = sha256_hex(body);
-
-    match feed::parser::parse(body) {
-      | Ok(parsed) => {
-        let started = Instant::now();
-
-        let payload_res = repo
-          .insert_payload_with_items(
-            &feed.id,
-            now_ms,
-            res.etag.as_deref(),
-            res.last_modified,
-            Some(&hash),
-            &parsed,
-            &cfg.timezone
-          )
-          .await;
-
-        metrics::record_db_time(
-          "insert_payload_with_items",
-          started.elapsed().as_millis()
-            as u64
-        );
-
-        payload_res?;
-      }
-      | Err(e) => {
-        warn!(feed_id = %feed.id, error = %e, "Failed to parse feed");
+    if cfg.archive.enabled
+      && let Err(e) = archive_body(
+        cfg, repo, hash, body, now_ms
+      )
+      .await
+    {
+      warn!(feed_id = %feed.id, error = %e, "Failed to archive payload");
+    }
+
+    if !body_changed {
+      debug!(feed_id = %feed.id, content_hash = %hash, "Body unchanged");
+    } else if feed.dataset.is_some() {
+      ingest_dataset(
+        cfg, repo, feed, &res, body,
+        hash, now_ms
+      )
+      .await?;
+    } else {
+      match feed::parser::parse(body) {
+        | Ok(parsed) => {
+          let started = Instant::now();
+
+          let payload_res = repo
+            .insert_payload_with_items(
+              &feed.id,
+              now_ms,
+              res.etag.as_deref(),
+              res.last_modified,
+              Some(hash),
+              &parsed,
+              &cfg.timezone
+            )
+            .await;
+
+          metrics::record_db_time(
+            "insert_payload_with_items",
+            started
+              .elapsed()
+              .as_millis()
+              as u64
+          );
+
+          payload_res?;
+        }
+        | Err(e) => {
+          warn!(feed_id = %feed.id, content_hash = %hash, error = %e, "Failed to parse feed");
+        }
       }
     }
   }
 
-  let started = Instant::now();
-
-  let state_res = repo
-    .insert_state(
-      &updated,
-      now_ms,
-      &cfg.timezone,
+  writes
+    .state(PendingState {
+      state: updated.clone(),
+      recorded_at_ms: now_ms,
       record_history
-    )
-    .await;
-
-  metrics::record_db_time(
-    "insert_state",
-    started.elapsed().as_millis()
-      as u64
-  );
+    })
+    .await?;
 
-  state_res?;
+  let max_errors = cfg
+    .scheduling
+    .policy_for(&feed.category)
+    .max_consecutive_errors;
 
   if is_immediate_error(cfg, res.status)
   {
@@ -329,15 +413,16 @@ where
     );
 
     err_res?;
-  } else if cfg.max_consecutive_errors
-    > 0
+
+    return Ok(None);
+  } else if max_errors > 0
     && updated.consecutive_error_count
-      >= cfg.max_consecutive_errors
+      >= max_errors
   {
     error!(
         feed_id = %feed.id,
         errors = updated.consecutive_error_count,
-        max_errors = cfg.max_consecutive_errors,
+        max_errors,
         "Feed reached max consecutive errors"
     );
 
@@ -362,9 +447,37 @@ where
     );
 
     err_res?;
+
+    return Ok(None);
   }
 
-  Ok(())
+  Ok(Some(updated.next_action_at_ms))
+}
+
+/// Fits the next action to the feed's
+/// polling windows; error backoff is
+/// never shortened.
+fn apply_windows(
+  cfg: &AppConfig,
+  feed: &crate::domain::model::FeedConfig,
+  updated: &mut LinkState,
+  now_ms: i64,
+  rand: f64
+) {
+  updated.next_action_at_ms =
+    adjust_next_action(
+      cfg.windows.for_feed(
+        &feed.id,
+        &feed.category
+      ),
+      &cfg.timezone,
+      now_ms,
+      updated.next_action_at_ms,
+      updated.phase
+        != LinkPhase::ErrorBackoff,
+      feed.base_poll_seconds,
+      rand
+    );
 }
 
 fn is_immediate_error(
@@ -0,0 +1,78 @@
+//! Startup smoothing: after downtime
+//! every feed's `next_action_at_ms` is
+//! in the past, so the first load
+//! spreads the overdue ones over a
+//! window instead of dispatching them
+//! together.
+
+use crate::domain::model::CatchUpConfig;
+use crate::ports::repo::ScheduledFeed;
+
+/// Reschedules feeds overdue by more
+/// than `min_overdue_seconds` across
+/// `window_seconds`. The `n` feeds get
+/// equal slots in the order of their
+/// original `next_action_at_ms`, so
+/// the most overdue runs first (ties
+/// keep their load order), and
+/// `rand01` places each inside its own
+/// slot, so their later schedules
+/// (based on when they actually ran)
+/// stay spread too. Returns how many
+/// feeds moved.
+pub fn spread_overdue<F>(
+  feeds: &mut [ScheduledFeed],
+  now_ms: i64,
+  cfg: &CatchUpConfig,
+  mut rand01: F
+) -> usize
+where
+  F: FnMut() -> f64
+{
+  if !cfg.enabled {
+    return 0;
+  }
+
+  let threshold_ms = now_ms
+    - (cfg.min_overdue_seconds * 1000)
+      as i64;
+
+  let mut overdue: Vec<(i64, usize)> =
+    feeds
+      .iter()
+      .enumerate()
+      .filter_map(|(i, f)| {
+        f.next_action_at_ms
+          .filter(|at| {
+            *at < threshold_ms
+          })
+          .map(|at| (at, i))
+      })
+      .collect();
+
+  // Earliest due is most overdue.
+  overdue.sort_by_key(|&(at, _)| at);
+
+  let n = overdue.len();
+
+  if n == 0 {
+    return 0;
+  }
+
+  let slot_ms = (cfg.window_seconds
+    * 1000) as f64
+    / n as f64;
+
+  for (rank, (_, i)) in
+    overdue.into_iter().enumerate()
+  {
+    let offset = slot_ms
+      * (rank as f64
+        + rand01().clamp(0.0, 1.0));
+
+    feeds[i].next_action_at_ms =
+      Some(now_ms + offset as i64);
+  }
+
+  n
+}
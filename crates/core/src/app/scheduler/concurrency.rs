@@ -1,48 +1,70 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
-use tokio::sync::{
-  OwnedSemaphorePermit,
-  RwLock,
-  Semaphore
+use tokio::sync::RwLock;
+
+use super::permits::{
+  ClassedPermit,
+  ClassedSemaphore
+};
+use crate::domain::model::{
+  AppConfig,
+  FeedPriority
 };
+use crate::infra::admin::Controls;
 
-use crate::domain::model::AppConfig;
+/// A domain's semaphore and the limit
+/// it was created with.
+type DomainSlot =
+  (usize, Arc<ClassedSemaphore>);
 
 #[derive(Clone)]
 
 pub struct ConcurrencyGuards {
-  global:  Option<Arc<Semaphore>>,
+  global: Option<Arc<ClassedSemaphore>>,
   domains: Arc<
-    RwLock<
-      HashMap<String, Arc<Semaphore>>
-    >
+    RwLock<HashMap<String, DomainSlot>>
   >,
-  cfg:     Arc<AppConfig>
+  cfg:      Arc<AppConfig>,
+  controls: Arc<Controls>,
+  aging:    Option<Duration>
 }
 
 impl ConcurrencyGuards {
   pub fn new(
-    cfg: Arc<AppConfig>
+    cfg: Arc<AppConfig>,
+    controls: Arc<Controls>
   ) -> Self {
+    let aging =
+      Some(Duration::from_secs(
+        cfg.priority.aging_seconds
+      ));
+
     let mut per: HashMap<
       String,
-      Arc<Semaphore>
+      DomainSlot
     > = HashMap::new();
 
     for (domain, dcfg) in &cfg.domains {
+      let limit =
+        dcfg.max_concurrent_requests;
+
       per.insert(
         domain.clone(),
-        Arc::new(Semaphore::new(
-          dcfg.max_concurrent_requests
-        ))
+        (
+          limit,
+          ClassedSemaphore::new(
+            limit, aging
+          )
+        )
       );
     }
 
     let global = cfg
       .global_max_concurrent_requests
       .map(|n| {
-        Arc::new(Semaphore::new(n))
+        ClassedSemaphore::new(n, aging)
       });
 
     Self {
@@ -50,85 +72,108 @@ impl ConcurrencyGuards {
       domains: Arc::new(RwLock::new(
         per
       )),
-      cfg
+      cfg,
+      controls,
+      aging
     }
   }
 
+  /// Acquires the global and domain
+  /// permits, higher priority classes
+  /// first. When the admin API has
+  /// changed the domain's limit, the
+  /// semaphore is replaced; permits
+  /// held on the old one run out
+  /// naturally.
   pub async fn permit(
     &self,
-    domain: &str
+    domain: &str,
+    priority: FeedPriority
   ) -> PermitPair {
-    let maybe = {
+    let limit = self
+      .controls
+      .domain_limit(domain)
+      .or_else(|| {
+        self
+          .cfg
+          .domains
+          .get(domain)
+          .map(|d| {
+            d.max_concurrent_requests
+          })
+      })
+      .unwrap_or(1);
+
+    let current = {
       self
         .domains
         .read()
         .await
         .get(domain)
-        .cloned()
+        .filter(|(n, _)| *n == limit)
+        .map(|(_, s)| s.clone())
     };
 
-    let sem = if let Some(s) = maybe {
+    let sem = if let Some(s) = current {
       s
     } else {
-      let limit = self
-        .cfg
-        .domains
-        .get(domain)
-        .map(|d| {
-          d.max_concurrent_requests
-        })
-        .unwrap_or(1);
-
       let mut guard =
         self.domains.write().await;
 
-      guard
+      let slot = guard
         .entry(domain.to_string())
         .or_insert_with(|| {
-          Arc::new(Semaphore::new(
-            limit
-          ))
-        })
-        .clone()
+          (
+            limit,
+            ClassedSemaphore::new(
+              limit, self.aging
+            )
+          )
+        });
+
+      if slot.0 != limit {
+        *slot = (
+          limit,
+          ClassedSemaphore::new(
+            limit, self.aging
+          )
+        );
+      }
+
+      slot.1.clone()
     };
 
     PermitPair::acquire(
-      self.global.clone(),
-      sem
+      self.global.as_ref(),
+      &sem,
+      priority
     )
     .await
   }
 }
 
 pub struct PermitPair {
-  _g: Option<OwnedSemaphorePermit>,
-  _d: OwnedSemaphorePermit
+  _g: Option<ClassedPermit>,
+  _d: ClassedPermit
 }
 
 impl PermitPair {
   async fn acquire(
-    global: Option<Arc<Semaphore>>,
-    domain: Arc<Semaphore>
+    global: Option<
+      &Arc<ClassedSemaphore>
+    >,
+    domain: &Arc<ClassedSemaphore>,
+    priority: FeedPriority
   ) -> Self {
     let g = match global {
       | Some(s) => {
-        Some(
-          s.acquire_owned()
-            .await
-            .expect(
-              "global semaphore closed"
-            )
-        )
+        Some(s.acquire(priority).await)
       }
       | None => None
     };
 
-    let d = domain
-      .acquire_owned()
-      .await
-      .expect(
-        "domain semaphore closed"
-      );
+    let d =
+      domain.acquire(priority).await;
 
     Self {
       _g: g, _d: d
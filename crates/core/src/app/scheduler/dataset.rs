@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::{
+  debug,
+  warn
+};
+
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig,
+  GetResult
+};
+use crate::feed::dataset::{
+  RowChangeKind,
+  changes_to_feed,
+  diff_rows,
+  parse_rows
+};
+use crate::infra::metrics;
+use crate::ports::repo::{
+  DatasetVersion,
+  Repo
+};
+
+/// Stores a new dataset version when
+/// the body hash differs from the
+/// latest one and emits one item per
+/// changed row, both in one write. The
+/// first version is a baseline and
+/// produces no items.
+pub async fn ingest_dataset<R>(
+  cfg: &AppConfig,
+  repo: &Arc<R>,
+  feed: &FeedConfig,
+  res: &GetResult,
+  body: &[u8],
+  hash: &str,
+  now_ms: i64
+) -> Result<(), String>
+where
+  R: Repo + ?Sized
+{
+  let Some(dataset) =
+    feed.dataset.as_ref()
+  else {
+    return Ok(());
+  };
+
+  let started = Instant::now();
+
+  let prev_res = repo
+    .latest_dataset_version(&feed.id)
+    .await;
+
+  metrics::record_db_time(
+    "latest_dataset_version",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  let prev = prev_res?;
+
+  if prev.as_ref().is_some_and(|p| {
+    p.content_hash == hash
+  }) {
+    debug!(feed_id = %feed.id, "Dataset unchanged");
+
+    return Ok(());
+  }
+
+  let rows = match parse_rows(
+    body, dataset
+  ) {
+    | Ok(rows) => rows,
+    | Err(e) => {
+      warn!(feed_id = %feed.id, error = %e, "Failed to parse dataset");
+
+      return Ok(());
+    }
+  };
+
+  let changes = match prev.as_ref() {
+    | Some(p) => {
+      match parse_rows(&p.body, dataset)
+      {
+        | Ok(prev_rows) => {
+          diff_rows(&prev_rows, &rows)
+        }
+        | Err(e) => {
+          warn!(feed_id = %feed.id, error = %e, "Previous dataset version unreadable; storing as baseline");
+
+          Vec::new()
+        }
+      }
+    }
+    | None => Vec::new()
+  };
+
+  let parsed = changes_to_feed(
+    &changes,
+    hash,
+    rows.len(),
+    now_ms
+  );
+
+  let count = |kind| {
+    changes
+      .iter()
+      .filter(|c| c.kind == kind)
+      .count() as i64
+  };
+
+  let version = DatasetVersion {
+    feed_id:       feed.id.clone(),
+    fetched_at_ms: now_ms,
+    content_hash:  hash.to_string(),
+    body:          body.to_vec(),
+    row_count:     rows.len() as i64,
+    added_count:   count(
+      RowChangeKind::Added
+    ),
+    removed_count: count(
+      RowChangeKind::Removed
+    ),
+    changed_count: count(
+      RowChangeKind::Changed
+    )
+  };
+
+  let started = Instant::now();
+
+  let version_res = repo
+    .insert_dataset_version(
+      &version,
+      res.etag.as_deref(),
+      res.last_modified,
+      &parsed,
+      &cfg.timezone
+    )
+    .await;
+
+  metrics::record_db_time(
+    "insert_dataset_version",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  version_res?;
+
+  debug!(
+    feed_id = %feed.id,
+    rows = version.row_count,
+    added = version.added_count,
+    removed = version.removed_count,
+    changed = version.changed_count,
+    "Stored dataset version"
+  );
+
+  Ok(())
+}
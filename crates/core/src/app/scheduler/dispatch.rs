@@ -0,0 +1,593 @@
+//! Feed dispatch for the category
+//! loops: queue reloads, on-demand
+//! refresh requests, and the per-feed
+//! tasks that report back to the loop.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+use tracing::{
+  debug,
+  info,
+  warn
+};
+
+use super::catch_up::spread_overdue;
+use super::concurrency::ConcurrencyGuards;
+use super::drain::InFlight;
+use super::lease;
+use super::processing::process_feed;
+use super::queue::DueQueue;
+use super::supervisor::retry_transient;
+use crate::app::context::AppContext;
+use crate::domain::model::FeedConfig;
+use crate::infra::admin::Controls;
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::http::Http;
+use crate::ports::random::RandomSource;
+use crate::ports::repo::{
+  ClaimedFetch,
+  FetchRequestStatus,
+  Repo
+};
+
+/// How often each category loop checks
+/// `fetch_requests`.
+pub(super) const REQUEST_POLL:
+  Duration = Duration::from_secs(2);
+
+/// Requests left running this long are
+/// presumed orphaned by a fetcher that
+/// died and are claimed again.
+const REQUEST_STALE_MS: i64 = 600_000;
+
+pub(super) type Completion = (
+  FeedConfig,
+  Result<Option<i64>, String>
+);
+
+/// Feeds that came due while paused,
+/// held until the pause set changes.
+#[derive(Default)]
+
+pub(super) struct Parked {
+  feeds: HashMap<String, FeedConfig>,
+  generation: u64
+}
+
+impl Parked {
+  /// Parks the paused feeds in `due`
+  /// and returns the rest.
+  pub(super) fn filter(
+    &mut self,
+    controls: &Controls,
+    due: Vec<FeedConfig>
+  ) -> Vec<FeedConfig> {
+    due
+      .into_iter()
+      .filter_map(|feed| {
+        if controls.is_paused(&feed) {
+          debug!(feed_id = %feed.id, "Feed paused; parking");
+
+          self
+            .feeds
+            .insert(feed.id.clone(), feed);
+
+          None
+        } else {
+          Some(feed)
+        }
+      })
+      .collect()
+  }
+
+  /// After a pause change, queues the
+  /// parked feeds that are no longer
+  /// paused as due now.
+  pub(super) fn release(
+    &mut self,
+    controls: &Controls,
+    queue: &mut DueQueue,
+    now_ms: i64
+  ) {
+    let generation =
+      controls.generation();
+
+    if generation == self.generation {
+      return;
+    }
+
+    self.generation = generation;
+
+    let resumed: Vec<String> = self
+      .feeds
+      .values()
+      .filter(|f| {
+        !controls.is_paused(f)
+      })
+      .map(|f| f.id.clone())
+      .collect();
+
+    for id in resumed {
+      if let Some(feed) =
+        self.feeds.remove(&id)
+      {
+        queue.schedule(feed, now_ms);
+      }
+    }
+  }
+
+  /// A resync queues every feed again,
+  /// parked ones included.
+  pub(super) fn clear(&mut self) {
+    self.feeds.clear();
+  }
+}
+
+/// Reloads the category from the
+/// database, leaving in-flight feeds to
+/// be rescheduled by their completion.
+/// Feeds with no recorded state are due
+/// immediately. On the `first_load`
+/// overdue feeds are spread by the
+/// catch-up policy; later loads keep
+/// the queued slot of a feed whose
+/// stored time is overdue.
+pub(super) async fn resync<R, H, C, G>(
+  ctx: &AppContext<R, H, C, G>,
+  category: &str,
+  queue: &mut DueQueue,
+  in_flight: &InFlight,
+  first_load: bool
+) -> Result<(), String>
+where
+  R: Repo + ?Sized,
+  H: Http,
+  C: Clock,
+  G: RandomSource
+{
+  let started =
+    std::time::Instant::now();
+
+  let mut feeds = retry_transient(
+    "scheduled_feeds_for_category",
+    || {
+      ctx
+        .repo
+        .scheduled_feeds_for_category(
+          category
+        )
+    }
+  )
+  .await?;
+
+  metrics::record_db_time(
+    "scheduled_feeds_for_category",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  let now_ms =
+    ctx.clock.now_epoch_ms().await;
+
+  if first_load
+    && ctx.cfg.catch_up.enabled
+  {
+    let mut draws =
+      Vec::with_capacity(feeds.len());
+
+    for _ in 0..feeds.len() {
+      draws
+        .push(ctx.rng.next_f64().await);
+    }
+
+    let mut draws = draws.into_iter();
+
+    let spread = spread_overdue(
+      &mut feeds,
+      now_ms,
+      &ctx.cfg.catch_up,
+      || draws.next().unwrap_or(0.5)
+    );
+
+    if spread > 0 {
+      info!(
+        category,
+        spread,
+        window_seconds = ctx
+          .cfg
+          .catch_up
+          .window_seconds,
+        "Spreading overdue feeds \
+         after downtime"
+      );
+    }
+  }
+
+  let known: HashSet<String> = feeds
+    .iter()
+    .map(|s| s.feed.id.clone())
+    .collect();
+
+  queue.retain(|id| known.contains(id));
+
+  let mut due = 0;
+
+  for scheduled in feeds {
+    if in_flight
+      .contains_key(&scheduled.feed.id)
+    {
+      continue;
+    }
+
+    let stored = scheduled
+      .next_action_at_ms
+      .unwrap_or(now_ms);
+
+    // An overdue stored time is not
+    // newer than a slot the queue
+    // already holds, such as one from
+    // the catch-up spread, which only
+    // lives in memory.
+    let at_ms = if stored <= now_ms {
+      queue
+        .due_at(&scheduled.feed.id)
+        .unwrap_or(stored)
+    } else {
+      stored
+    };
+
+    if at_ms <= now_ms {
+      due += 1;
+    }
+
+    queue
+      .schedule(scheduled.feed, at_ms);
+  }
+
+  info!(
+    category,
+    queued = queue.len(),
+    due,
+    in_flight = in_flight.len(),
+    "Scheduler queue loaded"
+  );
+
+  Ok(())
+}
+
+/// Runs `feed` on its own task and
+/// reports back on `done`. A
+/// `request` id forces a GET and
+/// records its outcome on the
+/// `fetch_requests` row. The handle
+/// lets the loop abandon it at the
+/// shutdown deadline.
+pub(super) fn spawn_feed<R, H, C, G>(
+  ctx: &AppContext<R, H, C, G>,
+  concurrency: &ConcurrencyGuards,
+  feed: FeedConfig,
+  request: Option<i64>,
+  done: mpsc::UnboundedSender<
+    Completion
+  >
+) -> AbortHandle
+where
+  R: Repo + ?Sized + 'static,
+  H: Http + 'static,
+  C: Clock + 'static,
+  G: RandomSource + 'static
+{
+  let ctx = ctx.clone();
+
+  let concurrency = concurrency.clone();
+
+  tokio::spawn(async move {
+    let cfg = ctx.cfg.clone();
+
+    let repo = ctx.repo.clone();
+
+    let warn_after =
+      cfg.log_tick_warn_seconds;
+
+    let dispatched_ms =
+      ctx.clock.now_epoch_ms().await;
+
+    let started = Instant::now();
+
+    let leased_feed = feed.clone();
+
+    let work = lease::run_leased(
+      &cfg,
+      repo.as_ref(),
+      &ctx.writes,
+      &ctx.controls,
+      &leased_feed,
+      process_feed(
+        ctx.clone(),
+        concurrency,
+        feed.clone(),
+        request.is_some()
+      )
+    );
+
+    tokio::pin!(work);
+
+    let result = if warn_after > 0 {
+      tokio::select! {
+          result = &mut work => result,
+          _ = tokio::time::sleep(Duration::from_secs(warn_after)) => {
+              warn!(
+                  feed_id = %feed.id,
+                  domain = %feed.domain,
+                  elapsed_ms = started.elapsed().as_millis(),
+                  "Feed action still running"
+              );
+
+              work.await
+          }
+      }
+    } else {
+      work.await
+    };
+
+    if let Some(request_id) = request {
+      finish_request(
+        &ctx,
+        request_id,
+        &feed,
+        dispatched_ms,
+        &result
+      )
+      .await;
+    }
+
+    let _ = done.send((feed, result));
+  })
+  .abort_handle()
+}
+
+/// Claims refresh requests for feeds
+/// not already running, up to `limit`.
+/// Requests whose feed is leased by
+/// another fetcher go back to pending.
+pub(super) async fn claim_requests<
+  R,
+  H,
+  C,
+  G
+>(
+  ctx: &AppContext<R, H, C, G>,
+  category: &str,
+  in_flight: &InFlight,
+  limit: usize
+) -> Vec<ClaimedFetch>
+where
+  R: Repo + ?Sized,
+  H: Http,
+  C: Clock,
+  G: RandomSource
+{
+  if limit == 0 {
+    return Vec::new();
+  }
+
+  let now_ms =
+    ctx.clock.now_epoch_ms().await;
+
+  let busy: Vec<String> =
+    in_flight.keys().cloned().collect();
+
+  let started =
+    std::time::Instant::now();
+
+  let claimed = match ctx
+    .repo
+    .claim_fetch_requests(
+      category,
+      &busy,
+      limit,
+      now_ms,
+      now_ms - REQUEST_STALE_MS,
+      &ctx.cfg.timezone
+    )
+    .await
+  {
+    | Ok(claimed) => claimed,
+    | Err(e) => {
+      warn!(category, error = %e, "Claiming refresh requests failed");
+
+      return Vec::new();
+    }
+  };
+
+  metrics::record_db_time(
+    "claim_fetch_requests",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  if claimed.is_empty() {
+    return claimed;
+  }
+
+  let requests: HashMap<String, i64> =
+    claimed
+      .iter()
+      .map(|c| {
+        (
+          c.feed.id.clone(),
+          c.request_id
+        )
+      })
+      .collect();
+
+  let (owned, elsewhere) =
+    lease::claim(
+      &ctx.cfg,
+      ctx.repo.as_ref(),
+      claimed
+        .into_iter()
+        .map(|c| c.feed)
+        .collect()
+    )
+    .await;
+
+  if !elsewhere.is_empty() {
+    let ids: Vec<i64> = elsewhere
+      .iter()
+      .filter_map(|f| {
+        requests.get(&f.id).copied()
+      })
+      .collect();
+
+    if let Err(e) = ctx
+      .repo
+      .requeue_fetch_requests(&ids)
+      .await
+    {
+      warn!(category, error = %e, "Requeueing refresh requests failed");
+    }
+  }
+
+  owned
+    .into_iter()
+    .filter_map(|feed| {
+      Some(ClaimedFetch {
+        request_id: *requests
+          .get(&feed.id)?,
+        feed
+      })
+    })
+    .collect()
+}
+
+/// Records a forced fetch's outcome
+/// from the state `do_get` stored,
+/// still buffered or already written.
+/// A request only counts as done when
+/// a GET was recorded after dispatch
+/// and carried no error.
+async fn finish_request<R, H, C, G>(
+  ctx: &AppContext<R, H, C, G>,
+  request_id: i64,
+  feed: &FeedConfig,
+  dispatched_ms: i64,
+  result: &Result<Option<i64>, String>
+) where
+  R: Repo + ?Sized,
+  H: Http,
+  C: Clock,
+  G: RandomSource
+{
+  let (status, http_status, error) =
+    match result {
+      | Err(e) => {
+        (
+          FetchRequestStatus::Failed,
+          None,
+          Some(e.clone())
+        )
+      }
+      | Ok(_) => {
+        let stored = match ctx
+          .writes
+          .pending_state(&feed.id)
+        {
+          | Some(state) => {
+            Ok(Some((
+              state.last_get_at_ms,
+              state
+                .last_get_status
+                .map(i64::from),
+              state.last_get_error.map(
+                |e| format!("{e:?}")
+              )
+            )))
+          }
+          | None => {
+            ctx
+              .repo
+              .latest_state(&feed.id)
+              .await
+              .map(|row| {
+                row.map(|r| {
+                  (
+                    r.last_get_at_ms,
+                    r.last_get_status,
+                    r.last_get_error
+                  )
+                })
+              })
+          }
+        };
+
+        match stored {
+          | Ok(Some((
+            Some(at_ms),
+            http_status,
+            error
+          )))
+            if at_ms
+              >= dispatched_ms =>
+          {
+            let status = if error
+              .is_some()
+            {
+              FetchRequestStatus::Failed
+            } else {
+              FetchRequestStatus::Done
+            };
+
+            (status, http_status, error)
+          }
+          | Ok(_) => (
+            FetchRequestStatus::Failed,
+            None,
+            Some(
+              "no GET was recorded"
+                .to_string()
+            )
+          ),
+          | Err(e) => (
+            FetchRequestStatus::Failed,
+            None,
+            Some(e)
+          )
+        }
+      }
+    };
+
+  let finished_ms =
+    ctx.clock.now_epoch_ms().await;
+
+  info!(
+    feed_id = %feed.id,
+    request_id,
+    status = status.as_str(),
+    http_status,
+    "Refresh request finished"
+  );
+
+  if let Err(e) = ctx
+    .repo
+    .finish_fetch_request(
+      request_id,
+      status,
+      http_status,
+      error.as_deref(),
+      finished_ms,
+      &ctx.cfg.timezone
+    )
+    .await
+  {
+    warn!(feed_id = %feed.id, request_id, error = %e, "Recording refresh outcome failed");
+  }
+}
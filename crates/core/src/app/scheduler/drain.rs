@@ -0,0 +1,88 @@
+//! Actions a category loop is waiting
+//! on, and what becomes of those still
+//! running at the shutdown deadline.
+
+use std::collections::HashMap;
+
+use tokio::task::AbortHandle;
+use tracing::warn;
+
+use crate::app::context::AppContext;
+use crate::ports::clock::Clock;
+use crate::ports::http::Http;
+use crate::ports::random::RandomSource;
+use crate::ports::repo::Repo;
+
+/// A dispatched action's task and, for
+/// a refresh request, its id.
+pub(super) struct Running {
+  pub task:    AbortHandle,
+  pub request: Option<i64>
+}
+
+/// Running actions by feed id.
+pub(super) type InFlight =
+  HashMap<String, Running>;
+
+/// Stops the actions still running at
+/// the drain deadline. Their feeds keep
+/// the stored due time, so they run
+/// first after a restart; refresh
+/// requests go back to pending and
+/// leases are released so another
+/// fetcher can take the feeds at once.
+pub(super) async fn abandon<
+  R,
+  H,
+  C,
+  G
+>(
+  ctx: &AppContext<R, H, C, G>,
+  category: &str,
+  in_flight: InFlight
+) where
+  R: Repo + ?Sized,
+  H: Http,
+  C: Clock,
+  G: RandomSource
+{
+  let mut feed_ids = Vec::new();
+
+  let mut requests = Vec::new();
+
+  for (feed_id, running) in in_flight {
+    running.task.abort();
+
+    requests.extend(running.request);
+
+    feed_ids.push(feed_id);
+  }
+
+  warn!(
+    category,
+    feeds = ?feed_ids,
+    requests = ?requests,
+    "Abandoning actions at the shutdown deadline"
+  );
+
+  if !requests.is_empty()
+    && let Err(e) = ctx
+      .repo
+      .requeue_fetch_requests(&requests)
+      .await
+  {
+    warn!(category, error = %e, "Requeueing abandoned refresh requests failed");
+  }
+
+  if ctx.cfg.leasing.enabled
+    && let Err(e) = ctx
+      .repo
+      .release_feed_leases(
+        &ctx.cfg.leasing.owner,
+        &feed_ids
+      )
+      .await
+  {
+    warn!(category, error = %e, "Releasing abandoned leases failed");
+  }
+}
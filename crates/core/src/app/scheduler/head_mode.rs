@@ -0,0 +1,107 @@
+//! Per-domain view of which feeds have
+//! fallen back to conditional GETs.
+//!
+//! Only each feed's own decision is
+//! persisted, as `get_only_since_ms` in
+//! its state. This view lives in the
+//! scheduler's context and starts
+//! empty: it is rebuilt lazily from
+//! that state as feeds are processed,
+//! so after a restart a domain's other
+//! feeds may send HEAD once more until
+//! enough of its GET-only feeds have
+//! come due again.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::sync::RwLock;
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::HeadProbeConfig;
+
+/// GET-only feeds per domain.
+#[derive(Debug, Default)]
+
+pub struct HeadModes {
+  get_only: RwLock<
+    HashMap<String, HashSet<String>>
+  >
+}
+
+impl HeadModes {
+  /// Records whether `state`'s feed is
+  /// GET-only.
+  pub fn record(
+    &self,
+    domain: &str,
+    state: &LinkState
+  ) {
+    let Ok(mut map) =
+      self.get_only.write()
+    else {
+      return;
+    };
+
+    if state.get_only_since_ms.is_some()
+    {
+      map
+        .entry(domain.to_string())
+        .or_default()
+        .insert(state.feed_id.clone());
+    } else if let Some(feeds) =
+      map.get_mut(domain)
+    {
+      feeds.remove(&state.feed_id);
+    }
+  }
+
+  /// Whether a feed should skip HEAD
+  /// because enough of its domain has:
+  /// only feeds that are not GET-only
+  /// yet and have no HEAD of their own
+  /// within the re-test interval
+  /// qualify.
+  pub fn domain_says_get_only(
+    &self,
+    cfg: &HeadProbeConfig,
+    domain: &str,
+    state: &LinkState,
+    now_ms: i64
+  ) -> bool {
+    if cfg.domain_threshold == 0
+      || state
+        .get_only_since_ms
+        .is_some()
+    {
+      return false;
+    }
+
+    let retest_ms = (cfg.retest_seconds
+      * 1000)
+      as i64;
+
+    if state
+      .last_head_at_ms
+      .is_some_and(|at| {
+        now_ms - at < retest_ms
+      })
+    {
+      return false;
+    }
+
+    self
+      .get_only
+      .read()
+      .ok()
+      .and_then(|map| {
+        map
+          .get(domain)
+          .map(HashSet::len)
+      })
+      .is_some_and(|n| {
+        n >= cfg.domain_threshold
+      })
+  }
+}
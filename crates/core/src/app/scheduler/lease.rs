@@ -0,0 +1,175 @@
+//! Work leases around feed actions when
+//! several fetchers share one Postgres
+//! schema. A feed is only processed by
+//! the replica holding its lease.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tracing::warn;
+
+use crate::app::write_behind::WriteBehind;
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig
+};
+use crate::infra::admin::Controls;
+use crate::ports::repo::Repo;
+
+/// How often a replica waiting on a
+/// shared domain slot retries.
+const TOKEN_POLL: Duration =
+  Duration::from_millis(250);
+
+/// Splits `due` into feeds this replica
+/// claimed and feeds leased elsewhere.
+/// A failed claim defers everything.
+pub(super) async fn claim<R>(
+  cfg: &AppConfig,
+  repo: &R,
+  due: Vec<FeedConfig>
+) -> (Vec<FeedConfig>, Vec<FeedConfig>)
+where
+  R: Repo + ?Sized
+{
+  if !cfg.leasing.enabled
+    || due.is_empty()
+  {
+    return (due, Vec::new());
+  }
+
+  let ids: Vec<String> = due
+    .iter()
+    .map(|f| f.id.clone())
+    .collect();
+
+  match repo
+    .claim_feed_leases(
+      &cfg.leasing.owner,
+      &ids,
+      cfg.leasing.lease_seconds
+    )
+    .await
+  {
+    | Ok(claimed) => {
+      due.into_iter().partition(|f| {
+        claimed.contains(&f.id)
+      })
+    }
+    | Err(e) => {
+      warn!(error = %e, due = ids.len(), "Lease claim failed");
+
+      (Vec::new(), due)
+    }
+  }
+}
+
+/// Runs `work` under `feed`'s lease:
+/// waits for a shared domain slot when
+/// configured, renews the lease every
+/// third of its TTL, and releases it
+/// (with the slot) afterwards. Buffered
+/// writes are flushed first, so the
+/// next replica to claim the feed reads
+/// its new `next_action_at`.
+pub async fn run_leased<R, F>(
+  cfg: &AppConfig,
+  repo: &R,
+  writes: &WriteBehind,
+  controls: &Controls,
+  feed: &FeedConfig,
+  work: F
+) -> Result<Option<i64>, String>
+where
+  R: Repo + ?Sized,
+  F: Future<
+    Output = Result<
+      Option<i64>,
+      String
+    >
+  >
+{
+  if !cfg.leasing.enabled {
+    return work.await;
+  }
+
+  let lease = &cfg.leasing;
+
+  let leased = async {
+    if lease.shared_domain_limits {
+      let limit = controls
+        .domain_limit(&feed.domain)
+        .or_else(|| {
+          cfg
+            .domains
+            .get(&feed.domain)
+            .map(|d| {
+              d.max_concurrent_requests
+            })
+        })
+        .unwrap_or(1);
+
+      while !repo
+        .acquire_domain_token(
+          &feed.domain,
+          limit,
+          &lease.owner,
+          &feed.id,
+          lease.lease_seconds
+        )
+        .await?
+      {
+        tokio::time::sleep(TOKEN_POLL)
+          .await;
+      }
+    }
+
+    work.await
+  };
+
+  tokio::pin!(leased);
+
+  let mut renew = tokio::time::interval(
+    Duration::from_secs(
+      (lease.lease_seconds / 3).max(1)
+    )
+  );
+
+  renew.tick().await;
+
+  let result = loop {
+    tokio::select! {
+        result = &mut leased => break result,
+        _ = renew.tick() => {
+            match repo
+                .renew_feed_lease(&lease.owner, &feed.id, lease.lease_seconds)
+                .await
+            {
+                | Ok(true) => {}
+                | Ok(false) => {
+                    warn!(feed_id = %feed.id, "Feed lease lost while running");
+                }
+                | Err(e) => {
+                    warn!(feed_id = %feed.id, error = %e, "Lease renewal failed");
+                }
+            }
+        }
+    }
+  };
+
+  if let Err(e) = writes.flush().await {
+    warn!(feed_id = %feed.id, error = %e, "Write flush before lease release failed");
+  }
+
+  if let Err(e) = repo
+    .release_feed_leases(
+      &lease.owner,
+      std::slice::from_ref(&feed.id)
+    )
+    .await
+  {
+    warn!(feed_id = %feed.id, error = %e, "Lease release failed");
+  }
+
+  result
+}
@@ -1,7 +1,25 @@
 mod actions;
+mod catch_up;
 mod concurrency;
+mod dataset;
+mod dispatch;
+mod drain;
+pub mod head_mode;
+mod lease;
 mod orchestrator;
+mod permits;
 mod processing;
+mod queue;
 mod state;
+mod supervisor;
 
+pub use catch_up::spread_overdue;
+pub use dataset::ingest_dataset;
+pub use lease::run_leased;
 pub use orchestrator::Scheduler;
+pub use permits::ClassedSemaphore;
+pub use queue::DueQueue;
+pub use supervisor::{
+  is_transient_error,
+  restart_delay
+};
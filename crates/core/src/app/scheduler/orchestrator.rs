@@ -1,18 +1,57 @@
-use std::time::Instant;
+use std::collections::HashSet;
+use std::time::Duration;
 
 use futures::stream::{
   FuturesUnordered,
   StreamExt
 };
+use tokio::sync::{
+  mpsc,
+  watch
+};
+use tokio::time::Instant;
+use tracing::{
+  debug,
+  info,
+  warn
+};
 
 use super::concurrency::ConcurrencyGuards;
-use super::processing::run_tick;
+use super::dispatch::{
+  Completion,
+  Parked,
+  REQUEST_POLL,
+  claim_requests,
+  resync,
+  spawn_feed
+};
+use super::drain::{
+  InFlight,
+  Running,
+  abandon
+};
+use super::lease;
+use super::queue::DueQueue;
+use super::supervisor::supervise_category;
+use crate::app::archive::prune_forever;
 use crate::app::context::AppContext;
+use crate::app::{
+  health,
+  partitions,
+  retention
+};
+use crate::domain::model::SqlDialect;
+use crate::infra::metrics;
 use crate::ports::clock::Clock;
 use crate::ports::http::Http;
 use crate::ports::random::RandomSource;
 use crate::ports::repo::Repo;
 
+/// How often each category reloads its
+/// queue from the database.
+const RESYNC_INTERVAL: Duration =
+  Duration::from_secs(300);
+
 pub struct Scheduler;
 
 impl Scheduler {
@@ -23,7 +62,8 @@ impl Scheduler {
     G
   >(
     ctx: AppContext<R, H, C, G>,
-    categories: Vec<String>
+    categories: Vec<String>,
+    shutdown: watch::Receiver<bool>
   ) -> Result<(), String>
   where
     R: Repo + ?Sized + 'static,
@@ -39,39 +79,141 @@ impl Scheduler {
       );
     }
 
-    let mut handles =
+    let mut background =
       FuturesUnordered::new();
 
-    for category in categories {
-      let ctx = ctx.clone();
+    if ctx.cfg.health.enabled {
+      background.push(tokio::spawn(
+        health::run_forever(
+          ctx.cfg.clone(),
+          ctx.repo.clone(),
+          ctx.clock.clone()
+        )
+      ));
+    }
 
-      let name = category.clone();
+    if ctx.cfg.retention.enabled {
+      background.push(tokio::spawn(
+        retention::run_forever(
+          ctx.cfg.clone(),
+          ctx.repo.clone(),
+          ctx.clock.clone(),
+          categories.clone()
+        )
+      ));
+    }
 
-      handles.push(tokio::spawn(async move {
-                Scheduler::run_forever_category(ctx, name).await
-            }));
+    if ctx.cfg.db_dialect
+      == SqlDialect::Postgres
+    {
+      background.push(tokio::spawn(
+        partitions::run_forever(
+          ctx.cfg.clone(),
+          ctx.repo.clone(),
+          ctx.clock.clone(),
+          categories.clone()
+        )
+      ));
     }
 
-    while let Some(handle) =
-      handles.next().await
+    if ctx.cfg.archive.enabled
+      && ctx.cfg.archive.retention_days
+        > 0
     {
-      match handle {
-        | Ok(Ok(())) => {}
-        | Ok(Err(e)) => {
-          return Err(e);
-        }
-        | Err(e) => {
-          return Err(format!(
-            "category task join \
-             error: {e}"
-          ));
+      background.push(tokio::spawn(
+        prune_forever(
+          ctx.cfg.clone(),
+          ctx.repo.clone(),
+          ctx.clock.clone()
+        )
+      ));
+    }
+
+    ctx
+      .controls
+      .reload_pauses(ctx.repo.as_ref())
+      .await?;
+
+    background.push(tokio::spawn(
+      ctx
+        .controls
+        .clone()
+        .sync_forever(ctx.repo.clone())
+    ));
+
+    let mut loops =
+      FuturesUnordered::new();
+
+    for category in categories {
+      let ctx = ctx.clone();
+
+      let shutdown = shutdown.clone();
+
+      loops.push(tokio::spawn(
+        async move {
+          supervise_category(
+            ctx, category, shutdown
+          )
+          .await
         }
+      ));
+    }
+
+    // Once shutdown starts, every
+    // category is left to drain and the
+    // first failure is reported at the
+    // end.
+    let mut first_err = None;
+
+    while !loops.is_empty() {
+      tokio::select! {
+          Some(handle) = loops.next() => {
+              let result = match handle {
+                  | Ok(result) => result,
+                  | Err(e) => Err(format!("category task join error: {e}"))
+              };
+
+              if let Err(e) = result {
+                  if !*shutdown.borrow() {
+                      return Err(e);
+                  }
+
+                  first_err.get_or_insert(e);
+              }
+          }
+          Some(handle) = background.next() => {
+              match handle {
+                  | Ok(Ok(())) => {}
+                  | Ok(Err(e)) => return Err(e),
+                  | Err(e) => {
+                      return Err(format!("background task join error: {e}"));
+                  }
+              }
+          }
       }
     }
 
-    Ok(())
+    for handle in background.iter() {
+      handle.abort();
+    }
+
+    first_err.map_or(Ok(()), Err)
   }
 
+  /// Event loop for one category. Feeds
+  /// wait in an in-memory [`DueQueue`]
+  /// seeded from `feed_state_current`;
+  /// the loop sleeps until the earliest
+  /// one is due or an action completes,
+  /// and reloads from the database
+  /// every [`RESYNC_INTERVAL`] so
+  /// feed changes made elsewhere are
+  /// picked up. Refresh requests are
+  /// polled every [`REQUEST_POLL`] and
+  /// dispatched ahead of due feeds,
+  /// even for paused feeds; due feeds
+  /// that are paused are parked until
+  /// the pause is lifted.
   pub async fn run_forever_category<
     R,
     H,
@@ -79,7 +221,8 @@ impl Scheduler {
     G
   >(
     ctx: AppContext<R, H, C, G>,
-    category: String
+    category: String,
+    mut shutdown: watch::Receiver<bool>
   ) -> Result<(), String>
   where
     R: Repo + ?Sized + 'static,
@@ -87,33 +230,318 @@ impl Scheduler {
     C: Clock + 'static,
     G: RandomSource + 'static
   {
-    let tick_interval =
-      std::time::Duration::from_secs(5);
-
     let cfg = ctx.cfg.clone();
 
     let concurrency =
       ConcurrencyGuards::new(
-        cfg.clone()
+        cfg.clone(),
+        ctx.controls.clone()
       );
 
-    let mut interval =
-      tokio::time::interval(
-        tick_interval
+    let policy = cfg
+      .scheduling
+      .policy_for(&category);
+
+    let parallelism =
+      policy.parallelism.max(1);
+
+    let mut queue =
+      DueQueue::with_aging(
+        (cfg.priority.aging_seconds
+          * 1000) as i64
       );
 
+    let mut in_flight = InFlight::new();
+
+    let (done_tx, mut done_rx) =
+      mpsc::unbounded_channel::<
+        Completion
+      >();
+
+    resync(
+      &ctx, &category, &mut queue,
+      &in_flight, true
+    )
+    .await?;
+
+    let mut next_resync =
+      Instant::now() + RESYNC_INTERVAL;
+
+    let mut next_request_poll =
+      Instant::now();
+
+    // Forced feeds that were not queued
+    // (parked in `error_feeds`, or
+    // added since the last resync) are
+    // not rescheduled on completion.
+    let mut detached: HashSet<String> =
+      HashSet::new();
+
+    let mut parked = Parked::default();
+
+    let mut stopping =
+      *shutdown.borrow();
+
+    let mut drain_deadline = None;
+
     loop {
-      interval.tick().await;
+      if stopping
+        && drain_deadline.is_none()
+      {
+        info!(
+          category = %category,
+          in_flight = in_flight.len(),
+          "Draining in-flight actions"
+        );
 
-      let tick_started = Instant::now();
+        drain_deadline = Some(
+          Instant::now()
+            + Duration::from_secs(
+              cfg
+                .shutdown_drain_seconds
+            )
+        );
+      }
 
-      run_tick(
-        &ctx,
-        &concurrency,
-        tick_started,
-        &category
-      )
-      .await?;
+      if let Some(deadline) =
+        drain_deadline
+      {
+        if in_flight.is_empty() {
+          info!(category = %category, "Category drained");
+
+          return Ok(());
+        }
+
+        if Instant::now() >= deadline {
+          let abandoned =
+            in_flight.len();
+
+          abandon(
+            &ctx, &category, in_flight
+          )
+          .await;
+
+          return Err(format!(
+            "{category}: {abandoned} \
+             action(s) still in \
+             flight at the shutdown \
+             deadline"
+          ));
+        }
+
+        tokio::select! {
+            Some((feed, result)) = done_rx.recv() => {
+                in_flight.remove(&feed.id);
+
+                if let Err(e) = result {
+                    warn!(feed_id = %feed.id, error = %e, "process_feed failed");
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => {}
+        }
+
+        continue;
+      }
+
+      if Instant::now() >= next_resync {
+        // Parked feeds are only in the
+        // queue again once a resync
+        // has read them back.
+        match resync(
+          &ctx, &category, &mut queue,
+          &in_flight, false
+        )
+        .await
+        {
+          | Ok(()) => parked.clear(),
+          | Err(e) => {
+            warn!(category = %category, error = %e, "Scheduler resync failed");
+          }
+        }
+
+        next_resync = Instant::now()
+          + RESYNC_INTERVAL;
+      }
+
+      if Instant::now()
+        >= next_request_poll
+      {
+        let forced = claim_requests(
+          &ctx,
+          &category,
+          &in_flight,
+          parallelism.saturating_sub(
+            in_flight.len()
+          )
+        )
+        .await;
+
+        for claimed in forced {
+          let feed = claimed.feed;
+
+          if queue
+            .remove(&feed.id)
+            .is_none()
+          {
+            detached
+              .insert(feed.id.clone());
+          }
+
+          info!(
+            category = %category,
+            feed_id = %feed.id,
+            request_id = claimed.request_id,
+            "Dispatching refresh request"
+          );
+
+          let request =
+            Some(claimed.request_id);
+
+          in_flight.insert(
+            feed.id.clone(),
+            Running {
+              task: spawn_feed(
+                &ctx,
+                &concurrency,
+                feed,
+                request,
+                done_tx.clone()
+              ),
+              request
+            }
+          );
+        }
+
+        next_request_poll =
+          Instant::now() + REQUEST_POLL;
+      }
+
+      let now_ms =
+        ctx.clock.now_epoch_ms().await;
+
+      parked.release(
+        &ctx.controls,
+        &mut queue,
+        now_ms
+      );
+
+      let due = parked.filter(
+        &ctx.controls,
+        queue.pop_due(
+          now_ms,
+          parallelism
+            .saturating_sub(
+              in_flight.len()
+            )
+            .min(policy.due_batch_size)
+        )
+      );
+
+      metrics::record_queue_lag(
+        &category,
+        &queue.lag_by_priority(now_ms)
+      );
+
+      let (due, leased_elsewhere) =
+        lease::claim(
+          &cfg,
+          ctx.repo.as_ref(),
+          due
+        )
+        .await;
+
+      for feed in leased_elsewhere {
+        queue.schedule(
+          feed,
+          now_ms
+            + (cfg
+              .leasing
+              .lease_seconds
+              * 1000)
+              as i64
+        );
+      }
+
+      if !due.is_empty() {
+        metrics::record_tick(
+          &category,
+          due.len() as u64
+        );
+
+        debug!(
+          category = %category,
+          due = due.len(),
+          in_flight = in_flight.len(),
+          queued = queue.len(),
+          "Dispatching due feeds"
+        );
+      }
+
+      for feed in due {
+        in_flight.insert(
+          feed.id.clone(),
+          Running {
+            task:    spawn_feed(
+              &ctx,
+              &concurrency,
+              feed,
+              None,
+              done_tx.clone()
+            ),
+            request: None
+          }
+        );
+      }
+
+      let mut wake = next_resync
+        .min(next_request_poll);
+
+      if in_flight.len() < parallelism
+        && let Some(at_ms) =
+          queue.next_due_ms()
+      {
+        let wait_ms = (at_ms - now_ms)
+          .max(0)
+          as u64;
+
+        wake = wake.min(
+          Instant::now()
+            + Duration::from_millis(
+              wait_ms
+            )
+        );
+      }
+
+      tokio::select! {
+          Some((feed, result)) = done_rx.recv() => {
+              in_flight.remove(&feed.id);
+
+              if detached.remove(&feed.id) {
+                  if let Err(e) = result {
+                      warn!(feed_id = %feed.id, error = %e, "process_feed failed");
+                  }
+
+                  continue;
+              }
+
+              match result {
+                  | Ok(Some(at_ms)) => queue.schedule(feed, at_ms),
+                  | Ok(None) => {}
+                  | Err(e) => {
+                      warn!(feed_id = %feed.id, error = %e, "process_feed failed");
+
+                      let retry_ms = ctx.clock.now_epoch_ms().await
+                          + (policy.error_backoff_base_seconds * 1000) as i64;
+
+                      queue.schedule(feed, retry_ms);
+                  }
+              }
+          }
+          _ = tokio::time::sleep_until(wake) => {}
+          changed = shutdown.changed() => {
+              stopping = changed.is_err() || *shutdown.borrow();
+          }
+      }
     }
   }
 }
@@ -0,0 +1,188 @@
+//! A counting semaphore that serves
+//! waiters by priority class instead of
+//! arrival order.
+
+use std::collections::BTreeSet;
+use std::sync::{
+  Arc,
+  Mutex
+};
+use std::time::Duration;
+
+use tokio::sync::{
+  Notify,
+  Semaphore
+};
+use tokio::time::Instant;
+
+use crate::domain::model::FeedPriority;
+
+/// A waiter's class and arrival
+/// ticket; the smallest goes next.
+type Place = (usize, u64);
+
+/// Waiters are served highest class
+/// first, in arrival order within a
+/// class. With aging a waiter moves up
+/// one class per `aging` spent waiting,
+/// matching the due queue, so lower
+/// classes are delayed under saturation
+/// rather than starved.
+pub struct ClassedSemaphore {
+  permits:  Semaphore,
+  queue: Mutex<(u64, BTreeSet<Place>)>,
+  released: Notify,
+  aging:    Option<Duration>
+}
+
+/// Returns its permit on drop.
+pub struct ClassedPermit {
+  sem: Arc<ClassedSemaphore>
+}
+
+/// Holds a waiter's place in line
+/// until it is served or given up on.
+struct Waiter<'a> {
+  sem:   &'a ClassedSemaphore,
+  place: Place
+}
+
+impl ClassedSemaphore {
+  pub fn new(
+    permits: usize,
+    aging: Option<Duration>
+  ) -> Arc<Self> {
+    Arc::new(Self {
+      permits:  Semaphore::new(permits),
+      queue:    Mutex::default(),
+      released: Notify::new(),
+      aging:    aging
+        .filter(|a| !a.is_zero())
+    })
+  }
+
+  pub async fn acquire(
+    self: &Arc<Self>,
+    priority: FeedPriority
+  ) -> ClassedPermit {
+    let mut waiter =
+      Waiter::enter(self, priority);
+
+    let mut promote_at = self
+      .aging
+      .map(|a| Instant::now() + a);
+
+    loop {
+      let released =
+        self.released.notified();
+
+      tokio::pin!(released);
+
+      released.as_mut().enable();
+
+      if waiter.is_next()
+        && let Ok(permit) =
+          self.permits.try_acquire()
+      {
+        permit.forget();
+
+        drop(waiter);
+
+        return ClassedPermit {
+          sem: self.clone()
+        };
+      }
+
+      match promote_at {
+        | Some(at)
+          if waiter.place.0 > 0 =>
+        {
+          tokio::select! {
+            _ = released => {}
+            _ = tokio::time::sleep_until(at) => {
+              waiter.promote();
+
+              promote_at = self
+                .aging
+                .map(|a| at + a);
+            }
+          }
+        }
+        | _ => released.await
+      }
+    }
+  }
+
+  fn line(
+    &self
+  ) -> std::sync::MutexGuard<
+    '_,
+    (u64, BTreeSet<Place>)
+  > {
+    self.queue.lock().unwrap_or_else(
+      |e| e.into_inner()
+    )
+  }
+}
+
+impl Waiter<'_> {
+  fn enter(
+    sem: &ClassedSemaphore,
+    priority: FeedPriority
+  ) -> Waiter<'_> {
+    let mut line = sem.line();
+
+    line.0 += 1;
+
+    let place = (
+      priority.rank() as usize,
+      line.0
+    );
+
+    line.1.insert(place);
+
+    Waiter {
+      sem,
+      place
+    }
+  }
+
+  fn is_next(&self) -> bool {
+    self.sem.line().1.first()
+      == Some(&self.place)
+  }
+
+  /// Moves up a class, keeping the
+  /// ticket so arrival order still
+  /// breaks ties.
+  fn promote(&mut self) {
+    let mut line = self.sem.line();
+
+    line.1.remove(&self.place);
+
+    self.place.0 -= 1;
+
+    line.1.insert(self.place);
+  }
+}
+
+impl Drop for Waiter<'_> {
+  /// The next in line may go now.
+  fn drop(&mut self) {
+    self
+      .sem
+      .line()
+      .1
+      .remove(&self.place);
+
+    self.sem.released.notify_waiters();
+  }
+}
+
+impl Drop for ClassedPermit {
+  fn drop(&mut self) {
+    self.sem.permits.add_permits(1);
+
+    self.sem.released.notify_waiters();
+  }
+}
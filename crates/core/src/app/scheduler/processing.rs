@@ -1,10 +1,5 @@
-use std::sync::Arc;
 use std::time::Instant;
 
-use futures::{
-  StreamExt,
-  stream
-};
 use tracing::{
   debug,
   info,
@@ -22,8 +17,15 @@ use super::state::{
   to_link_state
 };
 use crate::app::context::AppContext;
-use crate::domain::link_state::LinkState;
+use crate::domain::link_state::{
+  LinkState,
+  NextAction
+};
 use crate::domain::model::FeedConfig;
+use crate::domain::poll_window::{
+  quiet_spread_ms,
+  quiet_until
+};
 use crate::infra::metrics;
 use crate::infra::time::format_epoch_ms;
 use crate::ports::clock::Clock;
@@ -31,205 +33,120 @@ use crate::ports::http::Http;
 use crate::ports::random::RandomSource;
 use crate::ports::repo::Repo;
 
-pub async fn run_tick<R, H, C, G>(
-  ctx: &AppContext<R, H, C, G>,
-  concurrency: &ConcurrencyGuards,
-  tick_started: Instant,
-  category: &str
-) -> Result<(), String>
-where
-  R: Repo + ?Sized + 'static,
-  H: Http + 'static,
-  C: Clock + 'static,
-  G: RandomSource + 'static
-{
-  let due_batch_size: i64 = 1000;
-
-  let default_parallelism: usize = 64;
-
-  let cfg = ctx.cfg.clone();
-
-  let now_ms =
-    ctx.clock.now_epoch_ms().await;
-
-  let due_started = Instant::now();
-
-  let due = ctx
-    .repo
-    .due_feeds_for_category(
-      category,
-      now_ms,
-      due_batch_size
-    )
-    .await?;
-
-  let due_elapsed =
-    due_started.elapsed();
-
-  metrics::record_tick(
-    category,
-    due.len() as u64
-  );
-
-  metrics::record_db_time(
-    "due_feeds_for_category",
-    due_elapsed.as_millis() as u64
-  );
-
-  info!(
-    tick_time = %format_epoch_ms(now_ms, &cfg.timezone),
-    category = category,
-    due = due.len(),
-    due_batch_limit = due_batch_size,
-    due_query_ms = due_elapsed.as_millis(),
-    "Scheduler tick"
-  );
-
-  let parallelism = cfg
-    .global_max_concurrent_requests
-    .unwrap_or(default_parallelism);
-
-  let repo = ctx.repo.clone();
-
-  let http = ctx.http.clone();
-
-  let clock = ctx.clock.clone();
-
-  let rng = ctx.rng.clone();
-
-  let warn_after =
-    cfg.log_tick_warn_seconds;
-
-  let tick_guard = if warn_after > 0 {
-    let (tx, rx) =
-      tokio::sync::oneshot::channel::<()>(
-      );
-
-    let category = category.to_string();
-
-    let due_count = due.len();
-
-    let timezone = cfg.timezone;
-
-    let tick_time = format_epoch_ms(
-      now_ms, &timezone
-    );
-
-    let started = tick_started;
-
-    tokio::spawn(async move {
-      let sleep = tokio::time::sleep(
-        std::time::Duration::from_secs(
-          warn_after
-        )
-      );
-
-      tokio::pin!(sleep);
-
-      tokio::select! {
-          _ = &mut sleep => {
-              warn!(
-                  category = category,
-                  due = due_count,
-                  tick_time = %tick_time,
-                  elapsed_ms = started.elapsed().as_millis(),
-                  "Scheduler tick still running"
-              );
-          }
-          _ = rx => {}
-      }
-    });
-
-    Some(tx)
-  } else {
-    None
-  };
-
-  stream::iter(due)
-        .map(|feed| {
-            let cfg = cfg.clone();
-            let repo = repo.clone();
-            let http = http.clone();
-            let clock = clock.clone();
-            let rng = rng.clone();
-            let concurrency = concurrency.clone();
-
-            async move {
-                if let Err(e) = process_feed(cfg, repo, http, clock, rng, concurrency, feed).await {
-                    warn!(error = %e, "process_feed failed");
-                }
-            }
-        })
-        .buffer_unordered(parallelism)
-        .collect::<Vec<_>>()
-        .await;
-
-  if let Some(tx) = tick_guard {
-    let _ = tx.send(());
-  }
-
-  info!(
-    tick_time = %format_epoch_ms(now_ms, &cfg.timezone),
-    category = category,
-    total_ms = tick_started.elapsed().as_millis(),
-    "Scheduler tick complete"
-  );
-
-  Ok(())
-}
-
-async fn process_feed<R, H, C, G>(
-  cfg: Arc<
-    crate::domain::model::AppConfig
-  >,
-  repo: Arc<R>,
-  http: Arc<H>,
-  clock: Arc<C>,
-  rng: Arc<G>,
+/// Runs whichever action is due for
+/// `feed` (a GET when `force_get`) and
+/// returns when it should run next, or
+/// `None` once the feed has been moved
+/// to `error_feeds`.
+pub(super) async fn process_feed<
+  R,
+  H,
+  C,
+  G
+>(
+  ctx: AppContext<R, H, C, G>,
   concurrency: ConcurrencyGuards,
-  feed: FeedConfig
-) -> Result<(), String>
+  feed: FeedConfig,
+  force_get: bool
+) -> Result<Option<i64>, String>
 where
   R: Repo + ?Sized,
   H: Http,
   C: Clock,
   G: RandomSource
 {
+  let AppContext {
+    cfg,
+    repo,
+    http,
+    clock,
+    rng,
+    writes,
+    head_modes,
+    ..
+  } = ctx;
+
   let now_ms =
     clock.now_epoch_ms().await;
 
   let rand = rng.next_f64().await;
 
-  let started = Instant::now();
-
-  let stored =
-    repo.latest_state(&feed.id).await?;
+  let policy = cfg
+    .scheduling
+    .policy_for(&feed.category);
+
+  let stored = match writes
+    .pending_state(&feed.id)
+  {
+    | Some(state) => Some(state),
+    | None => {
+      let started = Instant::now();
+
+      let row = repo
+        .latest_state(&feed.id)
+        .await?;
+
+      metrics::record_db_time(
+        "latest_state",
+        started.elapsed().as_millis()
+          as u64
+      );
 
-  metrics::record_db_time(
-    "latest_state",
-    started.elapsed().as_millis()
-      as u64
-  );
+      row.and_then(|r| {
+        to_link_state(&r, &policy)
+      })
+    }
+  };
 
-  let state = stored
-    .and_then(|r| {
-      to_link_state(&r, &cfg)
-    })
+  let mut state = stored
     .unwrap_or_else(|| {
       LinkState::initial(
         feed.id.clone(),
         feed.base_poll_seconds,
-        cfg.max_poll_seconds,
-        cfg.jitter_fraction,
+        policy.max_poll_seconds,
+        policy.jitter_fraction,
         now_ms
       )
+      .with_error_backoff(
+        policy
+          .error_backoff_base_seconds,
+        policy
+          .max_error_backoff_seconds
+      )
     });
 
-  let action =
-    LinkState::decide_next_action(
-      &state, now_ms
+  head_modes
+    .record(&feed.domain, &state);
+
+  if head_modes.domain_says_get_only(
+    &cfg.head_probe,
+    &feed.domain,
+    &state,
+    now_ms
+  ) {
+    debug!(
+      feed_id = %feed.id,
+      domain = %feed.domain,
+      "Domain mishandles HEAD, using conditional GET"
     );
 
+    state.get_only_since_ms =
+      Some(now_ms);
+  }
+
+  let action = if force_get {
+    NextAction::DoGet {
+      state
+    }
+  } else {
+    LinkState::decide_next_action(
+      &state,
+      now_ms,
+      (cfg.head_probe.retest_seconds
+        * 1000) as i64
+    )
+  };
+
   let log_feed_timing = cfg
     .log_feed_timing_enabled
     && (cfg
@@ -247,16 +164,47 @@ where
     "Decided next action"
   );
 
+  let windows = cfg
+    .windows
+    .for_feed(&feed.id, &feed.category);
+
+  if !force_get
+    && !matches!(
+      action,
+      NextAction::SleepUntil { .. }
+    )
+    && let Some(until) = quiet_until(
+      windows,
+      now_ms,
+      &cfg.timezone
+    )
+  {
+    let spread = quiet_spread_ms(
+      feed.base_poll_seconds,
+      rand
+    );
+
+    debug!(
+      feed_id = %feed.id,
+      until = %format_epoch_ms(until, &cfg.timezone),
+      "Quiet window, deferring"
+    );
+
+    return Ok(Some(until + spread));
+  }
+
   match action {
-        crate::domain::link_state::NextAction::SleepUntil { .. } => Ok(()),
+        crate::domain::link_state::NextAction::SleepUntil { at_ms } => Ok(Some(at_ms)),
         crate::domain::link_state::NextAction::DoHead { state } => {
             let record_history = should_record_history(&cfg, rng.as_ref()).await;
             let started = Instant::now();
             let res = do_head(
                 &cfg,
                 &repo,
+                &writes,
                 &http,
                 &concurrency,
+                &head_modes,
                 &feed,
                 state,
                 now_ms,
@@ -293,6 +241,7 @@ where
             let res = do_get(
                 &cfg,
                 &repo,
+                &writes,
                 &http,
                 &concurrency,
                 &feed,
@@ -0,0 +1,276 @@
+//! In-memory timer queue of feeds keyed
+//! by `next_action_at_ms`, one heap per
+//! priority class. Rescheduling pushes
+//! a new heap entry; superseded entries
+//! are skipped when they reach the top.
+
+use std::cmp::Reverse;
+use std::collections::{
+  BinaryHeap,
+  HashMap
+};
+
+use crate::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
+
+type Heap = BinaryHeap<
+  Reverse<(i64, u64, String)>
+>;
+
+/// Feeds waiting for their next
+/// action. Among due feeds the highest
+/// class goes first; with aging enabled
+/// a feed counts as due `rank *
+/// aging_ms` later, so a `Low` feed
+/// overdue by two aging periods beats a
+/// `High` one that just came due.
+#[derive(Default)]
+
+pub struct DueQueue {
+  heaps:    [Heap; 3],
+  /// Sequence, due time and config
+  /// of each queued feed.
+  live: HashMap<
+    String,
+    (u64, i64, FeedConfig)
+  >,
+  seq:      u64,
+  aging_ms: i64
+}
+
+impl DueQueue {
+  /// `aging_ms == 0` (the default)
+  /// keeps class ordering strict.
+  pub fn with_aging(
+    aging_ms: i64
+  ) -> Self {
+    Self {
+      aging_ms: aging_ms.max(0),
+      ..Self::default()
+    }
+  }
+
+  /// Schedules (or reschedules) `feed`
+  /// at `at_ms`.
+  pub fn schedule(
+    &mut self,
+    feed: FeedConfig,
+    at_ms: i64
+  ) {
+    self.seq += 1;
+
+    self.heaps
+      [feed.priority.rank() as usize]
+      .push(Reverse((
+        at_ms,
+        self.seq,
+        feed.id.clone()
+      )));
+
+    self.live.insert(
+      feed.id.clone(),
+      (self.seq, at_ms, feed)
+    );
+
+    let queued: usize = self
+      .heaps
+      .iter()
+      .map(BinaryHeap::len)
+      .sum();
+
+    if queued > self.live.len() * 2 + 64
+    {
+      self.compact();
+    }
+  }
+
+  pub fn remove(
+    &mut self,
+    feed_id: &str
+  ) -> Option<FeedConfig> {
+    self
+      .live
+      .remove(feed_id)
+      .map(|(_, _, feed)| feed)
+  }
+
+  /// Drops every queued feed whose id
+  /// fails `keep`.
+  pub fn retain(
+    &mut self,
+    keep: impl Fn(&str) -> bool
+  ) {
+    self.live.retain(|id, _| keep(id));
+  }
+
+  pub fn contains(
+    &self,
+    feed_id: &str
+  ) -> bool {
+    self.live.contains_key(feed_id)
+  }
+
+  /// When a queued feed is due.
+  pub fn due_at(
+    &self,
+    feed_id: &str
+  ) -> Option<i64> {
+    self
+      .live
+      .get(feed_id)
+      .map(|(_, at, _)| *at)
+  }
+
+  pub fn len(&self) -> usize {
+    self.live.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.live.is_empty()
+  }
+
+  /// Earliest due time, if any feed is
+  /// queued.
+  pub fn next_due_ms(
+    &mut self
+  ) -> Option<i64> {
+    (0..self.heaps.len())
+      .filter_map(|class| {
+        self.head(class)
+      })
+      .min()
+  }
+
+  /// Removes and returns up to `max`
+  /// feeds due at or before `now_ms`,
+  /// highest class first (subject to
+  /// aging), earliest first within a
+  /// class.
+  pub fn pop_due(
+    &mut self,
+    now_ms: i64,
+    max: usize
+  ) -> Vec<FeedConfig> {
+    let mut due = Vec::new();
+
+    while due.len() < max {
+      let next = (0..self.heaps.len())
+        .filter_map(|class| {
+          self
+            .head(class)
+            .filter(|at| *at <= now_ms)
+            .map(|at| {
+              (
+                self.order(class, at),
+                class
+              )
+            })
+        })
+        .min();
+
+      let Some((_, class)) = next
+      else {
+        break;
+      };
+
+      let Some(Reverse((_, _, id))) =
+        self.heaps[class].pop()
+      else {
+        break;
+      };
+
+      if let Some((_, _, feed)) =
+        self.live.remove(&id)
+      {
+        due.push(feed);
+      }
+    }
+
+    due
+  }
+
+  /// How long the oldest due feed of
+  /// each class has been waiting, in
+  /// milliseconds; `0` when none is
+  /// due.
+  pub fn lag_by_priority(
+    &mut self,
+    now_ms: i64
+  ) -> [(FeedPriority, i64); 3] {
+    FeedPriority::ALL.map(|p| {
+      let lag = self
+        .head(p.rank() as usize)
+        .map(|at| (now_ms - at).max(0))
+        .unwrap_or(0);
+
+      (p, lag)
+    })
+  }
+
+  /// Sort key among due heads: class
+  /// then due time when strict, aged
+  /// due time (then due time)
+  /// otherwise.
+  fn order(
+    &self,
+    class: usize,
+    at: i64
+  ) -> (i64, i64) {
+    if self.aging_ms == 0 {
+      return (class as i64, at);
+    }
+
+    (
+      at.saturating_add(
+        self.aging_ms * class as i64
+      ),
+      at
+    )
+  }
+
+  /// Due time of the live head of
+  /// `class`, dropping stale entries on
+  /// the way.
+  fn head(
+    &mut self,
+    class: usize
+  ) -> Option<i64> {
+    let live = &self.live;
+
+    let heap = &mut self.heaps[class];
+
+    while let Some(Reverse((
+      at,
+      seq,
+      id
+    ))) = heap.peek()
+    {
+      if live
+        .get(id)
+        .is_some_and(|(s, ..)| s == seq)
+      {
+        return Some(*at);
+      }
+
+      heap.pop();
+    }
+
+    None
+  }
+
+  fn compact(&mut self) {
+    let live = &self.live;
+
+    for heap in &mut self.heaps {
+      heap.retain(
+        |Reverse((_, seq, id))| {
+          live.get(id).is_some_and(
+            |(s, ..)| s == seq
+          )
+        }
+      );
+    }
+  }
+}
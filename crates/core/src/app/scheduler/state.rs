@@ -7,7 +7,8 @@ use crate::domain::link_state::{
 };
 use crate::domain::model::{
   AppConfig,
-  ErrorKind
+  ErrorKind,
+  SchedulingPolicy
 };
 use crate::infra::time::format_epoch_ms;
 use crate::ports::random::RandomSource;
@@ -40,7 +41,7 @@ pub fn describe_action(
 
 pub fn to_link_state(
   row: &StateRow,
-  cfg: &AppConfig
+  policy: &SchedulingPolicy
 ) -> Option<LinkState> {
   let phase = parse_phase(&row.phase)?;
 
@@ -74,10 +75,14 @@ pub fn to_link_state(
       .base_poll_seconds
       .max(0)
       as u64,
-    max_poll_seconds: cfg
+    max_poll_seconds: policy
       .max_poll_seconds,
-    jitter_fraction: cfg
+    jitter_fraction: policy
       .jitter_fraction,
+    error_base_seconds: policy
+      .error_backoff_base_seconds,
+    max_error_seconds: policy
+      .max_error_backoff_seconds,
     next_action_at_ms: row
       .next_action_at_ms,
     jitter_seconds: row.jitter_seconds,
@@ -85,7 +90,9 @@ pub fn to_link_state(
     consecutive_error_count: row
       .consecutive_error_count
       .max(0)
-      as u32
+      as u32,
+    get_only_since_ms: row
+      .get_only_since_ms
   })
 }
 
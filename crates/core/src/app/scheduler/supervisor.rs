@@ -0,0 +1,257 @@
+//! Per-category supervision: restarts a
+//! failed category loop with
+//! exponential backoff so one
+//! category's database trouble does not
+//! stop the others.
+
+use std::future::Future;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use tokio::sync::watch;
+use tracing::{
+  error,
+  warn
+};
+
+use super::orchestrator::Scheduler;
+use crate::app::context::AppContext;
+use crate::domain::model::{
+  FatalPolicy,
+  SupervisorConfig
+};
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::http::Http;
+use crate::ports::random::RandomSource;
+use crate::ports::repo::Repo;
+
+/// Lower-cased fragments of SQLite,
+/// Postgres and pool errors that are
+/// worth retrying as-is.
+const TRANSIENT_MARKERS: [&str; 11] = [
+  "database is locked",
+  "database table is locked",
+  "lock timeout",
+  "deadlock",
+  "could not serialize",
+  "timed out",
+  "pool timed out",
+  "connection reset",
+  "connection refused",
+  "broken pipe",
+  "too many clients"
+];
+
+const TRANSIENT_RETRIES: u32 = 3;
+
+const TRANSIENT_RETRY_BASE: Duration =
+  Duration::from_millis(200);
+
+/// Whether a repo error string looks
+/// like contention or a dropped
+/// connection rather than a bug or a
+/// schema problem.
+pub fn is_transient_error(
+  err: &str
+) -> bool {
+  let err = err.to_ascii_lowercase();
+
+  TRANSIENT_MARKERS
+    .iter()
+    .any(|m| err.contains(m))
+}
+
+/// Backoff before restart `attempt`
+/// (1-based): `restart_base_seconds`
+/// doubled per attempt, capped at
+/// `restart_max_seconds`.
+pub fn restart_delay(
+  cfg: &SupervisorConfig,
+  attempt: u32
+) -> Duration {
+  let factor = 1u64
+    << attempt
+      .saturating_sub(1)
+      .min(20);
+
+  Duration::from_secs(
+    cfg
+      .restart_base_seconds
+      .saturating_mul(factor)
+      .min(cfg.restart_max_seconds)
+  )
+}
+
+/// Runs `op`, retrying transient
+/// errors a few times with a short
+/// doubling pause.
+pub(super) async fn retry_transient<
+  T,
+  F,
+  Fut
+>(
+  label: &str,
+  mut op: F
+) -> Result<T, String>
+where
+  F: FnMut() -> Fut,
+  Fut:
+    Future<Output = Result<T, String>>
+{
+  let mut attempt = 0;
+
+  loop {
+    match op().await {
+      | Err(e)
+        if attempt
+          < TRANSIENT_RETRIES
+          && is_transient_error(&e) =>
+      {
+        attempt += 1;
+
+        warn!(op = label, attempt, error = %e, "Transient repo error, retrying");
+
+        tokio::time::sleep(
+          TRANSIENT_RETRY_BASE
+            * 2u32.pow(attempt - 1)
+        )
+        .await;
+      }
+      | result => return result
+    }
+  }
+}
+
+/// Keeps `category`'s scheduler loop
+/// alive until shutdown. A run that
+/// lasted at least
+/// `restart_max_seconds` resets the
+/// backoff and the fatal streak.
+pub(super) async fn supervise_category<
+  R,
+  H,
+  C,
+  G
+>(
+  ctx: AppContext<R, H, C, G>,
+  category: String,
+  mut shutdown: watch::Receiver<bool>
+) -> Result<(), String>
+where
+  R: Repo + ?Sized + 'static,
+  H: Http + 'static,
+  C: Clock + 'static,
+  G: RandomSource + 'static
+{
+  let policy =
+    ctx.cfg.supervisor.clone();
+
+  let mut attempt = 0;
+
+  let mut fatal_streak = 0;
+
+  loop {
+    let started = Instant::now();
+
+    let run = tokio::spawn(
+      Scheduler::run_forever_category(
+        ctx.clone(),
+        category.clone(),
+        shutdown.clone()
+      )
+    )
+    .await;
+
+    let err = match run {
+      | Ok(Ok(())) => return Ok(()),
+      | Ok(Err(e)) => e,
+      | Err(e) => {
+        format!(
+          "category task failed: {e}"
+        )
+      }
+    };
+
+    if *shutdown.borrow() {
+      return Err(err);
+    }
+
+    if started.elapsed()
+      >= Duration::from_secs(
+        policy.restart_max_seconds
+      )
+    {
+      attempt = 0;
+
+      fatal_streak = 0;
+    }
+
+    attempt += 1;
+
+    let transient =
+      is_transient_error(&err);
+
+    if !transient {
+      fatal_streak += 1;
+    }
+
+    if !transient
+      && policy.max_restarts > 0
+      && fatal_streak
+        > policy.max_restarts
+    {
+      error!(
+        category = %category,
+        error = %err,
+        failures = fatal_streak,
+        policy = ?policy.on_fatal,
+        "Category keeps failing, escalating"
+      );
+
+      match policy.on_fatal {
+        | FatalPolicy::Exit => {
+          return Err(format!(
+            "{category}: {err}"
+          ));
+        }
+        | FatalPolicy::StopCategory => {
+          while !*shutdown.borrow() {
+            if shutdown
+              .changed()
+              .await
+              .is_err()
+            {
+              break;
+            }
+          }
+
+          return Ok(());
+        }
+      }
+    }
+
+    let delay =
+      restart_delay(&policy, attempt);
+
+    metrics::record_category_restart(
+      &category
+    );
+
+    warn!(
+      category = %category,
+      error = %err,
+      transient,
+      attempt,
+      delay_ms = delay.as_millis(),
+      "Category loop failed, restarting"
+    );
+
+    tokio::select! {
+        _ = tokio::time::sleep(delay) => {}
+        _ = shutdown.changed() => return Ok(()),
+    }
+  }
+}
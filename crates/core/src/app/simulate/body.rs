@@ -0,0 +1,144 @@
+//! Response bodies for a feed version:
+//! RSS for feeds, rows in the
+//! configured format for datasets. One
+//! entry per change, newest first.
+
+use chrono::DateTime;
+use serde_json::{
+  Map,
+  Value,
+  json
+};
+
+use crate::domain::model::{
+  DatasetConfig,
+  DatasetFormat,
+  FeedConfig
+};
+
+/// Entries kept in a body, like a feed
+/// that only lists recent posts.
+const WINDOW: usize = 10;
+
+/// `changes` holds the change times up
+/// to the served version; version 0 is
+/// the content at `start_ms`.
+pub fn render(
+  feed: &FeedConfig,
+  start_ms: i64,
+  changes: &[i64]
+) -> Vec<u8> {
+  let skip = (changes.len() + 1)
+    .saturating_sub(WINDOW);
+
+  let mut entries =
+    std::iter::once(start_ms)
+      .chain(changes.iter().copied())
+      .enumerate()
+      .skip(skip)
+      .collect::<Vec<_>>();
+
+  entries.reverse();
+
+  match &feed.dataset {
+    | Some(dataset) => {
+      rows(dataset, &entries)
+    }
+    | None => rss(feed, &entries)
+  }
+}
+
+fn rss(
+  feed: &FeedConfig,
+  entries: &[(usize, i64)]
+) -> Vec<u8> {
+  let mut out = format!(
+    "<?xml version=\"1.0\"?><rss \
+     version=\"2.0\"\
+     ><channel><title>{}</\
+     title><link>{}</link>",
+    feed.id, feed.url
+  );
+
+  for (version, at_ms) in entries {
+    let published =
+      DateTime::from_timestamp_millis(
+        *at_ms
+      )
+      .unwrap_or_default()
+      .to_rfc2822();
+
+    out.push_str(&format!(
+      "<item><guid>{}-{version}</\
+       guid><title>Update \
+       {version}</title><link>{}#\
+       {version}</\
+       link><pubDate>{published}</\
+       pubDate></item>",
+      feed.id, feed.url
+    ));
+  }
+
+  out.push_str("</channel></rss>");
+
+  out.into_bytes()
+}
+
+fn rows(
+  dataset: &DatasetConfig,
+  entries: &[(usize, i64)]
+) -> Vec<u8> {
+  match dataset.format {
+    | DatasetFormat::Csv => {
+      let mut out = format!(
+        "{},updated_at_ms\n",
+        dataset.key
+      );
+
+      for (version, at_ms) in entries {
+        out.push_str(&format!(
+          "{version},{at_ms}\n"
+        ));
+      }
+
+      out.into_bytes()
+    }
+    | DatasetFormat::Json => {
+      let records = entries
+        .iter()
+        .map(|(version, at_ms)| {
+          let mut row = Map::new();
+
+          row.insert(
+            dataset.key.clone(),
+            json!(version)
+          );
+          row.insert(
+            "updated_at_ms".to_string(),
+            json!(at_ms)
+          );
+
+          Value::Object(row)
+        })
+        .collect::<Vec<_>>();
+
+      // Nest the array under the
+      // `records_path` JSON pointer.
+      let doc = dataset
+        .records_path
+        .as_deref()
+        .unwrap_or_default()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .rev()
+        .fold(
+          Value::Array(records),
+          |inner, key| {
+            json!({ key: inner })
+          }
+        );
+
+      doc.to_string().into_bytes()
+    }
+  }
+}
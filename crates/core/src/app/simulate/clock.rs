@@ -0,0 +1,38 @@
+//! `Clock` that follows tokio time, so
+//! a paused runtime fast-forwards it.
+
+use tokio::time::Instant;
+
+use crate::ports::clock::Clock;
+
+pub struct VirtualClock {
+  start_ms: i64,
+  origin:   Instant
+}
+
+impl VirtualClock {
+  /// Must be created inside the
+  /// simulation runtime.
+  pub fn new(start_ms: i64) -> Self {
+    Self {
+      start_ms,
+      origin: Instant::now()
+    }
+  }
+
+  pub fn now_ms(&self) -> i64 {
+    self.start_ms
+      + self
+        .origin
+        .elapsed()
+        .as_millis() as i64
+  }
+}
+
+#[async_trait::async_trait]
+
+impl Clock for VirtualClock {
+  async fn now_epoch_ms(&self) -> i64 {
+    self.now_ms()
+  }
+}
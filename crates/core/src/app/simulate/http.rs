@@ -0,0 +1,512 @@
+//! `Http` answered from the scenario
+//! instead of the network. Each feed
+//! draws its change times and request
+//! outcomes from its own seeded
+//! streams, so one feed's traffic does
+//! not shift another's script. Only
+//! requests started before the horizon
+//! are counted, so a feed due exactly
+//! at shutdown cannot change a report.
+
+use std::collections::{
+  BTreeMap,
+  HashMap
+};
+use std::sync::{
+  Arc,
+  Mutex,
+  MutexGuard,
+  PoisonError
+};
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::body;
+use super::clock::VirtualClock;
+use super::scenario::{
+  FeedModel,
+  Scenario
+};
+use super::script::FeedRun;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig,
+  GetResult,
+  HeadResult
+};
+use crate::ports::http::Http;
+
+const HOUR_MS: i64 = 3_600_000;
+
+/// Requests one feed's origin saw
+/// before the horizon.
+#[derive(Debug, Clone, Default)]
+
+pub struct FeedTraffic {
+  pub heads:        u64,
+  pub gets:         u64,
+  /// 304s to conditional GETs.
+  pub not_modified: u64,
+  pub errors:       u64,
+  /// Content changes before the
+  /// horizon.
+  pub changes:      u64,
+  /// Per fetched change, ms from the
+  /// change to the first GET that
+  /// returned it.
+  pub detections:   Vec<i64>
+}
+
+/// Requests seen by one domain.
+#[derive(Debug, Clone, Default)]
+
+pub struct DomainTraffic {
+  pub requests:       u64,
+  pub peak_in_flight: usize,
+  /// Requests started per simulated
+  /// hour since the start.
+  pub by_hour: BTreeMap<i64, u64>,
+  in_flight:          usize
+}
+
+struct SimFeed {
+  config: FeedConfig,
+  model:  FeedModel
+}
+
+#[derive(Clone, Copy)]
+
+enum Request<'a> {
+  Head,
+  Get,
+  Conditional {
+    etag:             Option<&'a str>,
+    last_modified_ms: Option<i64>
+  }
+}
+
+struct Reply {
+  status:        u16,
+  body:          Option<Vec<u8>>,
+  etag:          Option<String>,
+  last_modified: Option<i64>,
+  latency_ms:    u64
+}
+
+impl Reply {
+  fn status(
+    status: u16,
+    latency_ms: u64
+  ) -> Self {
+    Self {
+      status,
+      body: None,
+      etag: None,
+      last_modified: None,
+      latency_ms
+    }
+  }
+
+  fn error(&self) -> Option<ErrorKind> {
+    match self.status {
+      | s @ 400..500 => {
+        Some(ErrorKind::Http4xx(s))
+      }
+      | s @ 500..600 => {
+        Some(ErrorKind::Http5xx(s))
+      }
+      | _ => None
+    }
+  }
+}
+
+pub struct ScriptedHttp {
+  clock:    Arc<VirtualClock>,
+  start_ms: i64,
+  until_ms: i64,
+  feeds:    Vec<SimFeed>,
+  by_url:   HashMap<String, usize>,
+  runs:     Mutex<Vec<FeedRun>>,
+  domains: Mutex<
+    BTreeMap<String, DomainTraffic>
+  >
+}
+
+impl ScriptedHttp {
+  /// Feeds sharing a URL are served
+  /// from the first one's script.
+  pub fn new(
+    clock: Arc<VirtualClock>,
+    until_ms: i64,
+    feeds: &[FeedConfig],
+    scenario: &Scenario,
+    seed: u64
+  ) -> Self {
+    let start_ms = clock.now_ms();
+
+    let mut by_url = HashMap::new();
+
+    let mut sims = Vec::new();
+
+    let mut runs = Vec::new();
+
+    for feed in feeds {
+      if by_url.contains_key(&feed.url)
+      {
+        continue;
+      }
+
+      let model =
+        scenario.model_for(feed);
+
+      by_url.insert(
+        feed.url.clone(),
+        sims.len()
+      );
+
+      runs.push(FeedRun::new(
+        seed, &feed.id, &model,
+        start_ms
+      ));
+
+      sims.push(SimFeed {
+        config: feed.clone(),
+        model
+      });
+    }
+
+    Self {
+      clock,
+      start_ms,
+      until_ms,
+      feeds: sims,
+      by_url,
+      runs: Mutex::new(runs),
+      domains: Mutex::new(
+        BTreeMap::new()
+      )
+    }
+  }
+
+  /// Per-feed traffic.
+  pub fn feed_traffic(
+    &self
+  ) -> Vec<(FeedConfig, FeedTraffic)>
+  {
+    let now_ms = self.clock.now_ms();
+
+    let mut runs = self.runs();
+
+    self
+      .feeds
+      .iter()
+      .zip(runs.iter_mut())
+      .map(|(feed, run)| {
+        run
+          .advance(now_ms, &feed.model);
+
+        let changes = run
+          .changes
+          .iter()
+          .take_while(|&&at_ms| {
+            at_ms < self.until_ms
+          })
+          .count();
+
+        (
+          feed.config.clone(),
+          FeedTraffic {
+            changes: changes as u64,
+            ..run.traffic.clone()
+          }
+        )
+      })
+      .collect()
+  }
+
+  pub fn domain_traffic(
+    &self
+  ) -> BTreeMap<String, DomainTraffic>
+  {
+    self
+      .domains
+      .lock()
+      .unwrap_or_else(
+        PoisonError::into_inner
+      )
+      .clone()
+  }
+
+  fn runs(
+    &self
+  ) -> MutexGuard<'_, Vec<FeedRun>> {
+    self.runs.lock().unwrap_or_else(
+      PoisonError::into_inner
+    )
+  }
+
+  fn domain(
+    &self,
+    domain: &str,
+    start: bool
+  ) {
+    let mut domains = self
+      .domains
+      .lock()
+      .unwrap_or_else(
+        PoisonError::into_inner
+      );
+
+    let load = domains
+      .entry(domain.to_string())
+      .or_default();
+
+    if !start {
+      load.in_flight -= 1;
+
+      return;
+    }
+
+    let hour = (self.clock.now_ms()
+      - self.start_ms)
+      / HOUR_MS;
+
+    load.requests += 1;
+    load.in_flight += 1;
+    load.peak_in_flight = load
+      .peak_in_flight
+      .max(load.in_flight);
+
+    *load
+      .by_hour
+      .entry(hour)
+      .or_default() += 1;
+  }
+
+  /// The outcome is drawn when the
+  /// request starts; the content is the
+  /// version current when it completes.
+  async fn serve(
+    &self,
+    url: &str,
+    request: Request<'_>
+  ) -> Reply {
+    let Some(&idx) =
+      self.by_url.get(url)
+    else {
+      return Reply::status(404, 0);
+    };
+
+    let feed = &self.feeds[idx];
+
+    let counted = self.clock.now_ms()
+      < self.until_ms;
+
+    let (latency_ms, failed) = {
+      let mut runs = self.runs();
+
+      let rng =
+        &mut runs[idx].request_rng;
+
+      let spread =
+        0.5 + rng.random::<f64>();
+
+      (
+        (feed.model.latency_ms as f64
+          * spread) as u64,
+        rng.random::<f64>()
+          < feed.model.error_rate
+      )
+    };
+
+    if counted {
+      self.domain(
+        &feed.config.domain,
+        true
+      );
+    }
+
+    tokio::time::sleep(
+      Duration::from_millis(latency_ms)
+    )
+    .await;
+
+    if counted {
+      self.domain(
+        &feed.config.domain,
+        false
+      );
+    }
+
+    let tally = u64::from(counted);
+
+    let now_ms = self.clock.now_ms();
+
+    let mut runs = self.runs();
+
+    let run = &mut runs[idx];
+
+    run.advance(now_ms, &feed.model);
+
+    match request {
+      | Request::Head => {
+        run.traffic.heads += tally
+      }
+      | _ => run.traffic.gets += tally
+    }
+
+    if failed {
+      run.traffic.errors += tally;
+
+      return Reply::status(
+        feed.model.error_status,
+        latency_ms
+      );
+    }
+
+    let version = run.changes.len();
+
+    let etag = format!(
+      "\"{}-{version}\"",
+      feed.config.id
+    );
+
+    let last_modified = run
+      .changes
+      .last()
+      .copied()
+      .unwrap_or(self.start_ms);
+
+    let unchanged = match request {
+      | Request::Conditional {
+        etag: seen,
+        last_modified_ms
+      } => {
+        feed.model.conditional
+          && match seen {
+            | Some(seen) => {
+              seen == etag
+            }
+            | None => {
+              last_modified_ms
+                .is_some_and(|since| {
+                  since >= last_modified
+                })
+            }
+          }
+      }
+      | _ => false
+    };
+
+    let (status, body) = match request {
+      | Request::Head
+        if !feed.model.head =>
+      {
+        return Reply::status(
+          405, latency_ms
+        );
+      }
+      | Request::Head => (200, None),
+      | _ if unchanged => {
+        run.traffic.not_modified +=
+          tally;
+
+        (304, Some(Vec::new()))
+      }
+      | _ => {
+        let fresh = run.deliver(now_ms);
+
+        if counted {
+          run
+            .traffic
+            .detections
+            .extend(fresh);
+        }
+
+        (
+          200,
+          Some(body::render(
+            &feed.config,
+            self.start_ms,
+            &run.changes
+          ))
+        )
+      }
+    };
+
+    Reply {
+      status,
+      body,
+      etag: Some(etag),
+      last_modified: Some(
+        last_modified
+      ),
+      latency_ms
+    }
+  }
+}
+
+#[async_trait::async_trait]
+
+impl Http for ScriptedHttp {
+  async fn head(
+    &self,
+    url: &str
+  ) -> HeadResult {
+    let reply = self
+      .serve(url, Request::Head)
+      .await;
+
+    HeadResult {
+      status:        Some(reply.status),
+      etag:          reply.etag.clone(),
+      last_modified: reply
+        .last_modified,
+      error:         reply.error(),
+      latency_ms:    reply.latency_ms
+    }
+  }
+
+  async fn get(
+    &self,
+    url: &str
+  ) -> GetResult {
+    self
+      .serve(url, Request::Get)
+      .await
+      .into()
+  }
+
+  async fn get_conditional(
+    &self,
+    url: &str,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>
+  ) -> GetResult {
+    self
+      .serve(
+        url,
+        Request::Conditional {
+          etag,
+          last_modified_ms
+        }
+      )
+      .await
+      .into()
+  }
+}
+
+impl From<Reply> for GetResult {
+  fn from(reply: Reply) -> Self {
+    Self {
+      status:        Some(reply.status),
+      error:         reply.error(),
+      body:          reply.body,
+      etag:          reply.etag,
+      last_modified: reply
+        .last_modified,
+      latency_ms:    reply.latency_ms
+    }
+  }
+}
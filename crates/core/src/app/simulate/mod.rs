@@ -0,0 +1,211 @@
+//! Runs the real scheduler against a
+//! virtual clock, a scripted origin
+//! ([`ScriptedHttp`]) and an in-memory
+//! repo on a paused tokio runtime, so
+//! days of polling finish in seconds.
+//! The same config, scenario and seed
+//! give the same script of changes and
+//! failures.
+
+mod body;
+mod clock;
+mod http;
+mod report;
+mod scenario;
+mod script;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use clock::VirtualClock;
+pub use http::{
+  DomainTraffic,
+  FeedTraffic,
+  ScriptedHttp
+};
+pub use report::{
+  DomainReport,
+  FeedReport,
+  Latency,
+  SimReport
+};
+pub use scenario::{
+  FeedModel,
+  Scenario
+};
+use tokio::sync::watch;
+
+use crate::app::context::AppContext;
+use crate::app::scheduler::Scheduler;
+use crate::app::write_behind::WriteBehind;
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig
+};
+use crate::infra::memory_repo::MemoryRepo;
+use crate::infra::random::MutexRng;
+use crate::ports::repo::{
+  ControlRepo,
+  Repo
+};
+
+#[derive(Debug, Clone, Copy)]
+
+pub struct SimulationPlan {
+  /// Virtual time at the start.
+  pub start_ms: i64,
+  pub days:     u64,
+  /// Seeds the scheduler's jitter and
+  /// every feed's script.
+  pub seed:     u64
+}
+
+/// Blocks the calling thread on its own
+/// runtime; call it from
+/// `spawn_blocking` inside async code.
+pub fn run(
+  cfg: AppConfig,
+  categories: Vec<String>,
+  feeds: Vec<FeedConfig>,
+  scenario: &Scenario,
+  plan: SimulationPlan
+) -> Result<SimReport, String> {
+  tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .map_err(|e| format!("failed to build simulation runtime: {e}"))?
+        .block_on(simulate(cfg, categories, feeds, scenario, plan))
+}
+
+async fn simulate(
+  mut cfg: AppConfig,
+  categories: Vec<String>,
+  feeds: Vec<FeedConfig>,
+  scenario: &Scenario,
+  plan: SimulationPlan
+) -> Result<SimReport, String> {
+  // Archiving does not change what is
+  // scheduled, and a directory archive
+  // would be written to for real.
+  cfg.archive.enabled = false;
+
+  let clock = Arc::new(
+    VirtualClock::new(plan.start_ms)
+  );
+
+  let repo =
+    Arc::new(MemoryRepo::with_clock(
+      clock.clone()
+    ));
+
+  repo
+    .migrate(
+      &cfg.timezone,
+      cfg.default_poll_seconds
+    )
+    .await?;
+
+  repo
+    .upsert_categories(
+      categories.clone(),
+      &cfg.timezone
+    )
+    .await?;
+
+  let horizon = Duration::from_secs(
+    plan.days * 86_400
+  );
+
+  let end_ms = plan.start_ms
+    + horizon.as_millis() as i64;
+
+  let http =
+    Arc::new(ScriptedHttp::new(
+      clock.clone(),
+      end_ms,
+      &feeds,
+      scenario,
+      plan.seed
+    ));
+
+  repo
+    .upsert_feeds_bulk(
+      feeds,
+      10_000,
+      &cfg.timezone
+    )
+    .await?;
+
+  let cfg = Arc::new(cfg);
+
+  let writes = WriteBehind::spawn(
+    repo.clone(),
+    &cfg.write_behind,
+    cfg.timezone
+  );
+
+  let ctx = AppContext {
+    cfg:        cfg.clone(),
+    repo:       repo.clone(),
+    http:       http.clone(),
+    clock:      clock.clone(),
+    rng:        Arc::new(
+      MutexRng::seeded(plan.seed)
+    ),
+    writes:     writes.clone(),
+    controls:   Arc::default(),
+    head_modes: Arc::default()
+  };
+
+  let (shutdown_tx, shutdown_rx) =
+    watch::channel(false);
+
+  let mut scheduler = tokio::spawn(
+    Scheduler::run_forever_by_category(
+      ctx,
+      categories,
+      shutdown_rx
+    )
+  );
+
+  // The paused runtime jumps straight
+  // to the next timer whenever every
+  // task is idle.
+  let result = tokio::select! {
+      joined = &mut scheduler => joined,
+      _ = tokio::time::sleep(horizon) => {
+          let _ = shutdown_tx.send(true);
+
+          scheduler.await
+      }
+  }
+  .map_err(|e| {
+    format!(
+      "scheduler task join error: {e}"
+    )
+  })?;
+
+  writes.flush().await?;
+
+  result?;
+
+  let parked = repo
+    .error_feeds()
+    .await?
+    .into_iter()
+    .filter(|f| {
+      f.last_error_at_ms < end_ms
+    })
+    .map(|f| f.feed_id)
+    .collect::<HashSet<_>>();
+
+  Ok(report::build(
+    &http,
+    &parked,
+    plan.start_ms,
+    end_ms,
+    plan.seed
+  ))
+}
@@ -0,0 +1,159 @@
+//! What a simulation run observed, per
+//! feed and per domain.
+
+use std::collections::HashSet;
+
+use super::http::ScriptedHttp;
+use crate::app::health::percentile;
+
+/// Nearest-rank detection latency.
+#[derive(
+  Debug, Clone, Copy, Default,
+)]
+
+pub struct Latency {
+  pub p50_ms: Option<i64>,
+  pub p95_ms: Option<i64>,
+  pub max_ms: Option<i64>
+}
+
+impl Latency {
+  fn of(mut samples: Vec<i64>) -> Self {
+    samples.sort_unstable();
+
+    Self {
+      p50_ms: percentile(&samples, 50),
+      p95_ms: percentile(&samples, 95),
+      max_ms: samples.last().copied()
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+
+pub struct FeedReport {
+  pub feed_id:      String,
+  pub domain:       String,
+  pub category:     String,
+  pub heads:        u64,
+  pub gets:         u64,
+  pub not_modified: u64,
+  pub errors:       u64,
+  pub changes:      u64,
+  /// Changes fetched before the end.
+  pub detected:     u64,
+  pub detection:    Latency,
+  /// Moved to `error_feeds` by the
+  /// scheduler.
+  pub parked:       bool
+}
+
+impl FeedReport {
+  pub fn requests(&self) -> u64 {
+    self.heads + self.gets
+  }
+}
+
+#[derive(Debug, Clone)]
+
+pub struct DomainReport {
+  pub domain:           String,
+  pub requests:         u64,
+  pub requests_per_day: f64,
+  /// Most requests started within one
+  /// simulated hour.
+  pub peak_hour:        u64,
+  pub peak_in_flight:   usize
+}
+
+#[derive(Debug, Clone)]
+
+pub struct SimReport {
+  pub start_ms:  i64,
+  pub end_ms:    i64,
+  pub seed:      u64,
+  /// Sorted by feed id.
+  pub feeds:     Vec<FeedReport>,
+  /// Sorted by domain.
+  pub domains:   Vec<DomainReport>,
+  /// Over every detected change.
+  pub detection: Latency
+}
+
+pub(super) fn build(
+  http: &ScriptedHttp,
+  parked: &HashSet<String>,
+  start_ms: i64,
+  end_ms: i64,
+  seed: u64
+) -> SimReport {
+  let days = (end_ms - start_ms).max(1)
+    as f64
+    / 86_400_000.0;
+
+  let mut all = Vec::new();
+
+  let mut feeds = http
+    .feed_traffic()
+    .into_iter()
+    .map(|(feed, traffic)| {
+      all.extend(&traffic.detections);
+
+      FeedReport {
+        parked:       parked
+          .contains(&feed.id),
+        feed_id:      feed.id,
+        domain:       feed.domain,
+        category:     feed.category,
+        heads:        traffic.heads,
+        gets:         traffic.gets,
+        not_modified: traffic
+          .not_modified,
+        errors:       traffic.errors,
+        changes:      traffic.changes,
+        detected:     traffic
+          .detections
+          .len()
+          as u64,
+        detection:    Latency::of(
+          traffic.detections
+        )
+      }
+    })
+    .collect::<Vec<_>>();
+
+  feeds.sort_by(|a, b| {
+    a.feed_id.cmp(&b.feed_id)
+  });
+
+  let domains = http
+    .domain_traffic()
+    .into_iter()
+    .map(|(domain, load)| {
+      DomainReport {
+        domain,
+        requests: load.requests,
+        requests_per_day: load.requests
+          as f64
+          / days,
+        peak_hour: load
+          .by_hour
+          .values()
+          .copied()
+          .max()
+          .unwrap_or(0),
+        peak_in_flight: load
+          .peak_in_flight
+      }
+    })
+    .collect();
+
+  SimReport {
+    start_ms,
+    end_ms,
+    seed,
+    feeds,
+    domains,
+    detection: Latency::of(all)
+  }
+}
@@ -0,0 +1,228 @@
+//! The scripted origin behaviour: how
+//! often each feed changes, how often
+//! requests fail and how long they
+//! take. Feed entries win over domain
+//! entries, which win over `defaults`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::domain::model::FeedConfig;
+
+/// Behaviour of one simulated feed.
+#[derive(
+  Debug, Clone, Copy, PartialEq,
+)]
+
+pub struct FeedModel {
+  /// Mean minutes between content
+  /// changes (exponentially
+  /// distributed); 0 never changes.
+  pub change_every_minutes: f64,
+  /// Share of requests answered with
+  /// `error_status`.
+  pub error_rate:           f64,
+  pub error_status:         u16,
+  /// Mean response time; each request
+  /// takes 0.5x to 1.5x of it.
+  pub latency_ms:           u64,
+  /// Whether HEAD is answered with
+  /// validators; `false` answers 405.
+  pub head:                 bool,
+  /// Whether conditional GETs of
+  /// unchanged content get a 304.
+  pub conditional:          bool
+}
+
+impl Default for FeedModel {
+  fn default() -> Self {
+    Self {
+      change_every_minutes: 60.0,
+      error_rate:           0.01,
+      error_status:         503,
+      latency_ms:           300,
+      head:                 true,
+      conditional:          true
+    }
+  }
+}
+
+#[derive(
+  Debug, Clone, Default, Deserialize,
+)]
+#[serde(deny_unknown_fields)]
+struct RawModel {
+  change_every_minutes: Option<f64>,
+  error_rate:           Option<f64>,
+  error_status:         Option<u16>,
+  latency_ms:           Option<u64>,
+  head:                 Option<bool>,
+  conditional:          Option<bool>
+}
+
+impl RawModel {
+  fn over(
+    &self,
+    base: FeedModel
+  ) -> FeedModel {
+    FeedModel {
+      change_every_minutes: self
+        .change_every_minutes
+        .unwrap_or(
+          base.change_every_minutes
+        ),
+      error_rate:           self
+        .error_rate
+        .unwrap_or(base.error_rate),
+      error_status:         self
+        .error_status
+        .unwrap_or(base.error_status),
+      latency_ms:           self
+        .latency_ms
+        .unwrap_or(base.latency_ms),
+      head:                 self
+        .head
+        .unwrap_or(base.head),
+      conditional:          self
+        .conditional
+        .unwrap_or(base.conditional)
+    }
+  }
+}
+
+#[derive(
+  Debug, Default, Deserialize,
+)]
+#[serde(deny_unknown_fields)]
+struct RawScenario {
+  defaults: Option<RawModel>,
+  #[serde(default)]
+  domains:  HashMap<String, RawModel>,
+  #[serde(default)]
+  feeds:    HashMap<String, RawModel>
+}
+
+/// Domain and feed entries of a
+/// scenario file.
+#[derive(Debug, Clone, Default)]
+
+pub struct Scenario {
+  defaults: FeedModel,
+  domains:  HashMap<String, FeedModel>,
+  feeds:    HashMap<String, RawModel>
+}
+
+impl Scenario {
+  pub fn load(
+    path: &Path
+  ) -> Result<Self, String> {
+    let raw =
+      std::fs::read_to_string(path)
+        .map_err(|e| {
+          format!(
+            "failed to read scenario \
+             {}: {e}",
+            path.display()
+          )
+        })?;
+
+    Self::parse(&raw).map_err(|e| {
+      format!(
+        "invalid scenario {}: {e}",
+        path.display()
+      )
+    })
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    let raw: RawScenario =
+      toml::from_str(raw)
+        .map_err(|e| e.to_string())?;
+
+    let defaults = raw
+      .defaults
+      .unwrap_or_default()
+      .over(FeedModel::default());
+
+    check(&defaults, "defaults")?;
+
+    let mut domains = HashMap::new();
+
+    for (name, m) in raw.domains {
+      let model = m.over(defaults);
+
+      check(&model, &name)?;
+
+      domains.insert(name, model);
+    }
+
+    // Feed entries are checked against
+    // the defaults; their values are
+    // laid over the feed's domain in
+    // `model_for`.
+    for (id, m) in &raw.feeds {
+      check(&m.over(defaults), id)?;
+    }
+
+    Ok(Self {
+      defaults,
+      domains,
+      feeds: raw.feeds
+    })
+  }
+
+  pub fn model_for(
+    &self,
+    feed: &FeedConfig
+  ) -> FeedModel {
+    let base = self
+      .domains
+      .get(&feed.domain)
+      .copied()
+      .unwrap_or(self.defaults);
+
+    match self.feeds.get(&feed.id) {
+      | Some(m) => m.over(base),
+      | None => base
+    }
+  }
+}
+
+fn check(
+  model: &FeedModel,
+  name: &str
+) -> Result<(), String> {
+  let every =
+    model.change_every_minutes;
+
+  if !every.is_finite() || every < 0.0 {
+    return Err(format!(
+      "{name}: change_every_minutes \
+       must be a number >= 0"
+    ));
+  }
+
+  if !(0.0..=1.0)
+    .contains(&model.error_rate)
+  {
+    return Err(format!(
+      "{name}: error_rate must be \
+       within [0, 1]"
+    ));
+  }
+
+  if !(400..600)
+    .contains(&model.error_status)
+  {
+    return Err(format!(
+      "{name}: error_status must be a \
+       4xx or 5xx code"
+    ));
+  }
+
+  Ok(())
+}
@@ -0,0 +1,136 @@
+//! One feed's script: a Poisson
+//! process of content changes and the
+//! random stream its request outcomes
+//! are drawn from.
+
+use rand::rngs::StdRng;
+use rand::{
+  Rng,
+  SeedableRng
+};
+
+use super::http::FeedTraffic;
+use super::scenario::FeedModel;
+use crate::domain::hashing::sha256_hex;
+
+pub(super) struct FeedRun {
+  /// Change times so far.
+  pub changes:     Vec<i64>,
+  next_change_ms:  i64,
+  change_rng:      StdRng,
+  pub request_rng: StdRng,
+  /// Changes already returned by a
+  /// GET.
+  delivered:       usize,
+  pub traffic:     FeedTraffic
+}
+
+impl FeedRun {
+  pub fn new(
+    seed: u64,
+    feed_id: &str,
+    model: &FeedModel,
+    start_ms: i64
+  ) -> Self {
+    let mut change_rng =
+      stream(seed, feed_id, "change");
+
+    let first =
+      gap_ms(&mut change_rng, model);
+
+    Self {
+      changes: Vec::new(),
+      next_change_ms: start_ms
+        .saturating_add(first),
+      change_rng,
+      request_rng: stream(
+        seed, feed_id, "request"
+      ),
+      delivered: 0,
+      traffic: FeedTraffic::default()
+    }
+  }
+
+  /// Records the changes made up to
+  /// `now_ms`.
+  pub fn advance(
+    &mut self,
+    now_ms: i64,
+    model: &FeedModel
+  ) {
+    while self.next_change_ms <= now_ms
+    {
+      self
+        .changes
+        .push(self.next_change_ms);
+
+      self.next_change_ms = self
+        .next_change_ms
+        .saturating_add(gap_ms(
+          &mut self.change_rng,
+          model
+        ));
+    }
+  }
+
+  /// Latency of each change not
+  /// returned by an earlier GET.
+  pub fn deliver(
+    &mut self,
+    now_ms: i64
+  ) -> Vec<i64> {
+    let fresh = self.changes
+      [self.delivered..]
+      .iter()
+      .map(|at_ms| now_ms - at_ms)
+      .collect();
+
+    self.delivered = self.changes.len();
+
+    fresh
+  }
+}
+
+/// Exponential gap with the model's
+/// mean; `i64::MAX` when the feed never
+/// changes.
+fn gap_ms(
+  rng: &mut StdRng,
+  model: &FeedModel
+) -> i64 {
+  if model.change_every_minutes <= 0.0 {
+    return i64::MAX;
+  }
+
+  let u = rng.random::<f64>();
+
+  let mean_ms = model
+    .change_every_minutes
+    * 60_000.0;
+
+  ((-(1.0 - u).ln() * mean_ms) as i64)
+    .max(1)
+}
+
+/// Independent stream per seed, feed
+/// and purpose.
+fn stream(
+  seed: u64,
+  feed_id: &str,
+  purpose: &str
+) -> StdRng {
+  let digest = sha256_hex(
+    format!(
+      "{seed}:{purpose}:{feed_id}"
+    )
+    .as_bytes()
+  );
+
+  StdRng::seed_from_u64(
+    u64::from_str_radix(
+      &digest[..16],
+      16
+    )
+    .unwrap_or(seed)
+  )
+}
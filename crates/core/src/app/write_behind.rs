@@ -0,0 +1,462 @@
+//! Write-behind buffer for fetch events
+//! and state snapshots. Actions queue
+//! their writes on a channel; one task
+//! flushes them with
+//! [`Repo::write_batch`] per
+//! `batch_size` rows or `flush_ms`,
+//! whichever comes first. Snapshots not
+//! yet flushed are served from memory
+//! so a feed never reads its own state
+//! back stale. A batch that keeps
+//! failing stays queued and is retried
+//! every `flush_ms`, up to
+//! [`MAX_HELD_BATCHES`] batches' worth
+//! of rows.
+
+use std::collections::HashMap;
+use std::sync::atomic::{
+  AtomicU64,
+  Ordering
+};
+use std::sync::{
+  Arc,
+  Mutex
+};
+use std::time::Duration;
+
+use chrono_tz::Tz;
+use tokio::sync::{
+  mpsc,
+  oneshot
+};
+use tokio::time::Instant;
+use tracing::{
+  error,
+  warn
+};
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::WriteBehindConfig;
+use crate::infra::metrics;
+use crate::ports::repo::{
+  PendingEvent,
+  PendingState,
+  Repo,
+  WriteBatch
+};
+
+/// Attempts per flush before the batch
+/// is held for the next one.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Held rows beyond this many batches
+/// are dropped and reported.
+const MAX_HELD_BATCHES: usize = 10;
+
+type Pending = Arc<
+  Mutex<
+    HashMap<String, (u64, LinkState)>
+  >
+>;
+
+enum Op {
+  Event(PendingEvent),
+  State(u64, PendingState),
+  Flush(
+    oneshot::Sender<Result<(), String>>
+  )
+}
+
+#[derive(Clone)]
+
+pub struct WriteBehind {
+  tx:      mpsc::Sender<Op>,
+  pending: Pending,
+  seq:     Arc<AtomicU64>,
+  sync:    bool
+}
+
+impl WriteBehind {
+  /// Starts the flush task. With
+  /// `[write_behind] enabled = false`
+  /// every write waits for its flush,
+  /// as direct writes did.
+  pub fn spawn<R>(
+    repo: Arc<R>,
+    wb: &WriteBehindConfig,
+    zone: Tz
+  ) -> Self
+  where
+    R: Repo + ?Sized + 'static
+  {
+    let (tx, rx) =
+      mpsc::channel(wb.batch_size * 4);
+
+    let pending = Pending::default();
+
+    tokio::spawn(
+      Flusher {
+        repo,
+        zone,
+        pending: pending.clone(),
+        batch: WriteBatch::default(),
+        seqs: Vec::new(),
+        held: None,
+        dropped: None,
+        limit: wb.batch_size
+          * MAX_HELD_BATCHES
+      }
+      .run(
+        rx,
+        wb.batch_size,
+        Duration::from_millis(
+          wb.flush_ms
+        )
+      )
+    );
+
+    Self {
+      tx,
+      pending,
+      seq: Arc::default(),
+      sync: !wb.enabled
+    }
+  }
+
+  pub async fn event(
+    &self,
+    event: PendingEvent
+  ) -> Result<(), String> {
+    self.send(Op::Event(event)).await
+  }
+
+  pub async fn state(
+    &self,
+    state: PendingState
+  ) -> Result<(), String> {
+    let seq = self
+      .seq
+      .fetch_add(1, Ordering::Relaxed);
+
+    if let Ok(mut map) =
+      self.pending.lock()
+    {
+      map.insert(
+        state.state.feed_id.clone(),
+        (seq, state.state.clone())
+      );
+    }
+
+    self
+      .send(Op::State(seq, state))
+      .await
+  }
+
+  /// The newest snapshot for `feed_id`
+  /// still waiting to be flushed.
+  pub fn pending_state(
+    &self,
+    feed_id: &str
+  ) -> Option<LinkState> {
+    self.pending.lock().ok().and_then(
+      |map| {
+        map
+          .get(feed_id)
+          .map(|(_, s)| s.clone())
+      }
+    )
+  }
+
+  /// Writes everything queued so far;
+  /// fails while a batch is held after
+  /// failing, or when rows were dropped
+  /// since the last flush.
+  pub async fn flush(
+    &self
+  ) -> Result<(), String> {
+    let (ack, done) =
+      oneshot::channel();
+
+    self
+      .tx
+      .send(Op::Flush(ack))
+      .await
+      .map_err(|_| closed())?;
+
+    done.await.map_err(|_| closed())?
+  }
+
+  async fn send(
+    &self,
+    op: Op
+  ) -> Result<(), String> {
+    self
+      .tx
+      .send(op)
+      .await
+      .map_err(|_| closed())?;
+
+    if self.sync {
+      self.flush().await
+    } else {
+      Ok(())
+    }
+  }
+}
+
+fn closed() -> String {
+  "write-behind task stopped"
+    .to_string()
+}
+
+struct Flusher<R: Repo + ?Sized> {
+  repo:    Arc<R>,
+  zone:    Tz,
+  pending: Pending,
+  batch:   WriteBatch,
+  seqs:    Vec<(String, u64)>,
+  /// Last error while `batch` is held
+  /// after failing.
+  held:    Option<String>,
+  dropped: Option<String>,
+  limit:   usize
+}
+
+impl<R: Repo + ?Sized> Flusher<R> {
+  async fn run(
+    mut self,
+    mut rx: mpsc::Receiver<Op>,
+    batch_size: usize,
+    every: Duration
+  ) {
+    let mut deadline: Option<Instant> =
+      None;
+
+    loop {
+      let op = match deadline {
+        | Some(at) => {
+          tokio::select! {
+              op = rx.recv() => op,
+              _ = tokio::time::sleep_until(at) => {
+                  self.flush().await;
+                  deadline = self.retry_at(every);
+                  continue;
+              }
+          }
+        }
+        | None => rx.recv().await
+      };
+
+      match op {
+        | None => {
+          self.flush().await;
+
+          if let Some(e) = &self.held {
+            error!(
+              error = %e,
+              rows = self.batch.len(),
+              "Write-behind stopped with unwritten rows"
+            );
+          }
+
+          return;
+        }
+        | Some(Op::Event(event)) => {
+          self.batch.events.push(event);
+        }
+        | Some(Op::State(
+          seq,
+          state
+        )) => {
+          self.seqs.push((
+            state.state.feed_id.clone(),
+            seq
+          ));
+
+          self.batch.states.push(state);
+        }
+        | Some(Op::Flush(ack)) => {
+          self.flush().await;
+
+          deadline =
+            self.retry_at(every);
+
+          let _ =
+            ack.send(self.outcome());
+
+          continue;
+        }
+      }
+
+      // A held batch waits for the
+      // timer instead of retrying on
+      // every write.
+      if self.held.is_none()
+        && self.batch.len()
+          >= batch_size
+      {
+        self.flush().await;
+
+        deadline = self.retry_at(every);
+      } else if deadline.is_none() {
+        deadline =
+          Some(Instant::now() + every);
+      }
+    }
+  }
+
+  /// Keeps the timer running while a
+  /// batch is held.
+  fn retry_at(
+    &self,
+    every: Duration
+  ) -> Option<Instant> {
+    self
+      .held
+      .is_some()
+      .then(|| Instant::now() + every)
+  }
+
+  fn outcome(
+    &mut self
+  ) -> Result<(), String> {
+    if let Some(e) = self.dropped.take()
+    {
+      return Err(e);
+    }
+
+    match &self.held {
+      | Some(e) => {
+        Err(format!(
+          "{} rows held after a \
+           failed write: {e}",
+          self.batch.len()
+        ))
+      }
+      | None => Ok(())
+    }
+  }
+
+  async fn flush(&mut self) {
+    if self.batch.is_empty() {
+      return;
+    }
+
+    let batch =
+      std::mem::take(&mut self.batch);
+
+    let seqs =
+      std::mem::take(&mut self.seqs);
+
+    let mut attempt = 1;
+
+    let failure = loop {
+      let started =
+        std::time::Instant::now();
+
+      let res = self
+        .repo
+        .write_batch(&batch, &self.zone)
+        .await;
+
+      metrics::record_db_time(
+        "write_batch",
+        started.elapsed().as_millis()
+          as u64
+      );
+
+      match res {
+        | Ok(()) => break None,
+        | Err(e)
+          if attempt < MAX_ATTEMPTS =>
+        {
+          warn!(
+            error = %e,
+            attempt,
+            rows = batch.len(),
+            "Write batch failed, retrying"
+          );
+
+          tokio::time::sleep(
+            Duration::from_millis(
+              500 << attempt
+            )
+          )
+          .await;
+
+          attempt += 1;
+        }
+        | Err(e) => break Some(e)
+      }
+    };
+
+    let Some(e) = failure else {
+      self.held = None;
+
+      self.release(seqs);
+
+      return;
+    };
+
+    if batch.len() > self.limit {
+      error!(
+        error = %e,
+        rows = batch.len(),
+        "Dropping held write batch"
+      );
+
+      self.dropped = Some(format!(
+        "dropped {} rows after failed \
+         writes: {e}",
+        batch.len()
+      ));
+
+      self.held = None;
+
+      self.release(seqs);
+
+      return;
+    }
+
+    error!(
+      error = %e,
+      rows = batch.len(),
+      "Write batch failed, holding it for the next flush"
+    );
+
+    // Nothing arrives while flushing,
+    // so the held rows go back as they
+    // were and new ones queue after
+    // them; their snapshots stay
+    // pending meanwhile.
+    self.batch = batch;
+
+    self.seqs = seqs;
+
+    self.held = Some(e);
+  }
+
+  /// Stops serving the flushed (or
+  /// dropped) snapshots, except ones
+  /// overwritten since they were
+  /// queued.
+  fn release(
+    &self,
+    seqs: Vec<(String, u64)>
+  ) {
+    let Ok(mut map) =
+      self.pending.lock()
+    else {
+      return;
+    };
+
+    for (feed_id, seq) in seqs {
+      if map
+        .get(&feed_id)
+        .is_some_and(|(s, _)| *s == seq)
+      {
+        map.remove(&feed_id);
+      }
+    }
+  }
+}
@@ -2,6 +2,9 @@
 //! feed: decides next actions, applies
 //! HEAD/GET results, and computes
 //! exponential backoff with jitter.
+//! Feeds whose origin mishandles HEAD
+//! switch to conditional GETs, with a
+//! periodic HEAD re-test.
 
 use crate::domain::model::{
   ErrorKind,
@@ -44,11 +47,24 @@ pub struct LinkState {
   pub max_poll_seconds:  u64,
   pub jitter_fraction:   f64,
 
+  /// Error backoff: the first failure
+  /// waits `error_base_seconds`, each
+  /// further one doubles it, up to
+  /// `max_error_seconds`.
+  pub error_base_seconds: u64,
+  pub max_error_seconds:  u64,
+
   pub next_action_at_ms: i64,
   pub jitter_seconds:    i64,
 
   pub note: Option<String>,
-  pub consecutive_error_count: u32
+  pub consecutive_error_count: u32,
+
+  /// Set while HEAD is considered
+  /// unreliable for this feed: polls
+  /// use conditional GETs until a
+  /// re-test HEAD behaves.
+  pub get_only_since_ms: Option<i64>
 }
 
 #[derive(Debug, Clone)]
@@ -82,16 +98,41 @@ impl LinkState {
       base_poll_seconds,
       max_poll_seconds,
       jitter_fraction,
+      error_base_seconds:
+        base_poll_seconds,
+      max_error_seconds:
+        max_poll_seconds,
       next_action_at_ms: now_ms,
       jitter_seconds: 0,
       note: Some("initial".to_string()),
-      consecutive_error_count: 0
+      consecutive_error_count: 0,
+      get_only_since_ms: None
     }
   }
 
+  /// Uses the given error backoff
+  /// instead of the poll interval.
+  pub fn with_error_backoff(
+    mut self,
+    base_seconds: u64,
+    max_seconds: u64
+  ) -> Self {
+    self.error_base_seconds =
+      base_seconds;
+
+    self.max_error_seconds =
+      max_seconds;
+
+    self
+  }
+
+  /// `head_retest_ms` is how long a
+  /// GET-only feed waits before HEAD is
+  /// tried again.
   pub fn decide_next_action(
     state: &LinkState,
-    now_ms: i64
+    now_ms: i64,
+    head_retest_ms: i64
   ) -> NextAction {
     if now_ms < state.next_action_at_ms
     {
@@ -107,6 +148,17 @@ impl LinkState {
           state: state.clone()
         }
       }
+      | _ if state
+        .get_only_since_ms
+        .is_some_and(|since| {
+          now_ms
+            < since + head_retest_ms
+        }) =>
+      {
+        NextAction::DoGet {
+          state: state.clone()
+        }
+      }
       | LinkPhase::NeedsHead => {
         NextAction::DoHead {
           state: state.clone()
@@ -124,12 +176,52 @@ impl LinkState {
     }
   }
 
+  /// Switches the feed to conditional
+  /// GETs and makes the next one due
+  /// immediately.
+  pub fn mark_get_only(
+    &mut self,
+    now_ms: i64,
+    note: String
+  ) {
+    self.phase = LinkPhase::NeedsGet;
+
+    self.get_only_since_ms =
+      Some(now_ms);
+
+    self.next_action_at_ms = now_ms;
+
+    self.jitter_seconds = 0;
+
+    self.note = Some(note);
+  }
+
   pub fn apply_head_result(
     mut state: LinkState,
     result: HeadResult,
     now_ms: i64,
     rand01: f64
   ) -> LinkState {
+    if head_unreliable(&result) {
+      state.last_head_at_ms =
+        Some(now_ms);
+
+      state.last_head_status =
+        result.status;
+
+      state.last_head_error = None;
+
+      state.mark_get_only(
+        now_ms,
+        format!(
+          "head-unreliable-{}",
+          result.status.unwrap_or(0)
+        )
+      );
+
+      return state;
+    }
+
     let modified =
       has_changed(&state, &result);
 
@@ -182,11 +274,10 @@ impl LinkState {
       )
     };
 
-    let delay = compute_delay_seconds(
-      state.base_poll_seconds,
+    let delay = state.delay(
+      is_error,
       backoff_idx,
-      state.max_poll_seconds,
-      state.jitter_fraction,
+      consecutive_error_count,
       rand01
     );
 
@@ -233,6 +324,18 @@ impl LinkState {
     state.consecutive_error_count =
       consecutive_error_count;
 
+    if !is_error
+      && state
+        .get_only_since_ms
+        .take()
+        .is_some()
+    {
+      state.note =
+        state.note.map(|n| {
+          format!("head-restored-{n}")
+        });
+    }
+
     state
   }
 
@@ -292,11 +395,10 @@ impl LinkState {
       )
     };
 
-    let delay = compute_delay_seconds(
-      state.base_poll_seconds,
+    let delay = state.delay(
+      is_error,
       backoff_idx,
-      state.max_poll_seconds,
-      state.jitter_fraction,
+      consecutive_error_count,
       rand01
     );
 
@@ -332,13 +434,72 @@ impl LinkState {
     state.jitter_seconds =
       delay.jitter_seconds;
 
-    state.note = note;
+    state.note = if state
+      .get_only_since_ms
+      .is_some()
+    {
+      note.map(|n| {
+        format!("get-only-{n}")
+      })
+    } else {
+      note
+    };
 
     state.consecutive_error_count =
       consecutive_error_count;
 
     state
   }
+
+  /// Errors back off from the error
+  /// base by consecutive failures;
+  /// other results from the poll
+  /// interval by the backoff index.
+  fn delay(
+    &self,
+    is_error: bool,
+    backoff_idx: u32,
+    consecutive_errors: u32,
+    rand01: f64
+  ) -> Delay {
+    if is_error {
+      compute_delay_seconds(
+        self.error_base_seconds,
+        consecutive_errors
+          .saturating_sub(1),
+        self.max_error_seconds,
+        self.jitter_fraction,
+        rand01
+      )
+    } else {
+      compute_delay_seconds(
+        self.base_poll_seconds,
+        backoff_idx,
+        self.max_poll_seconds,
+        self.jitter_fraction,
+        rand01
+      )
+    }
+  }
+}
+
+/// HEAD answers that cannot be used to
+/// detect change: refused (403, 405,
+/// 501) or a 2xx without `ETag` and
+/// `Last-Modified`.
+pub fn head_unreliable(
+  result: &HeadResult
+) -> bool {
+  match result.status {
+    | Some(403 | 405 | 501) => true,
+    | Some(200..=299) => {
+      result.etag.is_none()
+        && result
+          .last_modified
+          .is_none()
+    }
+    | _ => false
+  }
 }
 
 fn has_changed(
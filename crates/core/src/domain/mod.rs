@@ -1,7 +1,9 @@
 //! Core domain types and logic:
 //! configuration models, link-state
-//! machine, and hashing helpers.
+//! machine, polling windows and
+//! hashing helpers.
 
 pub mod hashing;
 pub mod link_state;
 pub mod model;
+pub mod poll_window;
@@ -11,6 +11,8 @@ use serde::{
   Serialize
 };
 
+use super::poll_window::PollWindows;
+
 #[derive(
   Debug, Clone, Serialize, Deserialize,
 )]
@@ -24,8 +26,10 @@ pub struct DomainConfig {
 )]
 
 pub struct CategoryConfig {
-  pub name:    String,
-  pub domains: Vec<String>
+  pub name:     String,
+  pub domains:  Vec<String>,
+  #[serde(default)]
+  pub priority: FeedPriority
 }
 
 #[derive(
@@ -41,7 +45,112 @@ pub struct FeedConfig {
   pub provenance:        Option<String>,
   pub tags: Option<Vec<String>>,
   pub language:          Option<String>,
-  pub content_type:      Option<String>
+  pub content_type:      Option<String>,
+  pub dataset: Option<DatasetConfig>,
+  #[serde(default)]
+  pub priority:          FeedPriority
+}
+
+/// Dispatch class: under backlog, due
+/// `High` feeds go before `Normal`
+/// before `Low`. Stored in `feeds` as
+/// its rank.
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  PartialEq,
+  Eq,
+  Hash,
+  Serialize,
+  Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedPriority {
+  High,
+  #[default]
+  Normal,
+  Low
+}
+
+impl FeedPriority {
+  pub const ALL: [Self; 3] = [
+    Self::High,
+    Self::Normal,
+    Self::Low
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | Self::High => "high",
+      | Self::Normal => "normal",
+      | Self::Low => "low"
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "high" => Ok(Self::High),
+      | "normal" => Ok(Self::Normal),
+      | "low" => Ok(Self::Low),
+      | other => {
+        Err(format!(
+          "invalid priority '{other}'"
+        ))
+      }
+    }
+  }
+
+  /// 0 for `High`; lower is served
+  /// first.
+  pub fn rank(&self) -> i64 {
+    match self {
+      | Self::High => 0,
+      | Self::Normal => 1,
+      | Self::Low => 2
+    }
+  }
+
+  /// Unknown ranks read back as
+  /// `Normal`.
+  pub fn from_rank(rank: i64) -> Self {
+    match rank {
+      | 0 => Self::High,
+      | 2 => Self::Low,
+      | _ => Self::Normal
+    }
+  }
+}
+
+/// Structured dataset source settings:
+/// rows are keyed by `key` and diffed
+/// between fetched versions.
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+
+pub struct DatasetConfig {
+  pub format:       DatasetFormat,
+  pub key:          String,
+  pub records_path: Option<String>
+}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  Serialize,
+  Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+  Csv,
+  Json
 }
 
 #[derive(
@@ -89,6 +198,22 @@ pub struct AppConfig {
   pub log_feed_timing_warn_ms: u64,
   pub log_feed_timing_log_all: bool,
   pub metrics: MetricsConfig,
+  pub admin: AdminConfig,
+  pub archive: ArchiveConfig,
+  pub retention: RetentionConfig,
+  pub health: HealthConfig,
+  pub supervisor: SupervisorConfig,
+  pub leasing: LeasingConfig,
+  pub catch_up: CatchUpConfig,
+  pub head_probe: HeadProbeConfig,
+  pub write_behind: WriteBehindConfig,
+  pub partitions: PartitionsConfig,
+  pub priority: PriorityConfig,
+  pub scheduling: SchedulingConfig,
+  pub windows: PollWindows,
+  /// How long a SIGINT/SIGTERM waits
+  /// for in-flight fetches to finish.
+  pub shutdown_drain_seconds: u64,
   pub mode: AppMode,
   pub timezone: Tz,
   pub domains:
@@ -103,6 +228,273 @@ pub struct MetricsConfig {
   pub bind:    String
 }
 
+/// Authenticated control API for
+/// operators; see `infra::admin`.
+#[derive(Debug, Clone)]
+
+pub struct AdminConfig {
+  pub enabled: bool,
+  pub bind:    String,
+  pub token:   String
+}
+
+/// Postgres work leases so several
+/// fetchers can share one schema. Each
+/// due feed is claimed by `owner` for
+/// `lease_seconds` (renewed while the
+/// action runs); with
+/// `shared_domain_limits` per-domain
+/// concurrency is also enforced across
+/// replicas.
+#[derive(Debug, Clone)]
+
+pub struct LeasingConfig {
+  pub enabled:              bool,
+  pub owner:                String,
+  pub lease_seconds:        u64,
+  pub shared_domain_limits: bool
+}
+
+/// Startup smoothing after downtime:
+/// feeds overdue by more than
+/// `min_overdue_seconds` are spread
+/// over `window_seconds`, most overdue
+/// first, instead of all firing at
+/// once.
+#[derive(Debug, Clone)]
+
+pub struct CatchUpConfig {
+  pub enabled:             bool,
+  pub window_seconds:      u64,
+  pub min_overdue_seconds: u64
+}
+
+/// Starvation protection for priority
+/// classes: a waiting feed gains one
+/// class per `aging_seconds` past due.
+/// `0` keeps ordering strict.
+#[derive(Debug, Clone)]
+
+pub struct PriorityConfig {
+  pub aging_seconds: u64
+}
+
+/// Detection of origins that mishandle
+/// HEAD. GET-only feeds try HEAD again
+/// after `retest_seconds`; once
+/// `domain_threshold` feeds of a domain
+/// are GET-only (0 disables), its other
+/// feeds without a recent HEAD skip it
+/// too.
+#[derive(Debug, Clone)]
+
+pub struct HeadProbeConfig {
+  pub retest_seconds:   u64,
+  pub domain_threshold: usize
+}
+
+/// Buffering of fetch events and state
+/// snapshots: written in one
+/// transaction per `batch_size` rows or
+/// every `flush_ms`. When disabled each
+/// write is flushed before the action
+/// completes.
+#[derive(Debug, Clone)]
+
+pub struct WriteBehindConfig {
+  pub enabled:    bool,
+  pub batch_size: usize,
+  pub flush_ms:   u64
+}
+
+/// Postgres time partitions for
+/// `fetch_events` and
+/// `feed_state_history`: how many
+/// months ahead to create, and whether
+/// unpartitioned tables are converted.
+#[derive(Debug, Clone)]
+
+pub struct PartitionsConfig {
+  pub premake_months:   u32,
+  pub convert_existing: bool
+}
+
+/// Restart policy for category
+/// scheduler loops. Transient errors
+/// always restart; other failures
+/// escalate to `on_fatal` after
+/// `max_restarts` in a row (0 never
+/// escalates).
+#[derive(Debug, Clone)]
+
+pub struct SupervisorConfig {
+  pub restart_base_seconds: u64,
+  pub restart_max_seconds:  u64,
+  pub max_restarts:         u32,
+  pub on_fatal:             FatalPolicy
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum FatalPolicy {
+  /// Stop the whole fetcher.
+  Exit,
+  /// Park the failing category and keep
+  /// the others running.
+  StopCategory
+}
+
+/// Daily feed/domain health rollups
+/// derived from `fetch_events`.
+#[derive(Debug, Clone)]
+
+pub struct HealthConfig {
+  pub enabled:          bool,
+  pub interval_seconds: u64
+}
+
+/// Raw response body archive. Bodies
+/// are gzip-compressed and stored once
+/// per content hash.
+#[derive(Debug, Clone)]
+
+pub struct ArchiveConfig {
+  pub enabled:        bool,
+  pub storage:        ArchiveStorage,
+  /// Drop archived bodies not seen for
+  /// this many days (0 keeps forever).
+  pub retention_days: u64
+}
+
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub enum ArchiveStorage {
+  Database,
+  Directory(PathBuf)
+}
+
+/// Polling, backoff and dispatch
+/// settings. `categories` holds the
+/// `categories.toml` overrides already
+/// merged over `defaults`.
+#[derive(Debug, Clone)]
+
+pub struct SchedulingConfig {
+  pub defaults:   SchedulingPolicy,
+  pub categories:
+    HashMap<String, SchedulingPolicy>
+}
+
+impl SchedulingConfig {
+  pub fn policy_for(
+    &self,
+    category: &str
+  ) -> SchedulingPolicy {
+    self
+      .categories
+      .get(category)
+      .copied()
+      .unwrap_or(self.defaults)
+  }
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq,
+)]
+
+pub struct SchedulingPolicy {
+  /// Poll interval for feeds without
+  /// their own `base_poll_seconds`.
+  pub default_poll_seconds:       u64,
+  pub max_poll_seconds:           u64,
+  pub jitter_fraction:            f64,
+  pub error_backoff_base_seconds: u64,
+  pub max_error_backoff_seconds:  u64,
+  pub max_consecutive_errors:     u32,
+  /// Actions in flight per category
+  /// loop.
+  pub parallelism:                usize,
+  /// Due feeds taken from the queue
+  /// per loop iteration.
+  pub due_batch_size:             usize
+}
+
+/// Periodic pruning of history tables.
+/// `categories` holds per-category
+/// policies already merged over
+/// `defaults`.
+#[derive(Debug, Clone)]
+
+pub struct RetentionConfig {
+  pub enabled:          bool,
+  pub interval_seconds: u64,
+  pub batch_size:       u64,
+  /// Postgres schema holding the
+  /// server's `entry_states` and
+  /// `favorites` tables.
+  pub server_schema:    String,
+  pub defaults:         RetentionPolicy,
+  pub categories:
+    HashMap<String, RetentionPolicy>
+}
+
+impl RetentionConfig {
+  pub fn policy_for(
+    &self,
+    category: &str
+  ) -> RetentionPolicy {
+    self
+      .categories
+      .get(category)
+      .copied()
+      .unwrap_or(self.defaults)
+  }
+}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  PartialEq,
+  Eq,
+)]
+
+pub struct RetentionPolicy {
+  pub fetch_events:  RetentionLimits,
+  pub state_history: RetentionLimits,
+  /// Applies to `feed_payloads` and
+  /// their `feed_items`.
+  pub payloads:      RetentionLimits
+}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  PartialEq,
+  Eq,
+)]
+
+pub struct RetentionLimits {
+  pub max_age_days:      Option<u64>,
+  pub max_rows_per_feed: Option<u64>
+}
+
+impl RetentionLimits {
+  pub fn is_unbounded(&self) -> bool {
+    self.max_age_days.is_none()
+      && self
+        .max_rows_per_feed
+        .is_none()
+  }
+}
+
 #[derive(
   Debug, Clone, Copy, PartialEq, Eq,
 )]
@@ -0,0 +1,290 @@
+//! Time-of-day polling windows,
+//! evaluated in the configured
+//! timezone. A window either polls at
+//! least every `poll_seconds` while
+//! open or is quiet: nothing is fetched
+//! until it closes.
+
+use std::collections::HashMap;
+
+use chrono::{
+  Datelike,
+  Duration,
+  NaiveDate,
+  NaiveDateTime,
+  TimeZone,
+  Timelike
+};
+use chrono_tz::Tz;
+
+/// Quiet windows chained back to back
+/// are followed at most this far.
+const MAX_HOPS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+
+pub struct PollWindow {
+  /// Bit 0 is Monday. For windows that
+  /// cross midnight this is the day
+  /// the window opens.
+  pub days:         u8,
+  /// Minutes after local midnight.
+  pub start_minute: u32,
+  /// Up to 1440; below `start_minute`
+  /// means the window ends the next
+  /// day.
+  pub end_minute:   u32,
+  /// `None` for a quiet window.
+  pub poll_seconds: Option<u64>
+}
+
+/// Windows per feed (from `feeds.toml`)
+/// and per category; a feed's own list
+/// replaces its category's.
+#[derive(Debug, Clone, Default)]
+
+pub struct PollWindows {
+  pub categories:
+    HashMap<String, Vec<PollWindow>>,
+  pub feeds:
+    HashMap<String, Vec<PollWindow>>
+}
+
+impl PollWindows {
+  pub fn for_feed(
+    &self,
+    feed_id: &str,
+    category: &str
+  ) -> &[PollWindow] {
+    self
+      .feeds
+      .get(feed_id)
+      .or_else(|| {
+        self.categories.get(category)
+      })
+      .map(Vec::as_slice)
+      .unwrap_or(&[])
+  }
+}
+
+impl PollWindow {
+  pub fn is_quiet(&self) -> bool {
+    self.poll_seconds.is_none()
+  }
+
+  fn opens_on(
+    &self,
+    day: u32
+  ) -> bool {
+    self.days & (1 << day) != 0
+  }
+
+  /// End of the occurrence containing
+  /// `local`, if one does.
+  fn open_until(
+    &self,
+    local: NaiveDateTime
+  ) -> Option<NaiveDateTime> {
+    let date = local.date();
+
+    let day = date
+      .weekday()
+      .num_days_from_monday();
+
+    let minute = local.hour() * 60
+      + local.minute();
+
+    if self.start_minute
+      < self.end_minute
+    {
+      return (self.opens_on(day)
+        && minute >= self.start_minute
+        && minute < self.end_minute)
+        .then(|| {
+          at_minute(
+            date,
+            self.end_minute
+          )
+        });
+    }
+
+    // Crosses midnight: either opened
+    // today, or yesterday and still
+    // open.
+    if self.opens_on(day)
+      && minute >= self.start_minute
+    {
+      return Some(at_minute(
+        date + Duration::days(1),
+        self.end_minute
+      ));
+    }
+
+    let yesterday = (day + 6) % 7;
+
+    (self.opens_on(yesterday)
+      && minute < self.end_minute)
+      .then(|| {
+        at_minute(date, self.end_minute)
+      })
+  }
+}
+
+fn at_minute(
+  date: NaiveDate,
+  minute: u32
+) -> NaiveDateTime {
+  date
+    .and_hms_opt(0, 0, 0)
+    .unwrap_or_default()
+    + Duration::minutes(minute as i64)
+}
+
+fn local_time(
+  at_ms: i64,
+  tz: &Tz
+) -> Option<NaiveDateTime> {
+  tz.timestamp_millis_opt(at_ms)
+    .single()
+    .map(|t| t.naive_local())
+}
+
+/// Local wall time back to epoch ms.
+/// Times skipped by a DST jump resolve
+/// an hour later.
+fn epoch_ms(
+  local: NaiveDateTime,
+  tz: &Tz
+) -> i64 {
+  tz.from_local_datetime(&local)
+    .earliest()
+    .or_else(|| {
+      tz.from_local_datetime(
+        &(local + Duration::hours(1))
+      )
+      .earliest()
+    })
+    .map(|t| t.timestamp_millis())
+    .unwrap_or(
+      local
+        .and_utc()
+        .timestamp_millis()
+    )
+}
+
+/// When `at_ms` falls in a quiet
+/// window, the time it (and any quiet
+/// window chained to it) closes.
+pub fn quiet_until(
+  windows: &[PollWindow],
+  at_ms: i64,
+  tz: &Tz
+) -> Option<i64> {
+  let mut at = at_ms;
+
+  for _ in 0..MAX_HOPS {
+    let Some(local) =
+      local_time(at, tz)
+    else {
+      break;
+    };
+
+    let Some(end) = windows
+      .iter()
+      .filter(|w| w.is_quiet())
+      .filter_map(|w| {
+        w.open_until(local)
+      })
+      .max()
+    else {
+      break;
+    };
+
+    at = epoch_ms(end, tz).max(at + 1);
+  }
+
+  (at != at_ms).then_some(at)
+}
+
+/// The shortest `poll_seconds` among
+/// open polling windows.
+pub fn poll_seconds_at(
+  windows: &[PollWindow],
+  at_ms: i64,
+  tz: &Tz
+) -> Option<u64> {
+  let local = local_time(at_ms, tz)?;
+
+  windows
+    .iter()
+    .filter(|w| {
+      w.open_until(local).is_some()
+    })
+    .filter_map(|w| w.poll_seconds)
+    .min()
+}
+
+/// How far past a quiet window's end a
+/// feed wakes: `rand01` of its base
+/// poll interval, so feeds do not all
+/// wake at once.
+pub fn quiet_spread_ms(
+  base_poll_seconds: u64,
+  rand01: f64
+) -> i64 {
+  (rand01.clamp(0.0, 1.0)
+    * base_poll_seconds as f64
+    * 1000.0) as i64
+}
+
+/// Applies `windows` to a computed next
+/// action. An open polling window caps
+/// the delay (unless `faster` is false,
+/// e.g. during error backoff); a next
+/// action inside a quiet window moves
+/// to when it closes, plus
+/// [`quiet_spread_ms`].
+pub fn adjust_next_action(
+  windows: &[PollWindow],
+  tz: &Tz,
+  now_ms: i64,
+  next_ms: i64,
+  faster: bool,
+  base_poll_seconds: u64,
+  rand01: f64
+) -> i64 {
+  if windows.is_empty() {
+    return next_ms;
+  }
+
+  let mut next = next_ms;
+
+  if faster
+    && let Some(seconds) =
+      poll_seconds_at(
+        windows, now_ms, tz
+      )
+  {
+    next = next.min(
+      now_ms + (seconds * 1000) as i64
+    );
+  }
+
+  if let Some(end) =
+    quiet_until(windows, next, tz)
+  {
+    next = end
+      + quiet_spread_ms(
+        base_poll_seconds,
+        rand01
+      );
+
+    if let Some(later) =
+      quiet_until(windows, next, tz)
+    {
+      next = later;
+    }
+  }
+
+  next
+}
@@ -0,0 +1,412 @@
+//! Parses CSV/JSON dataset bodies into
+//! keyed rows and turns row-level diffs
+//! between two versions into feed
+//! items.
+
+use std::collections::BTreeMap;
+
+use serde_json::{
+  Map,
+  Value
+};
+
+use super::parser::{
+  FeedItem,
+  FeedMetadata,
+  ParsedFeed
+};
+use crate::domain::model::{
+  DatasetConfig,
+  DatasetFormat
+};
+
+pub type Row = Map<String, Value>;
+
+/// Rows of one dataset version, keyed
+/// by the configured key column.
+pub type DatasetRows =
+  BTreeMap<String, Row>;
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum RowChangeKind {
+  Added,
+  Removed,
+  Changed
+}
+
+impl RowChangeKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | RowChangeKind::Added => "added",
+      | RowChangeKind::Removed => {
+        "removed"
+      }
+      | RowChangeKind::Changed => {
+        "changed"
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+
+pub struct RowChange {
+  pub kind:            RowChangeKind,
+  pub key:             String,
+  pub before:          Option<Row>,
+  pub after:           Option<Row>,
+  pub changed_columns: Vec<String>
+}
+
+pub fn parse_rows(
+  bytes: &[u8],
+  cfg: &DatasetConfig
+) -> Result<DatasetRows, String> {
+  let records = match cfg.format {
+    | DatasetFormat::Csv => {
+      csv_records(bytes)?
+    }
+    | DatasetFormat::Json => {
+      json_records(
+        bytes,
+        cfg.records_path.as_deref()
+      )?
+    }
+  };
+
+  let mut rows = DatasetRows::new();
+
+  for (idx, record) in
+    records.into_iter().enumerate()
+  {
+    let key = record
+      .get(&cfg.key)
+      .and_then(key_string)
+      .ok_or_else(|| {
+        format!(
+          "dataset row {idx} is \
+           missing key '{}'",
+          cfg.key
+        )
+      })?;
+
+    if rows
+      .insert(key.clone(), record)
+      .is_some()
+    {
+      return Err(format!(
+        "dataset has duplicate key \
+         '{key}'"
+      ));
+    }
+  }
+
+  Ok(rows)
+}
+
+fn csv_records(
+  bytes: &[u8]
+) -> Result<Vec<Row>, String> {
+  let mut reader =
+    csv::Reader::from_reader(bytes);
+
+  let headers = reader
+    .headers()
+    .map_err(|e| {
+      format!("csv header error: {e}")
+    })?
+    .clone();
+
+  let mut out = Vec::new();
+
+  for record in reader.records() {
+    let record =
+      record.map_err(|e| {
+        format!("csv record error: {e}")
+      })?;
+
+    let row = headers
+      .iter()
+      .zip(record.iter())
+      .map(|(h, v)| {
+        (
+          h.to_string(),
+          Value::String(v.to_string())
+        )
+      })
+      .collect::<Row>();
+
+    out.push(row);
+  }
+
+  Ok(out)
+}
+
+fn json_records(
+  bytes: &[u8],
+  records_path: Option<&str>
+) -> Result<Vec<Row>, String> {
+  let doc: Value =
+    serde_json::from_slice(bytes)
+      .map_err(|e| {
+        format!("json parse error: {e}")
+      })?;
+
+  let target = match records_path {
+    | Some(path) => {
+      doc.pointer(path).ok_or_else(
+        || {
+          format!(
+            "records_path '{path}' \
+             not found"
+          )
+        }
+      )?
+    }
+    | None => &doc
+  };
+
+  let Value::Array(items) = target
+  else {
+    return Err(
+      "dataset records are not a JSON \
+       array"
+        .to_string()
+    );
+  };
+
+  items
+    .iter()
+    .enumerate()
+    .map(|(idx, item)| {
+      item
+        .as_object()
+        .cloned()
+        .ok_or_else(|| {
+          format!(
+            "dataset record {idx} is \
+             not an object"
+          )
+        })
+    })
+    .collect()
+}
+
+fn key_string(
+  value: &Value
+) -> Option<String> {
+  match value {
+    | Value::String(s)
+      if !s.is_empty() =>
+    {
+      Some(s.clone())
+    }
+    | Value::Number(n) => {
+      Some(n.to_string())
+    }
+    | Value::Bool(b) => {
+      Some(b.to_string())
+    }
+    | _ => None
+  }
+}
+
+/// Compares two versions key by key;
+/// output is ordered by key, with
+/// removals before additions/changes.
+pub fn diff_rows(
+  prev: &DatasetRows,
+  next: &DatasetRows
+) -> Vec<RowChange> {
+  let mut changes = Vec::new();
+
+  for (key, before) in prev {
+    if !next.contains_key(key) {
+      changes.push(RowChange {
+        kind:
+          RowChangeKind::Removed,
+        key:             key.clone(),
+        before:          Some(
+          before.clone()
+        ),
+        after:           None,
+        changed_columns: Vec::new()
+      });
+    }
+  }
+
+  for (key, after) in next {
+    match prev.get(key) {
+      | None => {
+        changes.push(RowChange {
+          kind:
+            RowChangeKind::Added,
+          key:             key.clone(),
+          before:          None,
+          after:           Some(
+            after.clone()
+          ),
+          changed_columns: Vec::new()
+        });
+      }
+      | Some(before) => {
+        let changed_columns =
+          changed_columns(
+            before, after
+          );
+
+        if !changed_columns.is_empty() {
+          changes.push(RowChange {
+            kind:
+              RowChangeKind::Changed,
+            key: key.clone(),
+            before: Some(
+              before.clone()
+            ),
+            after: Some(after.clone()),
+            changed_columns
+          });
+        }
+      }
+    }
+  }
+
+  changes
+}
+
+fn changed_columns(
+  before: &Row,
+  after: &Row
+) -> Vec<String> {
+  let mut cols = before
+    .keys()
+    .chain(after.keys())
+    .filter(|k| {
+      before.get(*k) != after.get(*k)
+    })
+    .cloned()
+    .collect::<Vec<_>>();
+
+  cols.sort();
+
+  cols.dedup();
+
+  cols
+}
+
+/// Builds a payload with one item per
+/// row change. GUIDs embed the version
+/// hash and fetch time so every diff is
+/// a new item, even when an earlier
+/// body comes back.
+pub fn changes_to_feed(
+  changes: &[RowChange],
+  content_hash: &str,
+  row_count: usize,
+  now_ms: i64
+) -> ParsedFeed {
+  let items = changes
+    .iter()
+    .map(|c| {
+      change_to_item(
+        c,
+        content_hash,
+        now_ms
+      )
+    })
+    .collect::<Vec<_>>();
+
+  let count = |kind: RowChangeKind| {
+    changes
+      .iter()
+      .filter(|c| c.kind == kind)
+      .count()
+  };
+
+  let description = format!(
+    "{row_count} rows: {} added, {} \
+     removed, {} changed",
+    count(RowChangeKind::Added),
+    count(RowChangeKind::Removed),
+    count(RowChangeKind::Changed)
+  );
+
+  ParsedFeed {
+    metadata: FeedMetadata {
+      title:         None,
+      link:          None,
+      description:   Some(description),
+      language:      None,
+      updated_at_ms: Some(now_ms)
+    },
+    items
+  }
+}
+
+fn change_to_item(
+  change: &RowChange,
+  content_hash: &str,
+  now_ms: i64
+) -> FeedItem {
+  let kind = change.kind.as_str();
+
+  let title = match change.kind {
+    | RowChangeKind::Added => {
+      format!(
+        "Row added: {}",
+        change.key
+      )
+    }
+    | RowChangeKind::Removed => {
+      format!(
+        "Row removed: {}",
+        change.key
+      )
+    }
+    | RowChangeKind::Changed => {
+      format!(
+        "Row changed: {}",
+        change.key
+      )
+    }
+  };
+
+  let summary = if change
+    .changed_columns
+    .is_empty()
+  {
+    None
+  } else {
+    Some(format!(
+      "Changed columns: {}",
+      change.changed_columns.join(", ")
+    ))
+  };
+
+  let description = serde_json::json!({
+    "key": change.key,
+    "before": change.before,
+    "after": change.after,
+    "changed_columns": change.changed_columns
+  })
+  .to_string();
+
+  FeedItem {
+    title: Some(title),
+    link: None,
+    guid: Some(format!(
+      "{content_hash}@{now_ms}:{kind}:\
+       {}",
+      change.key
+    )),
+    published_at_ms: Some(now_ms),
+    category: Some(format!(
+      "dataset:{kind}"
+    )),
+    description: Some(description),
+    summary
+  }
+}
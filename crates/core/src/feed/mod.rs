@@ -1,3 +1,4 @@
 //! Feed parsing utilities.
 
+pub mod dataset;
 pub mod parser;
@@ -16,7 +16,7 @@ pub struct FeedMetadata {
   pub updated_at_ms: Option<i64>
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 
 pub struct FeedItem {
   pub title:           Option<String>,
@@ -0,0 +1,242 @@
+//! Operator controls and the
+//! authenticated admin HTTP API.
+//! Pauses live in `paused_targets`, so
+//! every fetcher sharing the database
+//! honours them; domain concurrency
+//! overrides only affect this process
+//! and are dropped on restart.
+
+mod routes;
+mod server;
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::sync::atomic::{
+  AtomicU64,
+  Ordering
+};
+use std::sync::{
+  Arc,
+  RwLock
+};
+use std::time::Duration;
+
+pub use server::AdminServer;
+use tracing::warn;
+
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig
+};
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  PauseScope,
+  PausedTarget,
+  Repo
+};
+
+/// How often pauses written by other
+/// fetchers are picked up.
+const PAUSE_SYNC: Duration =
+  Duration::from_secs(10);
+
+#[derive(Debug, Default, PartialEq)]
+
+struct Pauses {
+  feeds:      HashSet<String>,
+  domains:    HashSet<String>,
+  categories: HashSet<String>
+}
+
+/// Operator state shared by the
+/// category loops and the admin API:
+/// the pause set loaded from the
+/// database and this process's domain
+/// concurrency overrides. Created with
+/// the scheduler's context and handed
+/// to the admin server.
+#[derive(Debug, Default)]
+
+pub struct Controls {
+  pauses:        RwLock<Pauses>,
+  domain_limits:
+    RwLock<HashMap<String, usize>>,
+  generation:    AtomicU64
+}
+
+impl Controls {
+  /// Whether `feed` is paused directly
+  /// or through its domain or category.
+  pub fn is_paused(
+    &self,
+    feed: &FeedConfig
+  ) -> bool {
+    self.pauses.read().is_ok_and(|p| {
+      p.feeds.contains(&feed.id)
+        || p
+          .domains
+          .contains(&feed.domain)
+        || p
+          .categories
+          .contains(&feed.category)
+    })
+  }
+
+  /// Bumped whenever the pause set
+  /// changes, so the category loops
+  /// know to release parked feeds.
+  pub fn generation(&self) -> u64 {
+    self
+      .generation
+      .load(Ordering::Relaxed)
+  }
+
+  /// The runtime override for
+  /// `domain`, if one was set through
+  /// the API.
+  pub fn domain_limit(
+    &self,
+    domain: &str
+  ) -> Option<usize> {
+    self
+      .domain_limits
+      .read()
+      .ok()
+      .and_then(|l| {
+        l.get(domain).copied()
+      })
+  }
+
+  fn domain_limits(
+    &self
+  ) -> HashMap<String, usize> {
+    self
+      .domain_limits
+      .read()
+      .map(|l| l.clone())
+      .unwrap_or_default()
+  }
+
+  fn set_domain_limit(
+    &self,
+    domain: &str,
+    limit: Option<usize>
+  ) {
+    if let Ok(mut limits) =
+      self.domain_limits.write()
+    {
+      match limit {
+        | Some(n) => {
+          limits.insert(
+            domain.to_string(),
+            n
+          );
+        }
+        | None => {
+          limits.remove(domain);
+        }
+      }
+    }
+  }
+
+  fn apply_pauses(
+    &self,
+    targets: &[PausedTarget]
+  ) {
+    let mut next = Pauses::default();
+
+    for t in targets {
+      let set = match t.scope {
+        | PauseScope::Feed => {
+          &mut next.feeds
+        }
+        | PauseScope::Domain => {
+          &mut next.domains
+        }
+        | PauseScope::Category => {
+          &mut next.categories
+        }
+      };
+
+      set.insert(t.name.clone());
+    }
+
+    if let Ok(mut current) =
+      self.pauses.write()
+      && *current != next
+    {
+      *current = next;
+
+      self.generation.fetch_add(
+        1,
+        Ordering::Relaxed
+      );
+    }
+  }
+
+  /// Loads the pause set from the
+  /// database.
+  pub async fn reload_pauses<R>(
+    &self,
+    repo: &R
+  ) -> Result<(), String>
+  where
+    R: Repo + ?Sized
+  {
+    let targets =
+      repo.paused_targets().await?;
+
+    self.apply_pauses(&targets);
+
+    Ok(())
+  }
+
+  /// Reloads pauses every
+  /// [`PAUSE_SYNC`]; failures keep the
+  /// last known set.
+  pub async fn sync_forever<R>(
+    self: Arc<Self>,
+    repo: Arc<R>
+  ) -> Result<(), String>
+  where
+    R: Repo + ?Sized
+  {
+    loop {
+      tokio::time::sleep(PAUSE_SYNC)
+        .await;
+
+      if let Err(e) = self
+        .reload_pauses(repo.as_ref())
+        .await
+      {
+        warn!(error = %e, "Pause reload failed");
+      }
+    }
+  }
+}
+
+/// Starts the admin API when
+/// `[admin] enabled`, serving
+/// `controls`.
+pub async fn init(
+  cfg: Arc<AppConfig>,
+  repo: Arc<dyn Repo>,
+  clock: Arc<dyn Clock>,
+  controls: Arc<Controls>
+) -> Result<Option<AdminServer>, String>
+{
+  if !cfg.admin.enabled {
+    return Ok(None);
+  }
+
+  server::spawn(server::AdminState {
+    cfg,
+    repo,
+    clock,
+    controls
+  })
+  .await
+  .map(Some)
+}
@@ -0,0 +1,514 @@
+//! Admin API endpoints. Every response
+//! is JSON; errors carry an `error`
+//! field.
+
+use serde::Deserialize;
+use serde_json::{
+  Value,
+  json
+};
+
+use super::server::{
+  AdminState,
+  Request
+};
+use crate::ports::repo::{
+  PauseScope,
+  PausedTarget,
+  StateRow
+};
+
+const DEFAULT_EVENTS: usize = 20;
+
+const MAX_EVENTS: usize = 500;
+
+type Reply = (u16, Value);
+
+#[derive(
+  Debug, Default, Deserialize,
+)]
+
+struct PauseBody {
+  #[serde(default)]
+  reason: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+
+struct ConcurrencyBody {
+  max_concurrent_requests: usize
+}
+
+fn error(
+  status: u16,
+  message: impl Into<String>
+) -> Reply {
+  (
+    status,
+    json!({ "error": message.into() })
+  )
+}
+
+fn internal(e: String) -> Reply {
+  error(500, e)
+}
+
+pub(super) async fn handle(
+  state: &AdminState,
+  req: &Request
+) -> Reply {
+  let segments: Vec<String> = req
+    .path
+    .trim_matches('/')
+    .split('/')
+    .map(percent_decode)
+    .collect();
+
+  let segments: Vec<&str> = segments
+    .iter()
+    .map(String::as_str)
+    .collect();
+
+  let result = match (
+    req.method.as_str(),
+    segments.as_slice()
+  ) {
+    | ("GET", ["v1", "pauses"]) => {
+      list_pauses(state).await
+    }
+    | (
+      "PUT",
+      ["v1", "pauses", scope, name]
+    ) => {
+      pause(
+        state, scope, name, &req.body
+      )
+      .await
+    }
+    | (
+      "DELETE",
+      ["v1", "pauses", scope, name]
+    ) => {
+      resume(state, scope, name).await
+    }
+    | (
+      "GET",
+      ["v1", "feeds", feed_id]
+    ) => {
+      inspect_feed(
+        state, feed_id, &req.query
+      )
+      .await
+    }
+    | (
+      "GET",
+      ["v1", "error-feeds"]
+    ) => error_feeds(state).await,
+    | ("GET", ["v1", "domains"]) => {
+      Ok(list_domains(state))
+    }
+    | (
+      "PUT",
+      [
+        "v1",
+        "domains",
+        domain,
+        "concurrency"
+      ]
+    ) => {
+      set_concurrency(
+        state, domain, &req.body
+      )
+    }
+    | (
+      "DELETE",
+      [
+        "v1",
+        "domains",
+        domain,
+        "concurrency"
+      ]
+    ) => {
+      state
+        .controls
+        .set_domain_limit(domain, None);
+
+      Ok(domain_entry(state, domain))
+    }
+    | _ => Err(error(404, "not found"))
+  };
+
+  match result {
+    | Ok(value) => (200, value),
+    | Err(reply) => reply
+  }
+}
+
+fn parse_body<T>(
+  body: &[u8]
+) -> Result<T, Reply>
+where
+  T:
+    for<'de> Deserialize<'de> + Default
+{
+  if body
+    .iter()
+    .all(u8::is_ascii_whitespace)
+  {
+    return Ok(T::default());
+  }
+
+  serde_json::from_slice(body).map_err(
+    |e| {
+      error(
+        400,
+        format!("invalid body: {e}")
+      )
+    }
+  )
+}
+
+fn pause_json(
+  t: &PausedTarget
+) -> Value {
+  json!({
+    "scope": t.scope.as_str(),
+    "name": t.name,
+    "reason": t.reason,
+    "paused_at_ms": t.paused_at_ms
+  })
+}
+
+async fn list_pauses(
+  state: &AdminState
+) -> Result<Value, Reply> {
+  let pauses = state
+    .repo
+    .paused_targets()
+    .await
+    .map_err(internal)?;
+
+  Ok(json!({
+    "pauses": pauses.iter().map(pause_json).collect::<Vec<_>>()
+  }))
+}
+
+/// Feeds must exist; domains and
+/// categories can be paused ahead of
+/// their first feed.
+async fn pause(
+  state: &AdminState,
+  scope: &str,
+  name: &str,
+  body: &[u8]
+) -> Result<Value, Reply> {
+  let scope = PauseScope::parse(scope)
+    .map_err(|e| error(400, e))?;
+
+  let body: PauseBody =
+    parse_body(body)?;
+
+  if scope == PauseScope::Feed
+    && state
+      .repo
+      .feed(name)
+      .await
+      .map_err(internal)?
+      .is_none()
+  {
+    return Err(error(
+      404,
+      format!("unknown feed '{name}'")
+    ));
+  }
+
+  let target = PausedTarget {
+    scope,
+    name: name.to_string(),
+    reason: body.reason,
+    paused_at_ms: state
+      .clock
+      .now_epoch_ms()
+      .await
+  };
+
+  state
+    .repo
+    .pause_target(
+      &target,
+      &state.cfg.timezone
+    )
+    .await
+    .map_err(internal)?;
+
+  state
+    .controls
+    .reload_pauses(state.repo.as_ref())
+    .await
+    .map_err(internal)?;
+
+  Ok(pause_json(&target))
+}
+
+async fn resume(
+  state: &AdminState,
+  scope: &str,
+  name: &str
+) -> Result<Value, Reply> {
+  let scope = PauseScope::parse(scope)
+    .map_err(|e| error(400, e))?;
+
+  let removed = state
+    .repo
+    .resume_target(scope, name)
+    .await
+    .map_err(internal)?;
+
+  if !removed {
+    return Err(error(
+      404,
+      format!(
+        "{} '{name}' is not paused",
+        scope.as_str()
+      )
+    ));
+  }
+
+  state
+    .controls
+    .reload_pauses(state.repo.as_ref())
+    .await
+    .map_err(internal)?;
+
+  Ok(json!({
+    "scope": scope.as_str(),
+    "name": name,
+    "resumed": true
+  }))
+}
+
+fn state_json(s: &StateRow) -> Value {
+  json!({
+    "phase": s.phase,
+    "last_head_at_ms": s.last_head_at_ms,
+    "last_head_status": s.last_head_status,
+    "last_head_error": s.last_head_error,
+    "last_get_at_ms": s.last_get_at_ms,
+    "last_get_status": s.last_get_status,
+    "last_get_error": s.last_get_error,
+    "etag": s.etag,
+    "last_modified_ms": s.last_modified_ms,
+    "backoff_index": s.backoff_index,
+    "base_poll_seconds": s.base_poll_seconds,
+    "next_action_at_ms": s.next_action_at_ms,
+    "jitter_seconds": s.jitter_seconds,
+    "consecutive_error_count": s.consecutive_error_count,
+    "get_only_since_ms": s.get_only_since_ms,
+    "note": s.note
+  })
+}
+
+/// Current `LinkState` plus the newest
+/// `fetch_events`; `?events=N` picks
+/// how many (default 20, max 500).
+async fn inspect_feed(
+  state: &AdminState,
+  feed_id: &str,
+  query: &str
+) -> Result<Value, Reply> {
+  let limit = query
+    .split('&')
+    .filter_map(|kv| {
+      kv.strip_prefix("events=")
+    })
+    .next()
+    .map(|n| {
+      n.parse::<usize>().map_err(|_| {
+        error(
+          400,
+          "events must be a number"
+        )
+      })
+    })
+    .transpose()?
+    .unwrap_or(DEFAULT_EVENTS)
+    .min(MAX_EVENTS);
+
+  let feed = state
+    .repo
+    .feed(feed_id)
+    .await
+    .map_err(internal)?
+    .ok_or_else(|| {
+      error(
+        404,
+        format!(
+          "unknown feed '{feed_id}'"
+        )
+      )
+    })?;
+
+  let latest = state
+    .repo
+    .latest_state(feed_id)
+    .await
+    .map_err(internal)?;
+
+  let events = state
+    .repo
+    .recent_fetch_events(feed_id, limit)
+    .await
+    .map_err(internal)?;
+
+  Ok(json!({
+    "feed_id": feed.id,
+    "url": feed.url,
+    "domain": feed.domain,
+    "category": feed.category,
+    "paused": state.controls.is_paused(&feed),
+    "state": latest.as_ref().map(state_json),
+    "events": events.iter().map(|e| json!({
+      "id": e.id,
+      "event_time_ms": e.event_time_ms,
+      "method": e.method,
+      "status": e.status,
+      "error_kind": e.error_kind,
+      "latency_ms": e.latency_ms,
+      "backoff_index": e.backoff_index,
+      "scheduled_next_action_at_ms": e.scheduled_next_action_at_ms,
+      "debug": e.debug
+    })).collect::<Vec<_>>()
+  }))
+}
+
+async fn error_feeds(
+  state: &AdminState
+) -> Result<Value, Reply> {
+  let feeds = state
+    .repo
+    .error_feeds()
+    .await
+    .map_err(internal)?;
+
+  Ok(json!({
+    "error_feeds": feeds.iter().map(|f| json!({
+      "feed_id": f.feed_id,
+      "error_count": f.error_count,
+      "last_error_kind": f.last_error_kind,
+      "last_error_status": f.last_error_status,
+      "last_error_at_ms": f.last_error_at_ms,
+      "note": f.note
+    })).collect::<Vec<_>>()
+  }))
+}
+
+fn domain_entry(
+  state: &AdminState,
+  domain: &str
+) -> Value {
+  let configured =
+    state.cfg.domains.get(domain).map(
+      |d| d.max_concurrent_requests
+    );
+
+  let overridden =
+    state.controls.domain_limit(domain);
+
+  json!({
+    "domain": domain,
+    "configured": configured,
+    "override": overridden,
+    "effective": overridden.or(configured).unwrap_or(1)
+  })
+}
+
+fn list_domains(
+  state: &AdminState
+) -> Value {
+  let mut names: Vec<String> = state
+    .cfg
+    .domains
+    .keys()
+    .cloned()
+    .chain(
+      state
+        .controls
+        .domain_limits()
+        .into_keys()
+    )
+    .collect();
+
+  names.sort();
+  names.dedup();
+
+  json!({
+    "domains": names
+      .iter()
+      .map(|d| domain_entry(state, d))
+      .collect::<Vec<_>>()
+  })
+}
+
+fn set_concurrency(
+  state: &AdminState,
+  domain: &str,
+  body: &[u8]
+) -> Result<Value, Reply> {
+  let body: ConcurrencyBody =
+    serde_json::from_slice(body)
+      .map_err(|e| {
+        error(
+          400,
+          format!("invalid body: {e}")
+        )
+      })?;
+
+  if body.max_concurrent_requests == 0 {
+    return Err(error(
+      400,
+      "max_concurrent_requests must \
+       be at least 1"
+    ));
+  }
+
+  state.controls.set_domain_limit(
+    domain,
+    Some(body.max_concurrent_requests)
+  );
+
+  Ok(domain_entry(state, domain))
+}
+
+fn percent_decode(raw: &str) -> String {
+  let bytes = raw.as_bytes();
+
+  let mut out =
+    Vec::with_capacity(bytes.len());
+
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let hex = bytes
+      .get(i + 1..i + 3)
+      .and_then(|h| {
+        std::str::from_utf8(h).ok()
+      })
+      .and_then(|h| {
+        u8::from_str_radix(h, 16).ok()
+      });
+
+    match (bytes[i], hex) {
+      | (b'%', Some(b)) => {
+        out.push(b);
+        i += 3;
+      }
+      | (b, _) => {
+        out.push(b);
+        i += 1;
+      }
+    }
+  }
+
+  String::from_utf8_lossy(&out)
+    .into_owned()
+}
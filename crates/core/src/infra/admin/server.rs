@@ -0,0 +1,417 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{
+  Value,
+  json
+};
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+use tokio::net::{
+  TcpListener,
+  TcpStream
+};
+use tokio::task::AbortHandle;
+
+use super::{
+  Controls,
+  routes
+};
+use crate::domain::model::AppConfig;
+use crate::ports::clock::Clock;
+use crate::ports::repo::Repo;
+
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Bounds reading the head, and again
+/// the body, so a silent client cannot
+/// hold a connection open.
+const READ_TIMEOUT: Duration =
+  Duration::from_secs(10);
+
+pub(super) struct Request {
+  pub method: String,
+  pub path:   String,
+  pub query:  String,
+  pub body:   Vec<u8>
+}
+
+pub(super) struct AdminState {
+  pub cfg:      Arc<AppConfig>,
+  pub repo:     Arc<dyn Repo>,
+  pub clock:    Arc<dyn Clock>,
+  pub controls: Arc<Controls>
+}
+
+/// The running admin listener.
+pub struct AdminServer {
+  addr:   SocketAddr,
+  accept: AbortHandle
+}
+
+impl AdminServer {
+  /// The address it listens on.
+  pub fn addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  /// Closes the listener during
+  /// shutdown.
+  pub fn shutdown(&self) {
+    self.accept.abort();
+  }
+}
+
+/// The request line and headers, with
+/// whatever body bytes arrived with
+/// them.
+struct Head {
+  method: String,
+  path:   String,
+  query:  String,
+  auth:   Option<String>,
+  length: usize,
+  body:   Vec<u8>
+}
+
+pub(super) async fn spawn(
+  state: AdminState
+) -> Result<AdminServer, String> {
+  let bind =
+    state.cfg.admin.bind.clone();
+
+  let addr: SocketAddr =
+    bind.parse().map_err(|e| {
+      format!(
+        "invalid admin bind '{bind}': \
+         {e}"
+      )
+    })?;
+
+  let listener =
+    TcpListener::bind(addr)
+      .await
+      .map_err(|e| {
+        format!(
+          "failed to bind admin \
+           server on {bind}: {e}"
+        )
+      })?;
+
+  let local = listener
+    .local_addr()
+    .map_err(|e| e.to_string())?;
+
+  let state = Arc::new(state);
+
+  let accept =
+    tokio::spawn(async move {
+      loop {
+        let (stream, _) =
+          match listener.accept().await
+          {
+            | Ok(pair) => pair,
+            | Err(_) => continue
+          };
+
+        let state = state.clone();
+
+        tokio::spawn(async move {
+          serve(stream, &state).await;
+        });
+      }
+    });
+
+  Ok(AdminServer {
+    addr:   local,
+    accept: accept.abort_handle()
+  })
+}
+
+async fn serve(
+  mut stream: TcpStream,
+  state: &AdminState
+) {
+  let (status, body) =
+    match respond(&mut stream, state)
+      .await
+    {
+      | Ok(reply) | Err(reply) => reply
+    };
+
+  let _ = write_response(
+    &mut stream,
+    status,
+    &body
+  )
+  .await;
+}
+
+/// Checks the token before reading
+/// the body, so unauthenticated
+/// clients cannot make us buffer one.
+async fn respond(
+  stream: &mut TcpStream,
+  state: &AdminState
+) -> Result<(u16, Value), (u16, Value)>
+{
+  let bad = |e: String| {
+    (400, json!({ "error": e }))
+  };
+
+  let head = within(read_head(stream))
+    .await
+    .map_err(bad)?;
+
+  if !authorized(
+    head.auth.as_deref(),
+    &state.cfg.admin.token
+  ) {
+    return Err((
+      401,
+      json!({ "error": "missing or invalid bearer token" })
+    ));
+  }
+
+  let body = within(read_body(
+    stream,
+    head.body,
+    head.length
+  ))
+  .await
+  .map_err(bad)?;
+
+  let req = Request {
+    method: head.method,
+    path: head.path,
+    query: head.query,
+    body
+  };
+
+  Ok(routes::handle(state, &req).await)
+}
+
+async fn within<T>(
+  read: impl Future<
+    Output = Result<T, String>
+  >
+) -> Result<T, String> {
+  tokio::time::timeout(
+    READ_TIMEOUT,
+    read
+  )
+  .await
+  .map_err(|_| {
+    "timed out reading the request"
+      .to_string()
+  })?
+}
+
+/// Reads the request line and headers.
+async fn read_head(
+  stream: &mut TcpStream
+) -> Result<Head, String> {
+  let mut buf = Vec::new();
+
+  let mut chunk = [0u8; 4096];
+
+  let head_end = loop {
+    if let Some(at) = buf
+      .windows(4)
+      .position(|w| w == b"\r\n\r\n")
+    {
+      break at;
+    }
+
+    if buf.len() > MAX_HEAD_BYTES {
+      return Err(
+        "request head too large".into()
+      );
+    }
+
+    let n = stream
+      .read(&mut chunk)
+      .await
+      .map_err(|e| e.to_string())?;
+
+    if n == 0 {
+      return Err(
+        "connection closed".into()
+      );
+    }
+
+    buf.extend_from_slice(&chunk[..n]);
+  };
+
+  let head = String::from_utf8_lossy(
+    &buf[..head_end]
+  )
+  .to_string();
+
+  let mut lines = head.split("\r\n");
+
+  let mut parts = lines
+    .next()
+    .unwrap_or_default()
+    .split_whitespace();
+
+  let method = parts
+    .next()
+    .unwrap_or_default()
+    .to_string();
+
+  let target =
+    parts.next().unwrap_or("/");
+
+  let (path, query) = target
+    .split_once('?')
+    .unwrap_or((target, ""));
+
+  let mut auth = None;
+
+  let mut length = 0usize;
+
+  for line in lines {
+    let Some((name, value)) =
+      line.split_once(':')
+    else {
+      continue;
+    };
+
+    let value = value.trim();
+
+    if name.eq_ignore_ascii_case(
+      "authorization"
+    ) {
+      auth = Some(value.to_string());
+    } else if name.eq_ignore_ascii_case(
+      "content-length"
+    ) {
+      length = value.parse().map_err(
+        |_| {
+          "invalid Content-Length"
+            .to_string()
+        }
+      )?;
+    }
+  }
+
+  Ok(Head {
+    method,
+    path: path.to_string(),
+    query: query.to_string(),
+    auth,
+    length,
+    body: buf[head_end + 4..].to_vec()
+  })
+}
+
+/// Reads the rest of a
+/// `Content-Length` body.
+async fn read_body(
+  stream: &mut TcpStream,
+  mut body: Vec<u8>,
+  length: usize
+) -> Result<Vec<u8>, String> {
+  if length > MAX_BODY_BYTES {
+    return Err(
+      "request body too large".into()
+    );
+  }
+
+  let mut chunk = [0u8; 4096];
+
+  while body.len() < length {
+    let n = stream
+      .read(&mut chunk)
+      .await
+      .map_err(|e| e.to_string())?;
+
+    if n == 0 {
+      return Err(
+        "connection closed".into()
+      );
+    }
+
+    body.extend_from_slice(&chunk[..n]);
+  }
+
+  body.truncate(length);
+
+  Ok(body)
+}
+
+/// Compares without an early exit so
+/// response timing does not leak how
+/// much of the token matched.
+fn authorized(
+  header: Option<&str>,
+  token: &str
+) -> bool {
+  let Some(given) = header
+    .and_then(|h| {
+      h.strip_prefix("Bearer ")
+    })
+    .map(str::trim)
+  else {
+    return false;
+  };
+
+  given.len() == token.len()
+    && given
+      .bytes()
+      .zip(token.bytes())
+      .fold(0u8, |acc, (a, b)| {
+        acc | (a ^ b)
+      })
+      == 0
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    | 200 => "OK",
+    | 400 => "Bad Request",
+    | 401 => "Unauthorized",
+    | 404 => "Not Found",
+    | 405 => "Method Not Allowed",
+    | _ => "Internal Server Error"
+  }
+}
+
+async fn write_response(
+  stream: &mut TcpStream,
+  status: u16,
+  body: &Value
+) -> std::io::Result<()> {
+  let body = body.to_string();
+
+  let head = [
+    format!(
+      "HTTP/1.1 {status} {}",
+      reason(status)
+    ),
+    "Content-Type: application/json"
+      .to_string(),
+    format!(
+      "Content-Length: {}",
+      body.len()
+    ),
+    "Connection: close".to_string(),
+    String::new(),
+    String::new()
+  ]
+  .join("\r\n");
+
+  stream
+    .write_all(head.as_bytes())
+    .await?;
+
+  stream
+    .write_all(body.as_bytes())
+    .await
+}
@@ -0,0 +1,261 @@
+//! `manifest.json`: what a backup
+//! directory holds. Written last, so a
+//! directory without one is an
+//! interrupted backup.
+
+use std::path::{
+  Component,
+  Path
+};
+
+use serde::{
+  Deserialize,
+  Serialize
+};
+
+use super::stream;
+use crate::domain::model::SqlDialect;
+use crate::infra::db_copy::tables::TABLES;
+
+pub const MANIFEST: &str =
+  "manifest.json";
+
+/// Bumped when the directory layout
+/// changes.
+pub const FORMAT: u32 = 1;
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+
+pub struct BackupManifest {
+  pub format:                u32,
+  /// `sqlite` or `postgres`.
+  pub dialect:               String,
+  pub created_at_ms:         i64,
+  /// Fetcher schema version.
+  pub schema_version:        i64,
+  /// Server schema version, when the
+  /// server tables were included.
+  pub server_schema_version:
+    Option<i64>,
+  /// Files are gzip compressed.
+  pub compressed:            bool,
+  pub tables: Vec<TableRows>,
+  pub files: Vec<BackupFile>
+}
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+
+pub struct TableRows {
+  pub name:    String,
+  pub rows:    u64,
+  /// Column order of a logical export.
+  #[serde(
+    default,
+    skip_serializing_if = "Vec::is_empty"
+  )]
+  pub columns: Vec<String>
+}
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+
+pub struct BackupFile {
+  /// Relative to the backup directory.
+  pub path:   String,
+  pub bytes:  u64,
+  pub sha256: String
+}
+
+pub fn dialect_name(
+  dialect: SqlDialect
+) -> &'static str {
+  match dialect {
+    | SqlDialect::Sqlite => "sqlite",
+    | SqlDialect::Postgres => "postgres"
+  }
+}
+
+impl BackupManifest {
+  pub async fn load(
+    dir: &Path
+  ) -> Result<Self, String> {
+    let path = dir.join(MANIFEST);
+
+    let raw =
+      tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| {
+          format!(
+            "read {}: {e}",
+            path.display()
+          )
+        })?;
+
+    serde_json::from_str(&raw).map_err(
+      |e| {
+        format!(
+          "parse {}: {e}",
+          path.display()
+        )
+      }
+    )
+  }
+
+  pub async fn save(
+    &self,
+    dir: &Path
+  ) -> Result<(), String> {
+    let path = dir.join(MANIFEST);
+
+    let raw =
+      serde_json::to_string_pretty(
+        self
+      )
+      .map_err(|e| e.to_string())?;
+
+    tokio::fs::write(&path, raw)
+      .await
+      .map_err(|e| {
+        format!(
+          "write {}: {e}",
+          path.display()
+        )
+      })
+  }
+
+  pub fn rows(
+    &self,
+    table: &str
+  ) -> Option<u64> {
+    self
+      .tables
+      .iter()
+      .find(|t| t.name == table)
+      .map(|t| t.rows)
+  }
+
+  /// Checks the manifest against the
+  /// target and every file against its
+  /// recorded size and digest. Reads
+  /// only.
+  pub async fn validate(
+    &self,
+    dir: &Path,
+    dialect: SqlDialect,
+    supported: i64
+  ) -> Result<(), String> {
+    if self.format != FORMAT {
+      return Err(format!(
+        "unsupported backup format {} \
+         (this binary reads {FORMAT})",
+        self.format
+      ));
+    }
+
+    if self.dialect
+      != dialect_name(dialect)
+    {
+      return Err(format!(
+        "backup is a {} backup but \
+         the target is {}; use `db \
+         copy` to move between \
+         backends",
+        self.dialect,
+        dialect_name(dialect)
+      ));
+    }
+
+    if self.schema_version > supported {
+      return Err(format!(
+        "backup is at schema version \
+         {}, newer than this binary \
+         supports ({supported}); \
+         upgrade pulsewire",
+        self.schema_version
+      ));
+    }
+
+    for entry in &self.tables {
+      let Some(table) = TABLES
+        .iter()
+        .find(|t| t.name == entry.name)
+      else {
+        return Err(format!(
+          "backup holds unknown table \
+           {}",
+          entry.name
+        ));
+      };
+
+      let expected = table
+        .columns
+        .iter()
+        .map(|c| c.postgres);
+
+      if !entry.columns.is_empty()
+        && !entry
+          .columns
+          .iter()
+          .eq(expected)
+      {
+        return Err(format!(
+          "backup columns of {} do \
+           not match this binary's \
+           schema",
+          entry.name
+        ));
+      }
+    }
+
+    if self.files.is_empty() {
+      return Err(
+        "backup lists no files"
+          .to_string()
+      );
+    }
+
+    for file in &self.files {
+      let relative =
+        Path::new(&file.path);
+
+      if !relative.components().all(
+        |c| {
+          matches!(
+            c,
+            Component::Normal(_)
+          )
+        }
+      ) {
+        return Err(format!(
+          "backup file path {} leaves \
+           the backup directory",
+          file.path
+        ));
+      }
+
+      let (bytes, sha256) =
+        stream::digest(
+          &dir.join(relative)
+        )
+        .await?;
+
+      if bytes != file.bytes
+        || sha256 != file.sha256
+      {
+        return Err(format!(
+          "backup file {} is damaged: \
+           size or checksum differs \
+           from the manifest",
+          file.path
+        ));
+      }
+    }
+
+    Ok(())
+  }
+}
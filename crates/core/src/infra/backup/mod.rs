@@ -0,0 +1,156 @@
+//! `db backup` / `db restore`. A backup
+//! is a directory of data files plus a
+//! `manifest.json` recording the schema
+//! versions, row counts and file
+//! checksums; restore checks all of it
+//! before writing anything.
+
+mod manifest;
+mod postgres;
+mod sqlite;
+mod stream;
+
+use std::path::Path;
+
+use chrono::Utc;
+
+pub use self::manifest::{
+  BackupFile,
+  BackupManifest,
+  TableRows
+};
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use crate::infra::{
+  postgres_repo,
+  sqlite_repo
+};
+
+/// Backs up the configured database
+/// into `out`, which must be missing or
+/// empty.
+pub async fn backup(
+  cfg: &AppConfig,
+  out: &Path,
+  compress: bool,
+  progress: impl FnMut(&str, u64)
+) -> Result<BackupManifest, String> {
+  prepare(out).await?;
+
+  let now =
+    Utc::now().timestamp_millis();
+
+  let manifest = match cfg.db_dialect {
+    | SqlDialect::Sqlite => {
+      sqlite::backup(
+        &cfg.sqlite_path,
+        out,
+        compress,
+        now
+      )
+      .await?
+    }
+    | SqlDialect::Postgres => {
+      postgres::backup(
+        cfg, out, compress, now,
+        progress
+      )
+      .await?
+    }
+  };
+
+  manifest.save(out).await?;
+
+  Ok(manifest)
+}
+
+/// Restores the backup in `dir` into
+/// the configured database. SQLite
+/// files are only overwritten with
+/// `replace`; Postgres tables must be
+/// empty.
+pub async fn restore(
+  cfg: &AppConfig,
+  dir: &Path,
+  replace: bool,
+  progress: impl FnMut(&str, u64)
+) -> Result<BackupManifest, String> {
+  let manifest =
+    BackupManifest::load(dir).await?;
+
+  manifest
+    .validate(
+      dir,
+      cfg.db_dialect,
+      supported(cfg.db_dialect)
+    )
+    .await?;
+
+  match cfg.db_dialect {
+    | SqlDialect::Sqlite => {
+      sqlite::restore(
+        &cfg.sqlite_path,
+        dir,
+        &manifest,
+        replace
+      )
+      .await?
+    }
+    | SqlDialect::Postgres => {
+      postgres::restore(
+        cfg, dir, &manifest, progress
+      )
+      .await?
+    }
+  }
+
+  Ok(manifest)
+}
+
+/// Newest schema version this binary
+/// knows.
+fn supported(
+  dialect: SqlDialect
+) -> i64 {
+  let known: &[(i64, &str)] =
+    match dialect {
+      | SqlDialect::Sqlite => {
+        &sqlite_repo::migrations::MIGRATIONS
+      }
+      | SqlDialect::Postgres => {
+        &postgres_repo::migrations::MIGRATIONS
+      }
+    };
+
+  known
+    .iter()
+    .map(|(version, _)| *version)
+    .max()
+    .unwrap_or(0)
+}
+
+async fn prepare(
+  out: &Path
+) -> Result<(), String> {
+  if let Ok(mut entries) =
+    tokio::fs::read_dir(out).await
+    && let Ok(Some(_)) =
+      entries.next_entry().await
+  {
+    return Err(format!(
+      "{} is not empty",
+      out.display()
+    ));
+  }
+
+  tokio::fs::create_dir_all(out)
+    .await
+    .map_err(|e| {
+      format!(
+        "create {}: {e}",
+        out.display()
+      )
+    })
+}
@@ -0,0 +1,523 @@
+//! Postgres backups: a logical export
+//! of the fetcher and server tables,
+//! one JSON array per row, read inside
+//! a single repeatable-read
+//! transaction. Restore loads them back
+//! in one transaction.
+
+use std::path::Path;
+
+use serde_json::Value as Json;
+use sqlx::{
+  PgConnection,
+  PgPool
+};
+
+use super::manifest::{
+  BackupFile,
+  BackupManifest,
+  FORMAT,
+  TableRows,
+  dialect_name
+};
+use super::stream::{
+  Lines,
+  Sink,
+  Source
+};
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use crate::infra::database;
+use crate::infra::db_copy::postgres::{
+  self as rows,
+  qualified
+};
+use crate::infra::db_copy::tables::{
+  Kind,
+  TABLES,
+  Table
+};
+use crate::infra::db_copy::{
+  Row,
+  Value,
+  advance,
+  batch_limit,
+  missing
+};
+use crate::infra::postgres_repo::{
+  create_pool,
+  migrations
+};
+
+const BATCH: usize = 1000;
+
+pub async fn backup(
+  cfg: &AppConfig,
+  out: &Path,
+  compress: bool,
+  created_at_ms: i64,
+  mut progress: impl FnMut(&str, u64)
+) -> Result<BackupManifest, String> {
+  let pool = connect(cfg).await?;
+  let server_schema =
+    &cfg.retention.server_schema;
+
+  let schema_version =
+    migrations::status(&pool)
+      .await?
+      .current();
+
+  let err = |e: sqlx::Error| {
+    format!("export error: {e}")
+  };
+
+  let mut tx =
+    pool.begin().await.map_err(err)?;
+
+  sqlx::query(
+    "SET TRANSACTION ISOLATION LEVEL \
+     REPEATABLE READ, READ ONLY"
+  )
+  .execute(&mut *tx)
+  .await
+  .map_err(err)?;
+
+  let server_schema_version =
+    server_version(
+      &mut tx,
+      server_schema
+    )
+    .await?;
+
+  tokio::fs::create_dir_all(
+    out.join("tables")
+  )
+  .await
+  .map_err(|e| {
+    format!(
+      "create {}: {e}",
+      out.display()
+    )
+  })?;
+
+  let mut tables = Vec::new();
+  let mut files = Vec::new();
+
+  for table in TABLES {
+    let name =
+      qualified(table, server_schema);
+
+    if !rows::exists(&mut *tx, &name)
+      .await?
+    {
+      continue;
+    }
+
+    let path = format!(
+      "tables/{}.jsonl{}",
+      table.name,
+      if compress {
+        ".gz"
+      } else {
+        ""
+      }
+    );
+
+    let mut sink = Sink::create(
+      &out.join(&path),
+      compress
+    )
+    .await?;
+
+    let limit =
+      batch_limit(table, BATCH);
+
+    let mut after = 0;
+    let mut written = 0u64;
+
+    loop {
+      let batch = rows::read(
+        &mut *tx,
+        table,
+        server_schema,
+        after,
+        limit
+      )
+      .await?;
+
+      if batch.is_empty() {
+        break;
+      }
+
+      after =
+        advance(table, after, &batch)?;
+
+      let mut lines = String::new();
+
+      for row in &batch {
+        lines.push_str(&encode(row));
+        lines.push('\n');
+      }
+
+      sink
+        .write(lines.as_bytes())
+        .await?;
+
+      written += batch.len() as u64;
+
+      progress(table.name, written);
+    }
+
+    let (bytes, sha256) =
+      sink.finish().await?;
+
+    tables.push(TableRows {
+      name:    table.name.to_string(),
+      rows:    written,
+      columns: table
+        .columns
+        .iter()
+        .map(|c| c.postgres.to_string())
+        .collect()
+    });
+
+    files.push(BackupFile {
+      path,
+      bytes,
+      sha256
+    });
+  }
+
+  tx.commit().await.map_err(err)?;
+
+  Ok(BackupManifest {
+    format: FORMAT,
+    dialect: dialect_name(
+      SqlDialect::Postgres
+    )
+    .to_string(),
+    created_at_ms,
+    schema_version,
+    server_schema_version,
+    compressed: compress,
+    tables,
+    files
+  })
+}
+
+/// Loads a logical export into a
+/// migrated database whose tables are
+/// all empty; nothing is kept if any
+/// table fails.
+pub async fn restore(
+  cfg: &AppConfig,
+  dir: &Path,
+  manifest: &BackupManifest,
+  mut progress: impl FnMut(&str, u64)
+) -> Result<(), String> {
+  database::create_repo(
+    SqlDialect::Postgres,
+    cfg
+  )
+  .await?
+  .migrate(
+    &cfg.timezone,
+    cfg.default_poll_seconds
+  )
+  .await?;
+
+  let pool = connect(cfg).await?;
+  let server_schema =
+    &cfg.retention.server_schema;
+
+  let err = |e: sqlx::Error| {
+    format!("restore error: {e}")
+  };
+
+  let mut tx =
+    pool.begin().await.map_err(err)?;
+
+  let selected = TABLES
+    .iter()
+    .filter(|t| {
+      manifest.rows(t.name).is_some()
+    })
+    .collect::<Vec<_>>();
+
+  for table in &selected {
+    let name =
+      qualified(table, server_schema);
+
+    if !rows::exists(&mut *tx, &name)
+      .await?
+    {
+      return Err(missing(table));
+    }
+
+    if rows::count(&mut *tx, &name)
+      .await?
+      > 0
+    {
+      return Err(format!(
+        "target table {} is not empty",
+        table.name
+      ));
+    }
+  }
+
+  if selected.iter().any(|t| t.server) {
+    let target = server_version(
+      &mut tx,
+      server_schema
+    )
+    .await?;
+
+    if target
+      != manifest.server_schema_version
+    {
+      return Err(format!(
+        "target server schema is at \
+         version {}, the backup at \
+         {}; run the matching \
+         pulsewire-server against the \
+         target once",
+        target.unwrap_or(0),
+        manifest
+          .server_schema_version
+          .unwrap_or(0)
+      ));
+    }
+  }
+
+  for table in selected {
+    let name =
+      qualified(table, server_schema);
+
+    let Some(file) =
+      manifest.files.iter().find(|f| {
+        f.path.starts_with(&format!(
+          "tables/{}.jsonl",
+          table.name
+        ))
+      })
+    else {
+      return Err(format!(
+        "backup has no file for table \
+         {}",
+        table.name
+      ));
+    };
+
+    let mut lines = Lines::new(
+      Source::open(
+        &dir.join(&file.path),
+        manifest.compressed
+      )
+      .await?
+    );
+
+    let limit =
+      batch_limit(table, BATCH);
+
+    let mut batch = Vec::new();
+    let mut loaded = 0u64;
+
+    loop {
+      let line = lines.next().await?;
+
+      if let Some(line) = &line {
+        batch
+          .push(decode(table, line)?);
+      }
+
+      if batch.len() >= limit
+        || (line.is_none()
+          && !batch.is_empty())
+      {
+        rows::write(
+          &mut tx,
+          table,
+          server_schema,
+          &batch
+        )
+        .await?;
+
+        loaded += batch.len() as u64;
+        batch.clear();
+
+        progress(table.name, loaded);
+      }
+
+      if line.is_none() {
+        break;
+      }
+    }
+
+    if table.serial {
+      rows::reset_sequence(
+        &mut *tx, &name
+      )
+      .await?;
+    }
+
+    let expected =
+      manifest.rows(table.name);
+
+    if Some(loaded) != expected {
+      return Err(format!(
+        "{}: loaded {loaded} rows, \
+         the manifest says {}",
+        table.name,
+        expected.unwrap_or(0)
+      ));
+    }
+  }
+
+  tx.commit().await.map_err(err)
+}
+
+async fn connect(
+  cfg: &AppConfig
+) -> Result<PgPool, String> {
+  create_pool(
+    &cfg.postgres,
+    &cfg.timezone
+  )
+  .await
+}
+
+async fn server_version(
+  conn: &mut PgConnection,
+  server_schema: &str
+) -> Result<Option<i64>, String> {
+  let table = format!(
+    "\"{server_schema}\".\
+     server_schema_migrations"
+  );
+
+  if !rows::exists(&mut *conn, &table)
+    .await?
+  {
+    return Ok(None);
+  }
+
+  sqlx::query_scalar(&format!(
+    "SELECT max(version) FROM {table}"
+  ))
+  .fetch_one(conn)
+  .await
+  .map_err(|e| format!("{table}: {e}"))
+}
+
+fn encode(row: &Row) -> String {
+  let values = row
+    .iter()
+    .map(|value| {
+      match value {
+        | Value::Int(v) => {
+          Json::from(*v)
+        }
+        | Value::Text(v) => {
+          Json::from(v.clone())
+        }
+        | Value::Bytes(v) => {
+          Json::from(
+            v.as_ref().map(hex::encode)
+          )
+        }
+        | Value::Tags(v) => {
+          Json::from(v.clone())
+        }
+      }
+    })
+    .collect();
+
+  Json::Array(values).to_string()
+}
+
+fn decode(
+  table: &Table,
+  line: &str
+) -> Result<Row, String> {
+  let bad = |what: &str| {
+    format!(
+      "{}: bad row in backup: {what}",
+      table.name
+    )
+  };
+
+  let values: Vec<Json> =
+    serde_json::from_str(line)
+      .map_err(|e| {
+        bad(&e.to_string())
+      })?;
+
+  if values.len() != table.columns.len()
+  {
+    return Err(bad(
+      "wrong number of columns"
+    ));
+  }
+
+  table
+    .columns
+    .iter()
+    .zip(values)
+    .map(|(c, v)| {
+      let mismatch = || bad(c.postgres);
+
+      Ok(match c.kind {
+        | _ if v.is_null() => {
+          null(c.kind)
+        }
+        | Kind::Int | Kind::Ms => {
+          Value::Int(Some(
+            v.as_i64()
+              .ok_or_else(mismatch)?
+          ))
+        }
+        | Kind::Bytes => {
+          Value::Bytes(Some(
+            v.as_str()
+              .and_then(|s| {
+                hex::decode(s).ok()
+              })
+              .ok_or_else(mismatch)?
+          ))
+        }
+        | Kind::Tags => {
+          Value::Tags(Some(
+            serde_json::from_value(v)
+              .map_err(|_| mismatch())?
+          ))
+        }
+        | Kind::Text
+        | Kind::Day
+        | Kind::Json
+        | Kind::Stamp => {
+          Value::Text(Some(
+            v.as_str()
+              .ok_or_else(mismatch)?
+              .to_string()
+          ))
+        }
+      })
+    })
+    .collect()
+}
+
+fn null(kind: Kind) -> Value {
+  match kind {
+    | Kind::Int | Kind::Ms => {
+      Value::Int(None)
+    }
+    | Kind::Bytes => Value::Bytes(None),
+    | Kind::Tags => Value::Tags(None),
+    | Kind::Text
+    | Kind::Day
+    | Kind::Json
+    | Kind::Stamp => Value::Text(None)
+  }
+}
@@ -0,0 +1,484 @@
+//! SQLite backups: a `VACUUM INTO`
+//! snapshot, which is consistent even
+//! while the fetcher keeps writing to
+//! the WAL, restored by swapping the
+//! database file.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use sqlx::SqlitePool;
+use sqlx::sqlite::{
+  SqliteConnectOptions,
+  SqlitePoolOptions
+};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+use super::manifest::{
+  BackupFile,
+  BackupManifest,
+  FORMAT,
+  TableRows,
+  dialect_name
+};
+use super::stream::{
+  self,
+  Sink,
+  Source
+};
+use crate::domain::model::SqlDialect;
+use crate::infra::db_copy::sqlite as rows;
+use crate::infra::db_copy::tables::TABLES;
+use crate::infra::instance_lock::InstanceLock;
+use crate::infra::sqlite_repo::migrations;
+
+const SNAPSHOT: &str =
+  "database.sqlite";
+
+pub async fn backup(
+  db_path: &Path,
+  out: &Path,
+  compress: bool,
+  created_at_ms: i64
+) -> Result<BackupManifest, String> {
+  if !db_path.is_file() {
+    return Err(format!(
+      "no SQLite database at {}",
+      db_path.display()
+    ));
+  }
+
+  let raw = out.join(SNAPSHOT);
+
+  {
+    let live = open(db_path).await?;
+
+    sqlx::query("VACUUM INTO ?1")
+      .bind(raw.to_string_lossy())
+      .execute(&live)
+      .await
+      .map_err(|e| {
+        format!("snapshot error: {e}")
+      })?;
+
+    live.close().await;
+  }
+
+  let snapshot = open(&raw).await?;
+
+  // One self-contained file, whatever
+  // mode the live database runs in.
+  sqlx::query(
+    "PRAGMA journal_mode=DELETE"
+  )
+  .execute(&snapshot)
+  .await
+  .map_err(|e| {
+    format!("snapshot error: {e}")
+  })?;
+
+  let schema_version =
+    migrations::status(&snapshot)
+      .await?
+      .current();
+
+  let server_schema_version =
+    server_version(&snapshot).await?;
+
+  let tables =
+    counts(&snapshot).await?;
+
+  snapshot.close().await;
+
+  let name = if compress {
+    format!("{SNAPSHOT}.gz")
+  } else {
+    SNAPSHOT.to_string()
+  };
+
+  let (bytes, sha256) = if compress {
+    let stored =
+      gzip(&raw, &out.join(&name))
+        .await?;
+
+    let _ =
+      tokio::fs::remove_file(&raw)
+        .await;
+
+    stored
+  } else {
+    stream::digest(&raw).await?
+  };
+
+  Ok(BackupManifest {
+    format: FORMAT,
+    dialect: dialect_name(
+      SqlDialect::Sqlite
+    )
+    .to_string(),
+    created_at_ms,
+    schema_version,
+    server_schema_version,
+    compressed: compress,
+    tables,
+    files: vec![BackupFile {
+      path: name,
+      bytes,
+      sha256
+    }]
+  })
+}
+
+/// Replaces the database at `db_path`
+/// with the snapshot, holding the
+/// fetcher's instance lock meanwhile.
+pub async fn restore(
+  db_path: &Path,
+  dir: &Path,
+  manifest: &BackupManifest,
+  replace: bool
+) -> Result<(), String> {
+  if db_path.exists() && !replace {
+    return Err(format!(
+      "{} already exists; pass \
+       --replace to overwrite it",
+      db_path.display()
+    ));
+  }
+
+  let lock =
+    InstanceLock::acquire(db_path)?;
+
+  let result =
+    swap(db_path, dir, manifest).await;
+
+  lock.release();
+
+  result
+}
+
+async fn swap(
+  db_path: &Path,
+  dir: &Path,
+  manifest: &BackupManifest
+) -> Result<(), String> {
+  let file = &manifest.files[0];
+
+  let tmp =
+    suffixed(db_path, ".restore");
+
+  let unpacked = unpack(
+    &dir.join(&file.path),
+    &tmp,
+    manifest.compressed
+  )
+  .await;
+
+  let checked = match unpacked {
+    | Ok(()) => {
+      check(&tmp, manifest).await
+    }
+    | Err(e) => Err(e)
+  };
+
+  if let Err(e) = checked {
+    let _ =
+      tokio::fs::remove_file(&tmp)
+        .await;
+
+    return Err(e);
+  }
+
+  // The old database and its WAL stay
+  // intact until the snapshot is in
+  // place, so a failed swap leaves it
+  // as it was.
+  let aside =
+    match move_aside(db_path).await {
+      | Ok(aside) => aside,
+      | Err(e) => {
+        let _ =
+          tokio::fs::remove_file(&tmp)
+            .await;
+
+        return Err(e);
+      }
+    };
+
+  if let Err(e) =
+    tokio::fs::rename(&tmp, db_path)
+      .await
+  {
+    let _ =
+      tokio::fs::remove_file(&tmp)
+        .await;
+
+    put_back(&aside).await;
+
+    return Err(format!(
+      "rename {} to {}: {e}",
+      tmp.display(),
+      db_path.display()
+    ));
+  }
+
+  for (_, moved) in &aside {
+    let _ =
+      tokio::fs::remove_file(moved)
+        .await;
+  }
+
+  Ok(())
+}
+
+/// Renames the database and its `-wal`
+/// and `-shm` files to `.replaced`
+/// names, returning each original and
+/// moved path. Undoes its own moves on
+/// failure.
+async fn move_aside(
+  db_path: &Path
+) -> Result<
+  Vec<(PathBuf, PathBuf)>,
+  String
+> {
+  let mut moved = Vec::new();
+
+  for suffix in ["", "-wal", "-shm"] {
+    let path =
+      suffixed(db_path, suffix);
+
+    if !path.exists() {
+      continue;
+    }
+
+    let to = suffixed(
+      db_path,
+      &format!(".replaced{suffix}")
+    );
+
+    if let Err(e) =
+      tokio::fs::rename(&path, &to)
+        .await
+    {
+      put_back(&moved).await;
+
+      return Err(format!(
+        "move {} aside: {e}",
+        path.display()
+      ));
+    }
+
+    moved.push((path, to));
+  }
+
+  Ok(moved)
+}
+
+async fn put_back(
+  moved: &[(PathBuf, PathBuf)]
+) {
+  for (path, to) in moved {
+    if let Err(e) =
+      tokio::fs::rename(to, path).await
+    {
+      warn!(
+        from = %to.display(),
+        to = %path.display(),
+        error = %e,
+        "Restoring the replaced database file failed"
+      );
+    }
+  }
+}
+
+/// Confirms the unpacked snapshot holds
+/// what the manifest promises.
+async fn check(
+  path: &Path,
+  manifest: &BackupManifest
+) -> Result<(), String> {
+  let pool = open(path).await?;
+
+  let version =
+    migrations::status(&pool)
+      .await?
+      .current();
+
+  let tables = counts(&pool).await?;
+
+  pool.close().await;
+
+  if version != manifest.schema_version
+  {
+    return Err(format!(
+      "snapshot is at schema version \
+       {version}, the manifest says {}",
+      manifest.schema_version
+    ));
+  }
+
+  for entry in &manifest.tables {
+    let found = tables
+      .iter()
+      .find(|t| t.name == entry.name)
+      .map(|t| t.rows);
+
+    if found != Some(entry.rows) {
+      return Err(format!(
+        "snapshot table {} holds {} \
+         rows, the manifest says {}",
+        entry.name,
+        found.unwrap_or(0),
+        entry.rows
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+async fn open(
+  path: &Path
+) -> Result<SqlitePool, String> {
+  SqlitePoolOptions::new()
+    .max_connections(1)
+    .connect_with(
+      SqliteConnectOptions::new()
+        .filename(path)
+    )
+    .await
+    .map_err(|e| {
+      format!(
+        "open {}: {e}",
+        path.display()
+      )
+    })
+}
+
+async fn counts(
+  pool: &SqlitePool
+) -> Result<Vec<TableRows>, String> {
+  let mut out = Vec::new();
+
+  for table in TABLES {
+    if rows::exists(pool, table).await?
+    {
+      out.push(TableRows {
+        name:    table.name.to_string(),
+        rows:    rows::count(
+          pool, table
+        )
+        .await?,
+        columns: Vec::new()
+      });
+    }
+  }
+
+  Ok(out)
+}
+
+async fn server_version(
+  pool: &SqlitePool
+) -> Result<Option<i64>, String> {
+  let has_table: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = 'server_schema_migrations'"
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+  if has_table.is_none() {
+    return Ok(None);
+  }
+
+  sqlx::query_scalar(
+    "SELECT max(version) FROM \
+     server_schema_migrations"
+  )
+  .fetch_one(pool)
+  .await
+  .map_err(|e| e.to_string())
+}
+
+/// Writes a compressed copy of `from`
+/// to `to`.
+async fn gzip(
+  from: &Path,
+  to: &Path
+) -> Result<(u64, String), String> {
+  let mut source =
+    Source::open(from, false).await?;
+
+  let mut sink =
+    Sink::create(to, true).await?;
+
+  while let Some(chunk) =
+    source.next().await?
+  {
+    sink.write(&chunk).await?;
+  }
+
+  sink.finish().await
+}
+
+/// Copies `from` to `to`,
+/// decompressing when needed.
+async fn unpack(
+  from: &Path,
+  to: &Path,
+  compressed: bool
+) -> Result<(), String> {
+  let mut source =
+    Source::open(from, compressed)
+      .await?;
+
+  let mut file =
+    tokio::fs::File::create(to)
+      .await
+      .map_err(|e| {
+      format!(
+        "create {}: {e}",
+        to.display()
+      )
+    })?;
+
+  while let Some(chunk) =
+    source.next().await?
+  {
+    file
+      .write_all(&chunk)
+      .await
+      .map_err(|e| {
+        format!(
+          "write {}: {e}",
+          to.display()
+        )
+      })?;
+  }
+
+  file.sync_all().await.map_err(|e| {
+    format!(
+      "sync {}: {e}",
+      to.display()
+    )
+  })
+}
+
+fn suffixed(
+  path: &Path,
+  suffix: &str
+) -> PathBuf {
+  let mut name =
+    path.as_os_str().to_os_string();
+
+  name.push(suffix);
+
+  PathBuf::from(name)
+}
@@ -0,0 +1,334 @@
+//! Backup files on disk: written and
+//! read in chunks, gzip compressed when
+//! asked, hashed as they are written.
+
+use std::io::Write;
+use std::mem;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use flate2::Compression;
+use flate2::write::{
+  GzDecoder,
+  GzEncoder
+};
+use sha2::{
+  Digest,
+  Sha256
+};
+use tokio::fs::File;
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+
+const CHUNK: usize = 64 * 1024;
+
+/// A backup file being written.
+pub struct Sink {
+  path:   PathBuf,
+  file:   File,
+  gzip:   Option<GzEncoder<Vec<u8>>>,
+  hasher: Sha256,
+  bytes:  u64
+}
+
+impl Sink {
+  pub async fn create(
+    path: &Path,
+    compress: bool
+  ) -> Result<Self, String> {
+    let file = File::create(path)
+      .await
+      .map_err(|e| {
+        format!(
+          "create {}: {e}",
+          path.display()
+        )
+      })?;
+
+    Ok(Self {
+      path: path.to_path_buf(),
+      file,
+      gzip: compress.then(|| {
+        GzEncoder::new(
+          Vec::new(),
+          Compression::default()
+        )
+      }),
+      hasher: Sha256::new(),
+      bytes: 0
+    })
+  }
+
+  pub async fn write(
+    &mut self,
+    data: &[u8]
+  ) -> Result<(), String> {
+    if let Some(enc) = &mut self.gzip {
+      enc.write_all(data).map_err(
+        |e| format!("gzip error: {e}")
+      )?;
+
+      let out =
+        mem::take(enc.get_mut());
+
+      return self.emit(&out).await;
+    }
+
+    self.emit(data).await
+  }
+
+  async fn emit(
+    &mut self,
+    data: &[u8]
+  ) -> Result<(), String> {
+    if data.is_empty() {
+      return Ok(());
+    }
+
+    self.hasher.update(data);
+    self.bytes += data.len() as u64;
+
+    self
+      .file
+      .write_all(data)
+      .await
+      .map_err(|e| {
+        format!(
+          "write {}: {e}",
+          self.path.display()
+        )
+      })
+  }
+
+  /// Flushes the file to disk and
+  /// returns its size and SHA-256.
+  pub async fn finish(
+    mut self
+  ) -> Result<(u64, String), String> {
+    if let Some(enc) = self.gzip.take()
+    {
+      let rest =
+        enc.finish().map_err(|e| {
+          format!("gzip error: {e}")
+        })?;
+
+      self.emit(&rest).await?;
+    }
+
+    self
+      .file
+      .sync_all()
+      .await
+      .map_err(|e| {
+        format!(
+          "sync {}: {e}",
+          self.path.display()
+        )
+      })?;
+
+    Ok((
+      self.bytes,
+      hex::encode(
+        self.hasher.finalize()
+      )
+    ))
+  }
+}
+
+/// A backup file being read back,
+/// decompressed.
+pub struct Source {
+  path:   PathBuf,
+  file:   File,
+  gunzip: Option<GzDecoder<Vec<u8>>>,
+  done:   bool
+}
+
+impl Source {
+  pub async fn open(
+    path: &Path,
+    compressed: bool
+  ) -> Result<Self, String> {
+    let file = File::open(path)
+      .await
+      .map_err(|e| {
+      format!(
+        "open {}: {e}",
+        path.display()
+      )
+    })?;
+
+    Ok(Self {
+      path: path.to_path_buf(),
+      file,
+      gunzip: compressed.then(|| {
+        GzDecoder::new(Vec::new())
+      }),
+      done: false
+    })
+  }
+
+  /// The next chunk of plain bytes, or
+  /// `None` at the end of the file.
+  pub async fn next(
+    &mut self
+  ) -> Result<Option<Vec<u8>>, String>
+  {
+    let gunzip_err =
+      |e: std::io::Error| {
+        format!(
+          "gunzip {}: {e}",
+          self.path.display()
+        )
+      };
+
+    while !self.done {
+      let mut buf = vec![0; CHUNK];
+
+      let n = self
+        .file
+        .read(&mut buf)
+        .await
+        .map_err(|e| {
+          format!(
+            "read {}: {e}",
+            self.path.display()
+          )
+        })?;
+
+      self.done = n == 0;
+
+      let Some(dec) = &mut self.gunzip
+      else {
+        buf.truncate(n);
+
+        return Ok(
+          (n > 0).then_some(buf)
+        );
+      };
+
+      if n == 0 {
+        dec
+          .try_finish()
+          .map_err(gunzip_err)?;
+      } else {
+        dec
+          .write_all(&buf[..n])
+          .map_err(gunzip_err)?;
+      }
+
+      let out =
+        mem::take(dec.get_mut());
+
+      if !out.is_empty() {
+        return Ok(Some(out));
+      }
+    }
+
+    Ok(None)
+  }
+}
+
+/// Newline-separated records from a
+/// [`Source`].
+pub struct Lines {
+  source: Source,
+  buf:    Vec<u8>,
+  /// Start of the unread part of
+  /// `buf`.
+  start:  usize
+}
+
+impl Lines {
+  pub fn new(source: Source) -> Self {
+    Self {
+      source,
+      buf: Vec::new(),
+      start: 0
+    }
+  }
+
+  pub async fn next(
+    &mut self
+  ) -> Result<Option<String>, String>
+  {
+    loop {
+      if let Some(n) = self.buf
+        [self.start..]
+        .iter()
+        .position(|b| *b == b'\n')
+      {
+        let line = text(
+          &self.buf[self.start
+            ..self.start + n],
+          &self.source.path
+        )?;
+
+        self.start += n + 1;
+
+        return Ok(Some(line));
+      }
+
+      self.buf.drain(..self.start);
+      self.start = 0;
+
+      match self.source.next().await? {
+        | Some(chunk) => {
+          self.buf.extend(chunk)
+        }
+        | None if self.buf.is_empty() =>
+        {
+          return Ok(None);
+        }
+        | None => {
+          let line = text(
+            &self.buf,
+            &self.source.path
+          )?;
+
+          self.buf.clear();
+
+          return Ok(Some(line));
+        }
+      }
+    }
+  }
+}
+
+fn text(
+  bytes: &[u8],
+  path: &Path
+) -> Result<String, String> {
+  String::from_utf8(bytes.to_vec())
+    .map_err(|e| {
+      format!("{}: {e}", path.display())
+    })
+}
+
+/// Size and SHA-256 of a file as
+/// stored.
+pub async fn digest(
+  path: &Path
+) -> Result<(u64, String), String> {
+  let mut source =
+    Source::open(path, false).await?;
+
+  let mut hasher = Sha256::new();
+  let mut bytes = 0u64;
+
+  while let Some(chunk) =
+    source.next().await?
+  {
+    hasher.update(&chunk);
+    bytes += chunk.len() as u64;
+  }
+
+  Ok((
+    bytes,
+    hex::encode(hasher.finalize())
+  ))
+}
@@ -0,0 +1,282 @@
+//! `categories.toml`: category names,
+//! the domains each one owns, its
+//! default priority class, its
+//! scheduling overrides and polling
+//! windows.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+
+use super::ConfigError;
+use super::parse::parse_feed_priority;
+use super::raw::{
+  RawCategoriesFile,
+  RawCategoryEntry
+};
+use super::windows::parse_windows;
+use crate::domain::model::{
+  CategoryConfig,
+  FeedPriority,
+  SchedulingPolicy
+};
+use crate::domain::poll_window::PollWindow;
+
+pub(crate) struct Categories {
+  pub list:     Vec<CategoryConfig>,
+  pub names:    HashSet<String>,
+  pub policies:
+    HashMap<String, SchedulingPolicy>,
+  pub windows:
+    HashMap<String, Vec<PollWindow>>,
+  by_domain:    HashMap<String, String>
+}
+
+impl Categories {
+  pub fn parse(
+    raw: RawCategoriesFile,
+    defaults: &SchedulingPolicy
+  ) -> Result<Self, ConfigError> {
+    let mut names = HashSet::new();
+
+    let mut by_domain = HashMap::new();
+
+    let mut policies = HashMap::new();
+
+    let mut windows = HashMap::new();
+
+    let mut list = Vec::new();
+
+    for c in &raw.categories {
+      let name =
+        c.name.trim().to_string();
+
+      if name.is_empty() {
+        return Err(
+          ConfigError::Invalid(
+            "category name cannot be \
+             empty"
+              .into()
+          )
+        );
+      }
+
+      if !names.insert(name.clone()) {
+        return Err(
+          ConfigError::Invalid(
+            format!(
+              "duplicate category \
+               name '{name}'"
+            )
+          )
+        );
+      }
+
+      let mut domains = Vec::new();
+
+      for d in &c.domains {
+        let domain =
+          d.trim().to_ascii_lowercase();
+
+        if domain.is_empty() {
+          return Err(
+            ConfigError::Invalid(
+              format!(
+                "category '{name}' \
+                 has empty domain"
+              )
+            )
+          );
+        }
+
+        if by_domain
+          .insert(
+            domain.clone(),
+            name.clone()
+          )
+          .is_some()
+        {
+          return Err(
+            ConfigError::Invalid(
+              format!(
+                "domain '{domain}' \
+                 appears in multiple \
+                 categories"
+              )
+            )
+          );
+        }
+
+        domains.push(domain);
+      }
+
+      let priority =
+        parse_feed_priority(
+          c.priority.as_deref(),
+          &format!("category '{name}'")
+        )?
+        .unwrap_or_default();
+
+      policies.insert(
+        name.clone(),
+        merge_policy(defaults, c)
+      );
+
+      if let Some(raw) = &c.windows {
+        windows.insert(
+          name.clone(),
+          parse_windows(
+            raw,
+            &format!(
+              "category '{name}'"
+            )
+          )?
+        );
+      }
+
+      list.push(CategoryConfig {
+        name,
+        domains,
+        priority
+      });
+    }
+
+    if list.is_empty() {
+      return Err(ConfigError::Invalid(
+        "categories.toml must define \
+         at least one category"
+          .into()
+      ));
+    }
+
+    Ok(Self {
+      list,
+      names,
+      policies,
+      windows,
+      by_domain
+    })
+  }
+
+  /// The feed's explicit category,
+  /// which must exist, or the one
+  /// owning its domain.
+  pub fn resolve(
+    &self,
+    feed_id: &str,
+    explicit: Option<String>,
+    domain: &str
+  ) -> Result<String, ConfigError> {
+    match explicit {
+      | Some(cat) => {
+        if !self.names.contains(&cat) {
+          return Err(
+            ConfigError::Invalid(
+              format!(
+                "feed '{feed_id}' \
+                 category '{cat}' \
+                 missing from \
+                 categories"
+              )
+            )
+          );
+        }
+
+        Ok(cat)
+      }
+      | None => {
+        self
+          .by_domain
+          .get(domain)
+          .cloned()
+          .ok_or_else(|| {
+            ConfigError::Invalid(
+              format!(
+                "feed '{feed_id}' \
+                 domain '{domain}' \
+                 missing from \
+                 categories"
+              )
+            )
+          })
+      }
+    }
+  }
+
+  pub fn policy(
+    &self,
+    category: &str
+  ) -> SchedulingPolicy {
+    self.policies[category]
+  }
+
+  pub fn priority(
+    &self,
+    category: &str
+  ) -> FeedPriority {
+    self
+      .list
+      .iter()
+      .find(|c| c.name == category)
+      .map(|c| c.priority)
+      .unwrap_or_default()
+  }
+}
+
+/// Overlays the fields an entry sets on
+/// the global policy; range checks are
+/// left to `validate_semantic`.
+fn merge_policy(
+  defaults: &SchedulingPolicy,
+  c: &RawCategoryEntry
+) -> SchedulingPolicy {
+  let mut policy = *defaults;
+
+  if let Some(p) = &c.polling {
+    policy.default_poll_seconds =
+      p.default_seconds.unwrap_or(
+        policy.default_poll_seconds
+      );
+
+    policy.max_poll_seconds =
+      p.max_seconds.unwrap_or(
+        policy.max_poll_seconds
+      );
+
+    policy.jitter_fraction =
+      p.jitter_fraction.unwrap_or(
+        policy.jitter_fraction
+      );
+  }
+
+  if let Some(b) = &c.backoff {
+    policy.error_backoff_base_seconds =
+      b.error_base_seconds.unwrap_or(
+        policy
+          .error_backoff_base_seconds
+      );
+
+    policy.max_error_backoff_seconds =
+      b.max_error_seconds.unwrap_or(
+        policy
+          .max_error_backoff_seconds
+      );
+
+    policy.max_consecutive_errors = b
+      .max_consecutive_errors
+      .unwrap_or(
+        policy.max_consecutive_errors
+      );
+  }
+
+  policy.parallelism = c
+    .parallelism
+    .unwrap_or(policy.parallelism);
+
+  policy.due_batch_size = c
+    .due_batch_size
+    .unwrap_or(policy.due_batch_size);
+
+  policy
+}
@@ -69,6 +69,105 @@ pub(crate) fn default_metrics_bind()
   "0.0.0.0:9898".to_string()
 }
 
+pub(crate) fn default_admin_bind()
+-> String {
+  "127.0.0.1:9899".to_string()
+}
+
+pub(crate) fn default_health_interval_seconds()
+-> u64 {
+  900
+}
+
+pub(crate) fn default_shutdown_drain_seconds()
+-> u64 {
+  30
+}
+
+pub(crate) fn default_restart_base_seconds()
+-> u64 {
+  1
+}
+
+pub(crate) fn default_restart_max_seconds()
+-> u64 {
+  300
+}
+
+pub(crate) fn default_max_restarts()
+-> u32 {
+  5
+}
+
+pub(crate) fn default_catch_up_enabled()
+-> bool {
+  true
+}
+
+pub(crate) fn default_catch_up_window_seconds()
+-> u64 {
+  600
+}
+
+pub(crate) fn default_catch_up_min_overdue_seconds()
+-> u64 {
+  300
+}
+
+pub(crate) fn default_due_batch_size()
+-> usize {
+  1000
+}
+
+/// Per-loop parallelism when neither
+/// `[scheduler] parallelism` nor
+/// `global_max_concurrent_requests` is
+/// set.
+pub(crate) fn default_parallelism()
+-> usize {
+  64
+}
+
+pub(crate) fn default_priority_aging_seconds()
+-> u64 {
+  300
+}
+
+pub(crate) fn default_head_retest_seconds()
+-> u64 {
+  86_400
+}
+
+pub(crate) fn default_head_domain_threshold()
+-> usize {
+  3
+}
+
+pub(crate) fn default_write_behind_enabled()
+-> bool {
+  true
+}
+
+pub(crate) fn default_write_behind_batch_size()
+-> usize {
+  500
+}
+
+pub(crate) fn default_write_behind_flush_ms()
+-> u64 {
+  250
+}
+
+pub(crate) fn default_partitions_premake_months()
+-> u32 {
+  2
+}
+
+pub(crate) fn default_lease_seconds()
+-> u64 {
+  120
+}
+
 pub(crate) fn default_log_tick_warn_seconds()
 -> u64 {
   600
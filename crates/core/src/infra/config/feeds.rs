@@ -8,8 +8,10 @@ use tokio::fs;
 
 use super::ConfigError;
 use super::raw::{
+  RawDataset,
   RawFeedDefaults,
-  RawFeedsFile
+  RawFeedsFile,
+  RawPollWindow
 };
 use super::schema::validate_toml;
 
@@ -95,6 +97,9 @@ pub(crate) async fn load_all_feeds(
     tags:              None,
     language:          None,
     content_type:      None,
+    priority:          None,
+    windows:           None,
+    dataset:           None,
     feeds:             all
   })
 }
@@ -219,7 +224,10 @@ struct FeedDefaults {
   provenance:        Option<String>,
   tags: Option<Vec<String>>,
   language:          Option<String>,
-  content_type:      Option<String>
+  content_type:      Option<String>,
+  priority:          Option<String>,
+  windows: Option<Vec<RawPollWindow>>,
+  dataset:           Option<RawDataset>
 }
 
 impl FeedDefaults {
@@ -231,7 +239,10 @@ impl FeedDefaults {
       provenance:        None,
       tags:              None,
       language:          None,
-      content_type:      None
+      content_type:      None,
+      priority:          None,
+      windows:           None,
+      dataset:           None
     }
   }
 
@@ -265,7 +276,10 @@ impl FeedDefaults {
       language: file.language.clone(),
       content_type: file
         .content_type
-        .clone()
+        .clone(),
+      priority: file.priority.clone(),
+      windows: file.windows.clone(),
+      dataset: file.dataset.clone()
     })
   }
 
@@ -299,7 +313,10 @@ impl FeedDefaults {
       language: file.language.clone(),
       content_type: file
         .content_type
-        .clone()
+        .clone(),
+      priority: file.priority.clone(),
+      windows: file.windows.clone(),
+      dataset: file.dataset.clone()
     })
   }
 
@@ -346,6 +363,24 @@ impl FeedDefaults {
         .clone()
         .or_else(|| {
           global.content_type.clone()
+        }),
+      priority:          file
+        .priority
+        .clone()
+        .or_else(|| {
+          global.priority.clone()
+        }),
+      windows:           file
+        .windows
+        .clone()
+        .or_else(|| {
+          global.windows.clone()
+        }),
+      dataset:           file
+        .dataset
+        .clone()
+        .or_else(|| {
+          global.dataset.clone()
         })
     }
   }
@@ -388,6 +423,21 @@ fn apply_defaults(
       defaults.content_type.clone();
   }
 
+  if feed.priority.is_none() {
+    feed.priority =
+      defaults.priority.clone();
+  }
+
+  if feed.windows.is_none() {
+    feed.windows =
+      defaults.windows.clone();
+  }
+
+  if feed.dataset.is_none() {
+    feed.dataset =
+      defaults.dataset.clone();
+  }
+
   let prefix = match feed
     .id_prefix
     .as_deref()
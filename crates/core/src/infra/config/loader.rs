@@ -5,9 +5,12 @@ use chrono_tz::Tz;
 use tokio::fs;
 
 use super::ConfigError;
+use super::categories::Categories;
 use super::defaults::{
+  default_health_interval_seconds,
   default_metrics_bind,
   default_metrics_enabled,
+  default_shutdown_drain_seconds,
   normalize_domains,
   normalize_log_level,
   normalize_log_rotation,
@@ -15,9 +18,19 @@ use super::defaults::{
 };
 use super::feeds::load_all_feeds;
 use super::parse::{
+  parse_admin,
+  parse_archive,
+  parse_catch_up,
+  parse_dataset,
   parse_dialect,
+  parse_feed_priority,
+  parse_head_probe,
+  parse_leasing,
   parse_mode,
   parse_postgres,
+  parse_priority_policy,
+  parse_scheduling_defaults,
+  parse_supervisor,
   url_host
 };
 use super::paths::{
@@ -28,20 +41,30 @@ use super::raw::{
   RawAppFile,
   RawCategoriesFile,
   RawDomainsFile,
+  RawHealth,
   RawMetrics
 };
+use super::retention::parse_retention;
 use super::schema::{
   load_schema,
   validate_toml
 };
+use super::storage::{
+  parse_partitions,
+  parse_write_behind
+};
+use super::windows::parse_windows;
 use crate::domain::model::{
   AppConfig,
   CategoryConfig,
   DomainConfig,
   FeedConfig,
+  HealthConfig,
   MetricsConfig,
-  PostgresConfig
+  PostgresConfig,
+  SchedulingConfig
 };
+use crate::domain::poll_window::PollWindows;
 
 pub struct ConfigLoader;
 
@@ -172,6 +195,11 @@ impl ConfigLoader {
     )
     .await?;
 
+    let scheduling_defaults =
+      parse_scheduling_defaults(
+        &raw_cfg
+      );
+
     let mode = parse_mode(
       raw_cfg.app.mode.as_deref()
     )?;
@@ -255,92 +283,10 @@ impl ConfigLoader {
       );
     }
 
-    let mut category_names =
-      std::collections::HashSet::new();
-
-    let mut domain_to_category =
-      HashMap::new();
-
-    let mut categories = Vec::new();
-
-    for c in raw_categories.categories {
-      let name =
-        c.name.trim().to_string();
-
-      if name.is_empty() {
-        return Err(
-          ConfigError::Invalid(
-            "category name cannot be \
-             empty"
-              .into()
-          )
-        );
-      }
-
-      if !category_names
-        .insert(name.clone())
-      {
-        return Err(
-          ConfigError::Invalid(
-            format!(
-              "duplicate category \
-               name '{name}'"
-            )
-          )
-        );
-      }
-
-      let mut domains_vec = Vec::new();
-
-      for d in c.domains {
-        let domain =
-          d.trim().to_ascii_lowercase();
-
-        if domain.is_empty() {
-          return Err(
-            ConfigError::Invalid(
-              format!(
-                "category '{name}' \
-                 has empty domain"
-              )
-            )
-          );
-        }
-
-        if domain_to_category
-          .insert(
-            domain.clone(),
-            name.clone()
-          )
-          .is_some()
-        {
-          return Err(
-            ConfigError::Invalid(
-              format!(
-                "domain '{domain}' \
-                 appears in multiple \
-                 categories"
-              )
-            )
-          );
-        }
-
-        domains_vec.push(domain);
-      }
-
-      categories.push(CategoryConfig {
-        name,
-        domains: domains_vec
-      });
-    }
-
-    if categories.is_empty() {
-      return Err(ConfigError::Invalid(
-        "categories.toml must define \
-         at least one category"
-          .into()
-      ));
-    }
+    let categories = Categories::parse(
+      raw_categories,
+      &scheduling_defaults
+    )?;
 
     let history_sample_rate = raw_cfg
       .state_history
@@ -362,6 +308,9 @@ impl ConfigLoader {
 
     let mut feeds = Vec::new();
 
+    let mut feed_windows =
+      HashMap::new();
+
     for f in raw_feeds.feeds {
       let domain = url_host(&f.url)
         .ok_or_else(|| {
@@ -374,58 +323,95 @@ impl ConfigLoader {
       let domain =
         domain.to_ascii_lowercase();
 
-      let category = if let Some(cat) =
-        f.category.clone()
-      {
-        if !category_names
-          .contains(&cat)
-        {
-          return Err(
-            ConfigError::Invalid(
-              format!(
-                "feed '{}' category \
-                 '{cat}' missing from \
-                 categories",
-                f.id
-              )
-            )
-          );
-        }
+      let category = categories
+        .resolve(
+          &f.id,
+          f.category.clone(),
+          &domain
+        )?;
+
+      let priority =
+        parse_feed_priority(
+          f.priority.as_deref(),
+          &format!("feed '{}'", f.id)
+        )?
+        .unwrap_or_else(
+          || {
+            categories
+              .priority(&category)
+          }
+        );
+
+      let base_poll_seconds =
+        f.base_poll_seconds.unwrap_or(
+          categories
+            .policy(&category)
+            .default_poll_seconds
+        );
 
-        cat
-      } else {
-        domain_to_category
-          .get(&domain)
-          .cloned()
-          .ok_or_else(|| {
-            ConfigError::Invalid(
-              format!(
-                "feed '{}' domain \
-                 '{domain}' missing \
-                 from categories",
-                f.id
-              )
-            )
-          })?
-      };
+      let dataset = parse_dataset(
+        f.dataset, &f.id
+      )?;
+
+      if let Some(raw) = &f.windows {
+        feed_windows.insert(
+          f.id.clone(),
+          parse_windows(
+            raw,
+            &format!("feed '{}'", f.id)
+          )?
+        );
+      }
 
       feeds.push(FeedConfig {
         id: f.id,
         url: f.url,
         domain,
         category,
-        base_poll_seconds: f
-          .base_poll_seconds
-          .unwrap_or(
-            raw_cfg
-              .polling
-              .default_seconds
-          ),
+        base_poll_seconds,
         provenance: f.provenance,
         tags: f.tags,
         language: f.language,
-        content_type: f.content_type
+        content_type: f.content_type,
+        dataset,
+        priority
+      });
+    }
+
+    let archive = parse_archive(
+      raw_cfg.archive,
+      config_path
+    )?;
+
+    let retention = parse_retention(
+      raw_cfg.retention,
+      &categories.names
+    )?;
+
+    let supervisor = parse_supervisor(
+      raw_cfg.supervisor
+    )?;
+
+    let leasing = parse_leasing(
+      raw_cfg.leasing,
+      db_dialect
+    )?;
+
+    let health_cfg = raw_cfg
+      .health
+      .unwrap_or(RawHealth {
+        enabled:          false,
+        interval_seconds:
+          default_health_interval_seconds()
       });
+
+    if health_cfg.interval_seconds == 0
+    {
+      return Err(ConfigError::Invalid(
+        "health.interval_seconds must \
+         be greater than 0"
+          .into()
+      ));
     }
 
     let metrics_cfg = raw_cfg
@@ -483,13 +469,58 @@ impl ConfigLoader {
           enabled: metrics_cfg.enabled,
           bind: metrics_cfg.bind
         },
+        admin: parse_admin(
+          raw_cfg.admin
+        )?,
+        archive,
+        retention,
+        health: HealthConfig {
+          enabled:          health_cfg
+            .enabled,
+          interval_seconds: health_cfg
+            .interval_seconds
+        },
+        supervisor,
+        leasing,
+        catch_up: parse_catch_up(
+          raw_cfg.catch_up
+        )?,
+        head_probe: parse_head_probe(
+          raw_cfg.head_probe
+        ),
+        write_behind: parse_write_behind(
+          raw_cfg.write_behind
+        )?,
+        partitions: parse_partitions(
+          raw_cfg.partitions
+        )?,
+        priority: parse_priority_policy(
+          raw_cfg.priority
+        ),
+        scheduling: SchedulingConfig {
+          defaults:   scheduling_defaults,
+          categories: categories
+            .policies
+            .clone()
+        },
+        windows: PollWindows {
+          categories: categories
+            .windows,
+          feeds:      feed_windows
+        },
+        shutdown_drain_seconds: raw_cfg
+          .shutdown
+          .map(|s| s.drain_seconds)
+          .unwrap_or_else(
+            default_shutdown_drain_seconds
+          ),
         mode,
         timezone,
         domains,
         state_history_sample_rate: history_sample_rate
       },
       feeds,
-      categories
+      categories: categories.list
     })
   }
 }
@@ -2,6 +2,7 @@
 //! (app/domains/feeds) and normalizes
 //! it into `AppConfig` + feed list.
 
+mod categories;
 mod defaults;
 mod error;
 mod feeds;
@@ -9,8 +10,11 @@ mod loader;
 mod parse;
 mod paths;
 mod raw;
+mod retention;
 mod schema;
 mod semantic;
+mod storage;
+mod windows;
 
 pub use error::ConfigError;
 pub use loader::{
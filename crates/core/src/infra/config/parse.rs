@@ -1,10 +1,53 @@
+use std::path::Path;
+
 use super::ConfigError;
-use super::raw::RawPostgres;
+use super::defaults::{
+  default_admin_bind,
+  default_catch_up_enabled,
+  default_catch_up_min_overdue_seconds,
+  default_catch_up_window_seconds,
+  default_due_batch_size,
+  default_head_domain_threshold,
+  default_head_retest_seconds,
+  default_lease_seconds,
+  default_max_restarts,
+  default_parallelism,
+  default_priority_aging_seconds,
+  default_restart_base_seconds,
+  default_restart_max_seconds
+};
+use super::paths::resolve_log_dir;
+use super::raw::{
+  RawAdmin,
+  RawAppFile,
+  RawArchive,
+  RawCatchUp,
+  RawDataset,
+  RawHeadProbe,
+  RawLeasing,
+  RawPostgres,
+  RawPriority,
+  RawSupervisor
+};
 use crate::domain::model::{
+  AdminConfig,
   AppMode,
+  ArchiveConfig,
+  ArchiveStorage,
+  CatchUpConfig,
+  DatasetConfig,
+  DatasetFormat,
+  FatalPolicy,
+  FeedPriority,
+  HeadProbeConfig,
+  LeasingConfig,
   PostgresConfig,
-  SqlDialect
+  PriorityConfig,
+  SchedulingPolicy,
+  SqlDialect,
+  SupervisorConfig
 };
+use crate::infra::instance_lock::hostname;
 
 pub(crate) fn parse_dialect(
   s: Option<&str>
@@ -50,7 +93,7 @@ pub(crate) fn parse_postgres(
   })
 }
 
-fn validate_schema_name(
+pub(crate) fn validate_schema_name(
   raw: &str
 ) -> Result<String, ConfigError> {
   let trimmed = raw.trim();
@@ -103,6 +146,72 @@ pub(crate) fn parse_mode(
   }
 }
 
+pub(crate) fn parse_dataset(
+  raw: Option<RawDataset>,
+  feed_id: &str
+) -> Result<
+  Option<DatasetConfig>,
+  ConfigError
+> {
+  let Some(raw) = raw else {
+    return Ok(None);
+  };
+
+  let format = match raw
+    .format
+    .trim()
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    | "csv" => DatasetFormat::Csv,
+    | "json" => DatasetFormat::Json,
+    | other => {
+      return Err(ConfigError::Invalid(
+        format!(
+          "feed '{feed_id}' has \
+           invalid dataset.format \
+           '{other}', expected 'csv' \
+           or 'json'"
+        )
+      ));
+    }
+  };
+
+  let key = raw.key.trim().to_string();
+
+  if key.is_empty() {
+    return Err(ConfigError::Invalid(
+      format!(
+        "feed '{feed_id}' dataset.key \
+         cannot be empty"
+      )
+    ));
+  }
+
+  let records_path = raw
+    .records_path
+    .map(|p| p.trim().to_string())
+    .filter(|p| !p.is_empty());
+
+  if records_path.is_some()
+    && format == DatasetFormat::Csv
+  {
+    return Err(ConfigError::Invalid(
+      format!(
+        "feed '{feed_id}' \
+         dataset.records_path only \
+         applies to json datasets"
+      )
+    ));
+  }
+
+  Ok(Some(DatasetConfig {
+    format,
+    key,
+    records_path
+  }))
+}
+
 pub(crate) fn url_host(
   url: &str
 ) -> Option<String> {
@@ -134,3 +243,350 @@ pub(crate) fn url_host(
     Some(host.to_string())
   }
 }
+
+pub(crate) fn parse_archive(
+  raw: Option<RawArchive>,
+  config_path: &Path
+) -> Result<ArchiveConfig, ConfigError>
+{
+  let Some(raw) = raw else {
+    return Ok(ArchiveConfig {
+      enabled:        false,
+      storage:
+        ArchiveStorage::Database,
+      retention_days: 0
+    });
+  };
+
+  let storage = match raw
+    .storage
+    .as_deref()
+    .map(str::to_ascii_lowercase)
+    .as_deref()
+  {
+    | None | Some("database") => {
+      if raw.directory.is_some() {
+        return Err(
+          ConfigError::Invalid(
+            "archive.directory \
+             requires storage = \
+             'directory'"
+              .into()
+          )
+        );
+      }
+
+      ArchiveStorage::Database
+    }
+    | Some("directory") => {
+      let dir = raw
+        .directory
+        .as_deref()
+        .unwrap_or("archive");
+
+      ArchiveStorage::Directory(
+        resolve_log_dir(
+          config_path,
+          dir
+        )
+      )
+    }
+    | Some(other) => {
+      return Err(ConfigError::Invalid(
+        format!(
+          "invalid archive.storage \
+           '{other}', expected \
+           'database' or 'directory'"
+        )
+      ));
+    }
+  };
+
+  Ok(ArchiveConfig {
+    enabled: raw.enabled,
+    storage,
+    retention_days: raw.retention_days
+  })
+}
+
+pub(crate) fn parse_supervisor(
+  raw: Option<RawSupervisor>
+) -> Result<SupervisorConfig, ConfigError>
+{
+  let raw =
+    raw.unwrap_or(RawSupervisor {
+      restart_base_seconds:
+        default_restart_base_seconds(),
+      restart_max_seconds:
+        default_restart_max_seconds(),
+      max_restarts:
+        default_max_restarts(),
+      on_fatal:             None
+    });
+
+  if raw.restart_base_seconds == 0
+    || raw.restart_max_seconds
+      < raw.restart_base_seconds
+  {
+    return Err(ConfigError::Invalid(
+      "supervisor.\
+       restart_base_seconds must be > \
+       0 and <= restart_max_seconds"
+        .into()
+    ));
+  }
+
+  let on_fatal = match raw
+    .on_fatal
+    .as_deref()
+    .map(str::to_ascii_lowercase)
+    .as_deref()
+  {
+    | None | Some("exit") => {
+      FatalPolicy::Exit
+    }
+    | Some("stop_category") => {
+      FatalPolicy::StopCategory
+    }
+    | Some(other) => {
+      return Err(ConfigError::Invalid(
+        format!(
+          "invalid supervisor.\
+           on_fatal '{other}', \
+           expected 'exit' or \
+           'stop_category'"
+        )
+      ));
+    }
+  };
+
+  Ok(SupervisorConfig {
+    restart_base_seconds: raw
+      .restart_base_seconds,
+    restart_max_seconds: raw
+      .restart_max_seconds,
+    max_restarts: raw.max_restarts,
+    on_fatal
+  })
+}
+
+/// `owner` defaults to
+/// `<hostname>-<pid>`, unique per
+/// running replica.
+pub(crate) fn parse_leasing(
+  raw: Option<RawLeasing>,
+  dialect: SqlDialect
+) -> Result<LeasingConfig, ConfigError>
+{
+  let raw = raw.unwrap_or(RawLeasing {
+    enabled:              false,
+    owner:                None,
+    lease_seconds:
+      default_lease_seconds(),
+    shared_domain_limits: None
+  });
+
+  if raw.enabled
+    && dialect != SqlDialect::Postgres
+  {
+    return Err(ConfigError::Invalid(
+      "leasing.enabled requires \
+       database.dialect = 'postgres'"
+        .into()
+    ));
+  }
+
+  if raw.lease_seconds < 3 {
+    return Err(ConfigError::Invalid(
+      "leasing.lease_seconds must be \
+       at least 3"
+        .into()
+    ));
+  }
+
+  let owner = raw
+    .owner
+    .filter(|o| !o.trim().is_empty())
+    .unwrap_or_else(|| {
+      let host = hostname();
+
+      format!(
+        "{host}-{}",
+        std::process::id()
+      )
+    });
+
+  Ok(LeasingConfig {
+    enabled: raw.enabled,
+    owner,
+    lease_seconds: raw.lease_seconds,
+    shared_domain_limits: raw
+      .shared_domain_limits
+      .unwrap_or(true)
+  })
+}
+
+/// `PULSEWIRE_ADMIN_TOKEN` wins over
+/// `admin.token` so the secret can stay
+/// out of the config file.
+pub(crate) fn parse_admin(
+  raw: Option<RawAdmin>
+) -> Result<AdminConfig, ConfigError> {
+  let raw = raw.unwrap_or(RawAdmin {
+    enabled: false,
+    bind:    default_admin_bind(),
+    token:   None
+  });
+
+  let token = std::env::var(
+    "PULSEWIRE_ADMIN_TOKEN"
+  )
+  .ok()
+  .or(raw.token)
+  .map(|t| t.trim().to_string())
+  .unwrap_or_default();
+
+  if raw.enabled && token.is_empty() {
+    return Err(ConfigError::Invalid(
+      "admin.enabled requires \
+       admin.token or \
+       PULSEWIRE_ADMIN_TOKEN"
+        .into()
+    ));
+  }
+
+  Ok(AdminConfig {
+    enabled: raw.enabled,
+    bind: raw.bind,
+    token
+  })
+}
+
+pub(crate) fn parse_catch_up(
+  raw: Option<RawCatchUp>
+) -> Result<CatchUpConfig, ConfigError>
+{
+  let raw = raw.unwrap_or(RawCatchUp {
+    enabled:
+      default_catch_up_enabled(),
+    window_seconds:
+      default_catch_up_window_seconds(),
+    min_overdue_seconds:
+      default_catch_up_min_overdue_seconds()
+  });
+
+  if raw.enabled
+    && raw.window_seconds == 0
+  {
+    return Err(ConfigError::Invalid(
+      "catch_up.window_seconds must \
+       be greater than 0"
+        .into()
+    ));
+  }
+
+  Ok(CatchUpConfig {
+    enabled:             raw.enabled,
+    window_seconds:      raw
+      .window_seconds,
+    min_overdue_seconds: raw
+      .min_overdue_seconds
+  })
+}
+
+/// Global polling/backoff values plus
+/// `[scheduler]`; parallelism falls
+/// back to
+/// `global_max_concurrent_requests`.
+pub(crate) fn parse_scheduling_defaults(
+  raw: &RawAppFile
+) -> SchedulingPolicy {
+  let scheduler =
+    raw.scheduler.as_ref();
+
+  SchedulingPolicy {
+    default_poll_seconds:       raw
+      .polling
+      .default_seconds,
+    max_poll_seconds:           raw
+      .polling
+      .max_seconds,
+    jitter_fraction:            raw
+      .polling
+      .jitter_fraction,
+    error_backoff_base_seconds: raw
+      .backoff
+      .error_base_seconds,
+    max_error_backoff_seconds:  raw
+      .backoff
+      .max_error_seconds,
+    max_consecutive_errors:     raw
+      .backoff
+      .max_consecutive_errors,
+    parallelism:                scheduler
+      .and_then(|s| s.parallelism)
+      .or(
+        raw
+          .requests
+          .global_max_concurrent_requests
+      )
+      .unwrap_or_else(default_parallelism),
+    due_batch_size:             scheduler
+      .map(|s| s.due_batch_size)
+      .unwrap_or_else(
+        default_due_batch_size
+      )
+  }
+}
+
+pub(crate) fn parse_priority_policy(
+  raw: Option<RawPriority>
+) -> PriorityConfig {
+  PriorityConfig {
+    aging_seconds: raw
+      .map(|p| p.aging_seconds)
+      .unwrap_or_else(
+        default_priority_aging_seconds
+      )
+  }
+}
+
+pub(crate) fn parse_head_probe(
+  raw: Option<RawHeadProbe>
+) -> HeadProbeConfig {
+  let raw =
+    raw.unwrap_or(RawHeadProbe {
+      retest_seconds:
+        default_head_retest_seconds(),
+      domain_threshold:
+        default_head_domain_threshold()
+    });
+
+  HeadProbeConfig {
+    retest_seconds:   raw
+      .retest_seconds,
+    domain_threshold: raw
+      .domain_threshold
+  }
+}
+
+/// `None` when unset, so the caller can
+/// fall back to the category's class.
+pub(crate) fn parse_feed_priority(
+  raw: Option<&str>,
+  owner: &str
+) -> Result<
+  Option<FeedPriority>,
+  ConfigError
+> {
+  raw
+    .map(|p| {
+      FeedPriority::parse(p.trim())
+        .map_err(|e| {
+          ConfigError::Invalid(format!(
+            "{owner}: {e}"
+          ))
+        })
+    })
+    .transpose()
+}
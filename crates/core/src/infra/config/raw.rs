@@ -1,7 +1,18 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
 use super::defaults::{
+  default_admin_bind,
+  default_catch_up_enabled,
+  default_catch_up_min_overdue_seconds,
+  default_catch_up_window_seconds,
+  default_due_batch_size,
+  default_head_domain_threshold,
+  default_head_retest_seconds,
+  default_health_interval_seconds,
   default_immediate_error_statuses,
+  default_lease_seconds,
   default_log_feed_timing_warn_ms,
   default_log_file_directory,
   default_log_file_enabled,
@@ -10,15 +21,24 @@ use super::defaults::{
   default_log_file_rotation,
   default_log_tick_warn_seconds,
   default_max_consecutive_errors,
+  default_max_restarts,
   default_metrics_bind,
   default_metrics_enabled,
+  default_partitions_premake_months,
   default_pg_database,
   default_pg_host,
   default_pg_password,
   default_pg_port,
   default_pg_schema,
   default_pg_user,
-  default_sqlite_path
+  default_priority_aging_seconds,
+  default_restart_base_seconds,
+  default_restart_max_seconds,
+  default_shutdown_drain_seconds,
+  default_sqlite_path,
+  default_write_behind_batch_size,
+  default_write_behind_enabled,
+  default_write_behind_flush_ms
 };
 
 #[derive(Debug, Deserialize)]
@@ -37,6 +57,33 @@ pub(crate) struct RawAppFile {
   #[serde(default)]
   pub metrics:       Option<RawMetrics>,
   #[serde(default)]
+  pub admin:         Option<RawAdmin>,
+  #[serde(default)]
+  pub archive:       Option<RawArchive>,
+  #[serde(default)]
+  pub retention: Option<RawRetention>,
+  #[serde(default)]
+  pub health:        Option<RawHealth>,
+  #[serde(default)]
+  pub shutdown: Option<RawShutdown>,
+  #[serde(default)]
+  pub supervisor: Option<RawSupervisor>,
+  #[serde(default)]
+  pub leasing:       Option<RawLeasing>,
+  #[serde(default)]
+  pub catch_up:      Option<RawCatchUp>,
+  #[serde(default)]
+  pub priority: Option<RawPriority>,
+  #[serde(default)]
+  pub scheduler: Option<RawScheduler>,
+  #[serde(default)]
+  pub head_probe: Option<RawHeadProbe>,
+  #[serde(default)]
+  pub write_behind:
+    Option<RawWriteBehind>,
+  #[serde(default)]
+  pub partitions: Option<RawPartitions>,
+  #[serde(default)]
   pub state_history:
     Option<RawStateHistory>
 }
@@ -117,6 +164,16 @@ pub(crate) struct RawBackoff {
 
 #[derive(Debug, Deserialize)]
 
+pub(crate) struct RawScheduler {
+  pub parallelism:    Option<usize>,
+  #[serde(
+    default = "default_due_batch_size"
+  )]
+  pub due_batch_size: usize
+}
+
+#[derive(Debug, Deserialize)]
+
 pub(crate) struct RawRequests {
   pub global_max_concurrent_requests:
     Option<usize>,
@@ -178,6 +235,191 @@ pub(crate) struct RawMetrics {
 
 #[derive(Debug, Deserialize)]
 
+pub(crate) struct RawAdmin {
+  #[serde(default)]
+  pub enabled: bool,
+  #[serde(
+    default = "default_admin_bind"
+  )]
+  pub bind:    String,
+  #[serde(default)]
+  pub token:   Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawLeasing {
+  #[serde(default)]
+  pub enabled:              bool,
+  #[serde(default)]
+  pub owner: Option<String>,
+  #[serde(
+    default = "default_lease_seconds"
+  )]
+  pub lease_seconds:        u64,
+  #[serde(default)]
+  pub shared_domain_limits:
+    Option<bool>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawCatchUp {
+  #[serde(
+    default = "default_catch_up_enabled"
+  )]
+  pub enabled:             bool,
+  #[serde(
+    default = "default_catch_up_window_seconds"
+  )]
+  pub window_seconds:      u64,
+  #[serde(
+    default = "default_catch_up_min_overdue_seconds"
+  )]
+  pub min_overdue_seconds: u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawPriority {
+  #[serde(
+    default = "default_priority_aging_seconds"
+  )]
+  pub aging_seconds: u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawHeadProbe {
+  #[serde(
+    default = "default_head_retest_seconds"
+  )]
+  pub retest_seconds:   u64,
+  #[serde(
+    default = "default_head_domain_threshold"
+  )]
+  pub domain_threshold: usize
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawSupervisor {
+  #[serde(
+    default = "default_restart_base_seconds"
+  )]
+  pub restart_base_seconds: u64,
+  #[serde(
+    default = "default_restart_max_seconds"
+  )]
+  pub restart_max_seconds:  u64,
+  #[serde(
+    default = "default_max_restarts"
+  )]
+  pub max_restarts:         u32,
+  #[serde(default)]
+  pub on_fatal: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawWriteBehind {
+  #[serde(
+    default = "default_write_behind_enabled"
+  )]
+  pub enabled:    bool,
+  #[serde(
+    default = "default_write_behind_batch_size"
+  )]
+  pub batch_size: usize,
+  #[serde(
+    default = "default_write_behind_flush_ms"
+  )]
+  pub flush_ms:   u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawPartitions {
+  #[serde(
+    default = "default_partitions_premake_months"
+  )]
+  pub premake_months:   u32,
+  #[serde(default)]
+  pub convert_existing: bool
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawShutdown {
+  #[serde(
+    default = "default_shutdown_drain_seconds"
+  )]
+  pub drain_seconds: u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawHealth {
+  #[serde(default)]
+  pub enabled:          bool,
+  #[serde(
+    default = "default_health_interval_seconds"
+  )]
+  pub interval_seconds: u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawArchive {
+  #[serde(default)]
+  pub enabled:        bool,
+  pub storage:        Option<String>,
+  pub directory:      Option<String>,
+  #[serde(default)]
+  pub retention_days: u64
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawRetention {
+  #[serde(default)]
+  pub enabled:            bool,
+  pub interval_seconds:   Option<u64>,
+  pub batch_size:         Option<u64>,
+  pub server_schema: Option<String>,
+  pub fetch_events:
+    Option<RawRetentionLimits>,
+  pub feed_state_history:
+    Option<RawRetentionLimits>,
+  pub payloads:
+    Option<RawRetentionLimits>,
+  #[serde(default)]
+  pub categories:
+    HashMap<String, RawRetentionPolicy>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawRetentionPolicy {
+  pub fetch_events:
+    Option<RawRetentionLimits>,
+  pub feed_state_history:
+    Option<RawRetentionLimits>,
+  pub payloads:
+    Option<RawRetentionLimits>
+}
+
+#[derive(
+  Debug, Clone, Copy, Deserialize,
+)]
+
+pub(crate) struct RawRetentionLimits {
+  pub max_age_days:      Option<u64>,
+  pub max_rows_per_feed: Option<u64>
+}
+
+#[derive(Debug, Deserialize)]
+
 pub(crate) struct RawStateHistory {
   pub sample_rate: Option<f64>
 }
@@ -205,6 +447,10 @@ pub(crate) struct RawFeedsFile {
   pub tags: Option<Vec<String>>,
   pub language:          Option<String>,
   pub content_type:      Option<String>,
+  pub priority:          Option<String>,
+  pub windows:
+    Option<Vec<RawPollWindow>>,
+  pub dataset: Option<RawDataset>,
   pub feeds:             Vec<RawFeed>
 }
 
@@ -217,7 +463,11 @@ pub(crate) struct RawFeedDefaults {
   pub provenance:        Option<String>,
   pub tags: Option<Vec<String>>,
   pub language:          Option<String>,
-  pub content_type:      Option<String>
+  pub content_type:      Option<String>,
+  pub priority:          Option<String>,
+  pub windows:
+    Option<Vec<RawPollWindow>>,
+  pub dataset: Option<RawDataset>
 }
 
 #[derive(Debug, Deserialize)]
@@ -229,8 +479,34 @@ pub(crate) struct RawCategoriesFile {
 #[derive(Debug, Deserialize)]
 
 pub(crate) struct RawCategoryEntry {
-  pub name:    String,
-  pub domains: Vec<String>
+  pub name:           String,
+  pub domains:        Vec<String>,
+  pub priority:       Option<String>,
+  pub polling:
+    Option<RawCategoryPolling>,
+  pub backoff:
+    Option<RawCategoryBackoff>,
+  pub parallelism:    Option<usize>,
+  pub due_batch_size: Option<usize>,
+  pub windows:
+    Option<Vec<RawPollWindow>>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawCategoryPolling {
+  pub default_seconds: Option<u64>,
+  pub max_seconds:     Option<u64>,
+  pub jitter_fraction: Option<f64>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub(crate) struct RawCategoryBackoff {
+  pub error_base_seconds: Option<u64>,
+  pub max_error_seconds: Option<u64>,
+  pub max_consecutive_errors:
+    Option<u32>
 }
 
 #[derive(Debug, Deserialize)]
@@ -244,5 +520,28 @@ pub(crate) struct RawFeed {
   pub provenance:        Option<String>,
   pub tags: Option<Vec<String>>,
   pub language:          Option<String>,
-  pub content_type:      Option<String>
+  pub content_type:      Option<String>,
+  pub priority:          Option<String>,
+  pub windows:
+    Option<Vec<RawPollWindow>>,
+  pub dataset: Option<RawDataset>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+
+pub(crate) struct RawDataset {
+  pub format:       String,
+  pub key:          String,
+  pub records_path: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+
+pub(crate) struct RawPollWindow {
+  pub days:         Option<Vec<String>>,
+  pub start:        String,
+  pub end:          String,
+  pub poll_seconds: Option<u64>,
+  #[serde(default)]
+  pub quiet:        bool
 }
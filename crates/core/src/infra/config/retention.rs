@@ -0,0 +1,155 @@
+//! Normalizes the `[retention]`
+//! section: fills defaults and merges
+//! category overrides over the global
+//! limits.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+
+use super::ConfigError;
+use super::parse::validate_schema_name;
+use super::raw::{
+  RawRetention,
+  RawRetentionLimits
+};
+use crate::domain::model::{
+  RetentionConfig,
+  RetentionLimits,
+  RetentionPolicy
+};
+
+const DEFAULT_INTERVAL_SECONDS: u64 =
+  3600;
+
+const DEFAULT_BATCH_SIZE: u64 = 1000;
+
+const DEFAULT_SERVER_SCHEMA: &str =
+  "server";
+
+pub(crate) fn parse_retention(
+  raw: Option<RawRetention>,
+  category_names: &HashSet<String>
+) -> Result<RetentionConfig, ConfigError>
+{
+  let Some(raw) = raw else {
+    return Ok(RetentionConfig {
+      enabled:          false,
+      interval_seconds:
+        DEFAULT_INTERVAL_SECONDS,
+      batch_size:
+        DEFAULT_BATCH_SIZE,
+      server_schema:
+        DEFAULT_SERVER_SCHEMA
+          .to_string(),
+      defaults:
+        RetentionPolicy::default(),
+      categories:       HashMap::new()
+    });
+  };
+
+  let interval_seconds =
+    raw.interval_seconds.unwrap_or(
+      DEFAULT_INTERVAL_SECONDS
+    );
+
+  let batch_size = raw
+    .batch_size
+    .unwrap_or(DEFAULT_BATCH_SIZE);
+
+  if interval_seconds == 0
+    || batch_size == 0
+  {
+    return Err(ConfigError::Invalid(
+      "retention.interval_seconds and \
+       retention.batch_size must be \
+       greater than 0"
+        .into()
+    ));
+  }
+
+  let server_schema =
+    validate_schema_name(
+      raw
+        .server_schema
+        .as_deref()
+        .unwrap_or(
+          DEFAULT_SERVER_SCHEMA
+        )
+    )?;
+
+  let defaults = RetentionPolicy {
+    fetch_events:  limits(
+      raw.fetch_events,
+      RetentionLimits::default()
+    ),
+    state_history: limits(
+      raw.feed_state_history,
+      RetentionLimits::default()
+    ),
+    payloads:      limits(
+      raw.payloads,
+      RetentionLimits::default()
+    )
+  };
+
+  let mut categories = HashMap::new();
+
+  for (name, policy) in raw.categories {
+    if !category_names.contains(&name) {
+      return Err(ConfigError::Invalid(
+        format!(
+          "retention category \
+           '{name}' missing from \
+           categories"
+        )
+      ));
+    }
+
+    categories.insert(
+      name,
+      RetentionPolicy {
+        fetch_events:  limits(
+          policy.fetch_events,
+          defaults.fetch_events
+        ),
+        state_history: limits(
+          policy.feed_state_history,
+          defaults.state_history
+        ),
+        payloads:      limits(
+          policy.payloads,
+          defaults.payloads
+        )
+      }
+    );
+  }
+
+  Ok(RetentionConfig {
+    enabled: raw.enabled,
+    interval_seconds,
+    batch_size,
+    server_schema,
+    defaults,
+    categories
+  })
+}
+
+fn limits(
+  raw: Option<RawRetentionLimits>,
+  base: RetentionLimits
+) -> RetentionLimits {
+  let Some(raw) = raw else {
+    return base;
+  };
+
+  RetentionLimits {
+    max_age_days:      raw
+      .max_age_days
+      .or(base.max_age_days),
+    max_rows_per_feed: raw
+      .max_rows_per_feed
+      .or(base.max_rows_per_feed)
+  }
+}
@@ -3,7 +3,8 @@ use std::collections::HashSet;
 use super::ConfigError;
 use crate::domain::model::{
   AppConfig,
-  CategoryConfig
+  CategoryConfig,
+  SchedulingPolicy
 };
 
 pub fn validate_semantic(
@@ -33,5 +34,88 @@ pub fn validate_semantic(
     }
   }
 
+  check_policy(
+    "config.toml",
+    &app.scheduling.defaults
+  )?;
+
+  for c in categories {
+    check_policy(
+      &format!("category '{}'", c.name),
+      &app
+        .scheduling
+        .policy_for(&c.name)
+    )?;
+  }
+
   Ok(())
 }
+
+/// Checks a merged policy, so an
+/// override is also judged against the
+/// global values it did not replace.
+fn check_policy(
+  owner: &str,
+  p: &SchedulingPolicy
+) -> Result<(), ConfigError> {
+  let problem = if p
+    .default_poll_seconds
+    == 0
+  {
+    Some(
+      "polling.default_seconds must \
+       be greater than 0"
+        .to_string()
+    )
+  } else if p.default_poll_seconds
+    > p.max_poll_seconds
+  {
+    Some(format!(
+      "polling.default_seconds ({}) \
+       exceeds polling.max_seconds \
+       ({})",
+      p.default_poll_seconds,
+      p.max_poll_seconds
+    ))
+  } else if !(0.0..=1.0)
+    .contains(&p.jitter_fraction)
+  {
+    Some(format!(
+      "polling.jitter_fraction must \
+       be between 0 and 1, got {}",
+      p.jitter_fraction
+    ))
+  } else if p.error_backoff_base_seconds
+    > p.max_error_backoff_seconds
+  {
+    Some(format!(
+      "backoff.error_base_seconds \
+       ({}) exceeds \
+       backoff.max_error_seconds ({})",
+      p.error_backoff_base_seconds,
+      p.max_error_backoff_seconds
+    ))
+  } else if p.parallelism == 0 {
+    Some(
+      "parallelism must be at least 1"
+        .to_string()
+    )
+  } else if p.due_batch_size == 0 {
+    Some(
+      "due_batch_size must be at \
+       least 1"
+        .to_string()
+    )
+  } else {
+    None
+  };
+
+  match problem {
+    | Some(msg) => {
+      Err(ConfigError::Invalid(
+        format!("{owner}: {msg}")
+      ))
+    }
+    | None => Ok(())
+  }
+}
@@ -0,0 +1,82 @@
+//! Storage-side tuning: how writes are
+//! batched on their way to the
+//! database and how Postgres history
+//! tables are partitioned.
+
+use super::ConfigError;
+use super::defaults::{
+  default_partitions_premake_months,
+  default_write_behind_batch_size,
+  default_write_behind_enabled,
+  default_write_behind_flush_ms
+};
+use super::raw::{
+  RawPartitions,
+  RawWriteBehind
+};
+use crate::domain::model::{
+  PartitionsConfig,
+  WriteBehindConfig
+};
+
+pub(crate) fn parse_write_behind(
+  raw: Option<RawWriteBehind>
+) -> Result<
+  WriteBehindConfig,
+  ConfigError
+> {
+  let raw =
+    raw.unwrap_or(RawWriteBehind {
+      enabled:
+        default_write_behind_enabled(),
+      batch_size:
+        default_write_behind_batch_size(
+        ),
+      flush_ms:
+        default_write_behind_flush_ms()
+    });
+
+  if raw.batch_size == 0
+    || raw.flush_ms == 0
+  {
+    return Err(ConfigError::Invalid(
+      "write_behind.batch_size and \
+       flush_ms must be greater than 0"
+        .into()
+    ));
+  }
+
+  Ok(WriteBehindConfig {
+    enabled:    raw.enabled,
+    batch_size: raw.batch_size,
+    flush_ms:   raw.flush_ms
+  })
+}
+
+pub(crate) fn parse_partitions(
+  raw: Option<RawPartitions>
+) -> Result<PartitionsConfig, ConfigError>
+{
+  let raw = raw
+    .unwrap_or(RawPartitions {
+    premake_months:
+      default_partitions_premake_months(
+      ),
+    convert_existing: false
+  });
+
+  if raw.premake_months == 0 {
+    return Err(ConfigError::Invalid(
+      "partitions.premake_months must \
+       be greater than 0"
+        .into()
+    ));
+  }
+
+  Ok(PartitionsConfig {
+    premake_months:   raw
+      .premake_months,
+    convert_existing: raw
+      .convert_existing
+  })
+}
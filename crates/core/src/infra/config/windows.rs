@@ -0,0 +1,142 @@
+//! `windows` entries on feeds and
+//! categories.
+
+use super::ConfigError;
+use super::raw::RawPollWindow;
+use crate::domain::poll_window::PollWindow;
+
+const DAYS: [&str; 7] = [
+  "mon", "tue", "wed", "thu", "fri",
+  "sat", "sun"
+];
+
+pub(crate) fn parse_windows(
+  raw: &[RawPollWindow],
+  owner: &str
+) -> Result<Vec<PollWindow>, ConfigError>
+{
+  raw
+    .iter()
+    .enumerate()
+    .map(|(i, w)| {
+      parse_window(w).map_err(|e| {
+        ConfigError::Invalid(format!(
+          "{owner}: windows[{i}]: {e}"
+        ))
+      })
+    })
+    .collect()
+}
+
+fn parse_window(
+  w: &RawPollWindow
+) -> Result<PollWindow, String> {
+  let days = match &w.days {
+    | None => 0b111_1111,
+    | Some(names) => {
+      let mut mask = 0u8;
+
+      for name in names {
+        let day = DAYS
+          .iter()
+          .position(|d| {
+            name.eq_ignore_ascii_case(d)
+          })
+          .ok_or_else(|| {
+            format!(
+              "unknown day '{name}' \
+               (use mon..sun)"
+            )
+          })?;
+
+        mask |= 1 << day;
+      }
+
+      if mask == 0 {
+        return Err(
+          "days cannot be empty".into()
+        );
+      }
+
+      mask
+    }
+  };
+
+  let start_minute =
+    parse_clock(&w.start, false)?;
+
+  let end_minute =
+    parse_clock(&w.end, true)?;
+
+  if start_minute == end_minute {
+    return Err(
+      "start and end must differ (use \
+       00:00-24:00 for a whole day)"
+        .into()
+    );
+  }
+
+  let poll_seconds =
+    match (w.quiet, w.poll_seconds) {
+      | (true, None) => None,
+      | (false, Some(s)) if s > 0 => {
+        Some(s)
+      }
+      | (false, Some(_)) => {
+        return Err(
+          "poll_seconds must be \
+           greater than 0"
+            .into()
+        );
+      }
+      | _ => {
+        return Err(
+          "set exactly one of quiet = \
+           true or poll_seconds"
+            .into()
+        );
+      }
+    };
+
+  Ok(PollWindow {
+    days,
+    start_minute,
+    end_minute,
+    poll_seconds
+  })
+}
+
+/// `HH:MM`; `24:00` only as an end.
+fn parse_clock(
+  raw: &str,
+  is_end: bool
+) -> Result<u32, String> {
+  let invalid = || {
+    format!(
+      "invalid time '{raw}' (expected \
+       HH:MM)"
+    )
+  };
+
+  let (h, m) = raw
+    .trim()
+    .split_once(':')
+    .ok_or_else(invalid)?;
+
+  let h: u32 =
+    h.parse().map_err(|_| invalid())?;
+
+  let m: u32 =
+    m.parse().map_err(|_| invalid())?;
+
+  let minute = h * 60 + m;
+
+  if m >= 60
+    || minute > 1440
+    || (minute == 1440 && !is_end)
+  {
+    return Err(invalid());
+  }
+
+  Ok(minute)
+}
@@ -1,5 +1,6 @@
 //! Database wiring: creates repository
-//! implementations per SQL dialect.
+//! implementations per SQL dialect and
+//! reads the server's migration table.
 
 use std::sync::Arc;
 
@@ -7,9 +8,18 @@ use crate::domain::model::{
   AppConfig,
   SqlDialect
 };
-use crate::infra::postgres_repo::PostgresRepo;
-use crate::infra::sqlite_repo::SqliteRepo;
-use crate::ports::repo::Repo;
+use crate::infra::postgres_repo::{
+  self,
+  PostgresRepo
+};
+use crate::infra::sqlite_repo::{
+  self,
+  SqliteRepo
+};
+use crate::ports::repo::{
+  Repo,
+  SchemaMigration
+};
 
 pub async fn create_repo(
   dialect: SqlDialect,
@@ -38,3 +48,40 @@ pub async fn create_repo(
     }
   }
 }
+
+/// The server migrations recorded in
+/// the configured database, looked up
+/// in `retention.server_schema` on
+/// Postgres. The server applies them
+/// itself when it starts.
+pub async fn server_schema_migrations(
+  cfg: &AppConfig
+) -> Result<Vec<SchemaMigration>, String>
+{
+  match cfg.db_dialect {
+    | SqlDialect::Sqlite => {
+      let (pool, _) =
+        sqlite_repo::create_pools(
+          &cfg.sqlite_path
+        )
+        .await?;
+
+      sqlite_repo::migrations::server_status(&pool)
+        .await
+    }
+    | SqlDialect::Postgres => {
+      let pool =
+        postgres_repo::create_pool(
+          &cfg.postgres,
+          &cfg.timezone
+        )
+        .await?;
+
+      postgres_repo::migrations::server_status(
+        &pool,
+        &cfg.retention.server_schema
+      )
+      .await
+    }
+  }
+}
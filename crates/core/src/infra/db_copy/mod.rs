@@ -0,0 +1,426 @@
+//! `db copy`: moves every fetcher and
+//! server table between two stores of
+//! either dialect in batches, keeping
+//! ids, then checks row counts.
+
+pub(crate) mod postgres;
+pub(crate) mod sqlite;
+pub(crate) mod tables;
+
+use std::path::Path;
+
+use chrono_tz::Tz;
+use sqlx::{
+  PgPool,
+  SqlitePool
+};
+
+use self::tables::{
+  TABLES,
+  Table
+};
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+
+/// One column value, in the SQLite
+/// representation.
+#[derive(Debug, Clone)]
+
+pub enum Value {
+  Int(Option<i64>),
+  Text(Option<String>),
+  Bytes(Option<Vec<u8>>),
+  Tags(Option<Vec<String>>)
+}
+
+pub(crate) type Row = Vec<Value>;
+
+/// Bind parameters per statement; below
+/// both dialects' limits.
+pub(crate) const MAX_PARAMS: usize =
+  30_000;
+
+/// A database to copy from or into.
+pub enum Store {
+  Sqlite(SqlitePool),
+  Postgres {
+    pool:          PgPool,
+    server_schema: String
+  }
+}
+
+/// Rows copied per table.
+#[derive(Debug, Clone, Default)]
+
+pub struct CopyReport {
+  pub tables: Vec<(String, u64)>
+}
+
+impl Store {
+  pub async fn open(
+    cfg: &AppConfig
+  ) -> Result<Self, String> {
+    match cfg.db_dialect {
+      | SqlDialect::Sqlite => {
+        Self::sqlite(&cfg.sqlite_path)
+          .await
+      }
+      | SqlDialect::Postgres => {
+        Self::postgres(
+          cfg,
+          &cfg.timezone
+        )
+        .await
+      }
+    }
+  }
+
+  pub async fn sqlite(
+    path: &Path
+  ) -> Result<Self, String> {
+    let (pool, _) =
+      crate::infra::sqlite_repo::create_pools(path)
+        .await?;
+
+    Ok(Self::Sqlite(pool))
+  }
+
+  async fn postgres(
+    cfg: &AppConfig,
+    zone: &Tz
+  ) -> Result<Self, String> {
+    let pool =
+      crate::infra::postgres_repo::create_pool(
+        &cfg.postgres,
+        zone
+      )
+      .await?;
+
+    Ok(Self::Postgres {
+      pool,
+      server_schema: cfg
+        .retention
+        .server_schema
+        .clone()
+    })
+  }
+
+  async fn exists(
+    &self,
+    table: &Table
+  ) -> Result<bool, String> {
+    match self {
+      | Self::Sqlite(pool) => {
+        sqlite::exists(pool, table)
+          .await
+      }
+      | Self::Postgres {
+        pool,
+        server_schema
+      } => {
+        postgres::exists(
+          pool,
+          &postgres::qualified(
+            table,
+            server_schema
+          )
+        )
+        .await
+      }
+    }
+  }
+
+  async fn count(
+    &self,
+    table: &Table
+  ) -> Result<u64, String> {
+    match self {
+      | Self::Sqlite(pool) => {
+        sqlite::count(pool, table).await
+      }
+      | Self::Postgres {
+        pool,
+        server_schema
+      } => {
+        postgres::count(
+          pool,
+          &postgres::qualified(
+            table,
+            server_schema
+          )
+        )
+        .await
+      }
+    }
+  }
+
+  /// Rows after `after` (an id for
+  /// serial tables, else an offset).
+  async fn read(
+    &self,
+    table: &Table,
+    after: i64,
+    limit: usize
+  ) -> Result<Vec<Row>, String> {
+    match self {
+      | Self::Sqlite(pool) => {
+        sqlite::read(
+          pool, table, after, limit
+        )
+        .await
+      }
+      | Self::Postgres {
+        pool,
+        server_schema
+      } => {
+        postgres::read(
+          pool,
+          table,
+          server_schema,
+          after,
+          limit
+        )
+        .await
+      }
+    }
+  }
+
+  async fn write(
+    &self,
+    table: &Table,
+    rows: &[Row]
+  ) -> Result<(), String> {
+    match self {
+      | Self::Sqlite(pool) => {
+        sqlite::write(pool, table, rows)
+          .await
+      }
+      | Self::Postgres {
+        pool,
+        server_schema
+      } => {
+        let mut tx = pool
+          .begin()
+          .await
+          .map_err(|e| {
+            format!(
+              "{}: {e}",
+              table.name
+            )
+          })?;
+
+        postgres::write(
+          &mut tx,
+          table,
+          server_schema,
+          rows
+        )
+        .await?;
+
+        tx.commit().await.map_err(|e| {
+          format!("{}: {e}", table.name)
+        })
+      }
+    }
+  }
+
+  /// Moves a serial table's id sequence
+  /// past the copied ids.
+  async fn finish(
+    &self,
+    table: &Table
+  ) -> Result<(), String> {
+    match self {
+      | Self::Sqlite(_) => Ok(()),
+      | Self::Postgres {
+        pool,
+        server_schema
+      } => {
+        postgres::reset_sequence(
+          pool,
+          &postgres::qualified(
+            table,
+            server_schema
+          )
+        )
+        .await
+      }
+    }
+  }
+}
+
+/// Copies every table present in `from`
+/// into `to`, which must already have
+/// the same schema version and hold no
+/// rows in those tables. Server tables
+/// are copied when the source has them.
+pub async fn copy(
+  from: &Store,
+  to: &Store,
+  batch_size: usize,
+  mut progress: impl FnMut(&str, u64)
+) -> Result<CopyReport, String> {
+  let mut tables = Vec::new();
+
+  for table in TABLES {
+    if !from.exists(table).await? {
+      continue;
+    }
+
+    if !to.exists(table).await? {
+      return Err(missing(table));
+    }
+
+    if to.count(table).await? > 0 {
+      return Err(format!(
+        "target table {} is not empty",
+        table.name
+      ));
+    }
+
+    tables.push(table);
+  }
+
+  let mut report =
+    CopyReport::default();
+
+  for table in tables {
+    let limit =
+      batch_limit(table, batch_size);
+
+    let mut after = 0;
+    let mut copied = 0u64;
+
+    loop {
+      let rows = from
+        .read(table, after, limit)
+        .await?;
+
+      if rows.is_empty() {
+        break;
+      }
+
+      after =
+        advance(table, after, &rows)?;
+
+      to.write(table, &rows).await?;
+
+      copied += rows.len() as u64;
+
+      progress(table.name, copied);
+    }
+
+    if table.serial {
+      to.finish(table).await?;
+    }
+
+    report.tables.push((
+      table.name.to_string(),
+      copied
+    ));
+  }
+
+  verify(from, to, &report).await?;
+
+  Ok(report)
+}
+
+/// Rows per read, capped so one insert
+/// stays under `MAX_PARAMS`.
+pub(crate) fn batch_limit(
+  table: &Table,
+  batch_size: usize
+) -> usize {
+  batch_size
+    .min(
+      MAX_PARAMS / table.columns.len()
+    )
+    .max(1)
+}
+
+/// The `after` cursor for the read
+/// following `rows`.
+pub(crate) fn advance(
+  table: &Table,
+  after: i64,
+  rows: &[Row]
+) -> Result<i64, String> {
+  if !table.serial {
+    return Ok(
+      after + rows.len() as i64
+    );
+  }
+
+  match rows.last().map(|r| &r[0]) {
+    | Some(Value::Int(Some(id))) => {
+      Ok(*id)
+    }
+    | _ => {
+      Err(format!(
+        "{}: row without id",
+        table.name
+      ))
+    }
+  }
+}
+
+/// Error for a table the target lacks.
+pub(crate) fn missing(
+  table: &Table
+) -> String {
+  format!(
+    "table {} is missing in the \
+     target{}",
+    table.name,
+    if table.server {
+      "; start pulsewire-server \
+       against it once to create the \
+       server schema"
+    } else {
+      ""
+    }
+  )
+}
+
+/// Compares row counts on both sides
+/// once everything is copied.
+async fn verify(
+  from: &Store,
+  to: &Store,
+  report: &CopyReport
+) -> Result<(), String> {
+  let mut mismatches = Vec::new();
+
+  for (name, _) in &report.tables {
+    let Some(table) = TABLES
+      .iter()
+      .find(|t| t.name == name)
+    else {
+      continue;
+    };
+
+    let source =
+      from.count(table).await?;
+    let target =
+      to.count(table).await?;
+
+    if source != target {
+      mismatches.push(format!(
+        "{name} (source {source}, \
+         target {target})"
+      ));
+    }
+  }
+
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(format!(
+      "row counts differ after copy: \
+       {}",
+      mismatches.join(", ")
+    ))
+  }
+}
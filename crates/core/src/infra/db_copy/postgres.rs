@@ -0,0 +1,340 @@
+//! Postgres side of `db copy`. Reads
+//! convert columns to the SQLite form
+//! in SQL; writes convert them back.
+
+use sqlx::postgres::{
+  PgArguments,
+  PgRow
+};
+use sqlx::query::Query;
+use sqlx::{
+  PgConnection,
+  PgExecutor,
+  Postgres,
+  Row as _
+};
+
+use super::tables::{
+  Column,
+  Kind,
+  Table
+};
+use super::{
+  MAX_PARAMS,
+  Row,
+  Value
+};
+
+/// Fetcher tables resolve through the
+/// pool's search path; server tables
+/// live in their own schema.
+pub fn qualified(
+  table: &Table,
+  server_schema: &str
+) -> String {
+  if table.server {
+    format!(
+      "\"{server_schema}\".{}",
+      table.name
+    )
+  } else {
+    table.name.to_string()
+  }
+}
+
+pub async fn exists(
+  db: impl PgExecutor<'_>,
+  name: &str
+) -> Result<bool, String> {
+  let found: Option<String> =
+    sqlx::query_scalar(
+      "SELECT to_regclass($1)::text"
+    )
+    .bind(name)
+    .fetch_one(db)
+    .await
+    .map_err(|e| {
+      format!("{name}: {e}")
+    })?;
+
+  Ok(found.is_some())
+}
+
+pub async fn count(
+  db: impl PgExecutor<'_>,
+  name: &str
+) -> Result<u64, String> {
+  let n: i64 =
+    sqlx::query_scalar(&format!(
+      "SELECT count(*) FROM {name}"
+    ))
+    .fetch_one(db)
+    .await
+    .map_err(|e| {
+      format!("{name}: {e}")
+    })?;
+
+  Ok(n as u64)
+}
+
+fn select_expr(c: &Column) -> String {
+  let name = c.postgres;
+
+  match c.kind {
+    | Kind::Int => {
+      format!("{name}::BIGINT")
+    }
+    | Kind::Ms => {
+      format!(
+        "(EXTRACT(EPOCH FROM {name}) \
+         * 1000)::BIGINT"
+      )
+    }
+    | Kind::Day | Kind::Json => {
+      format!("{name}::TEXT")
+    }
+    | Kind::Stamp => {
+      format!(
+        "to_char({name} AT TIME ZONE \
+         'UTC', 'YYYY-MM-DD \
+         HH24:MI:SS')"
+      )
+    }
+    | Kind::Text
+    | Kind::Bytes
+    | Kind::Tags => name.to_string()
+  }
+}
+
+fn insert_expr(
+  c: &Column,
+  n: usize
+) -> String {
+  match c.kind {
+    | Kind::Ms => {
+      format!(
+        "to_timestamp(${n} / 1000.0)"
+      )
+    }
+    | Kind::Day => {
+      format!("${n}::DATE")
+    }
+    | Kind::Json => {
+      format!("${n}::JSONB")
+    }
+    | Kind::Stamp => {
+      format!(
+        "(${n}::TIMESTAMP AT TIME \
+         ZONE 'UTC')"
+      )
+    }
+    | Kind::Int
+    | Kind::Text
+    | Kind::Bytes
+    | Kind::Tags => format!("${n}")
+  }
+}
+
+pub async fn read(
+  db: impl PgExecutor<'_>,
+  table: &Table,
+  server_schema: &str,
+  after: i64,
+  limit: usize
+) -> Result<Vec<Row>, String> {
+  let name =
+    qualified(table, server_schema);
+
+  let columns = table
+    .columns
+    .iter()
+    .map(select_expr)
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  let sql = if table.serial {
+    format!(
+      "SELECT {columns} FROM {name} \
+       WHERE id > $1 ORDER BY id \
+       LIMIT $2"
+    )
+  } else {
+    format!(
+      "SELECT {columns} FROM {name} \
+       ORDER BY {} LIMIT $2 OFFSET $1",
+      table.key
+    )
+  };
+
+  let rows = sqlx::query(&sql)
+    .bind(after)
+    .bind(limit as i64)
+    .fetch_all(db)
+    .await
+    .map_err(|e| {
+      format!("{name}: {e}")
+    })?;
+
+  rows
+    .iter()
+    .map(|row| {
+      decode(&name, table, row)
+    })
+    .collect()
+}
+
+fn decode(
+  name: &str,
+  table: &Table,
+  row: &PgRow
+) -> Result<Row, String> {
+  let err = |e: sqlx::Error| {
+    format!("{name}: {e}")
+  };
+
+  table
+    .columns
+    .iter()
+    .enumerate()
+    .map(|(i, c)| {
+      Ok(match c.kind {
+        | Kind::Int | Kind::Ms => {
+          Value::Int(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+        | Kind::Bytes => {
+          Value::Bytes(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+        | Kind::Tags => {
+          Value::Tags(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+        | Kind::Text
+        | Kind::Day
+        | Kind::Json
+        | Kind::Stamp => {
+          Value::Text(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+      })
+    })
+    .collect()
+}
+
+/// Inserts `rows` on `conn`; the caller
+/// owns the transaction.
+pub async fn write(
+  conn: &mut PgConnection,
+  table: &Table,
+  server_schema: &str,
+  rows: &[Row]
+) -> Result<(), String> {
+  let name =
+    qualified(table, server_schema);
+
+  let width = table.columns.len();
+
+  let columns = table
+    .columns
+    .iter()
+    .map(|c| c.postgres)
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  for chunk in
+    rows.chunks(MAX_PARAMS / width)
+  {
+    let tuples = (0..chunk.len())
+      .map(|r| {
+        let exprs = table
+          .columns
+          .iter()
+          .enumerate()
+          .map(|(i, c)| {
+            insert_expr(
+              c,
+              r * width + i + 1
+            )
+          })
+          .collect::<Vec<_>>()
+          .join(", ");
+
+        format!("({exprs})")
+      })
+      .collect::<Vec<_>>()
+      .join(", ");
+
+    let sql = format!(
+      "INSERT INTO {name} ({columns}) \
+       VALUES {tuples}"
+    );
+
+    let mut query = sqlx::query(&sql);
+
+    for value in chunk.iter().flatten()
+    {
+      query = bind(query, value);
+    }
+
+    query
+      .execute(&mut *conn)
+      .await
+      .map_err(|e| {
+        format!("{name}: {e}")
+      })?;
+  }
+
+  Ok(())
+}
+
+fn bind<'q>(
+  query: Query<
+    'q,
+    Postgres,
+    PgArguments
+  >,
+  value: &'q Value
+) -> Query<'q, Postgres, PgArguments> {
+  match value {
+    | Value::Int(v) => query.bind(*v),
+    | Value::Text(v) => {
+      query.bind(v.as_deref())
+    }
+    | Value::Bytes(v) => {
+      query.bind(v.as_deref())
+    }
+    | Value::Tags(v) => {
+      query.bind(v.as_deref())
+    }
+  }
+}
+
+pub async fn reset_sequence(
+  db: impl PgExecutor<'_>,
+  name: &str
+) -> Result<(), String> {
+  sqlx::query(&format!(
+    "SELECT \
+     setval(pg_get_serial_sequence($1, \
+     'id'), max(id)) FROM {name} \
+     HAVING max(id) IS NOT NULL"
+  ))
+  .bind(name)
+  .execute(db)
+  .await
+  .map(|_| ())
+  .map_err(|e| format!("{name}: {e}"))
+}
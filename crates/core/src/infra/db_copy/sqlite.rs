@@ -0,0 +1,255 @@
+//! SQLite side of `db copy`.
+
+use sqlx::query::Query;
+use sqlx::sqlite::{
+  SqliteArguments,
+  SqliteRow
+};
+use sqlx::{
+  Row as _,
+  Sqlite,
+  SqlitePool
+};
+
+use super::tables::{
+  Kind,
+  Table
+};
+use super::{
+  MAX_PARAMS,
+  Row,
+  Value
+};
+
+pub async fn exists(
+  pool: &SqlitePool,
+  table: &Table
+) -> Result<bool, String> {
+  let found: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = ?"
+    )
+    .bind(table.name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!("{}: {e}", table.name)
+    })?;
+
+  Ok(found.is_some())
+}
+
+pub async fn count(
+  pool: &SqlitePool,
+  table: &Table
+) -> Result<u64, String> {
+  let n: i64 =
+    sqlx::query_scalar(&format!(
+      "SELECT count(*) FROM {}",
+      table.name
+    ))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("{}: {e}", table.name)
+    })?;
+
+  Ok(n as u64)
+}
+
+pub async fn read(
+  pool: &SqlitePool,
+  table: &Table,
+  after: i64,
+  limit: usize
+) -> Result<Vec<Row>, String> {
+  let columns = table
+    .columns
+    .iter()
+    .map(|c| c.sqlite)
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  let sql = if table.serial {
+    format!(
+      "SELECT {columns} FROM {} WHERE \
+       id > ?1 ORDER BY id LIMIT ?2",
+      table.name
+    )
+  } else {
+    format!(
+      "SELECT {columns} FROM {} ORDER \
+       BY {} LIMIT ?2 OFFSET ?1",
+      table.name, table.key
+    )
+  };
+
+  let rows = sqlx::query(&sql)
+    .bind(after)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("{}: {e}", table.name)
+    })?;
+
+  rows
+    .iter()
+    .map(|row| decode(table, row))
+    .collect()
+}
+
+fn decode(
+  table: &Table,
+  row: &SqliteRow
+) -> Result<Row, String> {
+  let err = |e: sqlx::Error| {
+    format!("{}: {e}", table.name)
+  };
+
+  table
+    .columns
+    .iter()
+    .enumerate()
+    .map(|(i, c)| {
+      Ok(match c.kind {
+        | Kind::Int | Kind::Ms => {
+          Value::Int(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+        | Kind::Bytes => {
+          Value::Bytes(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+        | Kind::Tags => {
+          let raw: Option<String> = row
+            .try_get(i)
+            .map_err(err)?;
+
+          Value::Tags(
+            raw
+              .map(|s| {
+                serde_json::from_str(&s)
+              })
+              .transpose()
+              .map_err(|e| {
+                format!(
+                  "{}.{}: {e}",
+                  table.name, c.sqlite
+                )
+              })?
+          )
+        }
+        | Kind::Text
+        | Kind::Day
+        | Kind::Json
+        | Kind::Stamp => {
+          Value::Text(
+            row
+              .try_get(i)
+              .map_err(err)?
+          )
+        }
+      })
+    })
+    .collect()
+}
+
+pub async fn write(
+  pool: &SqlitePool,
+  table: &Table,
+  rows: &[Row]
+) -> Result<(), String> {
+  let width = table.columns.len();
+
+  let columns = table
+    .columns
+    .iter()
+    .map(|c| c.sqlite)
+    .collect::<Vec<_>>()
+    .join(", ");
+
+  let tuple = format!(
+    "({})",
+    vec!["?"; width].join(", ")
+  );
+
+  let mut tx = pool
+    .begin()
+    .await
+    .map_err(|e| {
+      format!("{}: {e}", table.name)
+    })?;
+
+  for chunk in
+    rows.chunks(MAX_PARAMS / width)
+  {
+    let sql = format!(
+      "INSERT INTO {} ({columns}) \
+       VALUES {}",
+      table.name,
+      vec![tuple.as_str(); chunk.len()]
+        .join(", ")
+    );
+
+    let mut query = sqlx::query(&sql);
+
+    for value in chunk.iter().flatten()
+    {
+      query = bind(query, value)?;
+    }
+
+    query
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!("{}: {e}", table.name)
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("{}: {e}", table.name)
+  })
+}
+
+fn bind<'q>(
+  query: Query<
+    'q,
+    Sqlite,
+    SqliteArguments<'q>
+  >,
+  value: &'q Value
+) -> Result<
+  Query<
+    'q,
+    Sqlite,
+    SqliteArguments<'q>
+  >,
+  String
+> {
+  Ok(match value {
+    | Value::Int(v) => query.bind(*v),
+    | Value::Text(v) => {
+      query.bind(v.as_deref())
+    }
+    | Value::Bytes(v) => {
+      query.bind(v.as_deref())
+    }
+    | Value::Tags(v) => {
+      query.bind(
+        v.as_ref()
+          .map(serde_json::to_string)
+          .transpose()
+          .map_err(|e| e.to_string())?
+      )
+    }
+  })
+}
@@ -0,0 +1,487 @@
+//! The tables `db copy` moves, in
+//! foreign-key order, with each
+//! column's name and representation in
+//! both dialects.
+
+/// How a column is stored. Values
+/// travel in the SQLite form: epoch
+/// millis, JSON text and naive UTC
+/// text stamps.
+#[derive(Debug, Clone, Copy)]
+
+pub enum Kind {
+  Int,
+  Text,
+  Bytes,
+  /// SQLite epoch millis, Postgres
+  /// `TIMESTAMPTZ`.
+  Ms,
+  /// `YYYY-MM-DD` text, Postgres
+  /// `DATE`.
+  Day,
+  /// JSON text, Postgres `JSONB`.
+  Json,
+  /// Server stamps: SQLite
+  /// `datetime()` text, Postgres
+  /// `TIMESTAMPTZ`.
+  Stamp,
+  /// JSON array text, Postgres
+  /// `TEXT[]`.
+  Tags
+}
+
+pub struct Column {
+  pub sqlite:   &'static str,
+  pub postgres: &'static str,
+  pub kind:     Kind
+}
+
+pub struct Table {
+  pub name:    &'static str,
+  /// Lives in the server schema on
+  /// Postgres.
+  pub server:  bool,
+  /// Columns to page in order by.
+  pub key:     &'static str,
+  /// Has a generated `id`, the first
+  /// column, paged by keyset.
+  pub serial:  bool,
+  pub columns: &'static [Column]
+}
+
+const fn col(
+  name: &'static str,
+  kind: Kind
+) -> Column {
+  Column {
+    sqlite: name,
+    postgres: name,
+    kind
+  }
+}
+
+const fn int(
+  name: &'static str
+) -> Column {
+  col(name, Kind::Int)
+}
+
+const fn text(
+  name: &'static str
+) -> Column {
+  col(name, Kind::Text)
+}
+
+const fn stamp(
+  name: &'static str
+) -> Column {
+  col(name, Kind::Stamp)
+}
+
+const fn ms(
+  sqlite: &'static str,
+  postgres: &'static str
+) -> Column {
+  Column {
+    sqlite,
+    postgres,
+    kind: Kind::Ms
+  }
+}
+
+/// Fetcher tables, then server tables.
+/// Postgres-only lease tables hold no
+/// lasting state and are skipped.
+pub const TABLES: &[Table] = &[
+  Table {
+    name:    "categories",
+    server:  false,
+    key:     "name",
+    serial:  false,
+    columns: &[
+      text("name"),
+      ms("created_at_ms", "created_at")
+    ]
+  },
+  Table {
+    name:    "feeds",
+    server:  false,
+    key:     "id",
+    serial:  false,
+    columns: &[
+      text("id"),
+      text("url"),
+      text("domain"),
+      text("category"),
+      int("base_poll_seconds"),
+      col("tags", Kind::Tags),
+      text("dataset"),
+      int("priority"),
+      ms("created_at_ms", "created_at")
+    ]
+  },
+  Table {
+    name:    "feed_state_current",
+    server:  false,
+    key:     "feed_id",
+    serial:  false,
+    columns: &[
+      text("feed_id"),
+      text("phase"),
+      ms(
+        "last_head_at_ms",
+        "last_head_at"
+      ),
+      int("last_head_status"),
+      text("last_head_error"),
+      ms(
+        "last_get_at_ms",
+        "last_get_at"
+      ),
+      int("last_get_status"),
+      text("last_get_error"),
+      text("etag"),
+      ms(
+        "last_modified_ms",
+        "last_modified_at"
+      ),
+      int("backoff_index"),
+      int("base_poll_seconds"),
+      ms(
+        "next_action_at_ms",
+        "next_action_at"
+      ),
+      int("jitter_seconds"),
+      text("note"),
+      int("consecutive_error_count"),
+      ms(
+        "get_only_since_ms",
+        "get_only_since"
+      )
+    ]
+  },
+  Table {
+    name:    "feed_state_history",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("feed_id"),
+      ms(
+        "recorded_at_ms",
+        "recorded_at"
+      ),
+      text("phase"),
+      ms(
+        "last_head_at_ms",
+        "last_head_at"
+      ),
+      int("last_head_status"),
+      text("last_head_error"),
+      ms(
+        "last_get_at_ms",
+        "last_get_at"
+      ),
+      int("last_get_status"),
+      text("last_get_error"),
+      text("etag"),
+      ms(
+        "last_modified_ms",
+        "last_modified_at"
+      ),
+      int("backoff_index"),
+      int("base_poll_seconds"),
+      ms(
+        "next_action_at_ms",
+        "next_action_at"
+      ),
+      int("jitter_seconds"),
+      text("note"),
+      int("consecutive_error_count"),
+      ms(
+        "get_only_since_ms",
+        "get_only_since"
+      )
+    ]
+  },
+  Table {
+    name:    "fetch_events",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("feed_id"),
+      ms("event_time_ms", "event_time"),
+      text("method"),
+      int("status"),
+      text("error_kind"),
+      int("latency_ms"),
+      int("backoff_index"),
+      ms(
+        "scheduled_next_action_at_ms",
+        "scheduled_next_action_at"
+      ),
+      text("debug")
+    ]
+  },
+  Table {
+    name:    "feed_payloads",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("feed_id"),
+      ms("fetched_at_ms", "fetched_at"),
+      text("etag"),
+      ms(
+        "last_modified_ms",
+        "last_modified_at"
+      ),
+      text("content_hash"),
+      text("title"),
+      text("link"),
+      text("description"),
+      text("language"),
+      ms("updated_at_ms", "updated_at")
+    ]
+  },
+  Table {
+    name:    "feed_items",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      int("payload_id"),
+      text("feed_id"),
+      text("title"),
+      text("link"),
+      text("guid"),
+      ms(
+        "published_at_ms",
+        "published_at"
+      ),
+      text("category"),
+      text("description"),
+      text("summary")
+    ]
+  },
+  Table {
+    name:    "error_feeds",
+    server:  false,
+    key:     "feed_id",
+    serial:  false,
+    columns: &[
+      text("feed_id"),
+      int("error_count"),
+      text("last_error_kind"),
+      int("last_error_status"),
+      ms(
+        "last_error_at_ms",
+        "last_error_at"
+      ),
+      text("note")
+    ]
+  },
+  Table {
+    name:    "dataset_blobs",
+    server:  false,
+    key:     "content_hash",
+    serial:  false,
+    columns: &[
+      text("content_hash"),
+      col("body", Kind::Bytes),
+      int("size_bytes"),
+      ms("created_at_ms", "created_at")
+    ]
+  },
+  Table {
+    name:    "dataset_versions",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("feed_id"),
+      ms("fetched_at_ms", "fetched_at"),
+      text("content_hash"),
+      int("row_count"),
+      int("added_count"),
+      int("removed_count"),
+      int("changed_count")
+    ]
+  },
+  Table {
+    name:    "payload_archive",
+    server:  false,
+    key:     "content_hash",
+    serial:  false,
+    columns: &[
+      text("content_hash"),
+      text("encoding"),
+      col("body", Kind::Bytes),
+      text("location"),
+      int("raw_size"),
+      int("stored_size"),
+      ms(
+        "first_seen_at_ms",
+        "first_seen_at"
+      ),
+      ms(
+        "last_seen_at_ms",
+        "last_seen_at"
+      )
+    ]
+  },
+  Table {
+    name:    "feed_health_daily",
+    server:  false,
+    key:     "day, scope, subject",
+    serial:  false,
+    columns: &[
+      col("day", Kind::Day),
+      text("scope"),
+      text("subject"),
+      int("fetches"),
+      int("successes"),
+      col("error_counts", Kind::Json),
+      int("p50_latency_ms"),
+      int("p95_latency_ms"),
+      int("changes"),
+      int("items"),
+      ms("updated_at_ms", "updated_at")
+    ]
+  },
+  Table {
+    name:    "fetch_requests",
+    server:  false,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("feed_id"),
+      text("requested_by"),
+      ms(
+        "requested_at_ms",
+        "requested_at"
+      ),
+      text("status"),
+      ms("started_at_ms", "started_at"),
+      ms(
+        "finished_at_ms",
+        "finished_at"
+      ),
+      int("http_status"),
+      text("error")
+    ]
+  },
+  Table {
+    name:    "paused_targets",
+    server:  false,
+    key:     "scope, name",
+    serial:  false,
+    columns: &[
+      text("scope"),
+      text("name"),
+      text("reason"),
+      ms("paused_at_ms", "paused_at")
+    ]
+  },
+  Table {
+    name:    "users",
+    server:  true,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      text("username"),
+      text("password_hash"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "user_tokens",
+    server:  true,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      int("user_id"),
+      text("token_hash"),
+      stamp("expires_at"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "user_password_resets",
+    server:  true,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      int("user_id"),
+      text("token_hash"),
+      stamp("expires_at"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "subscriptions",
+    server:  true,
+    key:     "user_id, feed_id",
+    serial:  false,
+    columns: &[
+      int("user_id"),
+      text("feed_id"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "entry_states",
+    server:  true,
+    key:     "user_id, item_id",
+    serial:  false,
+    columns: &[
+      int("user_id"),
+      int("item_id"),
+      stamp("read_at")
+    ]
+  },
+  Table {
+    name:    "favorites",
+    server:  true,
+    key:     "user_id, feed_id",
+    serial:  false,
+    columns: &[
+      int("user_id"),
+      text("feed_id"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "folders",
+    server:  true,
+    key:     "id",
+    serial:  true,
+    columns: &[
+      int("id"),
+      int("user_id"),
+      text("name"),
+      stamp("created_at")
+    ]
+  },
+  Table {
+    name:    "folder_feeds",
+    server:  true,
+    key:     "folder_id, feed_id",
+    serial:  false,
+    columns: &[
+      int("folder_id"),
+      text("feed_id"),
+      stamp("created_at")
+    ]
+  }
+];
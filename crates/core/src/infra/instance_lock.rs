@@ -0,0 +1,553 @@
+//! Single-instance guard for SQLite
+//! fetchers: a `<db>.lock` file holding
+//! the owner's pid, host, process
+//! instance and a heartbeat refreshed
+//! in place while it runs. A lock whose
+//! heartbeat has gone stale, or whose
+//! pid is gone (or reused by this
+//! process) on this host, is moved
+//! aside and taken over; a starter that
+//! finds the file changed under it
+//! backs off.
+
+use std::fs::{
+  File,
+  OpenOptions
+};
+use std::io::{
+  ErrorKind,
+  Seek,
+  SeekFrom,
+  Write
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::sync::atomic::{
+  AtomicU64,
+  Ordering
+};
+use std::sync::{
+  Arc,
+  LazyLock
+};
+use std::time::{
+  Duration,
+  SystemTime,
+  UNIX_EPOCH
+};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{
+  error,
+  warn
+};
+
+const HEARTBEAT: Duration =
+  Duration::from_secs(10);
+
+/// Six missed heartbeats.
+const STALE_AFTER_MS: i64 = 60_000;
+
+/// Tells this process apart from an
+/// earlier one that had the same pid,
+/// e.g. PID 1 in a restarted container.
+static INSTANCE: LazyLock<String> =
+  LazyLock::new(|| {
+    format!(
+      "{:x}",
+      SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+    )
+  });
+
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub struct LockInfo {
+  pub pid:          u32,
+  pub host:         String,
+  /// Empty in locks written before the
+  /// field existed.
+  pub instance:     String,
+  pub started_ms:   i64,
+  pub heartbeat_ms: i64
+}
+
+impl LockInfo {
+  fn render(&self) -> String {
+    let lines = [
+      format!("pid={}", self.pid),
+      format!("host={}", self.host),
+      format!(
+        "instance={}",
+        self.instance
+      ),
+      format!(
+        "started_ms={}",
+        self.started_ms
+      ),
+      format!(
+        "heartbeat_ms={}",
+        self.heartbeat_ms
+      )
+    ];
+
+    lines.join("\n") + "\n"
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Option<Self> {
+    let mut pid = None;
+    let mut host = None;
+    let mut instance = String::new();
+    let mut started_ms = None;
+    let mut heartbeat_ms = None;
+
+    for line in raw.lines() {
+      let Some((key, value)) =
+        line.split_once('=')
+      else {
+        continue;
+      };
+
+      match key.trim() {
+        | "pid" => {
+          pid =
+            value.trim().parse().ok()
+        }
+        | "host" => {
+          host = Some(
+            value.trim().to_string()
+          )
+        }
+        | "instance" => {
+          instance =
+            value.trim().to_string()
+        }
+        | "started_ms" => {
+          started_ms =
+            value.trim().parse().ok()
+        }
+        | "heartbeat_ms" => {
+          heartbeat_ms =
+            value.trim().parse().ok()
+        }
+        | _ => {}
+      }
+    }
+
+    Some(Self {
+      pid: pid?,
+      host: host?,
+      instance,
+      started_ms: started_ms?,
+      heartbeat_ms: heartbeat_ms?
+    })
+  }
+
+  /// Whether the holder should be
+  /// presumed dead at `now_ms`. On this
+  /// host, a lock with our own pid but
+  /// another instance was left by an
+  /// earlier process.
+  pub fn is_stale(
+    &self,
+    now_ms: i64,
+    host: &str
+  ) -> bool {
+    let reused = self.pid
+      == std::process::id()
+      && self.instance != *INSTANCE;
+
+    now_ms - self.heartbeat_ms
+      > STALE_AFTER_MS
+      || (self.host == host
+        && (reused
+          || !pid_alive(self.pid)))
+  }
+}
+
+pub struct InstanceLock {
+  path:      PathBuf,
+  info:      LockInfo,
+  file:      Arc<File>,
+  heartbeat: Option<JoinHandle<()>>
+}
+
+impl InstanceLock {
+  /// `<db_path>.lock`.
+  pub fn path_for(
+    db_path: &Path
+  ) -> PathBuf {
+    let mut name = db_path
+      .as_os_str()
+      .to_os_string();
+
+    name.push(".lock");
+
+    PathBuf::from(name)
+  }
+
+  /// Takes the lock for `db_path`, or
+  /// fails naming the live holder.
+  pub fn acquire(
+    db_path: &Path
+  ) -> Result<Self, String> {
+    let path = Self::path_for(db_path);
+
+    if let Some(parent) = path.parent()
+      && !parent.as_os_str().is_empty()
+    {
+      std::fs::create_dir_all(parent)
+        .map_err(|e| {
+        format!(
+          "create {}: {e}",
+          parent.display()
+        )
+      })?;
+    }
+
+    let now = now_ms();
+
+    let info = LockInfo {
+      pid:          std::process::id(),
+      host:         hostname(),
+      instance:     INSTANCE.clone(),
+      started_ms:   now,
+      heartbeat_ms: now
+    };
+
+    // One takeover attempt: a second
+    // collision means another process
+    // won the race.
+    for _ in 0..2 {
+      match OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+      {
+        | Ok(mut file) => {
+          file
+            .write_all(
+              info.render().as_bytes()
+            )
+            .map_err(|e| {
+              format!(
+                "write {}: {e}",
+                path.display()
+              )
+            })?;
+
+          // A starter that judged an
+          // earlier file stale may have
+          // moved this one aside.
+          if !still_at(&file, &path, &info)
+          {
+            return Err(format!(
+              "lost the race for {} to \
+               another fetcher",
+              path.display()
+            ));
+          }
+
+          return Ok(Self {
+            path,
+            info,
+            file: Arc::new(file),
+            heartbeat: None
+          });
+        }
+        | Err(e)
+          if e.kind()
+            == ErrorKind::AlreadyExists =>
+        {
+          let held = read_info(&path);
+
+          match held {
+            | Some(held)
+              if !held.is_stale(
+                now_ms(),
+                &info.host
+              ) =>
+            {
+              return Err(format!(
+                "another fetcher (pid {} \
+                 on {}, heartbeat {}s \
+                 ago) holds {}; stop it \
+                 or remove the lock file \
+                 if it is not running",
+                held.pid,
+                held.host,
+                (now_ms()
+                  - held.heartbeat_ms)
+                  / 1000,
+                path.display()
+              ));
+            }
+            | held => {
+              warn!(
+                lock = %path.display(),
+                previous = ?held,
+                "Taking over stale instance lock"
+              );
+
+              move_aside(
+                &path,
+                held.as_ref(),
+                &info
+              )?;
+            }
+          }
+        }
+        | Err(e) => {
+          return Err(format!(
+            "open {}: {e}",
+            path.display()
+          ));
+        }
+      }
+    }
+
+    Err(format!(
+      "could not take {} after moving \
+       a stale lock aside",
+      path.display()
+    ))
+  }
+
+  /// Refreshes the heartbeat every 10s
+  /// until the lock is released. If
+  /// another process has taken the
+  /// file, `shutdown` is flipped so
+  /// this one stops writing.
+  ///
+  /// The heartbeat is written through
+  /// the handle we created, never by
+  /// path: once the file is moved aside
+  /// the write lands in the moved file,
+  /// so a rival's lock is never
+  /// overwritten.
+  pub fn spawn_heartbeat(
+    &mut self,
+    shutdown: watch::Sender<bool>
+  ) {
+    let path = self.path.clone();
+
+    let file = self.file.clone();
+
+    let mut info = self.info.clone();
+
+    self.heartbeat = Some(
+      tokio::spawn(async move {
+        let mut ticker =
+          tokio::time::interval(
+            HEARTBEAT
+          );
+
+        ticker.tick().await;
+
+        loop {
+          ticker.tick().await;
+
+          if !still_at(
+            &file, &path, &info
+          ) {
+            error!(
+              lock = %path.display(),
+              holder = ?read_info(&path),
+              "Instance lock lost, shutting down"
+            );
+
+            let _ = shutdown.send(true);
+
+            return;
+          }
+
+          info.heartbeat_ms = now_ms();
+
+          if let Err(e) =
+            rewrite(&file, &info)
+          {
+            warn!(lock = %path.display(), error = %e, "Instance lock heartbeat failed");
+          }
+        }
+      })
+    );
+  }
+
+  /// Stops the heartbeat and removes
+  /// the lock file if it is still
+  /// ours.
+  pub fn release(mut self) {
+    if let Some(handle) =
+      self.heartbeat.take()
+    {
+      handle.abort();
+    }
+
+    if still_at(
+      &self.file, &self.path,
+      &self.info
+    ) {
+      let _ = std::fs::remove_file(
+        &self.path
+      );
+    }
+  }
+}
+
+/// Whether `path` still names the
+/// file we created.
+#[cfg(unix)]
+fn still_at(
+  file: &File,
+  path: &Path,
+  _info: &LockInfo
+) -> bool {
+  use std::os::unix::fs::MetadataExt;
+
+  match (
+    file.metadata(),
+    std::fs::metadata(path)
+  ) {
+    | (Ok(ours), Ok(now)) => {
+      ours.dev() == now.dev()
+        && ours.ino() == now.ino()
+    }
+    | _ => false
+  }
+}
+
+#[cfg(not(unix))]
+fn still_at(
+  _file: &File,
+  path: &Path,
+  info: &LockInfo
+) -> bool {
+  read_info(path).is_some_and(|h| {
+    h.pid == info.pid
+      && h.host == info.host
+      && h.instance == info.instance
+  })
+}
+
+fn read_info(
+  path: &Path
+) -> Option<LockInfo> {
+  std::fs::read_to_string(path)
+    .ok()
+    .and_then(|raw| {
+      LockInfo::parse(&raw)
+    })
+}
+
+/// Renames the stale lock to a name of
+/// our own; only one starter can move
+/// a given file. When what moved is no
+/// longer the lock judged stale (its
+/// holder heartbeat, or a rival took
+/// over first), it is linked back
+/// without replacing a newer file.
+fn move_aside(
+  path: &Path,
+  stale: Option<&LockInfo>,
+  info: &LockInfo
+) -> Result<(), String> {
+  static ASIDE: AtomicU64 =
+    AtomicU64::new(0);
+
+  let aside =
+    path.with_extension(format!(
+      "lock.stale.{}.{}",
+      info.pid,
+      ASIDE.fetch_add(
+        1,
+        Ordering::Relaxed
+      )
+    ));
+
+  match std::fs::rename(path, &aside) {
+    | Ok(()) => {}
+    | Err(e)
+      if e.kind()
+        == ErrorKind::NotFound =>
+    {
+      return Ok(());
+    }
+    | Err(e) => {
+      return Err(format!(
+        "move aside {}: {e}",
+        path.display()
+      ));
+    }
+  }
+
+  if read_info(&aside).as_ref() != stale
+  {
+    let _ =
+      std::fs::hard_link(&aside, path);
+  }
+
+  let _ = std::fs::remove_file(&aside);
+
+  Ok(())
+}
+
+/// Overwrites the file in place; only
+/// the heartbeat digits change, so the
+/// length stays the same.
+fn rewrite(
+  mut file: &File,
+  info: &LockInfo
+) -> Result<(), String> {
+  file
+    .seek(SeekFrom::Start(0))
+    .and_then(|_| {
+      file.write_all(
+        info.render().as_bytes()
+      )
+    })
+    .map_err(|e| e.to_string())
+}
+
+fn now_ms() -> i64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .unwrap_or_default()
+    .as_millis() as i64
+}
+
+/// `HOSTNAME`, else `/etc/hostname`.
+pub(crate) fn hostname() -> String {
+  std::env::var("HOSTNAME")
+    .ok()
+    .or_else(|| {
+      std::fs::read_to_string(
+        "/etc/hostname"
+      )
+      .ok()
+    })
+    .map(|h| h.trim().to_string())
+    .filter(|h| !h.is_empty())
+    .unwrap_or_else(|| {
+      "localhost".to_string()
+    })
+}
+
+/// Without `/proc` only the heartbeat
+/// can prove a holder dead.
+fn pid_alive(pid: u32) -> bool {
+  let proc = Path::new("/proc");
+
+  !proc.exists()
+    || proc
+      .join(pid.to_string())
+      .exists()
+}
@@ -1,7 +1,7 @@
 //! Logging bootstrap using `tracing`
 //! with env-filter override support.
 
-use std::sync::OnceLock;
+use std::sync::Mutex;
 
 use thiserror::Error;
 use tracing::Level;
@@ -26,7 +26,7 @@ pub enum BootError {
   Fatal(String)
 }
 
-static LOG_GUARDS: OnceLock<Vec<tracing_appender::non_blocking::WorkerGuard>> = OnceLock::new();
+static LOG_GUARDS: Mutex<Vec<tracing_appender::non_blocking::WorkerGuard>> = Mutex::new(Vec::new());
 
 pub fn init_logging(cfg: &AppConfig) {
   // Base level from config, still
@@ -129,13 +129,29 @@ pub fn init_logging(cfg: &AppConfig) {
     }
   }
 
-  let _ = LOG_GUARDS.set(guards);
+  if let Ok(mut held) =
+    LOG_GUARDS.lock()
+  {
+    *held = guards;
+  }
 
   tracing_subscriber::registry()
     .with(layers)
     .init();
 }
 
+/// Drops the file appender guards,
+/// flushing buffered lines to disk.
+/// Call once, right before exit; later
+/// events only reach stdout.
+pub fn flush_logs() {
+  if let Ok(mut held) =
+    LOG_GUARDS.lock()
+  {
+    held.clear();
+  }
+}
+
 fn parse_level(level: &str) -> Level {
   match level {
     | "error" => Level::ERROR,
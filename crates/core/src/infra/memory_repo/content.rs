@@ -0,0 +1,70 @@
+//! Stored content a fetch is compared
+//! against, and dataset versions.
+
+use chrono_tz::Tz;
+
+use super::MemoryRepo;
+use crate::feed::parser::ParsedFeed;
+use crate::ports::repo::{
+  ContentRepo,
+  DatasetVersion
+};
+
+#[async_trait::async_trait]
+
+impl ContentRepo for MemoryRepo {
+  async fn latest_content_hash(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    Ok(
+      self
+        .store()
+        .payloads
+        .iter()
+        .filter(|p| {
+          p.feed_id == feed_id
+        })
+        .max_by_key(|p| {
+          (p.fetched_at_ms, p.id)
+        })
+        .and_then(|p| {
+          p.content_hash.clone()
+        })
+    )
+  }
+
+  async fn latest_dataset_version(
+    &self,
+    feed_id: &str
+  ) -> Result<
+    Option<DatasetVersion>,
+    String
+  > {
+    Ok(
+      self
+        .store()
+        .latest_dataset_version(
+          feed_id
+        )
+    )
+  }
+
+  async fn insert_dataset_version(
+    &self,
+    version: &DatasetVersion,
+    _etag: Option<&str>,
+    _last_modified_ms: Option<i64>,
+    items: &ParsedFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .store()
+      .insert_dataset_version(
+        version, items
+      );
+
+    Ok(())
+  }
+}
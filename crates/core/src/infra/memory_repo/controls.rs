@@ -0,0 +1,112 @@
+//! Operator pauses and the admin API's
+//! read-only views.
+
+use chrono_tz::Tz;
+
+use super::MemoryRepo;
+use crate::domain::model::FeedConfig;
+use crate::ports::repo::{
+  ControlRepo,
+  ErrorFeed,
+  FetchEvent,
+  PauseScope,
+  PausedTarget
+};
+
+#[async_trait::async_trait]
+
+impl ControlRepo for MemoryRepo {
+  async fn feed(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<FeedConfig>, String>
+  {
+    Ok(
+      self
+        .store()
+        .feed(feed_id)
+        .cloned()
+    )
+  }
+
+  async fn pause_target(
+    &self,
+    target: &PausedTarget,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().paused.insert(
+      (
+        target.scope.as_str(),
+        target.name.clone()
+      ),
+      target.clone()
+    );
+
+    Ok(())
+  }
+
+  async fn resume_target(
+    &self,
+    scope: PauseScope,
+    name: &str
+  ) -> Result<bool, String> {
+    Ok(
+      self
+        .store()
+        .paused
+        .remove(&(
+          scope.as_str(),
+          name.to_string()
+        ))
+        .is_some()
+    )
+  }
+
+  async fn paused_targets(
+    &self
+  ) -> Result<Vec<PausedTarget>, String>
+  {
+    Ok(
+      self
+        .store()
+        .paused
+        .values()
+        .cloned()
+        .collect()
+    )
+  }
+
+  async fn recent_fetch_events(
+    &self,
+    feed_id: &str,
+    limit: usize
+  ) -> Result<Vec<FetchEvent>, String>
+  {
+    Ok(
+      self
+        .store()
+        .recent_events(feed_id, limit)
+    )
+  }
+
+  /// Most recent error first.
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeed>, String>
+  {
+    let mut feeds = self
+      .store()
+      .error_feeds
+      .values()
+      .cloned()
+      .collect::<Vec<_>>();
+
+    feeds.sort_by_key(|f| {
+      std::cmp::Reverse(
+        f.last_error_at_ms
+      )
+    });
+
+    Ok(feeds)
+  }
+}
@@ -0,0 +1,79 @@
+//! Feed definitions, categories and
+//! the scheduler's due-feed seed.
+
+use super::store::Store;
+use crate::domain::model::FeedConfig;
+use crate::ports::repo::ScheduledFeed;
+
+impl Store {
+  /// Inserts or replaces feeds by id,
+  /// dropping the fields the SQL
+  /// backends do not read back.
+  pub fn upsert_feeds(
+    &mut self,
+    feeds: Vec<FeedConfig>
+  ) {
+    for feed in feeds {
+      let feed = FeedConfig {
+        provenance: None,
+        tags: None,
+        language: None,
+        content_type: None,
+        ..feed
+      };
+
+      match self
+        .feed_index
+        .get(&feed.id)
+      {
+        | Some(&i) => {
+          self.feeds[i] = feed
+        }
+        | None => {
+          self.feed_index.insert(
+            feed.id.clone(),
+            self.feeds.len()
+          );
+
+          self.feeds.push(feed);
+        }
+      }
+    }
+  }
+
+  pub fn upsert_categories(
+    &mut self,
+    names: Vec<String>
+  ) {
+    self.categories.extend(names);
+  }
+
+  /// Feeds of `category` not parked in
+  /// `error_feeds`, in insertion order.
+  pub fn scheduled_feeds(
+    &self,
+    category: &str
+  ) -> Vec<ScheduledFeed> {
+    self
+      .feeds
+      .iter()
+      .filter(|f| {
+        f.category == category
+          && !self
+            .error_feeds
+            .contains_key(&f.id)
+      })
+      .map(|f| {
+        ScheduledFeed {
+          feed:              f.clone(),
+          next_action_at_ms: self
+            .current
+            .get(&f.id)
+            .map(|s| {
+              s.next_action_at_ms
+            })
+        }
+      })
+      .collect()
+  }
+}
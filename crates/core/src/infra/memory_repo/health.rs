@@ -0,0 +1,172 @@
+//! Health reads over events and
+//! payloads, and the daily rollups.
+
+use super::store::Store;
+use crate::ports::repo::{
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup
+};
+
+impl Store {
+  /// Events of known feeds in
+  /// `[start_ms, end_ms)`.
+  pub fn health_events(
+    &self,
+    start_ms: i64,
+    end_ms: i64
+  ) -> Vec<HealthEvent> {
+    self
+      .events
+      .iter()
+      .filter(|e| {
+        (start_ms..end_ms).contains(
+          &e.event.event_time_ms
+        )
+      })
+      .filter_map(|e| {
+        let feed =
+          self.feed(&e.feed_id)?;
+
+        Some(HealthEvent {
+          feed_id:       e
+            .feed_id
+            .clone(),
+          domain:        feed
+            .domain
+            .clone(),
+          event_time_ms: e
+            .event
+            .event_time_ms,
+          error_kind:    e
+            .event
+            .error_kind
+            .clone(),
+          latency_ms:    e
+            .event
+            .latency_ms
+        })
+      })
+      .collect()
+  }
+
+  /// Payloads of known feeds in
+  /// `[start_ms, end_ms)`, compared
+  /// with the feed's previous payload
+  /// by fetch time, then id.
+  pub fn health_payloads(
+    &self,
+    start_ms: i64,
+    end_ms: i64
+  ) -> Vec<HealthPayload> {
+    self
+      .payloads
+      .iter()
+      .filter(|p| {
+        (start_ms..end_ms)
+          .contains(&p.fetched_at_ms)
+      })
+      .filter_map(|p| {
+        let feed =
+          self.feed(&p.feed_id)?;
+
+        let previous = self
+          .payloads
+          .iter()
+          .filter(|q| {
+            q.feed_id == p.feed_id
+              && (q.fetched_at_ms, q.id)
+                < (
+                  p.fetched_at_ms,
+                  p.id
+                )
+          })
+          .max_by_key(|q| {
+            (q.fetched_at_ms, q.id)
+          })
+          .and_then(|q| {
+            q.content_hash.as_ref()
+          });
+
+        Some(HealthPayload {
+          feed_id:       p
+            .feed_id
+            .clone(),
+          domain:        feed
+            .domain
+            .clone(),
+          fetched_at_ms: p
+            .fetched_at_ms,
+          changed:       p
+            .content_hash
+            .as_ref()
+            != previous,
+          item_count:    self
+            .items
+            .values()
+            .filter(|i| {
+              i.payload_id == p.id
+            })
+            .count()
+            as i64
+        })
+      })
+      .collect()
+  }
+
+  pub fn replace_health_rollups(
+    &mut self,
+    day: &str,
+    rows: &[HealthRollup]
+  ) {
+    self
+      .rollups
+      .retain(|r| r.day != day);
+
+    self
+      .rollups
+      .extend(rows.iter().cloned());
+  }
+
+  /// Newest day first, then subject.
+  pub fn health_rollups(
+    &self,
+    query: &HealthQuery
+  ) -> Vec<HealthRollup> {
+    let mut rows = self
+      .rollups
+      .iter()
+      .filter(|r| {
+        r.scope == query.scope
+          && query
+            .subject
+            .as_ref()
+            .is_none_or(|s| {
+              *s == r.subject
+            })
+          && query
+            .since_day
+            .as_ref()
+            .is_none_or(|d| r.day >= *d)
+          && query
+            .until_day
+            .as_ref()
+            .is_none_or(|d| r.day <= *d)
+      })
+      .cloned()
+      .collect::<Vec<_>>();
+
+    rows.sort_by(|a, b| {
+      b.day.cmp(&a.day).then_with(
+        || a.subject.cmp(&b.subject)
+      )
+    });
+
+    rows.truncate(
+      query.limit.max(0) as usize
+    );
+
+    rows
+  }
+}
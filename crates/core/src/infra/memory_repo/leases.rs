@@ -0,0 +1,189 @@
+//! Work leases and shared domain
+//! tokens, expiring on the repo's
+//! clock like Postgres expires them on
+//! the database clock.
+
+use super::store::{
+  Lease,
+  Store
+};
+
+/// Domain tokens are held per feed
+/// action.
+fn token_holder(
+  owner: &str,
+  feed_id: &str
+) -> String {
+  format!("{owner}/{feed_id}")
+}
+
+impl Lease {
+  fn new(
+    holder: String,
+    now_ms: i64,
+    ttl_seconds: u64
+  ) -> Self {
+    Self {
+      holder,
+      expires_at_ms: now_ms
+        + (ttl_seconds * 1000) as i64
+    }
+  }
+
+  fn free_for(
+    &self,
+    holder: &str,
+    now_ms: i64
+  ) -> bool {
+    self.holder == holder
+      || self.expires_at_ms < now_ms
+  }
+}
+
+impl Store {
+  /// Free, expired and already-owned
+  /// leases are taken.
+  pub fn claim_leases(
+    &mut self,
+    owner: &str,
+    feed_ids: &[String],
+    now_ms: i64,
+    ttl_seconds: u64
+  ) -> Vec<String> {
+    let mut claimed = Vec::new();
+
+    for id in feed_ids {
+      if self
+        .leases
+        .get(id)
+        .is_some_and(|l| {
+          !l.free_for(owner, now_ms)
+        })
+      {
+        continue;
+      }
+
+      self.leases.insert(
+        id.clone(),
+        Lease::new(
+          owner.to_string(),
+          now_ms,
+          ttl_seconds
+        )
+      );
+
+      claimed.push(id.clone());
+    }
+
+    claimed
+  }
+
+  /// Extends a lease still held by
+  /// `owner`, expired or not, and the
+  /// domain token taken under it.
+  pub fn renew_lease(
+    &mut self,
+    owner: &str,
+    feed_id: &str,
+    now_ms: i64,
+    ttl_seconds: u64
+  ) -> bool {
+    let holder =
+      token_holder(owner, feed_id);
+
+    for token in
+      self.tokens.values_mut()
+    {
+      if token.holder == holder {
+        *token = Lease::new(
+          holder.clone(),
+          now_ms,
+          ttl_seconds
+        );
+      }
+    }
+
+    match self.leases.get_mut(feed_id) {
+      | Some(lease)
+        if lease.holder == owner =>
+      {
+        *lease = Lease::new(
+          owner.to_string(),
+          now_ms,
+          ttl_seconds
+        );
+
+        true
+      }
+      | _ => false
+    }
+  }
+
+  pub fn release_leases(
+    &mut self,
+    owner: &str,
+    feed_ids: &[String]
+  ) {
+    for id in feed_ids {
+      let holder =
+        token_holder(owner, id);
+
+      self.tokens.retain(|_, t| {
+        t.holder != holder
+      });
+
+      if self
+        .leases
+        .get(id)
+        .is_some_and(|l| {
+          l.holder == owner
+        })
+      {
+        self.leases.remove(id);
+      }
+    }
+  }
+
+  /// Takes the lowest free slot below
+  /// `limit`.
+  pub fn acquire_token(
+    &mut self,
+    domain: &str,
+    limit: usize,
+    owner: &str,
+    feed_id: &str,
+    now_ms: i64,
+    ttl_seconds: u64
+  ) -> bool {
+    let holder =
+      token_holder(owner, feed_id);
+
+    let slot =
+      (0..limit.max(1)).find(|&slot| {
+        self
+          .tokens
+          .get(&(
+            domain.to_string(),
+            slot
+          ))
+          .is_none_or(|t| {
+            t.free_for(&holder, now_ms)
+          })
+      });
+
+    let Some(slot) = slot else {
+      return false;
+    };
+
+    self.tokens.insert(
+      (domain.to_string(), slot),
+      Lease::new(
+        holder,
+        now_ms,
+        ttl_seconds
+      )
+    );
+
+    true
+  }
+}
@@ -0,0 +1,77 @@
+//! In-memory repository for tests,
+//! simulations and fetcher dry runs.
+//! It mirrors the SQLite repository's
+//! behavior, including due-feed
+//! selection and retention, but keeps
+//! nothing once dropped. Work leases
+//! follow Postgres instead, so several
+//! schedulers sharing one repo see
+//! each other's claims.
+
+mod content;
+mod controls;
+mod feeds;
+mod health;
+mod leases;
+mod payloads;
+mod repo;
+mod requests;
+mod retention;
+mod state;
+mod store;
+
+use std::sync::{
+  Arc,
+  Mutex,
+  MutexGuard,
+  PoisonError
+};
+
+use store::Store;
+
+use crate::infra::system_clock::SystemClock;
+use crate::ports::clock::Clock;
+
+pub struct MemoryRepo {
+  /// Stamps events recorded through
+  /// `insert_event`.
+  clock: Arc<dyn Clock>,
+  store: Mutex<Store>
+}
+
+impl Default for MemoryRepo {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MemoryRepo {
+  pub fn new() -> Self {
+    Self::with_clock(Arc::new(
+      SystemClock
+    ))
+  }
+
+  /// Uses `clock` for the event times
+  /// the SQL backends take from the
+  /// wall clock, so simulated runs stay
+  /// on virtual time.
+  pub fn with_clock(
+    clock: Arc<dyn Clock>
+  ) -> Self {
+    Self {
+      clock,
+      store: Mutex::new(
+        Store::default()
+      )
+    }
+  }
+
+  fn store(
+    &self
+  ) -> MutexGuard<'_, Store> {
+    self.store.lock().unwrap_or_else(
+      PoisonError::into_inner
+    )
+  }
+}
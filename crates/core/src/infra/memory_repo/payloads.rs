@@ -0,0 +1,331 @@
+//! Payloads with their items, the raw
+//! body archive and dataset versions.
+
+use super::store::{
+  ArchiveRow,
+  DatasetRow,
+  ItemRow,
+  PayloadRow,
+  Store
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+use crate::ports::repo::{
+  ArchivedPayload,
+  DatasetVersion,
+  PayloadFilter,
+  PayloadRef,
+  StoredItem
+};
+
+impl Store {
+  pub fn insert_payload(
+    &mut self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed
+  ) {
+    let payload_id =
+      self.next_id("feed_payloads");
+
+    self.payloads.push(PayloadRow {
+      id: payload_id,
+      feed_id: feed_id.to_string(),
+      fetched_at_ms,
+      content_hash: content_hash
+        .map(str::to_string)
+    });
+
+    self.insert_items(
+      payload_id,
+      &parsed.items
+    );
+  }
+
+  fn insert_items(
+    &mut self,
+    payload_id: i64,
+    items: &[FeedItem]
+  ) {
+    for item in items {
+      let id =
+        self.next_id("feed_items");
+
+      self.items.insert(id, ItemRow {
+        payload_id,
+        item: item.clone()
+      });
+    }
+  }
+
+  pub fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Vec<StoredItem> {
+    self
+      .items
+      .iter()
+      .filter(|(_, row)| {
+        row.payload_id == payload_id
+      })
+      .map(|(&id, row)| {
+        StoredItem {
+          id,
+          item: row.item.clone()
+        }
+      })
+      .collect()
+  }
+
+  pub fn apply_payload_items(
+    &mut self,
+    payload_id: i64,
+    inserts: &[FeedItem],
+    updates: &[StoredItem]
+  ) {
+    self.insert_items(
+      payload_id, inserts
+    );
+
+    for stored in updates {
+      if let Some(row) =
+        self.items.get_mut(&stored.id)
+      {
+        row.item = stored.item.clone();
+      }
+    }
+  }
+
+  /// Whether the hash is archived,
+  /// bumping `last_seen` if so.
+  pub fn touch_archived(
+    &mut self,
+    content_hash: &str,
+    seen_at_ms: i64
+  ) -> bool {
+    match self
+      .archive
+      .get_mut(content_hash)
+    {
+      | Some(row) => {
+        row.last_seen_at_ms = row
+          .last_seen_at_ms
+          .max(seen_at_ms);
+
+        true
+      }
+      | None => false
+    }
+  }
+
+  /// An existing hash keeps its body
+  /// and only moves `last_seen`
+  /// forward.
+  pub fn insert_archived(
+    &mut self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64
+  ) {
+    if !self.touch_archived(
+      &payload.content_hash,
+      seen_at_ms
+    ) {
+      self.archive.insert(
+        payload.content_hash.clone(),
+        ArchiveRow {
+          payload:         payload
+            .clone(),
+          last_seen_at_ms: seen_at_ms
+        }
+      );
+    }
+  }
+
+  /// Removes the least recently seen
+  /// bodies first.
+  pub fn prune_archived(
+    &mut self,
+    seen_before_ms: i64,
+    limit: i64
+  ) -> Vec<ArchivedPayload> {
+    let mut expired = self
+      .archive
+      .values()
+      .filter(|row| {
+        row.last_seen_at_ms
+          < seen_before_ms
+      })
+      .map(|row| {
+        (
+          row.last_seen_at_ms,
+          row
+            .payload
+            .content_hash
+            .clone()
+        )
+      })
+      .collect::<Vec<_>>();
+
+    expired.sort();
+    expired
+      .truncate(limit.max(0) as usize);
+
+    expired
+      .into_iter()
+      .filter_map(|(_, hash)| {
+        self.archive.remove(&hash)
+      })
+      .map(|row| {
+        ArchivedPayload {
+          body: None,
+          ..row.payload
+        }
+      })
+      .collect()
+  }
+
+  pub fn archived_payload_refs(
+    &self,
+    filter: &PayloadFilter,
+    after_id: i64,
+    limit: i64
+  ) -> Vec<PayloadRef> {
+    self
+      .payloads
+      .iter()
+      .filter(|p| {
+        let Some(feed) =
+          self.feed(&p.feed_id)
+        else {
+          return false;
+        };
+
+        let archived = p
+          .content_hash
+          .as_ref()
+          .is_some_and(|hash| {
+            self
+              .archive
+              .contains_key(hash)
+          });
+
+        let version = self
+          .datasets
+          .iter()
+          .any(|d| {
+            d.version.feed_id
+              == p.feed_id
+              && p.content_hash.as_ref()
+                == Some(
+                  &d.version
+                    .content_hash
+                )
+          });
+
+        p.id > after_id
+          && archived
+          && feed.dataset.is_none()
+          && !version
+          && filter
+            .feed_id
+            .as_ref()
+            .is_none_or(|id| {
+              *id == p.feed_id
+            })
+          && filter
+            .category
+            .as_ref()
+            .is_none_or(|c| {
+              *c == feed.category
+            })
+          && filter.since_ms.is_none_or(
+            |since| {
+              p.fetched_at_ms >= since
+            }
+          )
+          && filter.until_ms.is_none_or(
+            |until| {
+              p.fetched_at_ms < until
+            }
+          )
+      })
+      .take(limit.max(0) as usize)
+      .map(|p| {
+        PayloadRef {
+          id:            p.id,
+          feed_id:       p
+            .feed_id
+            .clone(),
+          fetched_at_ms: p
+            .fetched_at_ms,
+          content_hash:  p
+            .content_hash
+            .clone()
+            .unwrap_or_default()
+        }
+      })
+      .collect()
+  }
+
+  /// Newest by fetch time, then id.
+  pub fn latest_dataset_version(
+    &self,
+    feed_id: &str
+  ) -> Option<DatasetVersion> {
+    let row = self
+      .datasets
+      .iter()
+      .filter(|d| {
+        d.version.feed_id == feed_id
+      })
+      .max_by_key(|d| {
+        (d.version.fetched_at_ms, d.id)
+      })?;
+
+    Some(DatasetVersion {
+      body: self
+        .blobs
+        .get(&row.version.content_hash)?
+        .clone(),
+      ..row.version.clone()
+    })
+  }
+
+  /// The first body stored for a hash
+  /// is kept.
+  pub fn insert_dataset_version(
+    &mut self,
+    version: &DatasetVersion,
+    items: &ParsedFeed
+  ) {
+    self
+      .blobs
+      .entry(
+        version.content_hash.clone()
+      )
+      .or_insert_with(|| {
+        version.body.clone()
+      });
+
+    let id =
+      self.next_id("dataset_versions");
+
+    self.datasets.push(DatasetRow {
+      id,
+      version: DatasetVersion {
+        body: Vec::new(),
+        ..version.clone()
+      }
+    });
+
+    self.insert_payload(
+      &version.feed_id,
+      version.fetched_at_ms,
+      Some(&version.content_hash),
+      items
+    );
+  }
+}
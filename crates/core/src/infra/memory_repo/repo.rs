@@ -0,0 +1,534 @@
+//! The [`Repo`] implementation, kept
+//! apart from the tables it reads.
+
+use chrono_tz::Tz;
+
+use super::MemoryRepo;
+use crate::domain::link_state::LinkState;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+use crate::ports::repo::{
+  ArchivedPayload,
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus,
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup,
+  PartitionPlan,
+  PartitionReport,
+  PayloadFilter,
+  PayloadRef,
+  PendingEvent,
+  PruneScope,
+  PruneTarget,
+  Repo,
+  ScheduledFeed,
+  SchemaStatus,
+  StateRow,
+  StoredItem,
+  WriteBatch
+};
+
+#[async_trait::async_trait]
+
+impl Repo for MemoryRepo {
+  async fn migrate(
+    &self,
+    _zone: &Tz,
+    _default_poll_seconds: u64
+  ) -> Result<(), String> {
+    Ok(())
+  }
+
+  async fn schema_status(
+    &self
+  ) -> Result<SchemaStatus, String> {
+    Ok(SchemaStatus::new(
+      &[],
+      Vec::new()
+    ))
+  }
+
+  async fn upsert_feeds_bulk(
+    &self,
+    feeds: Vec<FeedConfig>,
+    _chunk_size: usize,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().upsert_feeds(feeds);
+
+    Ok(())
+  }
+
+  async fn upsert_categories(
+    &self,
+    categories: Vec<String>,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .store()
+      .upsert_categories(categories);
+
+    Ok(())
+  }
+
+  async fn latest_state(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<StateRow>, String>
+  {
+    Ok(
+      self
+        .store()
+        .current
+        .get(feed_id)
+        .cloned()
+    )
+  }
+
+  async fn scheduled_feeds_for_category(
+    &self,
+    category: &str
+  ) -> Result<Vec<ScheduledFeed>, String>
+  {
+    Ok(
+      self
+        .store()
+        .scheduled_feeds(category)
+    )
+  }
+
+  async fn insert_state(
+    &self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    _zone: &Tz,
+    record_history: bool
+  ) -> Result<(), String> {
+    self.store().insert_state(
+      state,
+      recorded_at_ms,
+      record_history
+    );
+
+    Ok(())
+  }
+
+  async fn insert_event(
+    &self,
+    feed_id: &str,
+    method: &str,
+    status: Option<i64>,
+    error_kind: Option<ErrorKind>,
+    latency_ms: Option<i64>,
+    backoff_index: i64,
+    scheduled_next_action_at_ms: i64,
+    debug: Option<&str>,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let event = PendingEvent {
+      feed_id: feed_id.to_string(),
+      event_time_ms: self
+        .clock
+        .now_epoch_ms()
+        .await,
+      method: method.to_string(),
+      status,
+      error_kind,
+      latency_ms,
+      backoff_index,
+      scheduled_next_action_at_ms,
+      debug: debug.map(str::to_string)
+    };
+
+    self.store().insert_event(&event);
+
+    Ok(())
+  }
+
+  async fn write_batch(
+    &self,
+    batch: &WriteBatch,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().write_batch(batch);
+
+    Ok(())
+  }
+
+  async fn insert_payload_with_items(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    _etag: Option<&str>,
+    _last_modified_ms: Option<i64>,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().insert_payload(
+      feed_id,
+      fetched_at_ms,
+      content_hash,
+      parsed
+    );
+
+    Ok(())
+  }
+
+  async fn mark_feed_error(
+    &self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().mark_feed_error(
+      feed_id,
+      error_kind,
+      status,
+      error_count,
+      observed_at_ms
+    );
+
+    Ok(())
+  }
+
+  async fn touch_archived_payload(
+    &self,
+    content_hash: &str,
+    seen_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<bool, String> {
+    Ok(self.store().touch_archived(
+      content_hash,
+      seen_at_ms
+    ))
+  }
+
+  async fn insert_archived_payload(
+    &self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().insert_archived(
+      payload, seen_at_ms
+    );
+
+    Ok(())
+  }
+
+  async fn archived_payload(
+    &self,
+    content_hash: &str
+  ) -> Result<
+    Option<ArchivedPayload>,
+    String
+  > {
+    Ok(
+      self
+        .store()
+        .archive
+        .get(content_hash)
+        .map(|row| row.payload.clone())
+    )
+  }
+
+  async fn prune_archived_payloads(
+    &self,
+    seen_before_ms: i64,
+    limit: i64,
+    _zone: &Tz
+  ) -> Result<
+    Vec<ArchivedPayload>,
+    String
+  > {
+    Ok(self.store().prune_archived(
+      seen_before_ms,
+      limit
+    ))
+  }
+
+  async fn archived_payload_refs(
+    &self,
+    filter: &PayloadFilter,
+    after_id: i64,
+    limit: i64,
+    _zone: &Tz
+  ) -> Result<Vec<PayloadRef>, String>
+  {
+    Ok(
+      self
+        .store()
+        .archived_payload_refs(
+          filter, after_id, limit
+        )
+    )
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItem>, String>
+  {
+    Ok(
+      self
+        .store()
+        .payload_items(payload_id)
+    )
+  }
+
+  async fn apply_payload_items(
+    &self,
+    payload_id: i64,
+    _feed_id: &str,
+    inserts: &[FeedItem],
+    updates: &[StoredItem],
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().apply_payload_items(
+      payload_id, inserts, updates
+    );
+
+    Ok(())
+  }
+
+  /// Nothing holds server read state
+  /// here, so no item is protected.
+  async fn prune_rows(
+    &self,
+    target: PruneTarget,
+    scope: &PruneScope,
+    _zone: &Tz
+  ) -> Result<u64, String> {
+    Ok(
+      self
+        .store()
+        .prune_rows(target, scope)
+    )
+  }
+
+  async fn maintain_partitions(
+    &self,
+    _plan: &PartitionPlan
+  ) -> Result<PartitionReport, String>
+  {
+    Ok(PartitionReport::default())
+  }
+
+  async fn health_events(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<HealthEvent>, String>
+  {
+    Ok(
+      self.store().health_events(
+        start_ms, end_ms
+      )
+    )
+  }
+
+  async fn health_payloads(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<HealthPayload>, String>
+  {
+    Ok(self.store().health_payloads(
+      start_ms, end_ms
+    ))
+  }
+
+  async fn replace_health_rollups(
+    &self,
+    day: &str,
+    rows: &[HealthRollup],
+    _now_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .store()
+      .replace_health_rollups(
+        day, rows
+      );
+
+    Ok(())
+  }
+
+  async fn health_rollups(
+    &self,
+    query: &HealthQuery
+  ) -> Result<Vec<HealthRollup>, String>
+  {
+    Ok(
+      self
+        .store()
+        .health_rollups(query)
+    )
+  }
+
+  async fn claim_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String],
+    ttl_seconds: u64
+  ) -> Result<Vec<String>, String> {
+    let now_ms =
+      self.clock.now_epoch_ms().await;
+
+    Ok(self.store().claim_leases(
+      owner,
+      feed_ids,
+      now_ms,
+      ttl_seconds
+    ))
+  }
+
+  async fn renew_feed_lease(
+    &self,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String> {
+    let now_ms =
+      self.clock.now_epoch_ms().await;
+
+    Ok(self.store().renew_lease(
+      owner,
+      feed_id,
+      now_ms,
+      ttl_seconds
+    ))
+  }
+
+  async fn release_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String]
+  ) -> Result<(), String> {
+    self
+      .store()
+      .release_leases(owner, feed_ids);
+
+    Ok(())
+  }
+
+  async fn acquire_domain_token(
+    &self,
+    domain: &str,
+    limit: usize,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String> {
+    let now_ms =
+      self.clock.now_epoch_ms().await;
+
+    Ok(self.store().acquire_token(
+      domain,
+      limit,
+      owner,
+      feed_id,
+      now_ms,
+      ttl_seconds
+    ))
+  }
+
+  async fn enqueue_fetch_request(
+    &self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<i64, String> {
+    self.store().enqueue_fetch_request(
+      feed_id,
+      requested_by,
+      requested_at_ms
+    )
+  }
+
+  async fn fetch_request(
+    &self,
+    id: i64
+  ) -> Result<
+    Option<FetchRequest>,
+    String
+  > {
+    Ok(
+      self
+        .store()
+        .requests
+        .get(&id)
+        .cloned()
+    )
+  }
+
+  async fn claim_fetch_requests(
+    &self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<ClaimedFetch>, String>
+  {
+    Ok(
+      self
+        .store()
+        .claim_fetch_requests(
+          category,
+          busy_feed_ids,
+          limit,
+          now_ms,
+          stale_before_ms
+        )
+    )
+  }
+
+  async fn requeue_fetch_requests(
+    &self,
+    ids: &[i64]
+  ) -> Result<(), String> {
+    self
+      .store()
+      .requeue_fetch_requests(ids);
+
+    Ok(())
+  }
+
+  async fn finish_fetch_request(
+    &self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.store().finish_fetch_request(
+      id,
+      status,
+      http_status,
+      error,
+      finished_at_ms
+    );
+
+    Ok(())
+  }
+}
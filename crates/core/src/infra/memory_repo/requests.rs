@@ -0,0 +1,172 @@
+//! On-demand refresh requests.
+
+use std::collections::HashSet;
+
+use super::store::Store;
+use crate::ports::repo::{
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus
+};
+
+impl Store {
+  /// Reuses the oldest pending request
+  /// of the feed.
+  pub fn enqueue_fetch_request(
+    &mut self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64
+  ) -> Result<i64, String> {
+    if self.feed(feed_id).is_none() {
+      return Err(format!(
+        "unknown feed '{feed_id}'"
+      ));
+    }
+
+    if let Some(pending) =
+      self.requests.values().find(|r| {
+        r.feed_id == feed_id
+          && r.status
+            == FetchRequestStatus::Pending
+      })
+    {
+      return Ok(pending.id);
+    }
+
+    let id =
+      self.next_id("fetch_requests");
+
+    self.requests.insert(
+      id,
+      FetchRequest {
+        id,
+        feed_id: feed_id.to_string(),
+        requested_by: requested_by
+          .map(str::to_string),
+        requested_at_ms,
+        status:
+          FetchRequestStatus::Pending,
+        started_at_ms: None,
+        finished_at_ms: None,
+        http_status: None,
+        error: None
+      }
+    );
+
+    Ok(id)
+  }
+
+  /// Pending and stale running
+  /// requests of the category, by feed
+  /// priority and then id.
+  pub fn claim_fetch_requests(
+    &mut self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64
+  ) -> Vec<ClaimedFetch> {
+    let mut candidates = self
+      .requests
+      .values()
+      .filter(|r| {
+        match r.status {
+          | FetchRequestStatus::Pending => {
+            true
+          }
+          | FetchRequestStatus::Running => {
+            r.started_at_ms.is_some_and(
+              |at| at < stale_before_ms
+            )
+          }
+          | _ => false
+        }
+      })
+      .filter_map(|r| {
+        let feed = self.feed(&r.feed_id)?;
+
+        (feed.category == category)
+          .then(|| (feed.clone(), r.id))
+      })
+      .collect::<Vec<_>>();
+
+    candidates.sort_by_key(
+      |(feed, id)| {
+        (feed.priority.rank(), *id)
+      }
+    );
+
+    let mut seen: HashSet<String> =
+      busy_feed_ids
+        .iter()
+        .cloned()
+        .collect();
+
+    let mut claimed = Vec::new();
+
+    for (feed, request_id) in candidates
+    {
+      if claimed.len() >= limit
+        || !seen.insert(feed.id.clone())
+      {
+        continue;
+      }
+
+      if let Some(request) = self
+        .requests
+        .get_mut(&request_id)
+      {
+        request.status =
+          FetchRequestStatus::Running;
+        request.started_at_ms =
+          Some(now_ms);
+      }
+
+      claimed.push(ClaimedFetch {
+        request_id,
+        feed
+      });
+    }
+
+    claimed
+  }
+
+  pub fn requeue_fetch_requests(
+    &mut self,
+    ids: &[i64]
+  ) {
+    for id in ids {
+      if let Some(request) =
+        self.requests.get_mut(id)
+        && request.status
+          == FetchRequestStatus::Running
+      {
+        request.status =
+          FetchRequestStatus::Pending;
+        request.started_at_ms = None;
+      }
+    }
+  }
+
+  pub fn finish_fetch_request(
+    &mut self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64
+  ) {
+    if let Some(request) =
+      self.requests.get_mut(&id)
+    {
+      request.status = status;
+      request.http_status = http_status;
+      request.error =
+        error.map(str::to_string);
+      request.finished_at_ms =
+        Some(finished_at_ms);
+    }
+  }
+}
@@ -0,0 +1,185 @@
+//! Retention deletes, scoped to one
+//! category like the SQL backends.
+
+use std::collections::{
+  BTreeSet,
+  HashMap
+};
+
+use super::store::Store;
+use crate::ports::repo::{
+  PruneScope,
+  PruneTarget
+};
+
+impl Store {
+  pub fn prune_rows(
+    &mut self,
+    target: PruneTarget,
+    scope: &PruneScope
+  ) -> u64 {
+    let limit =
+      scope.limit.max(0) as usize;
+
+    let doomed = match target {
+      | PruneTarget::FetchEvents => {
+        self.expired(
+          self.events.iter().map(|e| {
+            (
+              e.event.id,
+              e.feed_id.as_str(),
+              e.event.event_time_ms
+            )
+          }),
+          scope
+        )
+      }
+      | PruneTarget::StateHistory => {
+        self.expired(
+          self.history.iter().map(
+            |h| {
+              (
+                h.id,
+                h.row.feed_id.as_str(),
+                h.recorded_at_ms
+              )
+            }
+          ),
+          scope
+        )
+      }
+      | PruneTarget::Items => {
+        let payloads =
+          self.expired_payloads(scope);
+
+        self
+          .items
+          .iter()
+          .filter(|(_, row)| {
+            payloads
+              .contains(&row.payload_id)
+          })
+          .map(|(&id, _)| id)
+          .collect()
+      }
+      | PruneTarget::Payloads => {
+        self
+          .expired_payloads(scope)
+          .into_iter()
+          .filter(|&id| {
+            !self.items.values().any(
+              |row| {
+                row.payload_id == id
+              }
+            )
+          })
+          .collect()
+      }
+    };
+
+    let doomed = doomed
+      .into_iter()
+      .take(limit)
+      .collect::<BTreeSet<_>>();
+
+    match target {
+      | PruneTarget::FetchEvents => {
+        self.events.retain(|e| {
+          !doomed.contains(&e.event.id)
+        });
+      }
+      | PruneTarget::StateHistory => {
+        self.history.retain(|h| {
+          !doomed.contains(&h.id)
+        });
+      }
+      | PruneTarget::Items => {
+        self.items.retain(|id, _| {
+          !doomed.contains(id)
+        });
+      }
+      | PruneTarget::Payloads => {
+        self.payloads.retain(|p| {
+          !doomed.contains(&p.id)
+        });
+      }
+    }
+
+    doomed.len() as u64
+  }
+
+  fn expired_payloads(
+    &self,
+    scope: &PruneScope
+  ) -> BTreeSet<i64> {
+    self.expired(
+      self.payloads.iter().map(|p| {
+        (
+          p.id,
+          p.feed_id.as_str(),
+          p.fetched_at_ms
+        )
+      }),
+      scope
+    )
+  }
+
+  /// Ids of `(id, feed_id, ts)` rows in
+  /// the scope category that are older
+  /// than the cutoff or beyond the
+  /// newest `keep_per_feed` of their
+  /// feed.
+  fn expired<'a>(
+    &self,
+    rows: impl Iterator<
+      Item = (i64, &'a str, i64)
+    >,
+    scope: &PruneScope
+  ) -> BTreeSet<i64> {
+    let mut by_feed: HashMap<
+      &str,
+      Vec<(i64, i64)>
+    > = HashMap::new();
+
+    for (id, feed_id, ts) in rows {
+      if self.feed(feed_id).is_some_and(
+        |f| {
+          f.category == scope.category
+        }
+      ) {
+        by_feed
+          .entry(feed_id)
+          .or_default()
+          .push((ts, id));
+      }
+    }
+
+    let mut expired = BTreeSet::new();
+
+    for mut rows in
+      by_feed.into_values()
+    {
+      rows.sort_by(|a, b| b.cmp(a));
+
+      for (rank, (ts, id)) in
+        rows.into_iter().enumerate()
+      {
+        let old = scope
+          .cutoff_ms
+          .is_some_and(|c| ts < c);
+
+        let beyond = scope
+          .keep_per_feed
+          .is_some_and(|keep| {
+            rank as i64 >= keep
+          });
+
+        if old || beyond {
+          expired.insert(id);
+        }
+      }
+    }
+
+    expired
+  }
+}
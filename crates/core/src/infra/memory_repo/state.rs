@@ -0,0 +1,192 @@
+//! State snapshots, fetch events and
+//! the error-feed parking lot.
+
+use super::store::{
+  EventRow,
+  HistoryRow,
+  Store
+};
+use crate::domain::link_state::LinkState;
+use crate::domain::model::ErrorKind;
+use crate::ports::repo::{
+  ErrorFeed,
+  FetchEvent,
+  PendingEvent,
+  StateRow,
+  WriteBatch
+};
+
+/// The row the SQL backends store for
+/// `state`, enums as their `Debug`
+/// names.
+fn state_row(
+  state: &LinkState
+) -> StateRow {
+  StateRow {
+    feed_id:                 state
+      .feed_id
+      .clone(),
+    phase:                   format!(
+      "{:?}",
+      state.phase
+    ),
+    last_head_at_ms:         state
+      .last_head_at_ms,
+    last_head_status:        state
+      .last_head_status
+      .map(i64::from),
+    last_head_error:         state
+      .last_head_error
+      .map(|e| format!("{e:?}")),
+    last_get_at_ms:          state
+      .last_get_at_ms,
+    last_get_status:         state
+      .last_get_status
+      .map(i64::from),
+    last_get_error:          state
+      .last_get_error
+      .map(|e| format!("{e:?}")),
+    etag:                    state
+      .etag
+      .clone(),
+    last_modified_ms:        state
+      .last_modified_ms,
+    backoff_index:           i64::from(
+      state.backoff_index
+    ),
+    base_poll_seconds:       state
+      .base_poll_seconds
+      as i64,
+    next_action_at_ms:       state
+      .next_action_at_ms,
+    jitter_seconds:          state
+      .jitter_seconds,
+    note:                    state
+      .note
+      .clone(),
+    consecutive_error_count: i64::from(
+      state.consecutive_error_count
+    ),
+    get_only_since_ms:       state
+      .get_only_since_ms
+  }
+}
+
+impl Store {
+  /// Upserts the current snapshot,
+  /// appending it to the history first
+  /// when asked.
+  pub fn insert_state(
+    &mut self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    record_history: bool
+  ) {
+    let row = state_row(state);
+
+    if record_history {
+      let id = self
+        .next_id("feed_state_history");
+
+      self.history.push(HistoryRow {
+        id,
+        recorded_at_ms,
+        row: row.clone()
+      });
+    }
+
+    self
+      .current
+      .insert(row.feed_id.clone(), row);
+  }
+
+  pub fn insert_event(
+    &mut self,
+    event: &PendingEvent
+  ) {
+    let id =
+      self.next_id("fetch_events");
+
+    self.events.push(EventRow {
+      feed_id: event.feed_id.clone(),
+      event:   FetchEvent {
+        id,
+        event_time_ms: event
+          .event_time_ms,
+        method: event.method.clone(),
+        status: event.status,
+        error_kind: event
+          .error_kind
+          .map(|e| format!("{e:?}")),
+        latency_ms: event.latency_ms,
+        backoff_index: event
+          .backoff_index,
+        scheduled_next_action_at_ms:
+          event
+            .scheduled_next_action_at_ms,
+        debug: event.debug.clone()
+      }
+    });
+  }
+
+  /// Events first, then states, like
+  /// the SQL transaction.
+  pub fn write_batch(
+    &mut self,
+    batch: &WriteBatch
+  ) {
+    for event in &batch.events {
+      self.insert_event(event);
+    }
+
+    for pending in &batch.states {
+      self.insert_state(
+        &pending.state,
+        pending.recorded_at_ms,
+        pending.record_history
+      );
+    }
+  }
+
+  pub fn mark_feed_error(
+    &mut self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64
+  ) {
+    self.error_feeds.insert(
+      feed_id.to_string(),
+      ErrorFeed {
+        feed_id: feed_id.to_string(),
+        error_count,
+        last_error_kind: error_kind
+          .map(|e| format!("{e:?}")),
+        last_error_status: status,
+        last_error_at_ms:
+          observed_at_ms,
+        note: Some(
+          "max-consecutive-errors"
+            .to_string()
+        )
+      }
+    );
+  }
+
+  /// Newest first.
+  pub fn recent_events(
+    &self,
+    feed_id: &str,
+    limit: usize
+  ) -> Vec<FetchEvent> {
+    self
+      .events
+      .iter()
+      .rev()
+      .filter(|e| e.feed_id == feed_id)
+      .take(limit)
+      .map(|e| e.event.clone())
+      .collect()
+  }
+}
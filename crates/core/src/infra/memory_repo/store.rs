@@ -0,0 +1,127 @@
+//! The tables held by
+//! [`MemoryRepo`](super::MemoryRepo),
+//! one collection per SQL table.
+
+use std::collections::{
+  BTreeMap,
+  BTreeSet,
+  HashMap
+};
+
+use crate::domain::model::FeedConfig;
+use crate::feed::parser::FeedItem;
+use crate::ports::repo::{
+  ArchivedPayload,
+  DatasetVersion,
+  ErrorFeed,
+  FetchEvent,
+  FetchRequest,
+  HealthRollup,
+  PausedTarget,
+  StateRow
+};
+
+#[derive(Default)]
+
+pub(super) struct Store {
+  /// In insertion order, like rowids.
+  pub feeds:       Vec<FeedConfig>,
+  pub feed_index:
+    HashMap<String, usize>,
+  pub categories:  BTreeSet<String>,
+  pub current:
+    HashMap<String, StateRow>,
+  pub history:     Vec<HistoryRow>,
+  pub events:      Vec<EventRow>,
+  pub error_feeds:
+    HashMap<String, ErrorFeed>,
+  pub payloads:    Vec<PayloadRow>,
+  pub items: BTreeMap<i64, ItemRow>,
+  pub archive:
+    HashMap<String, ArchiveRow>,
+  pub datasets:    Vec<DatasetRow>,
+  pub blobs: HashMap<String, Vec<u8>>,
+  pub rollups:     Vec<HealthRollup>,
+  /// Keyed by scope name, so iteration
+  /// matches `ORDER BY scope, name`.
+  pub paused: BTreeMap<
+    (&'static str, String),
+    PausedTarget
+  >,
+  pub requests:
+    BTreeMap<i64, FetchRequest>,
+  pub leases: HashMap<String, Lease>,
+  /// Keyed by domain and slot.
+  pub tokens:
+    BTreeMap<(String, usize), Lease>,
+  last_ids: HashMap<&'static str, i64>
+}
+
+pub(super) struct HistoryRow {
+  pub id:             i64,
+  pub recorded_at_ms: i64,
+  pub row:            StateRow
+}
+
+pub(super) struct EventRow {
+  pub feed_id: String,
+  pub event:   FetchEvent
+}
+
+pub(super) struct PayloadRow {
+  pub id:            i64,
+  pub feed_id:       String,
+  pub fetched_at_ms: i64,
+  pub content_hash:  Option<String>
+}
+
+pub(super) struct ItemRow {
+  pub payload_id: i64,
+  pub item:       FeedItem
+}
+
+pub(super) struct ArchiveRow {
+  pub payload:         ArchivedPayload,
+  pub last_seen_at_ms: i64
+}
+
+/// A feed lease or domain token.
+pub(super) struct Lease {
+  pub holder:        String,
+  pub expires_at_ms: i64
+}
+
+/// A dataset version; its body lives
+/// in `blobs`.
+pub(super) struct DatasetRow {
+  pub id:      i64,
+  pub version: DatasetVersion
+}
+
+impl Store {
+  /// Next id of an autoincrement
+  /// column, starting at 1 per table.
+  pub fn next_id(
+    &mut self,
+    table: &'static str
+  ) -> i64 {
+    let id = self
+      .last_ids
+      .entry(table)
+      .or_default();
+
+    *id += 1;
+
+    *id
+  }
+
+  pub fn feed(
+    &self,
+    feed_id: &str
+  ) -> Option<&FeedConfig> {
+    self
+      .feed_index
+      .get(feed_id)
+      .map(|&i| &self.feeds[i])
+  }
+}
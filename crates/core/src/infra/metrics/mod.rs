@@ -27,6 +27,7 @@ use histogram::{
 
 use crate::domain::model::{
   CategoryConfig,
+  FeedPriority,
   MetricsConfig
 };
 
@@ -47,6 +48,10 @@ pub struct Metrics {
     Mutex<HashMap<String, u64>>,
   due_feeds_current_by_category:
     Mutex<HashMap<String, u64>>,
+  category_restarts:
+    Mutex<HashMap<String, u64>>,
+  queue_lag_ms:
+    Mutex<HashMap<String, u64>>,
   head_ok: AtomicU64,
   head_err: AtomicU64,
   get_ok: AtomicU64,
@@ -71,12 +76,15 @@ impl Metrics {
     let mut due = HashMap::new();
     let mut due_current =
       HashMap::new();
+    let mut restarts = HashMap::new();
 
     for c in categories {
       ticks.insert(c.name.clone(), 0);
       due.insert(c.name.clone(), 0);
       due_current
         .insert(c.name.clone(), 0);
+      restarts
+        .insert(c.name.clone(), 0);
     }
 
     let start_time_seconds =
@@ -95,6 +103,12 @@ impl Metrics {
       ),
       due_feeds_current_by_category:
         Mutex::new(due_current),
+      category_restarts: Mutex::new(
+        restarts
+      ),
+      queue_lag_ms: Mutex::new(
+        HashMap::new()
+      ),
       head_ok: AtomicU64::new(0),
       head_err: AtomicU64::new(0),
       get_ok: AtomicU64::new(0),
@@ -141,6 +155,12 @@ pub async fn init(
     .await
 }
 
+/// Closes the metrics listener during
+/// shutdown.
+pub fn shutdown() {
+  server::stop();
+}
+
 pub fn record_tick(
   category: &str,
   due_count: u64
@@ -177,6 +197,49 @@ pub fn record_tick(
   }
 }
 
+pub fn record_category_restart(
+  category: &str
+) {
+  let Some(metrics) = METRICS.get()
+  else {
+    return;
+  };
+
+  if let Ok(mut restarts) =
+    metrics.category_restarts.lock()
+  {
+    *restarts
+      .entry(category.to_string())
+      .or_insert(0) += 1;
+  }
+}
+
+/// How long the oldest still-due feed
+/// of each priority class has waited.
+pub fn record_queue_lag(
+  category: &str,
+  lag_ms: &[(FeedPriority, i64)]
+) {
+  let Some(metrics) = METRICS.get()
+  else {
+    return;
+  };
+
+  if let Ok(mut lags) =
+    metrics.queue_lag_ms.lock()
+  {
+    for (priority, ms) in lag_ms {
+      lags.insert(
+        format!(
+          "{category}|{}",
+          priority.as_str()
+        ),
+        (*ms).max(0) as u64
+      );
+    }
+  }
+}
+
 pub fn record_http_result(
   action: &str,
   domain: &str,
@@ -112,6 +112,53 @@ pub(super) fn render(
     ));
   }
 
+  let restarts = metrics
+    .category_restarts
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+
+  out.push_str(
+    "# HELP pulsewire_category_restarts_total Supervisor restarts per category.\n",
+  );
+
+  out.push_str(
+    "# TYPE pulsewire_category_restarts_total counter\n",
+  );
+
+  for (category, count) in
+    sorted_map(&restarts)
+  {
+    out.push_str(&format!(
+      "pulsewire_category_restarts_total{{category=\"{}\"}} {}\n",
+      escape_label(&category),
+      count
+    ));
+  }
+
+  let queue_lag = metrics
+    .queue_lag_ms
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+
+  out.push_str(
+    "# HELP pulsewire_queue_lag_seconds Wait of the oldest due feed per category and priority.\n",
+  );
+
+  out.push_str(
+    "# TYPE pulsewire_queue_lag_seconds gauge\n",
+  );
+
+  for ((category, priority), ms) in
+    sorted_kv_map(&queue_lag)
+  {
+    out.push_str(&format!(
+      "pulsewire_queue_lag_seconds{{category=\"{}\",priority=\"{}\"}} {:.3}\n",
+      escape_label(&category),
+      escape_label(&priority),
+      ms as f64 / 1000.0
+    ));
+  }
+
   out.push_str(
     "# HELP pulsewire_inflight_actions \
      In-flight feed actions.\n"
@@ -1,14 +1,33 @@
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{
+  Arc,
+  OnceLock
+};
 
 use tokio::io::{
   AsyncReadExt,
   AsyncWriteExt
 };
 use tokio::net::TcpListener;
+use tokio::task::AbortHandle;
 
 use super::Metrics;
 
+static ACCEPT_LOOP: OnceLock<
+  AbortHandle
+> = OnceLock::new();
+
+/// Stops accepting scrapes; responses
+/// already being written finish on
+/// their own tasks.
+pub(super) fn stop() {
+  if let Some(handle) =
+    ACCEPT_LOOP.get()
+  {
+    handle.abort();
+  }
+}
+
 pub(super) async fn spawn(
   bind: &str,
   metrics: Arc<Metrics>
@@ -33,44 +52,47 @@ pub(super) async fn spawn(
         )
       })?;
 
-  tokio::spawn(async move {
-    loop {
-      let (mut stream, _) =
-        match listener.accept().await {
-          | Ok(pair) => pair,
-          | Err(_) => continue
-        };
-
-      let metrics = metrics.clone();
-
-      tokio::spawn(async move {
-        let mut buf = [0u8; 8192];
-
-        let n = match stream
-          .read(&mut buf)
-          .await
-        {
-          | Ok(n) => n,
-          | Err(_) => return
-        };
-
-        let req =
-          String::from_utf8_lossy(
-            &buf[..n]
-          );
+  let accept =
+    tokio::spawn(async move {
+      loop {
+        let (mut stream, _) =
+          match listener.accept().await
+          {
+            | Ok(pair) => pair,
+            | Err(_) => continue
+          };
+
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+          let mut buf = [0u8; 8192];
 
-        let path = req
-          .lines()
-          .next()
-          .and_then(|line| {
-            line
-              .split_whitespace()
-              .nth(1)
-          })
-          .unwrap_or("/");
-
-        let (status, body) =
-          if path == "/metrics" {
+          let n = match stream
+            .read(&mut buf)
+            .await
+          {
+            | Ok(n) => n,
+            | Err(_) => return
+          };
+
+          let req =
+            String::from_utf8_lossy(
+              &buf[..n]
+            );
+
+          let path = req
+            .lines()
+            .next()
+            .and_then(|line| {
+              line
+                .split_whitespace()
+                .nth(1)
+            })
+            .unwrap_or("/");
+
+          let (status, body) = if path
+            == "/metrics"
+          {
             ("200 OK", metrics.render())
           } else {
             (
@@ -79,23 +101,26 @@ pub(super) async fn spawn(
             )
           };
 
-        let resp = format!(
-          "HTTP/1.1 {status}\r\\
+          let resp = format!(
+            "HTTP/1.1 {status}\r\\
            nContent-Type: text/plain; \
-           version=0.0.4\r\\
+             version=0.0.4\r\\
            nContent-Length: \
-           {}\r\nConnection: \
-           close\r\n\r\n{}",
-          body.len(),
-          body
-        );
-
-        let _ = stream
-          .write_all(resp.as_bytes())
-          .await;
-      });
-    }
-  });
+             {}\r\nConnection: \
+             close\r\n\r\n{}",
+            body.len(),
+            body
+          );
+
+          let _ = stream
+            .write_all(resp.as_bytes())
+            .await;
+        });
+      }
+    });
+
+  let _ = ACCEPT_LOOP
+    .set(accept.abort_handle());
 
   Ok(())
 }
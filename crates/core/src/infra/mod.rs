@@ -1,10 +1,17 @@
 //! Infrastructure adapters: config
 //! loading, logging setup, HTTP client,
-//! SQLite repo, time, randomness.
+//! SQLite and in-memory repos, time,
+//! randomness, and the metrics and
+//! admin endpoints.
 
+pub mod admin;
+pub mod backup;
 pub mod config;
 pub mod database;
+pub mod db_copy;
+pub mod instance_lock;
 pub mod logging;
+pub mod memory_repo;
 pub mod metrics;
 pub mod postgres_repo;
 pub mod random;
@@ -0,0 +1,151 @@
+//! Raw payload archive (Postgres):
+//! compressed response bodies
+//! deduplicated by content hash.
+
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use tracing::debug;
+
+use super::util::ts_from_ms;
+use crate::ports::repo::ArchivedPayload;
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct ArchivedPayloadRow {
+  content_hash: String,
+  encoding:     String,
+  body:         Option<Vec<u8>>,
+  location:     Option<String>,
+  raw_size:     i64,
+  stored_size:  i64
+}
+
+impl From<ArchivedPayloadRow>
+  for ArchivedPayload
+{
+  fn from(
+    r: ArchivedPayloadRow
+  ) -> Self {
+    Self {
+      content_hash: r.content_hash,
+      encoding:     r.encoding,
+      body:         r.body,
+      location:     r.location,
+      raw_size:     r.raw_size,
+      stored_size:  r.stored_size
+    }
+  }
+}
+
+pub async fn touch_archived_payload(
+  pool: &PgPool,
+  content_hash: &str,
+  seen_at_ms: i64,
+  zone: &Tz
+) -> Result<bool, String> {
+  let res = sqlx::query(
+        r#"
+      UPDATE payload_archive
+      SET last_seen_at = GREATEST(last_seen_at, $2)
+      WHERE content_hash = $1
+      "#,
+    )
+    .bind(content_hash)
+    .bind(ts_from_ms(seen_at_ms, zone))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("touch archived payload: {e}"))?;
+
+  Ok(res.rows_affected() > 0)
+}
+
+pub async fn insert_archived_payload(
+  pool: &PgPool,
+  payload: &ArchivedPayload,
+  seen_at_ms: i64,
+  zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+        r#"
+      INSERT INTO payload_archive(
+        content_hash, encoding, body, location,
+        raw_size, stored_size, first_seen_at, last_seen_at
+      ) VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+      ON CONFLICT (content_hash) DO UPDATE SET
+        last_seen_at = GREATEST(payload_archive.last_seen_at, EXCLUDED.last_seen_at)
+      "#,
+    )
+    .bind(&payload.content_hash)
+    .bind(&payload.encoding)
+    .bind(payload.body.as_deref())
+    .bind(payload.location.as_deref())
+    .bind(payload.raw_size)
+    .bind(payload.stored_size)
+    .bind(ts_from_ms(seen_at_ms, zone))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("insert archived payload: {e}"))?;
+
+  debug!(
+    content_hash = %payload.content_hash,
+    stored_size = payload.stored_size,
+    "Archived payload"
+  );
+
+  Ok(())
+}
+
+pub async fn archived_payload(
+  pool: &PgPool,
+  content_hash: &str
+) -> Result<
+  Option<ArchivedPayload>,
+  String
+> {
+  let row = sqlx::query_as::<_, ArchivedPayloadRow>(
+        r#"
+      SELECT content_hash, encoding, body, location, raw_size, stored_size
+      FROM payload_archive
+      WHERE content_hash = $1
+      "#,
+    )
+    .bind(content_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("archived payload: {e}"))?;
+
+  Ok(row.map(ArchivedPayload::from))
+}
+
+pub async fn prune_archived_payloads(
+  pool: &PgPool,
+  seen_before_ms: i64,
+  limit: i64,
+  zone: &Tz
+) -> Result<Vec<ArchivedPayload>, String>
+{
+  let rows = sqlx::query_as::<_, ArchivedPayloadRow>(
+        r#"
+      DELETE FROM payload_archive
+      WHERE content_hash IN (
+        SELECT content_hash FROM payload_archive
+        WHERE last_seen_at < $1
+        ORDER BY last_seen_at
+        LIMIT $2
+      )
+      RETURNING content_hash, encoding, NULL::BYTEA AS body, location, raw_size, stored_size
+      "#,
+    )
+    .bind(ts_from_ms(seen_before_ms, zone))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("prune archived payloads: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(ArchivedPayload::from)
+      .collect()
+  )
+}
@@ -0,0 +1,240 @@
+//! Operator pauses and the admin API's
+//! read-only views (Postgres).
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use chrono_tz::Tz;
+
+use super::PostgresRepo;
+use super::models::DueFeedRow;
+use super::util::ts_from_ms;
+use crate::domain::model::FeedConfig;
+use crate::ports::repo::{
+  ControlRepo,
+  ErrorFeed,
+  FetchEvent,
+  PauseScope,
+  PausedTarget
+};
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct PausedRow {
+  scope:     String,
+  name:      String,
+  reason:    Option<String>,
+  paused_at: DateTime<Utc>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct EventRow {
+  id: i64,
+  event_time: DateTime<Utc>,
+  method: String,
+  status: Option<i64>,
+  error_kind: Option<String>,
+  latency_ms: Option<i64>,
+  backoff_index: i64,
+  scheduled_next_action_at:
+    DateTime<Utc>,
+  debug: Option<String>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct ErrorFeedRow {
+  feed_id:           String,
+  error_count:       i64,
+  last_error_kind:   Option<String>,
+  last_error_status: Option<i64>,
+  last_error_at:     DateTime<Utc>,
+  note:              Option<String>
+}
+
+#[async_trait::async_trait]
+
+impl ControlRepo for PostgresRepo {
+  async fn feed(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<FeedConfig>, String>
+  {
+    let row = sqlx::query_as::<_, DueFeedRow>(
+            r#"
+        SELECT id, url, domain, category, base_poll_seconds, dataset, priority,
+               NULL::timestamptz AS next_action_at
+        FROM feeds
+        WHERE id = $1
+        "#,
+        )
+        .bind(feed_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("feed error: {e}"))?;
+
+    Ok(row.map(FeedConfig::from))
+  }
+
+  async fn pause_target(
+    &self,
+    target: &PausedTarget,
+    zone: &Tz
+  ) -> Result<(), String> {
+    sqlx::query(
+            r#"
+        INSERT INTO paused_targets(scope, name, reason, paused_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT(scope, name) DO UPDATE SET
+          reason = EXCLUDED.reason,
+          paused_at = EXCLUDED.paused_at
+        "#,
+        )
+        .bind(target.scope.as_str())
+        .bind(&target.name)
+        .bind(&target.reason)
+        .bind(ts_from_ms(target.paused_at_ms, zone))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("pause_target error: {e}"))?;
+
+    Ok(())
+  }
+
+  async fn resume_target(
+    &self,
+    scope: PauseScope,
+    name: &str
+  ) -> Result<bool, String> {
+    let result = sqlx::query(
+            r#"DELETE FROM paused_targets WHERE scope = $1 AND name = $2"#,
+        )
+        .bind(scope.as_str())
+        .bind(name)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("resume_target error: {e}"))?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  async fn paused_targets(
+    &self
+  ) -> Result<Vec<PausedTarget>, String>
+  {
+    let rows = sqlx::query_as::<_, PausedRow>(
+            r#"
+        SELECT scope, name, reason, paused_at
+        FROM paused_targets
+        ORDER BY scope, name
+        "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("paused_targets error: {e}"))?;
+
+    rows
+      .into_iter()
+      .map(|r| {
+        Ok(PausedTarget {
+          scope:        PauseScope::parse(
+            &r.scope
+          )?,
+          name:         r.name,
+          reason:       r.reason,
+          paused_at_ms: r
+            .paused_at
+            .timestamp_millis()
+        })
+      })
+      .collect()
+  }
+
+  async fn recent_fetch_events(
+    &self,
+    feed_id: &str,
+    limit: usize
+  ) -> Result<Vec<FetchEvent>, String>
+  {
+    let rows = sqlx::query_as::<_, EventRow>(
+            r#"
+        SELECT id, event_time, method, status, error_kind, latency_ms,
+               backoff_index, scheduled_next_action_at, debug
+        FROM fetch_events
+        WHERE feed_id = $1
+        ORDER BY id DESC
+        LIMIT $2
+        "#,
+        )
+        .bind(feed_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("recent_fetch_events error: {e}"))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|r| {
+          FetchEvent {
+            id: r.id,
+            event_time_ms: r
+              .event_time
+              .timestamp_millis(),
+            method: r.method,
+            status: r.status,
+            error_kind: r.error_kind,
+            latency_ms: r.latency_ms,
+            backoff_index: r
+              .backoff_index,
+            scheduled_next_action_at_ms:
+              r.scheduled_next_action_at
+                .timestamp_millis(),
+            debug: r.debug
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeed>, String>
+  {
+    let rows = sqlx::query_as::<_, ErrorFeedRow>(
+            r#"
+        SELECT feed_id, error_count, last_error_kind, last_error_status,
+               last_error_at, note
+        FROM error_feeds
+        ORDER BY last_error_at DESC
+        "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("error_feeds error: {e}"))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|r| {
+          ErrorFeed {
+            feed_id:           r
+              .feed_id,
+            error_count:       r
+              .error_count,
+            last_error_kind:   r
+              .last_error_kind,
+            last_error_status: r
+              .last_error_status,
+            last_error_at_ms:  r
+              .last_error_at
+              .timestamp_millis(),
+            note:              r.note
+          }
+        })
+        .collect()
+    )
+  }
+}
@@ -0,0 +1,146 @@
+//! Structured dataset versions
+//! (Postgres): bodies are stored once
+//! per content hash and each fetch
+//! records a version row, together
+//! with the payload holding its row
+//! changes.
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use tracing::debug;
+
+use super::payloads;
+use super::util::{
+  now_epoch_ms,
+  ts_from_ms
+};
+use crate::feed::parser::ParsedFeed;
+use crate::ports::repo::DatasetVersion;
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct DatasetVersionRow {
+  feed_id:       String,
+  fetched_at:    DateTime<Utc>,
+  content_hash:  String,
+  body:          Vec<u8>,
+  row_count:     i64,
+  added_count:   i64,
+  removed_count: i64,
+  changed_count: i64
+}
+
+pub async fn latest_dataset_version(
+  pool: &PgPool,
+  feed_id: &str
+) -> Result<
+  Option<DatasetVersion>,
+  String
+> {
+  let row = sqlx::query_as::<_, DatasetVersionRow>(
+        r#"
+      SELECT v.feed_id, v.fetched_at, v.content_hash, b.body,
+             v.row_count, v.added_count, v.removed_count, v.changed_count
+      FROM dataset_versions v
+      JOIN dataset_blobs b ON b.content_hash = v.content_hash
+      WHERE v.feed_id = $1
+      ORDER BY v.fetched_at DESC, v.id DESC
+      LIMIT 1
+      "#,
+    )
+    .bind(feed_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("latest_dataset_version error: {e}"))?;
+
+  Ok(row.map(|r| {
+    DatasetVersion {
+      feed_id:       r.feed_id,
+      fetched_at_ms: r
+        .fetched_at
+        .timestamp_millis(),
+      content_hash:  r.content_hash,
+      body:          r.body,
+      row_count:     r.row_count,
+      added_count:   r.added_count,
+      removed_count: r.removed_count,
+      changed_count: r.changed_count
+    }
+  }))
+}
+
+pub async fn insert_dataset_version(
+  pool: &PgPool,
+  version: &DatasetVersion,
+  etag: Option<&str>,
+  last_modified_ms: Option<i64>,
+  items: &ParsedFeed,
+  zone: &Tz
+) -> Result<(), String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  sqlx::query(
+        r#"
+      INSERT INTO dataset_blobs(content_hash, body, size_bytes, created_at)
+      VALUES ($1, $2, $3, $4)
+      ON CONFLICT (content_hash) DO NOTHING
+      "#,
+    )
+    .bind(&version.content_hash)
+    .bind(&version.body)
+    .bind(version.body.len() as i64)
+    .bind(ts_from_ms(now_epoch_ms(), zone))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("insert dataset blob: {e}"))?;
+
+  sqlx::query(
+        r#"
+      INSERT INTO dataset_versions(
+        feed_id, fetched_at, content_hash,
+        row_count, added_count, removed_count, changed_count
+      ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+    )
+    .bind(&version.feed_id)
+    .bind(ts_from_ms(version.fetched_at_ms, zone))
+    .bind(&version.content_hash)
+    .bind(version.row_count)
+    .bind(version.added_count)
+    .bind(version.removed_count)
+    .bind(version.changed_count)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("insert dataset version: {e}"))?;
+
+  payloads::insert_payload_with_items(
+    &mut tx,
+    &version.feed_id,
+    version.fetched_at_ms,
+    etag,
+    last_modified_ms,
+    Some(&version.content_hash),
+    items,
+    zone
+  )
+  .await?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  debug!(
+    feed_id = %version.feed_id,
+    content_hash = %version.content_hash,
+    "Inserted dataset version"
+  );
+
+  Ok(())
+}
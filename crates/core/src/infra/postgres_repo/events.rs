@@ -3,13 +3,19 @@
 //! Postgres.
 
 use chrono_tz::Tz;
-use sqlx::PgPool;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{
+  PgPool,
+  Postgres
+};
 
 use super::util::{
   now_epoch_ms,
   ts_from_ms
 };
 use crate::domain::model::ErrorKind;
+use crate::ports::repo::PendingEvent;
 
 pub async fn insert_event(
   pool: &PgPool,
@@ -23,14 +29,40 @@ pub async fn insert_event(
   debug: Option<&str>,
   zone: &Tz
 ) -> Result<(), String> {
-  let now_ms = now_epoch_ms();
+  let event = PendingEvent {
+    feed_id: feed_id.to_string(),
+    event_time_ms: now_epoch_ms(),
+    method: method.to_string(),
+    status,
+    error_kind,
+    latency_ms,
+    backoff_index,
+    scheduled_next_action_at_ms,
+    debug: debug.map(str::to_string)
+  };
+
+  event_query(&event, zone)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!("insert_event error: {e}")
+    })?;
+
+  Ok(())
+}
 
-  let event_time =
-    ts_from_ms(now_ms, zone);
+pub fn event_query<'q>(
+  event: &'q PendingEvent,
+  zone: &Tz
+) -> Query<'q, Postgres, PgArguments> {
+  let event_time = ts_from_ms(
+    event.event_time_ms,
+    zone
+  );
 
   let scheduled_next_action_at =
     ts_from_ms(
-      scheduled_next_action_at_ms,
+      event.scheduled_next_action_at_ms,
       zone
     );
 
@@ -47,18 +79,13 @@ pub async fn insert_event(
       )
       "#,
     )
-    .bind(feed_id)
+    .bind(&event.feed_id)
     .bind(event_time)
-    .bind(method)
-    .bind(status)
-    .bind(error_kind.map(|e| format!("{:?}", e)))
-    .bind(latency_ms)
-    .bind(backoff_index)
+    .bind(&event.method)
+    .bind(event.status)
+    .bind(event.error_kind.map(|e| format!("{:?}", e)))
+    .bind(event.latency_ms)
+    .bind(event.backoff_index)
     .bind(scheduled_next_action_at)
-    .bind(debug.map(|s| s.to_string()))
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_event error: {e}"))?;
-
-  Ok(())
+    .bind(&event.debug)
 }
@@ -1,5 +1,5 @@
 //! Feed definition persistence: bulk
-//! upsert and due-feed selection for
+//! upsert and scheduling snapshot for
 //! Postgres.
 
 use std::time::Instant;
@@ -9,8 +9,12 @@ use sqlx::PgPool;
 use tracing::info;
 
 use super::models::DueFeedRow;
-use super::util::now_epoch_ms;
+use super::util::{
+  ms_from_ts,
+  now_epoch_ms
+};
 use crate::domain::model::FeedConfig;
+use crate::ports::repo::ScheduledFeed;
 
 pub async fn upsert_feeds_bulk(
   pool: &PgPool,
@@ -94,16 +98,25 @@ async fn upsert_chunk(
   );
 
   for f in feeds {
+    let dataset_json = f
+      .dataset
+      .as_ref()
+      .and_then(|ds| {
+        serde_json::to_string(ds).ok()
+      });
+
     sqlx::query(
             r#"
-        INSERT INTO feeds(id, url, domain, category, base_poll_seconds, tags, created_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO feeds(id, url, domain, category, base_poll_seconds, tags, dataset, priority, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         ON CONFLICT (id) DO UPDATE SET
           url = EXCLUDED.url,
           domain = EXCLUDED.domain,
           category = EXCLUDED.category,
           base_poll_seconds = EXCLUDED.base_poll_seconds,
-          tags = EXCLUDED.tags
+          tags = EXCLUDED.tags,
+          dataset = EXCLUDED.dataset,
+          priority = EXCLUDED.priority
         "#,
         )
         .bind(&f.id)
@@ -112,6 +125,8 @@ async fn upsert_chunk(
         .bind(&f.category)
         .bind(f.base_poll_seconds as i64)
         .bind(f.tags.clone())
+        .bind(dataset_json)
+        .bind(f.priority.rank())
         .bind(now_ts)
         .execute(&mut *tx)
         .await
@@ -132,52 +147,50 @@ async fn upsert_chunk(
   Ok(())
 }
 
-pub async fn due_feeds(
+pub async fn scheduled_feeds(
   pool: &PgPool,
   category: &str,
-  now_ms: i64,
-  limit: i64,
-  zone: &Tz
-) -> Result<Vec<FeedConfig>, String> {
+  _zone: &Tz
+) -> Result<Vec<ScheduledFeed>, String>
+{
   let start = Instant::now();
 
-  let now_ts = super::util::ts_from_ms(
-    now_ms, zone
-  );
-
   let rows = sqlx::query_as::<_, DueFeedRow>(
         r#"
-      SELECT f.id, f.url, f.domain, f.category, f.base_poll_seconds
+      SELECT f.id, f.url, f.domain, f.category, f.base_poll_seconds, f.dataset,
+             f.priority,
+             s.next_action_at
       FROM feeds f
       LEFT JOIN feed_state_current s ON s.feed_id = f.id
       LEFT JOIN error_feeds e ON e.feed_id = f.id
       WHERE f.category = $1
         AND e.feed_id IS NULL
-        AND (s.feed_id IS NULL OR s.next_action_at <= $2)
-      ORDER BY COALESCE(s.next_action_at, $2)
-      LIMIT $3
       "#,
     )
     .bind(category)
-    .bind(now_ts)
-    .bind(limit)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("due_feeds error: {e}"))?;
-
-  let elapsed = start.elapsed();
+    .map_err(|e| format!("scheduled_feeds error: {e}"))?;
 
   let feeds = rows
     .into_iter()
-    .map(FeedConfig::from)
+    .map(|row| {
+      let next_action_at_ms =
+        ms_from_ts(row.next_action_at);
+
+      ScheduledFeed {
+        feed: FeedConfig::from(row),
+        next_action_at_ms
+      }
+    })
     .collect::<Vec<_>>();
 
   info!(
     category,
-    limit,
-    due = feeds.len(),
-    elapsed_ms = elapsed.as_millis(),
-    "due_feeds query"
+    feeds = feeds.len(),
+    elapsed_ms =
+      start.elapsed().as_millis(),
+    "scheduled_feeds query"
   );
 
   Ok(feeds)
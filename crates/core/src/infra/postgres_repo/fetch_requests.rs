@@ -0,0 +1,280 @@
+//! On-demand refresh commands
+//! (Postgres). Claims use `FOR UPDATE
+//! SKIP LOCKED`, so each request runs
+//! on exactly one fetcher.
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+use super::models::DueFeedRow;
+use super::util::{
+  ms_from_ts,
+  ts_from_ms
+};
+use crate::domain::model::FeedConfig;
+use crate::ports::repo::{
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus
+};
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct FetchRequestRow {
+  id:           i64,
+  feed_id:      String,
+  requested_by: Option<String>,
+  requested_at: DateTime<Utc>,
+  status:       String,
+  started_at:   Option<DateTime<Utc>>,
+  finished_at:  Option<DateTime<Utc>>,
+  http_status:  Option<i32>,
+  error:        Option<String>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct ClaimRow {
+  request_id:        i64,
+  id:                String,
+  url:               String,
+  domain:            String,
+  category:          String,
+  base_poll_seconds: i64,
+  dataset:           Option<String>,
+  priority:          i64
+}
+
+pub async fn enqueue_fetch_request(
+  pool: &PgPool,
+  feed_id: &str,
+  requested_by: Option<&str>,
+  requested_at_ms: i64,
+  zone: &Tz
+) -> Result<i64, String> {
+  let known: Option<String> = sqlx::query_scalar(
+        r#"SELECT id FROM feeds WHERE id = $1"#,
+    )
+    .bind(feed_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("enqueue_fetch_request error: {e}"))?;
+
+  if known.is_none() {
+    return Err(format!(
+      "unknown feed '{feed_id}'"
+    ));
+  }
+
+  let pending: Option<i64> = sqlx::query_scalar(
+        r#"
+      SELECT id FROM fetch_requests
+      WHERE feed_id = $1 AND status = 'pending'
+      ORDER BY id
+      LIMIT 1
+      "#,
+    )
+    .bind(feed_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("enqueue_fetch_request error: {e}"))?;
+
+  if let Some(id) = pending {
+    return Ok(id);
+  }
+
+  sqlx::query_scalar(
+        r#"
+      INSERT INTO fetch_requests(feed_id, requested_by, requested_at, status)
+      VALUES ($1, $2, $3, 'pending')
+      RETURNING id
+      "#,
+    )
+    .bind(feed_id)
+    .bind(requested_by)
+    .bind(ts_from_ms(requested_at_ms, zone))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("enqueue_fetch_request error: {e}"))
+}
+
+pub async fn fetch_request(
+  pool: &PgPool,
+  id: i64
+) -> Result<Option<FetchRequest>, String>
+{
+  let row = sqlx::query_as::<_, FetchRequestRow>(
+        r#"
+      SELECT id, feed_id, requested_by, requested_at, status,
+             started_at, finished_at, http_status, error
+      FROM fetch_requests
+      WHERE id = $1
+      "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("fetch_request error: {e}"))?;
+
+  row
+    .map(|r| {
+      Ok(FetchRequest {
+        id:              r.id,
+        feed_id:         r.feed_id,
+        requested_by:    r.requested_by,
+        requested_at_ms: r
+          .requested_at
+          .timestamp_millis(),
+        status:
+          FetchRequestStatus::parse(
+            &r.status
+          )?,
+        started_at_ms:   ms_from_ts(
+          r.started_at
+        ),
+        finished_at_ms:  ms_from_ts(
+          r.finished_at
+        ),
+        http_status:     r
+          .http_status
+          .map(i64::from),
+        error:           r.error
+      })
+    })
+    .transpose()
+}
+
+/// Only the oldest claimable request
+/// per feed is taken; later ones stay
+/// pending for the next round.
+pub async fn claim_fetch_requests(
+  pool: &PgPool,
+  category: &str,
+  busy_feed_ids: &[String],
+  limit: usize,
+  now_ms: i64,
+  stale_before_ms: i64,
+  zone: &Tz
+) -> Result<Vec<ClaimedFetch>, String> {
+  if limit == 0 {
+    return Ok(Vec::new());
+  }
+
+  let rows = sqlx::query_as::<_, ClaimRow>(
+        r#"
+      WITH candidates AS (
+        SELECT r.id, r.feed_id
+        FROM fetch_requests r
+        JOIN feeds f ON f.id = r.feed_id
+        WHERE f.category = $1
+          AND NOT (r.feed_id = ANY($2))
+          AND (r.status = 'pending'
+               OR (r.status = 'running' AND r.started_at < $5))
+        ORDER BY f.priority, r.id
+        LIMIT $3
+        FOR UPDATE OF r SKIP LOCKED
+      ),
+      firsts AS (
+        SELECT min(id) AS id FROM candidates GROUP BY feed_id
+      )
+      UPDATE fetch_requests q
+      SET status = 'running', started_at = $4
+      FROM firsts c, feeds f
+      WHERE q.id = c.id AND f.id = q.feed_id
+      RETURNING q.id AS request_id, f.id, f.url, f.domain, f.category,
+                f.base_poll_seconds, f.dataset, f.priority
+      "#,
+    )
+    .bind(category)
+    .bind(busy_feed_ids)
+    .bind(limit as i64)
+    .bind(ts_from_ms(now_ms, zone))
+    .bind(ts_from_ms(stale_before_ms, zone))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("claim_fetch_requests error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| {
+        ClaimedFetch {
+          request_id: row.request_id,
+          feed:       FeedConfig::from(
+            DueFeedRow {
+              id:                row.id,
+              url:               row
+                .url,
+              domain:            row
+                .domain,
+              category:          row
+                .category,
+              base_poll_seconds: row
+                .base_poll_seconds,
+              dataset:           row
+                .dataset,
+              priority:          row
+                .priority,
+              next_action_at:    None
+            }
+          )
+        }
+      })
+      .collect()
+  )
+}
+
+pub async fn requeue_fetch_requests(
+  pool: &PgPool,
+  ids: &[i64]
+) -> Result<(), String> {
+  if ids.is_empty() {
+    return Ok(());
+  }
+
+  sqlx::query(
+        r#"
+      UPDATE fetch_requests
+      SET status = 'pending', started_at = NULL
+      WHERE id = ANY($1) AND status = 'running'
+      "#,
+    )
+    .bind(ids)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("requeue_fetch_requests error: {e}"))?;
+
+  Ok(())
+}
+
+pub async fn finish_fetch_request(
+  pool: &PgPool,
+  id: i64,
+  status: FetchRequestStatus,
+  http_status: Option<i64>,
+  error: Option<&str>,
+  finished_at_ms: i64,
+  zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+        r#"
+      UPDATE fetch_requests
+      SET status = $2, http_status = $3, error = $4, finished_at = $5
+      WHERE id = $1
+      "#,
+    )
+    .bind(id)
+    .bind(status.as_str())
+    .bind(http_status.map(|s| s as i32))
+    .bind(error)
+    .bind(ts_from_ms(finished_at_ms, zone))
+    .execute(pool)
+    .await
+    .map_err(|e| format!("finish_fetch_request error: {e}"))?;
+
+  Ok(())
+}
@@ -0,0 +1,269 @@
+//! Inputs for and storage of daily
+//! feed/domain health rollups
+//! (Postgres).
+
+use std::collections::BTreeMap;
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use tracing::debug;
+
+use super::util::ts_from_ms;
+use crate::ports::repo::{
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup
+};
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct HealthEventRow {
+  feed_id:    String,
+  domain:     String,
+  event_time: DateTime<Utc>,
+  error_kind: Option<String>,
+  latency_ms: Option<i64>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct HealthPayloadRow {
+  feed_id:      String,
+  domain:       String,
+  fetched_at:   DateTime<Utc>,
+  content_hash: Option<String>,
+  prev_hash:    Option<String>,
+  item_count:   i64
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct HealthRollupRow {
+  day:            String,
+  subject:        String,
+  fetches:        i64,
+  successes:      i64,
+  error_counts:   String,
+  p50_latency_ms: Option<i64>,
+  p95_latency_ms: Option<i64>,
+  changes:        i64,
+  items:          i64
+}
+
+pub async fn health_events(
+  pool: &PgPool,
+  start_ms: i64,
+  end_ms: i64,
+  zone: &Tz
+) -> Result<Vec<HealthEvent>, String> {
+  let rows = sqlx::query_as::<_, HealthEventRow>(
+        r#"
+      SELECT e.feed_id, f.domain, e.event_time,
+             e.error_kind, e.latency_ms
+      FROM fetch_events e
+      JOIN feeds f ON f.id = e.feed_id
+      WHERE e.event_time >= $1 AND e.event_time < $2
+      "#,
+    )
+    .bind(ts_from_ms(start_ms, zone))
+    .bind(ts_from_ms(end_ms, zone))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("health events: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        HealthEvent {
+          feed_id:       r.feed_id,
+          domain:        r.domain,
+          event_time_ms: r
+            .event_time
+            .timestamp_millis(),
+          error_kind:    r.error_kind,
+          latency_ms:    r.latency_ms
+        }
+      })
+      .collect()
+  )
+}
+
+pub async fn health_payloads(
+  pool: &PgPool,
+  start_ms: i64,
+  end_ms: i64,
+  zone: &Tz
+) -> Result<Vec<HealthPayload>, String>
+{
+  let rows = sqlx::query_as::<_, HealthPayloadRow>(
+        r#"
+      SELECT p.feed_id, f.domain, p.fetched_at, p.content_hash,
+             (SELECT q.content_hash FROM feed_payloads q
+              WHERE q.feed_id = p.feed_id
+                AND (q.fetched_at < p.fetched_at
+                     OR (q.fetched_at = p.fetched_at AND q.id < p.id))
+              ORDER BY q.fetched_at DESC, q.id DESC
+              LIMIT 1) AS prev_hash,
+             (SELECT COUNT(*) FROM feed_items i
+              WHERE i.payload_id = p.id) AS item_count
+      FROM feed_payloads p
+      JOIN feeds f ON f.id = p.feed_id
+      WHERE p.fetched_at >= $1 AND p.fetched_at < $2
+      "#,
+    )
+    .bind(ts_from_ms(start_ms, zone))
+    .bind(ts_from_ms(end_ms, zone))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("health payloads: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        HealthPayload {
+          changed:       r.content_hash
+            != r.prev_hash,
+          feed_id:       r.feed_id,
+          domain:        r.domain,
+          fetched_at_ms: r
+            .fetched_at
+            .timestamp_millis(),
+          item_count:    r.item_count
+        }
+      })
+      .collect()
+  )
+}
+
+pub async fn replace_health_rollups(
+  pool: &PgPool,
+  day: &str,
+  rows: &[HealthRollup],
+  now_ms: i64,
+  zone: &Tz
+) -> Result<(), String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  sqlx::query(
+    "DELETE FROM feed_health_daily \
+     WHERE day = $1::date"
+  )
+  .bind(day)
+  .execute(&mut *tx)
+  .await
+  .map_err(|e| {
+    format!("clear health day: {e}")
+  })?;
+
+  for r in rows {
+    let errors = serde_json::to_string(
+      &r.error_counts
+    )
+    .map_err(|e| {
+      format!(
+        "encode error counts: {e}"
+      )
+    })?;
+
+    sqlx::query(
+            r#"
+        INSERT INTO feed_health_daily(
+          day, scope, subject, fetches, successes, error_counts,
+          p50_latency_ms, p95_latency_ms, changes, items, updated_at
+        ) VALUES ($1::date, $2, $3, $4, $5, $6::jsonb, $7, $8, $9, $10, $11)
+        "#,
+        )
+        .bind(&r.day)
+        .bind(r.scope.as_str())
+        .bind(&r.subject)
+        .bind(r.fetches)
+        .bind(r.successes)
+        .bind(errors)
+        .bind(r.p50_latency_ms)
+        .bind(r.p95_latency_ms)
+        .bind(r.changes)
+        .bind(r.items)
+        .bind(ts_from_ms(now_ms, zone))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("insert health rollup: {e}"))?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  debug!(
+    day,
+    rows = rows.len(),
+    "Stored health rollups"
+  );
+
+  Ok(())
+}
+
+pub async fn health_rollups(
+  pool: &PgPool,
+  query: &HealthQuery
+) -> Result<Vec<HealthRollup>, String> {
+  let rows = sqlx::query_as::<_, HealthRollupRow>(
+        r#"
+      SELECT day::text AS day, subject, fetches, successes,
+             error_counts::text AS error_counts,
+             p50_latency_ms, p95_latency_ms, changes, items
+      FROM feed_health_daily
+      WHERE scope = $1
+        AND ($2::text IS NULL OR subject = $2)
+        AND ($3::date IS NULL OR day >= $3::date)
+        AND ($4::date IS NULL OR day <= $4::date)
+      ORDER BY day DESC, subject
+      LIMIT $5
+      "#,
+    )
+    .bind(query.scope.as_str())
+    .bind(query.subject.as_deref())
+    .bind(query.since_day.as_deref())
+    .bind(query.until_day.as_deref())
+    .bind(query.limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("health rollups: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        HealthRollup {
+          day:            r.day,
+          scope:          query.scope,
+          subject:        r.subject,
+          fetches:        r.fetches,
+          successes:      r.successes,
+          error_counts:
+            serde_json::from_str::<
+              BTreeMap<String, i64>
+            >(
+              &r.error_counts
+            )
+            .unwrap_or_default(),
+          p50_latency_ms: r
+            .p50_latency_ms,
+          p95_latency_ms: r
+            .p95_latency_ms,
+          changes:        r.changes,
+          items:          r.items
+        }
+      })
+      .collect()
+  )
+}
@@ -0,0 +1,153 @@
+//! Feed item reads and in-place
+//! rewrites used when replaying
+//! archived payloads (Postgres).
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use tracing::debug;
+
+use super::util::{
+  ms_from_ts,
+  ts_from_ms_opt
+};
+use crate::feed::parser::FeedItem;
+use crate::ports::repo::StoredItem;
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct StoredItemRow {
+  id:           i64,
+  title:        Option<String>,
+  link:         Option<String>,
+  guid:         Option<String>,
+  published_at: Option<DateTime<Utc>>,
+  category:     Option<String>,
+  description:  Option<String>,
+  summary:      Option<String>
+}
+
+pub async fn payload_items(
+  pool: &PgPool,
+  payload_id: i64
+) -> Result<Vec<StoredItem>, String> {
+  let rows = sqlx::query_as::<_, StoredItemRow>(
+        r#"
+      SELECT id, title, link, guid, published_at,
+             category, description, summary
+      FROM feed_items
+      WHERE payload_id = $1
+      ORDER BY id
+      "#,
+    )
+    .bind(payload_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("payload items: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        StoredItem {
+          id:   r.id,
+          item: FeedItem {
+            title:           r.title,
+            link:            r.link,
+            guid:            r.guid,
+            published_at_ms: ms_from_ts(
+              r.published_at
+            ),
+            category:        r.category,
+            description:     r
+              .description,
+            summary:         r.summary
+          }
+        }
+      })
+      .collect()
+  )
+}
+
+pub async fn apply_payload_items(
+  pool: &PgPool,
+  payload_id: i64,
+  feed_id: &str,
+  inserts: &[FeedItem],
+  updates: &[StoredItem],
+  zone: &Tz
+) -> Result<(), String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  for it in inserts {
+    sqlx::query(
+            r#"
+        INSERT INTO feed_items(
+          payload_id, feed_id, title, link, guid,
+          published_at,
+          category, description, summary
+        ) VALUES (
+          $1, $2, $3, $4, $5,
+          $6,
+          $7, $8, $9
+        )
+        "#,
+        )
+        .bind(payload_id)
+        .bind(feed_id)
+        .bind(it.title.clone())
+        .bind(it.link.clone())
+        .bind(it.guid.clone())
+        .bind(ts_from_ms_opt(it.published_at_ms, zone))
+        .bind(it.category.clone())
+        .bind(it.description.clone())
+        .bind(it.summary.clone())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("insert item: {e}"))?;
+  }
+
+  for stored in updates {
+    let it = &stored.item;
+
+    sqlx::query(
+            r#"
+        UPDATE feed_items SET
+          title = $2, link = $3, guid = $4,
+          published_at = $5,
+          category = $6, description = $7, summary = $8
+        WHERE id = $1
+        "#,
+        )
+        .bind(stored.id)
+        .bind(it.title.clone())
+        .bind(it.link.clone())
+        .bind(it.guid.clone())
+        .bind(ts_from_ms_opt(it.published_at_ms, zone))
+        .bind(it.category.clone())
+        .bind(it.description.clone())
+        .bind(it.summary.clone())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("update item: {e}"))?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  debug!(
+    payload_id,
+    inserted = inserts.len(),
+    updated = updates.len(),
+    "Applied reparsed items"
+  );
+
+  Ok(())
+}
@@ -0,0 +1,218 @@
+//! Work leases for running several
+//! fetchers against one schema. Rows
+//! are claimed with `FOR UPDATE SKIP
+//! LOCKED` and expire on the database
+//! clock, so replicas never need
+//! synchronised clocks.
+
+use sqlx::PgPool;
+
+/// Domain tokens are held per feed
+/// action.
+fn token_holder(
+  owner: &str,
+  feed_id: &str
+) -> String {
+  format!("{owner}/{feed_id}")
+}
+
+pub async fn claim_feed_leases(
+  pool: &PgPool,
+  owner: &str,
+  feed_ids: &[String],
+  ttl_seconds: u64
+) -> Result<Vec<String>, String> {
+  if feed_ids.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  sqlx::query(
+        r#"
+        INSERT INTO feed_leases(feed_id, owner, expires_at)
+        SELECT id, NULL, '-infinity'::timestamptz
+        FROM unnest($1::text[]) AS id
+        ON CONFLICT (feed_id) DO NOTHING
+        "#,
+    )
+    .bind(feed_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("seed feed leases: {e}"))?;
+
+  let claimed: Vec<String> = sqlx::query_scalar(
+        r#"
+        UPDATE feed_leases l
+        SET owner = $2,
+            expires_at = now() + make_interval(secs => $3)
+        FROM (
+          SELECT feed_id FROM feed_leases
+          WHERE feed_id = ANY($1)
+            AND (owner IS NULL OR owner = $2 OR expires_at < now())
+          FOR UPDATE SKIP LOCKED
+        ) c
+        WHERE l.feed_id = c.feed_id
+        RETURNING l.feed_id
+        "#,
+    )
+    .bind(feed_ids)
+    .bind(owner)
+    .bind(ttl_seconds as f64)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("claim feed leases: {e}"))?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(claimed)
+}
+
+/// Extends a held lease and any domain
+/// token taken for it. Returns `false`
+/// when the lease has been lost.
+pub async fn renew_feed_lease(
+  pool: &PgPool,
+  owner: &str,
+  feed_id: &str,
+  ttl_seconds: u64
+) -> Result<bool, String> {
+  let renewed = sqlx::query(
+        r#"
+        UPDATE feed_leases
+        SET expires_at = now() + make_interval(secs => $3)
+        WHERE feed_id = $1 AND owner = $2
+        "#,
+    )
+    .bind(feed_id)
+    .bind(owner)
+    .bind(ttl_seconds as f64)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("renew feed lease: {e}"))?
+    .rows_affected();
+
+  sqlx::query(
+        r#"
+        UPDATE domain_tokens
+        SET expires_at = now() + make_interval(secs => $2)
+        WHERE holder = $1
+        "#,
+    )
+    .bind(token_holder(owner, feed_id))
+    .bind(ttl_seconds as f64)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("renew domain token: {e}"))?;
+
+  Ok(renewed > 0)
+}
+
+pub async fn release_feed_leases(
+  pool: &PgPool,
+  owner: &str,
+  feed_ids: &[String]
+) -> Result<(), String> {
+  if feed_ids.is_empty() {
+    return Ok(());
+  }
+
+  let holders: Vec<String> = feed_ids
+    .iter()
+    .map(|id| token_holder(owner, id))
+    .collect();
+
+  sqlx::query(
+        r#"
+        UPDATE domain_tokens
+        SET holder = NULL, expires_at = '-infinity'::timestamptz
+        WHERE holder = ANY($1)
+        "#,
+    )
+    .bind(&holders)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("release domain tokens: {e}"))?;
+
+  sqlx::query(
+        r#"
+        UPDATE feed_leases
+        SET owner = NULL, expires_at = '-infinity'::timestamptz
+        WHERE feed_id = ANY($1) AND owner = $2
+        "#,
+    )
+    .bind(feed_ids)
+    .bind(owner)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("release feed leases: {e}"))?;
+
+  Ok(())
+}
+
+/// Takes one of `limit` shared slots
+/// for `domain`. Returns `false` when
+/// every slot is held by a live lease.
+pub async fn acquire_domain_token(
+  pool: &PgPool,
+  domain: &str,
+  limit: usize,
+  owner: &str,
+  feed_id: &str,
+  ttl_seconds: u64
+) -> Result<bool, String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  sqlx::query(
+        r#"
+        INSERT INTO domain_tokens(domain, slot, holder, expires_at)
+        SELECT $1, s, NULL, '-infinity'::timestamptz
+        FROM generate_series(0, $2 - 1) AS s
+        ON CONFLICT (domain, slot) DO NOTHING
+        "#,
+    )
+    .bind(domain)
+    .bind(limit.max(1) as i32)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("seed domain tokens: {e}"))?;
+
+  let slot: Option<i32> = sqlx::query_scalar(
+        r#"
+        UPDATE domain_tokens t
+        SET holder = $3,
+            expires_at = now() + make_interval(secs => $4)
+        FROM (
+          SELECT domain, slot FROM domain_tokens
+          WHERE domain = $1 AND slot < $2
+            AND (holder IS NULL OR holder = $3 OR expires_at < now())
+          ORDER BY slot
+          LIMIT 1
+          FOR UPDATE SKIP LOCKED
+        ) c
+        WHERE t.domain = c.domain AND t.slot = c.slot
+        RETURNING t.slot
+        "#,
+    )
+    .bind(domain)
+    .bind(limit.max(1) as i32)
+    .bind(token_holder(owner, feed_id))
+    .bind(ttl_seconds as f64)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("acquire domain token: {e}"))?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(slot.is_some())
+}
@@ -1,25 +1,306 @@
 //! Database migrations for Postgres:
-//! create tables/indexes.
+//! numbered steps recorded in
+//! `schema_migrations`, each applied in
+//! its own transaction, then the
+//! current time partitions.
 
-use sqlx::PgPool;
+use chrono::{
+  DateTime,
+  Utc
+};
+use sqlx::{
+  PgConnection,
+  PgPool
+};
 use tracing::info;
 
-use super::util::chunk_statements;
+use super::partitions;
+use super::util::{
+  chunk_statements,
+  now_epoch_ms
+};
+use crate::ports::repo::{
+  PartitionPlan,
+  SchemaMigration,
+  SchemaStatus
+};
 
-const POSTGRES_SCHEMA: &str =
+/// The DDL of migration 1, frozen as
+/// shipped.
+const BASELINE_SQL: &str =
   include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
-    "/res/sql/postgres/schema.sql"
+    "/res/sql/postgres/migrations/\
+     0001_baseline.sql"
   ));
 
+/// Every migration, oldest first. An
+/// applied step is never edited: new
+/// DDL goes in a new numbered step,
+/// appended here with its file under
+/// `res/sql/postgres/migrations/` and
+/// handled in `apply`.
+pub const MIGRATIONS: [(i64, &str); 1] =
+  [(1, "baseline")];
+
+/// Serializes migrations across
+/// fetchers sharing the database.
+const LOCK_SQL: &str =
+  "SELECT pg_advisory_xact_lock(\
+   hashtext('pulsewire.migrations'))";
+
 pub async fn migrate(
   pool: &PgPool,
   default_poll_seconds: u64
 ) -> Result<(), String> {
   info!("DB migrate start (postgres)");
 
+  let mut tx =
+    pool.begin().await.map_err(err)?;
+
+  lock(&mut tx).await?;
+
+  sqlx::query(
+    "CREATE TABLE IF NOT EXISTS \
+     schema_migrations(version BIGINT \
+     PRIMARY KEY, name TEXT NOT NULL, \
+     applied_at TIMESTAMPTZ NOT NULL \
+     DEFAULT now())"
+  )
+  .execute(&mut *tx)
+  .await
+  .map_err(err)?;
+
+  tx.commit().await.map_err(err)?;
+
+  let status = status(pool).await?;
+
+  status.check()?;
+
+  for step in status.pending() {
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(err)?;
+
+    lock(&mut tx).await?;
+
+    // Another fetcher may have applied
+    // it while this one waited.
+    let done: Option<i64> =
+      sqlx::query_scalar(
+        "SELECT version FROM \
+         schema_migrations WHERE \
+         version = $1"
+      )
+      .bind(step.version)
+      .fetch_optional(&mut *tx)
+      .await
+      .map_err(err)?;
+
+    if done.is_some() {
+      continue;
+    }
+
+    apply(
+      &mut tx,
+      step.version,
+      default_poll_seconds
+    )
+    .await
+    .map_err(|e| {
+      format!(
+        "migration {} ({}): {e}",
+        step.version, step.name
+      )
+    })?;
+
+    sqlx::query(
+      "INSERT INTO \
+       schema_migrations(version, \
+       name) VALUES ($1, $2)"
+    )
+    .bind(step.version)
+    .bind(&step.name)
+    .execute(&mut *tx)
+    .await
+    .map_err(err)?;
+
+    tx.commit().await.map_err(err)?;
+
+    info!(
+      version = step.version,
+      name = %step.name,
+      "Applied migration"
+    );
+  }
+
+  partitions::maintain(
+    pool,
+    &PartitionPlan {
+      now_ms:
+        now_epoch_ms(),
+      premake_months:          1,
+      convert_existing:        false,
+      fetch_events_before_ms:  None,
+      state_history_before_ms: None
+    }
+  )
+  .await?;
+
+  info!("DB migrate done");
+
+  Ok(())
+}
+
+pub async fn status(
+  pool: &PgPool
+) -> Result<SchemaStatus, String> {
+  let has_table: Option<String> =
+    sqlx::query_scalar(
+      "SELECT to_regclass('\
+       schema_migrations')::text"
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?;
+
+  let rows: Vec<(
+    i64,
+    String,
+    DateTime<Utc>
+  )> = if has_table.is_some() {
+    sqlx::query_as(
+      "SELECT version, name, \
+       applied_at FROM \
+       schema_migrations ORDER BY \
+       version"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?
+  } else {
+    Vec::new()
+  };
+
+  Ok(SchemaStatus::new(
+    &MIGRATIONS,
+    rows
+      .into_iter()
+      .map(|(version, name, at)| {
+        (
+          version,
+          name,
+          at.timestamp_millis()
+        )
+      })
+      .collect()
+  ))
+}
+
+/// The migrations `pulsewire-server`
+/// recorded in `server_schema`, oldest
+/// first; empty before it first ran
+/// here.
+pub async fn server_status(
+  pool: &PgPool,
+  server_schema: &str
+) -> Result<Vec<SchemaMigration>, String>
+{
+  let table = format!(
+    "\"{server_schema}\".\
+     server_schema_migrations"
+  );
+
+  let has_table: Option<String> =
+    sqlx::query_scalar(
+      "SELECT to_regclass($1)::text"
+    )
+    .bind(&table)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?;
+
+  if has_table.is_none() {
+    return Ok(Vec::new());
+  }
+
+  let rows: Vec<(
+    i64,
+    String,
+    DateTime<Utc>
+  )> = sqlx::query_as(&format!(
+    "SELECT version, name, applied_at \
+     FROM {table} ORDER BY version"
+  ))
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!("migration status: {e}")
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(version, name, at)| {
+        SchemaMigration {
+          version,
+          name,
+          applied_at_ms: Some(
+            at.timestamp_millis()
+          )
+        }
+      })
+      .collect()
+  )
+}
+
+async fn lock(
+  conn: &mut PgConnection
+) -> Result<(), String> {
+  sqlx::query(LOCK_SQL)
+    .execute(conn)
+    .await
+    .map(|_| ())
+    .map_err(err)
+}
+
+async fn apply(
+  conn: &mut PgConnection,
+  version: i64,
+  default_poll_seconds: u64
+) -> Result<(), String> {
+  match version {
+    | 1 => {
+      baseline(
+        conn,
+        default_poll_seconds
+      )
+      .await
+    }
+    | _ => {
+      Err(format!(
+        "unknown migration {version}"
+      ))
+    }
+  }
+}
+
+/// The schema as of versioning; its
+/// `IF NOT EXISTS` statements make it
+/// safe on older databases.
+async fn baseline(
+  conn: &mut PgConnection,
+  default_poll_seconds: u64
+) -> Result<(), String> {
   for ddl in
-    chunk_statements(POSTGRES_SCHEMA)
+    chunk_statements(BASELINE_SQL)
   {
     let stmt = ddl.replace(
       "{default_poll_seconds}",
@@ -27,7 +308,7 @@ pub async fn migrate(
     );
 
     sqlx::query(&stmt)
-      .execute(pool)
+      .execute(&mut *conn)
       .await
       .map_err(|e| {
         format!(
@@ -36,7 +317,9 @@ pub async fn migrate(
       })?;
   }
 
-  info!("DB migrate done");
-
   Ok(())
 }
+
+fn err(e: sqlx::Error) -> String {
+  format!("migrate error: {e}")
+}
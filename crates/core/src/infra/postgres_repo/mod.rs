@@ -2,17 +2,29 @@
 //! implementing persistence for feeds,
 //! state, events, and payloads.
 
+mod archive;
 mod connection;
+mod content;
+mod controls;
+mod datasets;
 mod error_feeds;
 mod events;
 mod feeds;
-mod migrations;
+mod fetch_requests;
+mod health;
+mod items;
+mod leases;
+pub(crate) mod migrations;
 mod models;
+mod partitions;
 mod payloads;
+mod retention;
 mod state;
 mod util;
 
 use chrono_tz::Tz;
+pub(crate) use connection::create_pool;
+pub use connection::wipe_database;
 use sqlx::PgPool;
 
 use crate::domain::link_state::LinkState;
@@ -21,10 +33,31 @@ use crate::domain::model::{
   FeedConfig,
   PostgresConfig
 };
-use crate::feed::parser::ParsedFeed;
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
 use crate::ports::repo::{
+  ArchivedPayload,
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus,
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup,
+  PartitionPlan,
+  PartitionReport,
+  PayloadFilter,
+  PayloadRef,
+  PruneScope,
+  PruneTarget,
   Repo,
-  StateRow
+  ScheduledFeed,
+  SchemaStatus,
+  StateRow,
+  StoredItem,
+  WriteBatch
 };
 
 pub struct PostgresRepo {
@@ -49,16 +82,6 @@ impl PostgresRepo {
   }
 }
 
-pub async fn wipe_database(
-  cfg: &PostgresConfig,
-  timezone: &Tz
-) -> Result<(), String> {
-  connection::wipe_database(
-    cfg, timezone
-  )
-  .await
-}
-
 #[async_trait::async_trait]
 
 impl Repo for PostgresRepo {
@@ -74,6 +97,12 @@ impl Repo for PostgresRepo {
     .await
   }
 
+  async fn schema_status(
+    &self
+  ) -> Result<SchemaStatus, String> {
+    migrations::status(&self.pool).await
+  }
+
   async fn upsert_feeds_bulk(
     &self,
     feeds: Vec<FeedConfig>,
@@ -111,18 +140,14 @@ impl Repo for PostgresRepo {
     .await
   }
 
-  async fn due_feeds_for_category(
+  async fn scheduled_feeds_for_category(
     &self,
-    category: &str,
-    now_ms: i64,
-    limit: i64
-  ) -> Result<Vec<FeedConfig>, String>
+    category: &str
+  ) -> Result<Vec<ScheduledFeed>, String>
   {
-    feeds::due_feeds(
+    feeds::scheduled_feeds(
       &self.pool,
       category,
-      now_ms,
-      limit,
       &self.timezone
     )
     .await
@@ -172,6 +197,17 @@ impl Repo for PostgresRepo {
     .await
   }
 
+  async fn write_batch(
+    &self,
+    batch: &WriteBatch,
+    zone: &Tz
+  ) -> Result<(), String> {
+    state::write_batch(
+      &self.pool, batch, zone
+    )
+    .await
+  }
+
   async fn insert_payload_with_items(
     &self,
     feed_id: &str,
@@ -182,8 +218,13 @@ impl Repo for PostgresRepo {
     parsed: &ParsedFeed,
     zone: &Tz
   ) -> Result<(), String> {
+    let mut tx =
+      self.pool.begin().await.map_err(
+        |e| format!("tx begin: {e}")
+      )?;
+
     payloads::insert_payload_with_items(
-      &self.pool,
+      &mut tx,
       feed_id,
       fetched_at_ms,
       etag,
@@ -192,7 +233,11 @@ impl Repo for PostgresRepo {
       parsed,
       zone
     )
-    .await
+    .await?;
+
+    tx.commit().await.map_err(|e| {
+      format!("tx commit: {e}")
+    })
   }
 
   async fn mark_feed_error(
@@ -215,4 +260,324 @@ impl Repo for PostgresRepo {
     )
     .await
   }
+
+  async fn touch_archived_payload(
+    &self,
+    content_hash: &str,
+    seen_at_ms: i64,
+    zone: &Tz
+  ) -> Result<bool, String> {
+    archive::touch_archived_payload(
+      &self.pool,
+      content_hash,
+      seen_at_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn insert_archived_payload(
+    &self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    archive::insert_archived_payload(
+      &self.pool, payload, seen_at_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn archived_payload(
+    &self,
+    content_hash: &str
+  ) -> Result<
+    Option<ArchivedPayload>,
+    String
+  > {
+    archive::archived_payload(
+      &self.pool,
+      content_hash
+    )
+    .await
+  }
+
+  async fn prune_archived_payloads(
+    &self,
+    seen_before_ms: i64,
+    limit: i64,
+    zone: &Tz
+  ) -> Result<
+    Vec<ArchivedPayload>,
+    String
+  > {
+    archive::prune_archived_payloads(
+      &self.pool,
+      seen_before_ms,
+      limit,
+      zone
+    )
+    .await
+  }
+
+  async fn archived_payload_refs(
+    &self,
+    filter: &PayloadFilter,
+    after_id: i64,
+    limit: i64,
+    zone: &Tz
+  ) -> Result<Vec<PayloadRef>, String>
+  {
+    payloads::archived_payload_refs(
+      &self.pool, filter, after_id,
+      limit, zone
+    )
+    .await
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItem>, String>
+  {
+    items::payload_items(
+      &self.pool, payload_id
+    )
+    .await
+  }
+
+  async fn apply_payload_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    inserts: &[FeedItem],
+    updates: &[StoredItem],
+    zone: &Tz
+  ) -> Result<(), String> {
+    items::apply_payload_items(
+      &self.pool, payload_id, feed_id,
+      inserts, updates, zone
+    )
+    .await
+  }
+
+  async fn prune_rows(
+    &self,
+    target: PruneTarget,
+    scope: &PruneScope,
+    zone: &Tz
+  ) -> Result<u64, String> {
+    retention::prune_rows(
+      &self.pool, target, scope, zone
+    )
+    .await
+  }
+
+  async fn maintain_partitions(
+    &self,
+    plan: &PartitionPlan
+  ) -> Result<PartitionReport, String>
+  {
+    partitions::maintain(
+      &self.pool, plan
+    )
+    .await
+  }
+
+  async fn health_events(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<HealthEvent>, String>
+  {
+    health::health_events(
+      &self.pool, start_ms, end_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn health_payloads(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<HealthPayload>, String>
+  {
+    health::health_payloads(
+      &self.pool, start_ms, end_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn replace_health_rollups(
+    &self,
+    day: &str,
+    rows: &[HealthRollup],
+    now_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    health::replace_health_rollups(
+      &self.pool, day, rows, now_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn health_rollups(
+    &self,
+    query: &HealthQuery
+  ) -> Result<Vec<HealthRollup>, String>
+  {
+    health::health_rollups(
+      &self.pool, query
+    )
+    .await
+  }
+
+  async fn claim_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String],
+    ttl_seconds: u64
+  ) -> Result<Vec<String>, String> {
+    leases::claim_feed_leases(
+      &self.pool,
+      owner,
+      feed_ids,
+      ttl_seconds
+    )
+    .await
+  }
+
+  async fn renew_feed_lease(
+    &self,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String> {
+    leases::renew_feed_lease(
+      &self.pool,
+      owner,
+      feed_id,
+      ttl_seconds
+    )
+    .await
+  }
+
+  async fn release_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String]
+  ) -> Result<(), String> {
+    leases::release_feed_leases(
+      &self.pool, owner, feed_ids
+    )
+    .await
+  }
+
+  async fn acquire_domain_token(
+    &self,
+    domain: &str,
+    limit: usize,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String> {
+    leases::acquire_domain_token(
+      &self.pool,
+      domain,
+      limit,
+      owner,
+      feed_id,
+      ttl_seconds
+    )
+    .await
+  }
+
+  async fn enqueue_fetch_request(
+    &self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64,
+    zone: &Tz
+  ) -> Result<i64, String> {
+    fetch_requests::enqueue_fetch_request(
+      &self.pool,
+      feed_id,
+      requested_by,
+      requested_at_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn fetch_request(
+    &self,
+    id: i64
+  ) -> Result<
+    Option<FetchRequest>,
+    String
+  > {
+    fetch_requests::fetch_request(
+      &self.pool, id
+    )
+    .await
+  }
+
+  async fn claim_fetch_requests(
+    &self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<ClaimedFetch>, String>
+  {
+    fetch_requests::claim_fetch_requests(
+      &self.pool,
+      category,
+      busy_feed_ids,
+      limit,
+      now_ms,
+      stale_before_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn requeue_fetch_requests(
+    &self,
+    ids: &[i64]
+  ) -> Result<(), String> {
+    fetch_requests::requeue_fetch_requests(
+      &self.pool, ids
+    )
+    .await
+  }
+
+  async fn finish_fetch_request(
+    &self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    fetch_requests::finish_fetch_request(
+      &self.pool,
+      id,
+      status,
+      http_status,
+      error,
+      finished_at_ms,
+      zone
+    )
+    .await
+  }
 }
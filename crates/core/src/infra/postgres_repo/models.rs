@@ -7,7 +7,10 @@ use chrono::{
   Utc
 };
 
-use crate::domain::model::FeedConfig;
+use crate::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
 use crate::ports::repo::StateRow;
 
 #[derive(Debug, sqlx::FromRow)]
@@ -31,7 +34,9 @@ pub struct StateRowRecord {
   pub next_action_at: DateTime<Utc>,
   pub jitter_seconds:          i64,
   pub note: Option<String>,
-  pub consecutive_error_count: i64
+  pub consecutive_error_count: i64,
+  pub get_only_since:
+    Option<DateTime<Utc>>
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -41,7 +46,11 @@ pub struct DueFeedRow {
   pub url:               String,
   pub domain:            String,
   pub category:          String,
-  pub base_poll_seconds: i64
+  pub base_poll_seconds: i64,
+  pub dataset:           Option<String>,
+  pub priority:          i64,
+  pub next_action_at:
+    Option<DateTime<Utc>>
 }
 
 impl From<StateRowRecord> for StateRow {
@@ -87,7 +96,11 @@ impl From<StateRowRecord> for StateRow {
       note:                    value
         .note,
       consecutive_error_count: value
-        .consecutive_error_count
+        .consecutive_error_count,
+      get_only_since_ms:
+        super::util::ms_from_ts(
+          value.get_only_since
+        )
     }
   }
 }
@@ -106,7 +119,17 @@ impl From<DueFeedRow> for FeedConfig {
       provenance:        None,
       tags:              None,
       language:          None,
-      content_type:      None
+      content_type:      None,
+      dataset:           row
+        .dataset
+        .as_deref()
+        .and_then(|raw| {
+          serde_json::from_str(raw).ok()
+        }),
+      priority:
+        FeedPriority::from_rank(
+          row.priority
+        )
     }
   }
 }
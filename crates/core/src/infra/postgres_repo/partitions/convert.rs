@@ -0,0 +1,254 @@
+//! Converting a pre-partitioning
+//! table in place.
+
+use chrono::{
+  DateTime,
+  Utc
+};
+use sqlx::PgPool;
+use tracing::{
+  info,
+  warn
+};
+
+use super::{
+  exec,
+  literal,
+  month_of,
+  next,
+  run_steps,
+  suffix
+};
+
+/// Turns an unpartitioned table into a
+/// partitioned one without copying
+/// rows: the old table is attached as
+/// the partition for everything before
+/// the month after its newest row. The
+/// slow steps (index build, constraint
+/// check) run before the short
+/// exclusive lock.
+pub(super) async fn convert(
+  pool: &PgPool,
+  table: &str,
+  column: &str,
+  now_ms: i64
+) -> Result<(), String> {
+  let newest: Option<DateTime<Utc>> =
+    sqlx::query_scalar(&format!(
+      "SELECT max({column}) FROM \
+       {table}"
+    ))
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("partition convert: {e}")
+    })?;
+
+  let until = next(month_of(
+    newest
+      .map(|t| t.timestamp_millis())
+      .unwrap_or(now_ms)
+      .max(now_ms)
+  ));
+
+  let legacy = format!(
+    "{table}_before_{}",
+    suffix(until)
+  );
+
+  let bound = literal(until);
+
+  info!(table, partition = %legacy, "Converting table to partitions");
+
+  drop_invalid_index(
+    pool,
+    &format!("{table}_id_key")
+  )
+  .await?;
+
+  exec(
+    pool,
+    &format!(
+      "CREATE UNIQUE INDEX \
+       CONCURRENTLY IF NOT EXISTS \
+       {table}_id_key ON {table} (id, \
+       {column})"
+    )
+  )
+  .await?;
+
+  exec(
+    pool,
+    &format!(
+      "ALTER TABLE {table} DROP \
+       CONSTRAINT IF EXISTS \
+       {table}_bound"
+    )
+  )
+  .await?;
+
+  exec(
+    pool,
+    &format!(
+      "ALTER TABLE {table} ADD \
+       CONSTRAINT {table}_bound CHECK \
+       ({column} < '{bound}') NOT \
+       VALID"
+    )
+  )
+  .await?;
+
+  exec(
+    pool,
+    &format!(
+      "ALTER TABLE {table} VALIDATE \
+       CONSTRAINT {table}_bound"
+    )
+  )
+  .await?;
+
+  let indexes: Vec<(String, String)> =
+    sqlx::query_as(
+      "SELECT indexname::text, \
+       indexdef FROM pg_indexes WHERE \
+       schemaname = current_schema() \
+       AND tablename = $1"
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("partition convert: {e}")
+    })?;
+
+  // A partition's primary key must
+  // include the partition column.
+  let mut steps = vec![
+    format!(
+      "LOCK TABLE {table} IN ACCESS \
+       EXCLUSIVE MODE"
+    ),
+    format!(
+      "ALTER TABLE {table} DROP \
+       CONSTRAINT IF EXISTS \
+       {table}_pkey"
+    ),
+    format!(
+      "ALTER TABLE {table} ADD \
+       CONSTRAINT {table}_id_key \
+       PRIMARY KEY USING INDEX \
+       {table}_id_key"
+    ),
+  ];
+
+  let pkey = format!("{table}_pkey");
+
+  for (index, _) in indexes
+    .iter()
+    .filter(|(index, _)| *index != pkey)
+  {
+    steps.push(format!(
+      "ALTER INDEX {index} RENAME TO \
+       {index}_before_{}",
+      suffix(until)
+    ));
+  }
+
+  steps.extend([
+    format!(
+      "ALTER TABLE {table} RENAME TO \
+       {legacy}"
+    ),
+    format!(
+      "CREATE TABLE {table} (LIKE \
+       {legacy} INCLUDING DEFAULTS) \
+       PARTITION BY RANGE ({column})"
+    ),
+    format!(
+      "ALTER SEQUENCE {table}_id_seq \
+       OWNED BY {table}.id"
+    ),
+    format!(
+      "ALTER TABLE {table} ADD \
+       PRIMARY KEY (id, {column})"
+    ),
+    format!(
+      "ALTER TABLE {table} ADD \
+       FOREIGN KEY (feed_id) \
+       REFERENCES feeds(id)"
+    ),
+    format!(
+      "ALTER TABLE {table} ATTACH \
+       PARTITION {legacy} FOR VALUES \
+       FROM (MINVALUE) TO ('{bound}')"
+    )
+  ]);
+
+  // Plain indexes are recreated on the
+  // parent, adopting the renamed ones.
+  steps.extend(
+    indexes
+      .iter()
+      .filter(|(_, def)| {
+        !def
+          .starts_with("CREATE UNIQUE")
+      })
+      .map(|(_, def)| {
+        def.replacen(
+          "CREATE INDEX",
+          "CREATE INDEX IF NOT EXISTS",
+          1
+        )
+      })
+  );
+
+  run_steps(pool, &steps).await
+}
+
+/// A failed `CREATE INDEX CONCURRENTLY`
+/// leaves an INVALID index behind,
+/// which `IF NOT EXISTS` would then
+/// accept; drop it so the retry builds
+/// it again.
+async fn drop_invalid_index(
+  pool: &PgPool,
+  index: &str
+) -> Result<(), String> {
+  let invalid: bool =
+    sqlx::query_scalar(
+      "SELECT EXISTS (SELECT 1 FROM \
+       pg_index i JOIN pg_class c ON \
+       c.oid = i.indexrelid JOIN \
+       pg_namespace n ON n.oid = \
+       c.relnamespace WHERE c.relname \
+       = $1 AND n.nspname = \
+       current_schema() AND NOT \
+       i.indisvalid)"
+    )
+    .bind(index)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("partition convert: {e}")
+    })?;
+
+  if invalid {
+    warn!(
+      index,
+      "Dropping invalid index left by \
+       an earlier conversion"
+    );
+
+    exec(
+      pool,
+      &format!(
+        "DROP INDEX CONCURRENTLY IF \
+         EXISTS {index}"
+      )
+    )
+    .await?;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,466 @@
+//! Monthly range partitions for the
+//! Postgres history tables. Partitions
+//! are named `<table>_pYYYY_MM`; a
+//! converted pre-partitioning table
+//! becomes `<table>_before_YYYY_MM`,
+//! holding everything before that
+//! month. Rows outside every partition
+//! land in `<table>_default` and are
+//! moved into their month's partition
+//! on the next pass.
+
+mod convert;
+
+use std::collections::BTreeSet;
+
+use chrono::{
+  DateTime,
+  Datelike,
+  TimeZone,
+  Utc
+};
+use sqlx::PgPool;
+use tracing::{
+  info,
+  warn
+};
+
+use crate::ports::repo::{
+  PartitionPlan,
+  PartitionReport
+};
+
+/// Partitioned tables and their time
+/// column.
+const TABLES: [(&str, &str); 2] = [
+  ("fetch_events", "event_time"),
+  ("feed_state_history", "recorded_at")
+];
+
+/// A (year, month) pair, month 1-12.
+type Month = (i32, u32);
+
+fn month_of(ms: i64) -> Month {
+  let at = Utc
+    .timestamp_millis_opt(ms)
+    .single()
+    .unwrap_or_default();
+
+  (at.year(), at.month())
+}
+
+fn next((year, month): Month) -> Month {
+  if month == 12 {
+    (year + 1, 1)
+  } else {
+    (year, month + 1)
+  }
+}
+
+fn start_of(
+  (year, month): Month
+) -> DateTime<Utc> {
+  Utc
+    .with_ymd_and_hms(
+      year, month, 1, 0, 0, 0
+    )
+    .single()
+    .unwrap_or_default()
+}
+
+fn literal(month: Month) -> String {
+  start_of(month)
+    .format("%Y-%m-%d 00:00:00+00")
+    .to_string()
+}
+
+fn suffix(
+  (year, month): Month
+) -> String {
+  format!("{year:04}_{month:02}")
+}
+
+/// The month a partition ends before,
+/// parsed from its name.
+fn upper_bound(
+  table: &str,
+  name: &str
+) -> Option<Month> {
+  let rest =
+    name.strip_prefix(table)?;
+
+  let (monthly, date) = if let Some(d) =
+    rest.strip_prefix("_p")
+  {
+    (true, d)
+  } else {
+    (
+      false,
+      rest.strip_prefix("_before_")?
+    )
+  };
+
+  let (year, month) =
+    date.split_once('_')?;
+
+  let month: Month = (
+    year.parse().ok()?,
+    month.parse().ok()?
+  );
+
+  if !(1..=12).contains(&month.1) {
+    return None;
+  }
+
+  Some(
+    if monthly {
+      next(month)
+    } else {
+      month
+    }
+  )
+}
+
+pub async fn maintain(
+  pool: &PgPool,
+  plan: &PartitionPlan
+) -> Result<PartitionReport, String> {
+  let mut report =
+    PartitionReport::default();
+
+  for (table, column) in TABLES {
+    match relkind(pool, table)
+      .await?
+      .as_deref()
+    {
+      | Some("p") => {}
+      | Some("r")
+        if plan.convert_existing =>
+      {
+        convert::convert(
+          pool,
+          table,
+          column,
+          plan.now_ms
+        )
+        .await?;
+
+        report
+          .converted
+          .push(table.to_string());
+      }
+      | Some("r") => {
+        warn!(
+          table,
+          "Table is not partitioned; \
+           set [partitions] \
+           convert_existing to \
+           convert it"
+        );
+
+        continue;
+      }
+      | _ => continue
+    }
+
+    let cutoff = match table {
+      | "fetch_events" => {
+        plan.fetch_events_before_ms
+      }
+      | _ => {
+        plan.state_history_before_ms
+      }
+    };
+
+    ensure_months(
+      pool,
+      (table, column),
+      plan,
+      &mut report
+    )
+    .await?;
+
+    if let Some(cutoff) = cutoff {
+      drop_before(
+        pool,
+        table,
+        cutoff,
+        &mut report
+      )
+      .await?;
+    }
+  }
+
+  Ok(report)
+}
+
+async fn relkind(
+  pool: &PgPool,
+  table: &str
+) -> Result<Option<String>, String> {
+  sqlx::query_scalar(
+    "SELECT c.relkind::text FROM \
+     pg_class c JOIN pg_namespace n \
+     ON n.oid = c.relnamespace WHERE \
+     c.relname = $1 AND n.nspname = \
+     current_schema()"
+  )
+  .bind(table)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    format!("partition lookup: {e}")
+  })
+}
+
+async fn partitions_of(
+  pool: &PgPool,
+  table: &str
+) -> Result<Vec<String>, String> {
+  sqlx::query_scalar(
+    "SELECT c.relname::text FROM \
+     pg_inherits i JOIN pg_class c ON \
+     c.oid = i.inhrelid JOIN pg_class \
+     p ON p.oid = i.inhparent JOIN \
+     pg_namespace n ON n.oid = \
+     p.relnamespace WHERE p.relname = \
+     $1 AND n.nspname = \
+     current_schema()"
+  )
+  .bind(table)
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!("partition list: {e}")
+  })
+}
+
+async fn exec(
+  pool: &PgPool,
+  sql: &str
+) -> Result<(), String> {
+  sqlx::query(sql)
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .map_err(|e| {
+      format!("partition ddl: {e}")
+    })
+}
+
+/// Runs `steps` in one transaction.
+async fn run_steps(
+  pool: &PgPool,
+  steps: &[String]
+) -> Result<(), String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("partition ddl: {e}")
+    )?;
+
+  for step in steps {
+    sqlx::query(step)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "partition ddl ({step}): {e}"
+        )
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("partition ddl: {e}")
+  })
+}
+
+/// Months that have rows in the
+/// default partition.
+async fn stray_months(
+  pool: &PgPool,
+  default: &str,
+  column: &str
+) -> Result<BTreeSet<Month>, String> {
+  let rows: Vec<(i32, i32)> =
+    sqlx::query_as(&format!(
+      "SELECT DISTINCT extract(year \
+       FROM {column} AT TIME ZONE \
+       'UTC')::int, extract(month \
+       FROM {column} AT TIME ZONE \
+       'UTC')::int FROM {default}"
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("partition strays: {e}")
+    })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(y, m)| (y, m as u32))
+      .collect()
+  )
+}
+
+/// Creates the default partition, the
+/// current month through
+/// `premake_months` ahead and every
+/// month with rows in the default
+/// partition, skipping months a
+/// converted table covers. Those rows
+/// are moved into the new partition in
+/// the same transaction, as Postgres
+/// refuses a partition whose range
+/// the default partition still holds.
+async fn ensure_months(
+  pool: &PgPool,
+  (table, column): (&str, &str),
+  plan: &PartitionPlan,
+  report: &mut PartitionReport
+) -> Result<(), String> {
+  let existing =
+    partitions_of(pool, table).await?;
+
+  let default =
+    format!("{table}_default");
+
+  if !existing.contains(&default) {
+    exec(
+      pool,
+      &format!(
+        "CREATE TABLE IF NOT EXISTS \
+         {default} PARTITION OF \
+         {table} DEFAULT"
+      )
+    )
+    .await?;
+  }
+
+  let covered = existing
+    .iter()
+    .filter(|name| {
+      name.contains("_before_")
+    })
+    .filter_map(|name| {
+      upper_bound(table, name)
+    })
+    .max();
+
+  let strays = stray_months(
+    pool, &default, column
+  )
+  .await?;
+
+  let mut months = strays.clone();
+
+  let mut month = month_of(plan.now_ms);
+
+  for _ in 0..=plan.premake_months {
+    months.insert(month);
+
+    month = next(month);
+  }
+
+  for month in months {
+    let name = format!(
+      "{table}_p{}",
+      suffix(month)
+    );
+
+    if covered
+      .is_some_and(|c| month < c)
+      || existing.contains(&name)
+    {
+      continue;
+    }
+
+    let (from, to) = (
+      literal(month),
+      literal(next(month))
+    );
+
+    if strays.contains(&month) {
+      run_steps(pool, &[
+        format!(
+          "CREATE TABLE {name} (LIKE \
+           {table} INCLUDING DEFAULTS)"
+        ),
+        format!(
+          "WITH moved AS (DELETE FROM \
+           {default} WHERE {column} \
+           >= '{from}' AND {column} < \
+           '{to}' RETURNING *) INSERT \
+           INTO {name} SELECT * FROM \
+           moved"
+        ),
+        format!(
+          "ALTER TABLE {table} ATTACH \
+           PARTITION {name} FOR \
+           VALUES FROM ('{from}') TO \
+           ('{to}')"
+        )
+      ])
+      .await?;
+
+      info!(partition = %name, "Moved default partition rows into new partition");
+    } else {
+      exec(
+        pool,
+        &format!(
+          "CREATE TABLE IF NOT EXISTS \
+           {name} PARTITION OF \
+           {table} FOR VALUES FROM \
+           ('{from}') TO ('{to}')"
+        )
+      )
+      .await?;
+
+      info!(partition = %name, "Created partition");
+    }
+
+    report.created.push(name);
+  }
+
+  Ok(())
+}
+
+/// Drops partitions whose whole range
+/// ends at or before `cutoff_ms`.
+async fn drop_before(
+  pool: &PgPool,
+  table: &str,
+  cutoff_ms: i64,
+  report: &mut PartitionReport
+) -> Result<(), String> {
+  for name in
+    partitions_of(pool, table).await?
+  {
+    let Some(upper) =
+      upper_bound(table, &name)
+    else {
+      continue;
+    };
+
+    if start_of(upper)
+      .timestamp_millis()
+      > cutoff_ms
+    {
+      continue;
+    }
+
+    exec(
+      pool,
+      &format!(
+        "DROP TABLE IF EXISTS {name}"
+      )
+    )
+    .await?;
+
+    info!(partition = %name, "Dropped expired partition");
+
+    report.dropped.push(name);
+  }
+
+  Ok(())
+}
@@ -1,19 +1,39 @@
 //! Inserts feed payload metadata and
-//! associated feed items in a single
-//! transaction (Postgres).
+//! associated feed items inside the
+//! caller's transaction (Postgres).
 
+use chrono::{
+  DateTime,
+  Utc
+};
 use chrono_tz::Tz;
-use sqlx::PgPool;
-use tracing::debug;
+use sqlx::{
+  PgConnection,
+  PgPool
+};
 
 use super::util::{
   ts_from_ms,
   ts_from_ms_opt
 };
 use crate::feed::parser::ParsedFeed;
+use crate::ports::repo::{
+  PayloadFilter,
+  PayloadRef
+};
 
+#[derive(Debug, sqlx::FromRow)]
+
+struct PayloadRefRow {
+  id:           i64,
+  feed_id:      String,
+  fetched_at:   DateTime<Utc>,
+  content_hash: String
+}
+
+/// Returns the new payload's id.
 pub async fn insert_payload_with_items(
-  pool: &PgPool,
+  conn: &mut PgConnection,
   feed_id: &str,
   fetched_at_ms: i64,
   etag: Option<&str>,
@@ -21,12 +41,7 @@ pub async fn insert_payload_with_items(
   content_hash: Option<&str>,
   parsed: &ParsedFeed,
   zone: &Tz
-) -> Result<(), String> {
-  let mut tx =
-    pool.begin().await.map_err(
-      |e| format!("tx begin: {e}")
-    )?;
-
+) -> Result<i64, String> {
   let fetched_at =
     ts_from_ms(fetched_at_ms, zone);
 
@@ -66,7 +81,7 @@ pub async fn insert_payload_with_items(
     .bind(parsed.metadata.description.clone())
     .bind(parsed.metadata.language.clone())
     .bind(updated_at)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut *conn)
     .await
     .map_err(|e| format!("insert payload: {e}"))?;
 
@@ -93,20 +108,64 @@ pub async fn insert_payload_with_items(
         .bind(it.category.clone())
         .bind(it.description.clone())
         .bind(it.summary.clone())
-        .execute(&mut *tx)
+        .execute(&mut *conn)
         .await
         .map_err(|e| format!("insert item: {e}"))?;
   }
 
-  tx.commit().await.map_err(|e| {
-    format!("tx commit: {e}")
-  })?;
+  Ok(payload_id)
+}
 
-  debug!(
-    feed_id,
-    payload_id,
-    "Inserted payload + items"
-  );
+pub async fn archived_payload_refs(
+  pool: &PgPool,
+  filter: &PayloadFilter,
+  after_id: i64,
+  limit: i64,
+  zone: &Tz
+) -> Result<Vec<PayloadRef>, String> {
+  let rows = sqlx::query_as::<_, PayloadRefRow>(
+        r#"
+      SELECT p.id, p.feed_id, p.fetched_at, p.content_hash
+      FROM feed_payloads p
+      JOIN feeds f ON f.id = p.feed_id
+      JOIN payload_archive a ON a.content_hash = p.content_hash
+      WHERE p.id > $1
+        AND f.dataset IS NULL
+        AND NOT EXISTS (
+          SELECT 1 FROM dataset_versions v
+          WHERE v.feed_id = p.feed_id AND v.content_hash = p.content_hash
+        )
+        AND ($2::TEXT IS NULL OR p.feed_id = $2)
+        AND ($3::TEXT IS NULL OR f.category = $3)
+        AND ($4::TIMESTAMPTZ IS NULL OR p.fetched_at >= $4)
+        AND ($5::TIMESTAMPTZ IS NULL OR p.fetched_at < $5)
+      ORDER BY p.id
+      LIMIT $6
+      "#,
+    )
+    .bind(after_id)
+    .bind(filter.feed_id.as_deref())
+    .bind(filter.category.as_deref())
+    .bind(ts_from_ms_opt(filter.since_ms, zone))
+    .bind(ts_from_ms_opt(filter.until_ms, zone))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("archived payload refs: {e}"))?;
 
-  Ok(())
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        PayloadRef {
+          id:            r.id,
+          feed_id:       r.feed_id,
+          fetched_at_ms: r
+            .fetched_at
+            .timestamp_millis(),
+          content_hash:  r.content_hash
+        }
+      })
+      .collect()
+  )
 }
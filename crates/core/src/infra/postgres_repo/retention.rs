@@ -0,0 +1,182 @@
+//! Batched retention deletes for the
+//! history tables, scoped to one
+//! category (Postgres).
+
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+use super::util::ts_from_ms_opt;
+use crate::ports::repo::{
+  PruneScope,
+  PruneTarget
+};
+
+/// Ranks rows per feed, newest first,
+/// restricted to the scope category.
+fn ranked(
+  table: &str,
+  ts: &str
+) -> String {
+  format!(
+    r#"
+    SELECT t.id AS id, t.{ts} AS ts,
+           ROW_NUMBER() OVER (
+             PARTITION BY t.feed_id
+             ORDER BY t.{ts} DESC, t.id DESC
+           ) AS rn
+    FROM {table} t
+    JOIN feeds f ON f.id = t.feed_id
+    WHERE f.category = $1
+    "#
+  )
+}
+
+const EXPIRED: &str =
+  "($2::timestamptz IS NOT NULL AND \
+   r.ts < $2) OR ($3::bigint IS NOT \
+   NULL AND r.rn > $3)";
+
+async fn table_exists(
+  pool: &PgPool,
+  qualified: &str
+) -> Result<bool, String> {
+  let found: Option<String> =
+    sqlx::query_scalar(
+      "SELECT to_regclass($1)::text"
+    )
+    .bind(qualified)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!("table lookup: {e}")
+    })?;
+
+  Ok(found.is_some())
+}
+
+/// Server tables live in their own
+/// schema; items they point at are
+/// kept when that schema exists.
+async fn item_guards(
+  pool: &PgPool,
+  server_schema: &str
+) -> Result<String, String> {
+  let mut guards = String::new();
+
+  let states = format!(
+    "\"{server_schema}\".entry_states"
+  );
+
+  if table_exists(pool, &states).await?
+  {
+    guards.push_str(&format!(
+      " AND NOT EXISTS (SELECT 1 FROM \
+       {states} s WHERE s.item_id = \
+       i.id)"
+    ));
+  }
+
+  let favorites = format!(
+    "\"{server_schema}\".favorites"
+  );
+
+  if table_exists(pool, &favorites)
+    .await?
+  {
+    guards.push_str(&format!(
+      " AND NOT EXISTS (SELECT 1 FROM \
+       {favorites} v WHERE v.feed_id \
+       = i.feed_id)"
+    ));
+  }
+
+  Ok(guards)
+}
+
+pub async fn prune_rows(
+  pool: &PgPool,
+  target: PruneTarget,
+  scope: &PruneScope,
+  zone: &Tz
+) -> Result<u64, String> {
+  let sql = match target {
+    | PruneTarget::FetchEvents => {
+      format!(
+        "DELETE FROM fetch_events \
+         WHERE id IN (SELECT r.id \
+         FROM ({}) r WHERE {EXPIRED} \
+         LIMIT $4)",
+        ranked(
+          "fetch_events",
+          "event_time"
+        )
+      )
+    }
+    | PruneTarget::StateHistory => {
+      format!(
+        "DELETE FROM \
+         feed_state_history WHERE id \
+         IN (SELECT r.id FROM ({}) r \
+         WHERE {EXPIRED} LIMIT $4)",
+        ranked(
+          "feed_state_history",
+          "recorded_at"
+        )
+      )
+    }
+    | PruneTarget::Items => {
+      let guards = item_guards(
+        pool,
+        &scope.server_schema
+      )
+      .await?;
+
+      format!(
+        "DELETE FROM feed_items WHERE \
+         id IN (SELECT i.id FROM \
+         feed_items i WHERE \
+         i.payload_id IN (SELECT r.id \
+         FROM ({}) r WHERE \
+         {EXPIRED}){guards} LIMIT $4)",
+        ranked(
+          "feed_payloads",
+          "fetched_at"
+        )
+      )
+    }
+    | PruneTarget::Payloads => {
+      format!(
+        "DELETE FROM feed_payloads \
+         WHERE id IN (SELECT r.id \
+         FROM ({}) r WHERE \
+         ({EXPIRED}) AND NOT EXISTS \
+         (SELECT 1 FROM feed_items i \
+         WHERE i.payload_id = r.id) \
+         LIMIT $4)",
+        ranked(
+          "feed_payloads",
+          "fetched_at"
+        )
+      )
+    }
+  };
+
+  let res = sqlx::query(&sql)
+    .bind(&scope.category)
+    .bind(ts_from_ms_opt(
+      scope.cutoff_ms,
+      zone
+    ))
+    .bind(scope.keep_per_feed)
+    .bind(scope.limit)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "prune {}: {e}",
+        target.as_str()
+      )
+    })?;
+
+  Ok(res.rows_affected())
+}
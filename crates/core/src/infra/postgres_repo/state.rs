@@ -2,11 +2,24 @@
 //! link state snapshots for Postgres.
 
 use chrono_tz::Tz;
-use sqlx::PgPool;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{
+  PgPool,
+  Postgres
+};
 
+use super::events::event_query;
 use super::models::StateRowRecord;
+use super::util::{
+  ts_from_ms,
+  ts_from_ms_opt
+};
 use crate::domain::link_state::LinkState;
-use crate::ports::repo::StateRow;
+use crate::ports::repo::{
+  StateRow,
+  WriteBatch
+};
 
 pub async fn latest_state(
   pool: &PgPool,
@@ -33,7 +46,8 @@ pub async fn latest_state(
         next_action_at,
         jitter_seconds,
         note,
-        consecutive_error_count
+        consecutive_error_count,
+        get_only_since
       FROM feed_state_current
       WHERE feed_id = $1
       "#
@@ -55,94 +69,159 @@ pub async fn insert_state(
   zone: &Tz,
   record_history: bool
 ) -> Result<(), String> {
-  let recorded_at =
-    super::util::ts_from_ms(
+  if record_history {
+    history_query(
+      state,
       recorded_at_ms,
       zone
-    );
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "insert_state history error: \
+         {e}"
+      )
+    })?;
+  }
 
-  let last_head_at =
-    super::util::ts_from_ms_opt(
-      state.last_head_at_ms,
-      zone
-    );
+  current_query(state, zone)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "insert_state current error: \
+         {e}"
+      )
+    })?;
 
-  let last_get_at =
-    super::util::ts_from_ms_opt(
-      state.last_get_at_ms,
-      zone
-    );
+  Ok(())
+}
 
-  let last_modified_at =
-    super::util::ts_from_ms_opt(
-      state.last_modified_ms,
-      zone
-    );
+/// Events first, then states, in one
+/// transaction.
+pub async fn write_batch(
+  pool: &PgPool,
+  batch: &WriteBatch,
+  zone: &Tz
+) -> Result<(), String> {
+  let mut tx = pool
+    .begin()
+    .await
+    .map_err(|e| {
+      format!("write_batch begin: {e}")
+    })?;
 
-  let next_action_at =
-    super::util::ts_from_ms(
-      state.next_action_at_ms,
-      zone
-    );
+  for event in &batch.events {
+    event_query(event, zone)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch event: {e}"
+        )
+      })?;
+  }
 
-  if record_history {
-    sqlx::query(
-            r#"
+  for pending in &batch.states {
+    if pending.record_history {
+      history_query(
+        &pending.state,
+        pending.recorded_at_ms,
+        zone
+      )
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch history: {e}"
+        )
+      })?;
+    }
+
+    current_query(&pending.state, zone)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch state: {e}"
+        )
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("write_batch commit: {e}")
+  })
+}
+
+fn history_query<'q>(
+  state: &'q LinkState,
+  recorded_at_ms: i64,
+  zone: &Tz
+) -> Query<'q, Postgres, PgArguments> {
+  sqlx::query(
+    r#"
         INSERT INTO feed_state_history(
           feed_id, recorded_at, phase,
           last_head_at, last_head_status, last_head_error,
           last_get_at, last_get_status, last_get_error,
           etag, last_modified_at,
           backoff_index, base_poll_seconds, next_action_at,
-          jitter_seconds, note, consecutive_error_count
+          jitter_seconds, note, consecutive_error_count,
+          get_only_since
         ) VALUES (
           $1, $2, $3,
           $4, $5, $6,
           $7, $8, $9,
           $10, $11,
           $12, $13, $14,
-          $15, $16, $17
+          $15, $16, $17,
+          $18
         )
         "#,
-        )
-        .bind(&state.feed_id)
-        .bind(recorded_at)
-        .bind(format!("{:?}", state.phase))
-        .bind(last_head_at)
-        .bind(state.last_head_status.map(|x| x as i64))
-        .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-        .bind(last_get_at)
-        .bind(state.last_get_status.map(|x| x as i64))
-        .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-        .bind(&state.etag)
-        .bind(last_modified_at)
-        .bind(state.backoff_index as i64)
-        .bind(state.base_poll_seconds as i64)
-        .bind(next_action_at)
-        .bind(state.jitter_seconds)
-        .bind(&state.note)
-        .bind(state.consecutive_error_count as i64)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("insert_state history error: {e}"))?;
-  }
+  )
+    .bind(&state.feed_id)
+    .bind(ts_from_ms(recorded_at_ms, zone))
+    .bind(format!("{:?}", state.phase))
+    .bind(ts_from_ms_opt(state.last_head_at_ms, zone))
+    .bind(state.last_head_status.map(|x| x as i64))
+    .bind(state.last_head_error.map(|e| format!("{:?}", e)))
+    .bind(ts_from_ms_opt(state.last_get_at_ms, zone))
+    .bind(state.last_get_status.map(|x| x as i64))
+    .bind(state.last_get_error.map(|e| format!("{:?}", e)))
+    .bind(&state.etag)
+    .bind(ts_from_ms_opt(state.last_modified_ms, zone))
+    .bind(state.backoff_index as i64)
+    .bind(state.base_poll_seconds as i64)
+    .bind(ts_from_ms(state.next_action_at_ms, zone))
+    .bind(state.jitter_seconds)
+    .bind(&state.note)
+    .bind(state.consecutive_error_count as i64)
+    .bind(ts_from_ms_opt(state.get_only_since_ms, zone))
+}
 
+fn current_query<'q>(
+  state: &'q LinkState,
+  zone: &Tz
+) -> Query<'q, Postgres, PgArguments> {
   sqlx::query(
-        r#"
+    r#"
       INSERT INTO feed_state_current(
         feed_id, phase,
         last_head_at, last_head_status, last_head_error,
         last_get_at, last_get_status, last_get_error,
         etag, last_modified_at,
         backoff_index, base_poll_seconds, next_action_at,
-        jitter_seconds, note, consecutive_error_count
+        jitter_seconds, note, consecutive_error_count,
+        get_only_since
       ) VALUES (
         $1, $2,
         $3, $4, $5,
         $6, $7, $8,
         $9, $10,
         $11, $12, $13,
-        $14, $15, $16
+        $14, $15, $16,
+        $17
       )
       ON CONFLICT(feed_id) DO UPDATE SET
         phase = excluded.phase,
@@ -159,28 +238,25 @@ pub async fn insert_state(
         next_action_at = excluded.next_action_at,
         jitter_seconds = excluded.jitter_seconds,
         note = excluded.note,
-        consecutive_error_count = excluded.consecutive_error_count
+        consecutive_error_count = excluded.consecutive_error_count,
+        get_only_since = excluded.get_only_since
       "#,
-    )
+  )
     .bind(&state.feed_id)
     .bind(format!("{:?}", state.phase))
-    .bind(last_head_at)
+    .bind(ts_from_ms_opt(state.last_head_at_ms, zone))
     .bind(state.last_head_status.map(|x| x as i64))
     .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-    .bind(last_get_at)
+    .bind(ts_from_ms_opt(state.last_get_at_ms, zone))
     .bind(state.last_get_status.map(|x| x as i64))
     .bind(state.last_get_error.map(|e| format!("{:?}", e)))
     .bind(&state.etag)
-    .bind(last_modified_at)
+    .bind(ts_from_ms_opt(state.last_modified_ms, zone))
     .bind(state.backoff_index as i64)
     .bind(state.base_poll_seconds as i64)
-    .bind(next_action_at)
+    .bind(ts_from_ms(state.next_action_at_ms, zone))
     .bind(state.jitter_seconds)
     .bind(&state.note)
     .bind(state.consecutive_error_count as i64)
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_state current error: {e}"))?;
-
-  Ok(())
+    .bind(ts_from_ms_opt(state.get_only_since_ms, zone))
 }
@@ -23,6 +23,18 @@ impl MutexRng {
       )
     }
   }
+
+  /// Same seed, same sequence; for
+  /// simulations.
+  pub fn seeded(seed: u64) -> Self {
+    Self {
+      inner: Mutex::new(
+        rand::SeedableRng::seed_from_u64(
+          seed
+        )
+      )
+    }
+  }
 }
 
 #[async_trait::async_trait]
@@ -169,18 +169,61 @@ impl Http for ReqwestHttp {
   async fn get(
     &self,
     url: &str
+  ) -> GetResult {
+    self
+      .send_get(
+        self.client.get(url),
+        url
+      )
+      .await
+  }
+
+  async fn get_conditional(
+    &self,
+    url: &str,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>
+  ) -> GetResult {
+    let mut req = self.client.get(url);
+
+    if let Some(etag) = etag {
+      req = req.header(
+        header::IF_NONE_MATCH,
+        etag
+      );
+    }
+
+    if let Some(since) =
+      last_modified_ms.and_then(
+        DateTime::from_timestamp_millis
+      )
+    {
+      req = req.header(
+        header::IF_MODIFIED_SINCE,
+        since
+          .format(
+            "%a, %d %b %Y %H:%M:%S GMT"
+          )
+          .to_string()
+      );
+    }
+
+    self.send_get(req, url).await
+  }
+}
+
+impl ReqwestHttp {
+  async fn send_get(
+    &self,
+    req: reqwest::RequestBuilder,
+    url: &str
   ) -> GetResult {
     let start =
       tokio::time::Instant::now();
 
     debug!(url, "HTTP GET start");
 
-    match self
-      .client
-      .get(url)
-      .send()
-      .await
-    {
+    match req.send().await {
       | Ok(resp) => {
         let status =
           Some(resp.status().as_u16());
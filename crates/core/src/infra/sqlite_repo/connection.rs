@@ -1,6 +1,6 @@
 //! Helpers to create/configure the
-//! SQLite pool and backfill missing
-//! columns.
+//! SQLite pools and backfill columns
+//! for the baseline migration.
 
 use std::path::{
   Path,
@@ -9,17 +9,54 @@ use std::path::{
 use std::str::FromStr;
 use std::time::Duration;
 
-use sqlx::SqlitePool;
 use sqlx::sqlite::{
   SqliteConnectOptions,
   SqliteJournalMode,
   SqlitePoolOptions
 };
+use sqlx::{
+  SqliteConnection,
+  SqlitePool
+};
 use tracing::info;
 
-pub async fn create_pool(
+/// Read pool and the writer's
+/// dedicated single-connection pool
+/// over the same database file.
+pub async fn create_pools(
   db_path: &Path
-) -> Result<SqlitePool, String> {
+) -> Result<
+  (SqlitePool, SqlitePool),
+  String
+> {
+  let opts = connect_options(db_path)?;
+
+  let writer = SqlitePoolOptions::new()
+    .max_connections(1)
+    .min_connections(1)
+    .idle_timeout(None)
+    .max_lifetime(None)
+    .connect_with(opts.clone())
+    .await
+    .map_err(|e| {
+      format!("db connect error: {e}")
+    })?;
+
+  let reader = SqlitePoolOptions::new()
+    .max_connections(10)
+    .connect_with(opts)
+    .await
+    .map_err(|e| {
+      format!("db connect error: {e}")
+    })?;
+
+  Ok((reader, writer))
+}
+
+fn connect_options(
+  db_path: &Path
+) -> Result<SqliteConnectOptions, String>
+{
   let full_path =
     if db_path.is_absolute() {
       db_path.to_path_buf()
@@ -67,23 +104,17 @@ pub async fn create_pool(
     ))
     .foreign_keys(true);
 
-  SqlitePoolOptions::new()
-    .max_connections(10)
-    .connect_with(opts)
-    .await
-    .map_err(|e| {
-      format!("db connect error: {e}")
-    })
+  Ok(opts)
 }
 
 pub async fn ensure_feed_base_poll_column(
-  pool: &SqlitePool,
+  conn: &mut SqliteConnection,
   default_poll_seconds: u64
 ) -> Result<(), String> {
   let has_column: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'base_poll_seconds' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect feeds table: {e}"))?;
 
@@ -99,7 +130,7 @@ pub async fn ensure_feed_base_poll_column(
   );
 
   sqlx::query(&ddl)
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|e| {
       format!(
@@ -118,12 +149,12 @@ pub async fn ensure_feed_base_poll_column(
 }
 
 pub async fn ensure_feed_state_note_column(
-  pool: &SqlitePool
+  conn: &mut SqliteConnection
 ) -> Result<(), String> {
   let has_column: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM pragma_table_info('feed_state_current') WHERE name = 'note' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect feed_state_current: {e}"))?;
 
@@ -135,7 +166,7 @@ pub async fn ensure_feed_state_note_column(
     "ALTER TABLE feed_state_current \
      ADD COLUMN note TEXT NULL"
   )
-  .execute(pool)
+  .execute(&mut *conn)
   .await
   .map_err(|e| {
     format!("add note column: {e}")
@@ -150,7 +181,7 @@ pub async fn ensure_feed_state_note_column(
 }
 
 pub async fn ensure_feed_state_error_count_column(
-  pool: &SqlitePool,
+  conn: &mut SqliteConnection,
   table: &str
 ) -> Result<(), String> {
   let sql = format!(
@@ -162,7 +193,7 @@ pub async fn ensure_feed_state_error_count_column(
 
   let has_column: Option<i64> =
     sqlx::query_scalar(&sql)
-      .fetch_optional(pool)
+      .fetch_optional(&mut *conn)
       .await
       .map_err(|e| {
         format!(
@@ -181,7 +212,7 @@ pub async fn ensure_feed_state_error_count_column(
   );
 
   sqlx::query(&ddl)
-    .execute(pool)
+    .execute(&mut *conn)
     .await
     .map_err(|e| {
       format!(
@@ -199,13 +230,62 @@ pub async fn ensure_feed_state_error_count_column(
   Ok(())
 }
 
+pub async fn ensure_feed_state_get_only_column(
+  conn: &mut SqliteConnection,
+  table: &str
+) -> Result<(), String> {
+  let sql = format!(
+    "SELECT 1 FROM \
+     pragma_table_info('{table}') \
+     WHERE name = 'get_only_since_ms' \
+     LIMIT 1"
+  );
+
+  let has_column: Option<i64> =
+    sqlx::query_scalar(&sql)
+      .fetch_optional(&mut *conn)
+      .await
+      .map_err(|e| {
+        format!(
+          "introspect {table}: {e}"
+        )
+      })?;
+
+  if has_column.is_some() {
+    return Ok(());
+  }
+
+  let ddl = format!(
+    "ALTER TABLE {table} ADD COLUMN \
+     get_only_since_ms INTEGER NULL"
+  );
+
+  sqlx::query(&ddl)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!(
+        "add get_only_since_ms \
+         column: {e}"
+      )
+    })?;
+
+  info!(
+    table,
+    "Added get_only_since_ms column \
+     to feed state table"
+  );
+
+  Ok(())
+}
+
 pub async fn ensure_feed_tags_column(
-  pool: &SqlitePool
+  conn: &mut SqliteConnection
 ) -> Result<(), String> {
   let has_table: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect sqlite_master: {e}"))?;
 
@@ -216,7 +296,7 @@ pub async fn ensure_feed_tags_column(
   let has_column: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'tags' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect feeds table: {e}"))?;
 
@@ -228,7 +308,7 @@ pub async fn ensure_feed_tags_column(
     "ALTER TABLE feeds ADD COLUMN \
      tags TEXT NULL"
   )
-  .execute(pool)
+  .execute(&mut *conn)
   .await
   .map_err(|e| {
     format!("add tags column: {e}")
@@ -240,12 +320,12 @@ pub async fn ensure_feed_tags_column(
 }
 
 pub async fn ensure_feed_category_column(
-  pool: &SqlitePool
+  conn: &mut SqliteConnection
 ) -> Result<(), String> {
   let has_table: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect sqlite_master: {e}"))?;
 
@@ -256,7 +336,7 @@ pub async fn ensure_feed_category_column(
   let has_column: Option<i64> = sqlx::query_scalar(
         r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'category' LIMIT 1"#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
     .map_err(|e| format!("introspect feeds table: {e}"))?;
 
@@ -268,7 +348,7 @@ pub async fn ensure_feed_category_column(
     "ALTER TABLE feeds ADD COLUMN \
      category TEXT NULL"
   )
-  .execute(pool)
+  .execute(&mut *conn)
   .await
   .map_err(|e| {
     format!("add category column: {e}")
@@ -281,6 +361,91 @@ pub async fn ensure_feed_category_column(
   Ok(())
 }
 
+pub async fn ensure_feed_dataset_column(
+  conn: &mut SqliteConnection
+) -> Result<(), String> {
+  let has_table: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("introspect sqlite_master: {e}"))?;
+
+  if has_table.is_none() {
+    return Ok(());
+  }
+
+  let has_column: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'dataset' LIMIT 1"#,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("introspect feeds table: {e}"))?;
+
+  if has_column.is_some() {
+    return Ok(());
+  }
+
+  sqlx::query(
+    "ALTER TABLE feeds ADD COLUMN \
+     dataset TEXT NULL"
+  )
+  .execute(&mut *conn)
+  .await
+  .map_err(|e| {
+    format!("add dataset column: {e}")
+  })?;
+
+  info!(
+    "Added dataset column to feeds"
+  );
+
+  Ok(())
+}
+
+pub async fn ensure_feed_priority_column(
+  conn: &mut SqliteConnection
+) -> Result<(), String> {
+  let has_table: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("introspect sqlite_master: {e}"))?;
+
+  if has_table.is_none() {
+    return Ok(());
+  }
+
+  let has_column: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'priority' LIMIT 1"#,
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| format!("introspect feeds table: {e}"))?;
+
+  if has_column.is_some() {
+    return Ok(());
+  }
+
+  sqlx::query(
+    "ALTER TABLE feeds ADD COLUMN \
+     priority INTEGER NOT NULL \
+     DEFAULT 1"
+  )
+  .execute(&mut *conn)
+  .await
+  .map_err(|e| {
+    format!("add priority column: {e}")
+  })?;
+
+  info!(
+    "Added priority column to feeds"
+  );
+
+  Ok(())
+}
+
 pub async fn set_synchronous(
   pool: &SqlitePool,
   mode: &str
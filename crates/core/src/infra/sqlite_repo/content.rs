@@ -0,0 +1,72 @@
+//! Stored content a fetch is compared
+//! against, and dataset versions.
+
+use chrono_tz::Tz;
+
+use super::{
+  SqliteRepo,
+  datasets
+};
+use crate::feed::parser::ParsedFeed;
+use crate::ports::repo::{
+  ContentRepo,
+  DatasetVersion
+};
+
+#[async_trait::async_trait]
+
+impl ContentRepo for SqliteRepo {
+  async fn latest_content_hash(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    let hash: Option<Option<String>> = sqlx::query_scalar(
+            r#"
+        SELECT content_hash
+        FROM feed_payloads
+        WHERE feed_id = ?1
+        ORDER BY fetched_at_ms DESC, id DESC
+        LIMIT 1
+        "#,
+        )
+        .bind(feed_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("latest_content_hash error: {e}"))?;
+
+    Ok(hash.flatten())
+  }
+
+  async fn latest_dataset_version(
+    &self,
+    feed_id: &str
+  ) -> Result<
+    Option<DatasetVersion>,
+    String
+  > {
+    datasets::latest_dataset_version(
+      &self.pool, feed_id
+    )
+    .await
+  }
+
+  async fn insert_dataset_version(
+    &self,
+    version: &DatasetVersion,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    items: &ParsedFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .writer
+      .insert_dataset_version(
+        version,
+        etag,
+        last_modified_ms,
+        items
+      )
+      .await
+  }
+}
@@ -1,7 +1,6 @@
 //! Records feeds that exceeded the
 //! consecutive error threshold.
 
-use chrono_tz::Tz;
 use sqlx::SqlitePool;
 
 use crate::domain::model::ErrorKind;
@@ -12,8 +11,7 @@ pub async fn mark_feed_error(
   error_kind: Option<ErrorKind>,
   status: Option<i64>,
   error_count: i64,
-  observed_at_ms: i64,
-  _zone: &Tz
+  observed_at_ms: i64
 ) -> Result<(), String> {
   sqlx::query(
         r#"
@@ -1,26 +1,36 @@
 //! Records fetch events (HEAD/GET) with
 //! timing/status/error info.
 
-use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::query::Query;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{
+  Sqlite,
+  SqlitePool
+};
 
-use super::util::now_epoch_ms;
-use crate::domain::model::ErrorKind;
+use crate::ports::repo::PendingEvent;
 
 pub async fn insert_event(
   pool: &SqlitePool,
-  feed_id: &str,
-  method: &str,
-  status: Option<i64>,
-  error_kind: Option<ErrorKind>,
-  latency_ms: Option<i64>,
-  backoff_index: i64,
-  scheduled_next_action_at_ms: i64,
-  debug: Option<&str>,
-  _zone: &Tz
+  event: &PendingEvent
 ) -> Result<(), String> {
-  let now_ms = now_epoch_ms();
+  event_query(event)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!("insert_event error: {e}")
+    })?;
+
+  Ok(())
+}
 
+pub fn event_query(
+  event: &PendingEvent
+) -> Query<
+  '_,
+  Sqlite,
+  SqliteArguments<'_>
+> {
   sqlx::query(
         r#"
       INSERT INTO fetch_events(
@@ -34,18 +44,13 @@ pub async fn insert_event(
       )
       "#,
     )
-    .bind(feed_id)
-    .bind(now_ms)
-    .bind(method)
-    .bind(status)
-    .bind(error_kind.map(|e| format!("{:?}", e)))
-    .bind(latency_ms)
-    .bind(backoff_index)
-    .bind(scheduled_next_action_at_ms)
-    .bind(debug.map(|s| s.to_string()))
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_event error: {e}"))?;
-
-  Ok(())
+    .bind(&event.feed_id)
+    .bind(event.event_time_ms)
+    .bind(&event.method)
+    .bind(event.status)
+    .bind(event.error_kind.map(|e| format!("{:?}", e)))
+    .bind(event.latency_ms)
+    .bind(event.backoff_index)
+    .bind(event.scheduled_next_action_at_ms)
+    .bind(&event.debug)
 }
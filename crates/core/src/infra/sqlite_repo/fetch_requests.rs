@@ -0,0 +1,289 @@
+//! On-demand refresh commands written
+//! by the server and CLI and claimed by
+//! the category loops.
+
+use std::collections::HashSet;
+
+use sqlx::SqlitePool;
+
+use super::models::DueFeedRow;
+use crate::domain::model::FeedConfig;
+use crate::ports::repo::{
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus
+};
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct FetchRequestRow {
+  id:              i64,
+  feed_id:         String,
+  requested_by:    Option<String>,
+  requested_at_ms: i64,
+  status:          String,
+  started_at_ms:   Option<i64>,
+  finished_at_ms:  Option<i64>,
+  http_status:     Option<i64>,
+  error:           Option<String>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct ClaimRow {
+  request_id:        i64,
+  id:                String,
+  url:               String,
+  domain:            String,
+  category:          String,
+  base_poll_seconds: i64,
+  dataset:           Option<String>,
+  priority:          i64
+}
+
+pub async fn enqueue_fetch_request(
+  pool: &SqlitePool,
+  feed_id: &str,
+  requested_by: Option<&str>,
+  requested_at_ms: i64
+) -> Result<i64, String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  let known: Option<String> = sqlx::query_scalar(
+        r#"SELECT id FROM feeds WHERE id = ?1"#,
+    )
+    .bind(feed_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("enqueue_fetch_request error: {e}"))?;
+
+  if known.is_none() {
+    return Err(format!(
+      "unknown feed '{feed_id}'"
+    ));
+  }
+
+  let pending: Option<i64> = sqlx::query_scalar(
+        r#"
+      SELECT id FROM fetch_requests
+      WHERE feed_id = ?1 AND status = 'pending'
+      ORDER BY id
+      LIMIT 1
+      "#,
+    )
+    .bind(feed_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("enqueue_fetch_request error: {e}"))?;
+
+  let id = match pending {
+    | Some(id) => id,
+    | None => {
+      sqlx::query_scalar(
+                r#"
+          INSERT INTO fetch_requests(feed_id, requested_by, requested_at_ms, status)
+          VALUES (?1, ?2, ?3, 'pending')
+          RETURNING id
+          "#,
+            )
+            .bind(feed_id)
+            .bind(requested_by)
+            .bind(requested_at_ms)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| format!("enqueue_fetch_request error: {e}"))?
+    }
+  };
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(id)
+}
+
+pub async fn fetch_request(
+  pool: &SqlitePool,
+  id: i64
+) -> Result<Option<FetchRequest>, String>
+{
+  let row = sqlx::query_as::<_, FetchRequestRow>(
+        r#"
+      SELECT id, feed_id, requested_by, requested_at_ms, status,
+             started_at_ms, finished_at_ms, http_status, error
+      FROM fetch_requests
+      WHERE id = ?1
+      "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("fetch_request error: {e}"))?;
+
+  row
+    .map(|r| {
+      Ok(FetchRequest {
+        id:              r.id,
+        feed_id:         r.feed_id,
+        requested_by:    r.requested_by,
+        requested_at_ms: r
+          .requested_at_ms,
+        status:
+          FetchRequestStatus::parse(
+            &r.status
+          )?,
+        started_at_ms:   r
+          .started_at_ms,
+        finished_at_ms:  r
+          .finished_at_ms,
+        http_status:     r.http_status,
+        error:           r.error
+      })
+    })
+    .transpose()
+}
+
+/// SQLite has a single fetcher, so the
+/// per-feed and busy filtering happens
+/// here rather than in SQL.
+pub async fn claim_fetch_requests(
+  pool: &SqlitePool,
+  category: &str,
+  busy_feed_ids: &[String],
+  limit: usize,
+  now_ms: i64,
+  stale_before_ms: i64
+) -> Result<Vec<ClaimedFetch>, String> {
+  if limit == 0 {
+    return Ok(Vec::new());
+  }
+
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  let rows = sqlx::query_as::<_, ClaimRow>(
+        r#"
+      SELECT r.id AS request_id, f.id, f.url, f.domain, f.category,
+             f.base_poll_seconds, f.dataset, f.priority
+      FROM fetch_requests r
+      JOIN feeds f ON f.id = r.feed_id
+      WHERE f.category = ?1
+        AND (r.status = 'pending'
+             OR (r.status = 'running' AND r.started_at_ms < ?2))
+      ORDER BY f.priority, r.id
+      "#,
+    )
+    .bind(category)
+    .bind(stale_before_ms)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| format!("claim_fetch_requests error: {e}"))?;
+
+  let mut seen: HashSet<String> =
+    busy_feed_ids
+      .iter()
+      .cloned()
+      .collect();
+
+  let mut claimed = Vec::new();
+
+  for row in rows {
+    if claimed.len() >= limit
+      || !seen.insert(row.id.clone())
+    {
+      continue;
+    }
+
+    sqlx::query(
+            r#"
+        UPDATE fetch_requests
+        SET status = 'running', started_at_ms = ?2
+        WHERE id = ?1
+        "#,
+        )
+        .bind(row.request_id)
+        .bind(now_ms)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("claim_fetch_requests error: {e}"))?;
+
+    claimed.push(ClaimedFetch {
+      request_id: row.request_id,
+      feed:       FeedConfig::from(
+        DueFeedRow {
+          id:                row.id,
+          url:               row.url,
+          domain:            row.domain,
+          category:          row
+            .category,
+          base_poll_seconds: row
+            .base_poll_seconds,
+          dataset:           row
+            .dataset,
+          priority:          row
+            .priority,
+          next_action_at_ms: None
+        }
+      )
+    });
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(claimed)
+}
+
+pub async fn requeue_fetch_requests(
+  pool: &SqlitePool,
+  ids: &[i64]
+) -> Result<(), String> {
+  for id in ids {
+    sqlx::query(
+            r#"
+        UPDATE fetch_requests
+        SET status = 'pending', started_at_ms = NULL
+        WHERE id = ?1 AND status = 'running'
+        "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("requeue_fetch_requests error: {e}"))?;
+  }
+
+  Ok(())
+}
+
+pub async fn finish_fetch_request(
+  pool: &SqlitePool,
+  id: i64,
+  status: FetchRequestStatus,
+  http_status: Option<i64>,
+  error: Option<&str>,
+  finished_at_ms: i64
+) -> Result<(), String> {
+  sqlx::query(
+        r#"
+      UPDATE fetch_requests
+      SET status = ?2, http_status = ?3, error = ?4, finished_at_ms = ?5
+      WHERE id = ?1
+      "#,
+    )
+    .bind(id)
+    .bind(status.as_str())
+    .bind(http_status)
+    .bind(error)
+    .bind(finished_at_ms)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("finish_fetch_request error: {e}"))?;
+
+  Ok(())
+}
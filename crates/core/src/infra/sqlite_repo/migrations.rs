@@ -1,25 +1,48 @@
-//! Database migrations: create
-//! tables/indexes and ensure new
-//! columns exist.
+//! Database migrations: numbered steps
+//! recorded in `schema_migrations`,
+//! each applied in its own transaction.
 
 use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::{
+  SqliteConnection,
+  SqlitePool
+};
 use tracing::info;
 
 use super::connection::{
   ensure_feed_base_poll_column,
   ensure_feed_category_column,
+  ensure_feed_dataset_column,
+  ensure_feed_priority_column,
   ensure_feed_state_error_count_column,
+  ensure_feed_state_get_only_column,
   ensure_feed_state_note_column,
   ensure_feed_tags_column
 };
+use super::util::now_epoch_ms;
+use crate::ports::repo::{
+  SchemaMigration,
+  SchemaStatus
+};
 
-const SQLITE_SCHEMA: &str =
+/// The DDL of migration 1, frozen as
+/// shipped.
+const BASELINE_SQL: &str =
   include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
-    "/res/sql/sqlite/schema.sql"
+    "/res/sql/sqlite/migrations/\
+     0001_baseline.sql"
   ));
 
+/// Every migration, oldest first. An
+/// applied step is never edited: new
+/// DDL goes in a new numbered step,
+/// appended here with its file under
+/// `res/sql/sqlite/migrations/` and
+/// handled in `apply`.
+pub const MIGRATIONS: [(i64, &str); 1] =
+  [(1, "baseline")];
+
 pub async fn migrate(
   pool: &SqlitePool,
   _zone: &Tz,
@@ -38,17 +61,213 @@ pub async fn migrate(
     )
   })?;
 
+  sqlx::query(
+    "CREATE TABLE IF NOT EXISTS \
+     schema_migrations(version \
+     INTEGER PRIMARY KEY, name TEXT \
+     NOT NULL, applied_at INTEGER NOT \
+     NULL)"
+  )
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "migrate error (table): {e}"
+    )
+  })?;
+
+  let status = status(pool).await?;
+
+  status.check()?;
+
+  for step in status.pending() {
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(|e| {
+        format!("migrate error: {e}")
+      })?;
+
+    apply(
+      &mut tx,
+      step.version,
+      default_poll_seconds
+    )
+    .await
+    .map_err(|e| {
+      format!(
+        "migration {} ({}): {e}",
+        step.version, step.name
+      )
+    })?;
+
+    sqlx::query(
+      "INSERT INTO \
+       schema_migrations(version, \
+       name, applied_at) VALUES (?, \
+       ?, ?)"
+    )
+    .bind(step.version)
+    .bind(&step.name)
+    .bind(now_epoch_ms())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+      format!("migrate error: {e}")
+    })?;
+
+    tx.commit().await.map_err(|e| {
+      format!("migrate error: {e}")
+    })?;
+
+    info!(
+      version = step.version,
+      name = %step.name,
+      "Applied migration"
+    );
+  }
+
+  info!("DB migrate done");
+
+  Ok(())
+}
+
+pub async fn status(
+  pool: &SqlitePool
+) -> Result<SchemaStatus, String> {
+  let has_table: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = 'schema_migrations'"
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?;
+
+  let applied = if has_table.is_some() {
+    sqlx::query_as(
+      "SELECT version, name, \
+       applied_at FROM \
+       schema_migrations ORDER BY \
+       version"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?
+  } else {
+    Vec::new()
+  };
+
+  Ok(SchemaStatus::new(
+    &MIGRATIONS,
+    applied
+  ))
+}
+
+/// The migrations `pulsewire-server`
+/// recorded in this database, oldest
+/// first; empty before it first ran
+/// here.
+pub async fn server_status(
+  pool: &SqlitePool
+) -> Result<Vec<SchemaMigration>, String>
+{
+  let has_table: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = 'server_schema_migrations'"
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?;
+
+  if has_table.is_none() {
+    return Ok(Vec::new());
+  }
+
+  // The server stores `applied_at` as
+  // UTC `datetime('now')` text.
+  let rows: Vec<(i64, String, i64)> =
+    sqlx::query_as(
+      "SELECT version, name, \
+       CAST(strftime('%s', \
+       applied_at) AS INTEGER) * 1000 \
+       FROM server_schema_migrations \
+       ORDER BY version"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("migration status: {e}")
+    })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(version, name, at)| {
+        SchemaMigration {
+          version,
+          name,
+          applied_at_ms: Some(at)
+        }
+      })
+      .collect()
+  )
+}
+
+async fn apply(
+  conn: &mut SqliteConnection,
+  version: i64,
+  default_poll_seconds: u64
+) -> Result<(), String> {
+  match version {
+    | 1 => {
+      baseline(
+        conn,
+        default_poll_seconds
+      )
+      .await
+    }
+    | _ => {
+      Err(format!(
+        "unknown migration {version}"
+      ))
+    }
+  }
+}
+
+/// The schema as of versioning, plus
+/// the column backfills older databases
+/// relied on; safe on any of them.
+async fn baseline(
+  conn: &mut SqliteConnection,
+  default_poll_seconds: u64
+) -> Result<(), String> {
   // Ensure category exists before
   // schema indexes use it on existing
   // DBs.
-  ensure_feed_category_column(pool)
+  ensure_feed_category_column(conn)
     .await?;
 
-  ensure_feed_tags_column(pool).await?;
+  ensure_feed_tags_column(conn).await?;
+
+  ensure_feed_dataset_column(conn)
+    .await?;
+
+  ensure_feed_priority_column(conn)
+    .await?;
 
   for ddl in schema_statements() {
     sqlx::query(ddl)
-      .execute(pool)
+      .execute(&mut *conn)
       .await
       .map_err(|e| {
         format!(
@@ -58,34 +277,35 @@ pub async fn migrate(
   }
 
   ensure_feed_base_poll_column(
-    pool,
+    conn,
     default_poll_seconds
   )
   .await?;
 
-  ensure_feed_state_note_column(pool)
+  ensure_feed_state_note_column(conn)
     .await?;
 
-  ensure_feed_state_error_count_column(
-    pool,
-    "feed_state_current"
-  )
-  .await?;
-
-  ensure_feed_state_error_count_column(
-    pool,
+  for table in [
+    "feed_state_current",
     "feed_state_history"
-  )
-  .await?;
+  ] {
+    ensure_feed_state_error_count_column(
+      conn, table
+    )
+    .await?;
 
-  info!("DB migrate done");
+    ensure_feed_state_get_only_column(
+      conn, table
+    )
+    .await?;
+  }
 
   Ok(())
 }
 
 fn schema_statements()
 -> impl Iterator<Item = &'static str> {
-  SQLITE_SCHEMA
+  BASELINE_SQL
     .split(';')
     .map(str::trim)
     .filter(|s| !s.is_empty())
@@ -1,47 +1,86 @@
 //! SQLite-backed repository
 //! implementing persistence for feeds,
-//! state, events, and payloads.
+//! state, events, and payloads. Writes
+//! go through a single [`Writer`] task;
+//! reads use the pool.
 
+mod archive;
 mod connection;
+mod content;
+mod controls;
+mod datasets;
 mod error_feeds;
 mod events;
 mod feeds;
-mod migrations;
+mod fetch_requests;
+mod health;
+mod items;
+pub(crate) mod migrations;
 mod models;
 mod payloads;
+mod retention;
 mod state;
 mod util;
+mod writer;
+mod writes;
 
 use std::path::Path;
 
 use chrono_tz::Tz;
+pub(crate) use connection::create_pools;
 use sqlx::SqlitePool;
+use util::now_epoch_ms;
+use writer::Writer;
 
 use crate::domain::link_state::LinkState;
 use crate::domain::model::{
   ErrorKind,
   FeedConfig
 };
-use crate::feed::parser::ParsedFeed;
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
 use crate::ports::repo::{
+  ArchivedPayload,
+  ClaimedFetch,
+  FetchRequest,
+  FetchRequestStatus,
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup,
+  PartitionPlan,
+  PartitionReport,
+  PayloadFilter,
+  PayloadRef,
+  PendingEvent,
+  PruneScope,
+  PruneTarget,
   Repo,
-  StateRow
+  ScheduledFeed,
+  SchemaStatus,
+  StateRow,
+  StoredItem,
+  WriteBatch
 };
 
 pub struct SqliteRepo {
-  pool: SqlitePool
+  pool:   SqlitePool,
+  writer: Writer
 }
 
 impl SqliteRepo {
   pub async fn new(
     db_path: &Path
   ) -> Result<Self, String> {
-    let pool =
-      connection::create_pool(db_path)
+    let (pool, writer) =
+      connection::create_pools(db_path)
         .await?;
 
     Ok(Self {
-      pool
+      pool,
+      writer: Writer::spawn(writer)
     })
   }
 }
@@ -54,12 +93,19 @@ impl Repo for SqliteRepo {
     zone: &Tz,
     default_poll_seconds: u64
   ) -> Result<(), String> {
-    migrations::migrate(
-      &self.pool,
-      zone,
-      default_poll_seconds
-    )
-    .await
+    self
+      .writer
+      .migrate(
+        *zone,
+        default_poll_seconds
+      )
+      .await
+  }
+
+  async fn schema_status(
+    &self
+  ) -> Result<SchemaStatus, String> {
+    migrations::status(&self.pool).await
   }
 
   async fn upsert_feeds_bulk(
@@ -68,11 +114,12 @@ impl Repo for SqliteRepo {
     chunk_size: usize,
     zone: &Tz
   ) -> Result<(), String> {
-    feeds::upsert_feeds_bulk(
-      &self.pool, feeds, chunk_size,
-      zone
-    )
-    .await
+    self
+      .writer
+      .upsert_feeds_bulk(
+        feeds, chunk_size, *zone
+      )
+      .await
   }
 
   async fn upsert_categories(
@@ -80,11 +127,10 @@ impl Repo for SqliteRepo {
     categories: Vec<String>,
     _zone: &Tz
   ) -> Result<(), String> {
-    feeds::upsert_categories(
-      &self.pool,
-      &categories
-    )
-    .await
+    self
+      .writer
+      .upsert_categories(categories)
+      .await
   }
 
   async fn latest_state(
@@ -98,16 +144,13 @@ impl Repo for SqliteRepo {
     .await
   }
 
-  async fn due_feeds_for_category(
+  async fn scheduled_feeds_for_category(
     &self,
-    category: &str,
-    now_ms: i64,
-    limit: i64
-  ) -> Result<Vec<FeedConfig>, String>
+    category: &str
+  ) -> Result<Vec<ScheduledFeed>, String>
   {
-    feeds::due_feeds(
-      &self.pool, category, now_ms,
-      limit
+    feeds::scheduled_feeds(
+      &self.pool, category
     )
     .await
   }
@@ -116,17 +159,17 @@ impl Repo for SqliteRepo {
     &self,
     state: &LinkState,
     recorded_at_ms: i64,
-    zone: &Tz,
+    _zone: &Tz,
     record_history: bool
   ) -> Result<(), String> {
-    state::insert_state(
-      &self.pool,
-      state,
-      recorded_at_ms,
-      zone,
-      record_history
-    )
-    .await
+    self
+      .writer
+      .insert_state(
+        state,
+        recorded_at_ms,
+        record_history
+      )
+      .await
   }
 
   async fn insert_event(
@@ -139,21 +182,31 @@ impl Repo for SqliteRepo {
     backoff_index: i64,
     scheduled_next_action_at_ms: i64,
     debug: Option<&str>,
-    zone: &Tz
+    _zone: &Tz
   ) -> Result<(), String> {
-    events::insert_event(
-      &self.pool,
-      feed_id,
-      method,
-      status,
-      error_kind,
-      latency_ms,
-      backoff_index,
-      scheduled_next_action_at_ms,
-      debug,
-      zone
-    )
-    .await
+    self
+      .writer
+      .insert_event(PendingEvent {
+        feed_id: feed_id.to_string(),
+        event_time_ms: now_epoch_ms(),
+        method: method.to_string(),
+        status,
+        error_kind,
+        latency_ms,
+        backoff_index,
+        scheduled_next_action_at_ms,
+        debug: debug
+          .map(str::to_string)
+      })
+      .await
+  }
+
+  async fn write_batch(
+    &self,
+    batch: &WriteBatch,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.writer.write_batch(batch).await
   }
 
   async fn insert_payload_with_items(
@@ -164,19 +217,19 @@ impl Repo for SqliteRepo {
     last_modified_ms: Option<i64>,
     content_hash: Option<&str>,
     parsed: &ParsedFeed,
-    zone: &Tz
+    _zone: &Tz
   ) -> Result<(), String> {
-    payloads::insert_payload_with_items(
-      &self.pool,
-      feed_id,
-      fetched_at_ms,
-      etag,
-      last_modified_ms,
-      content_hash,
-      parsed,
-      zone
-    )
-    .await
+    self
+      .writer
+      .insert_payload_with_items(
+        feed_id,
+        fetched_at_ms,
+        etag,
+        last_modified_ms,
+        content_hash,
+        parsed
+      )
+      .await
   }
 
   async fn mark_feed_error(
@@ -186,17 +239,313 @@ impl Repo for SqliteRepo {
     status: Option<i64>,
     error_count: i64,
     observed_at_ms: i64,
-    zone: &Tz
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .writer
+      .mark_feed_error(
+        feed_id,
+        error_kind,
+        status,
+        error_count,
+        observed_at_ms
+      )
+      .await
+  }
+
+  async fn touch_archived_payload(
+    &self,
+    content_hash: &str,
+    seen_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<bool, String> {
+    self
+      .writer
+      .touch_archived_payload(
+        content_hash,
+        seen_at_ms
+      )
+      .await
+  }
+
+  async fn insert_archived_payload(
+    &self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64,
+    _zone: &Tz
   ) -> Result<(), String> {
-    error_feeds::mark_feed_error(
+    self
+      .writer
+      .insert_archived_payload(
+        payload, seen_at_ms
+      )
+      .await
+  }
+
+  async fn archived_payload(
+    &self,
+    content_hash: &str
+  ) -> Result<
+    Option<ArchivedPayload>,
+    String
+  > {
+    archive::archived_payload(
       &self.pool,
-      feed_id,
-      error_kind,
-      status,
-      error_count,
-      observed_at_ms,
-      zone
+      content_hash
+    )
+    .await
+  }
+
+  async fn prune_archived_payloads(
+    &self,
+    seen_before_ms: i64,
+    limit: i64,
+    _zone: &Tz
+  ) -> Result<
+    Vec<ArchivedPayload>,
+    String
+  > {
+    self
+      .writer
+      .prune_archived_payloads(
+        seen_before_ms,
+        limit
+      )
+      .await
+  }
+
+  async fn archived_payload_refs(
+    &self,
+    filter: &PayloadFilter,
+    after_id: i64,
+    limit: i64,
+    _zone: &Tz
+  ) -> Result<Vec<PayloadRef>, String>
+  {
+    payloads::archived_payload_refs(
+      &self.pool, filter, after_id,
+      limit
+    )
+    .await
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItem>, String>
+  {
+    items::payload_items(
+      &self.pool, payload_id
+    )
+    .await
+  }
+
+  async fn apply_payload_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    inserts: &[FeedItem],
+    updates: &[StoredItem],
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .writer
+      .apply_payload_items(
+        payload_id, feed_id, inserts,
+        updates
+      )
+      .await
+  }
+
+  async fn prune_rows(
+    &self,
+    target: PruneTarget,
+    scope: &PruneScope,
+    _zone: &Tz
+  ) -> Result<u64, String> {
+    self
+      .writer
+      .prune_rows(target, scope)
+      .await
+  }
+
+  async fn maintain_partitions(
+    &self,
+    _plan: &PartitionPlan
+  ) -> Result<PartitionReport, String>
+  {
+    Ok(PartitionReport::default())
+  }
+
+  async fn health_events(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<HealthEvent>, String>
+  {
+    health::health_events(
+      &self.pool, start_ms, end_ms
+    )
+    .await
+  }
+
+  async fn health_payloads(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<HealthPayload>, String>
+  {
+    health::health_payloads(
+      &self.pool, start_ms, end_ms
+    )
+    .await
+  }
+
+  async fn replace_health_rollups(
+    &self,
+    day: &str,
+    rows: &[HealthRollup],
+    now_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .writer
+      .replace_health_rollups(
+        day, rows, now_ms
+      )
+      .await
+  }
+
+  async fn health_rollups(
+    &self,
+    query: &HealthQuery
+  ) -> Result<Vec<HealthRollup>, String>
+  {
+    health::health_rollups(
+      &self.pool, query
+    )
+    .await
+  }
+
+  async fn claim_feed_leases(
+    &self,
+    _owner: &str,
+    feed_ids: &[String],
+    _ttl_seconds: u64
+  ) -> Result<Vec<String>, String> {
+    Ok(feed_ids.to_vec())
+  }
+
+  async fn renew_feed_lease(
+    &self,
+    _owner: &str,
+    _feed_id: &str,
+    _ttl_seconds: u64
+  ) -> Result<bool, String> {
+    Ok(true)
+  }
+
+  async fn release_feed_leases(
+    &self,
+    _owner: &str,
+    _feed_ids: &[String]
+  ) -> Result<(), String> {
+    Ok(())
+  }
+
+  async fn acquire_domain_token(
+    &self,
+    _domain: &str,
+    _limit: usize,
+    _owner: &str,
+    _feed_id: &str,
+    _ttl_seconds: u64
+  ) -> Result<bool, String> {
+    Ok(true)
+  }
+
+  async fn enqueue_fetch_request(
+    &self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<i64, String> {
+    self
+      .writer
+      .enqueue_fetch_request(
+        feed_id,
+        requested_by,
+        requested_at_ms
+      )
+      .await
+  }
+
+  async fn fetch_request(
+    &self,
+    id: i64
+  ) -> Result<
+    Option<FetchRequest>,
+    String
+  > {
+    fetch_requests::fetch_request(
+      &self.pool, id
     )
     .await
   }
+
+  async fn claim_fetch_requests(
+    &self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64,
+    _zone: &Tz
+  ) -> Result<Vec<ClaimedFetch>, String>
+  {
+    self
+      .writer
+      .claim_fetch_requests(
+        category,
+        busy_feed_ids,
+        limit,
+        now_ms,
+        stale_before_ms
+      )
+      .await
+  }
+
+  async fn requeue_fetch_requests(
+    &self,
+    ids: &[i64]
+  ) -> Result<(), String> {
+    self
+      .writer
+      .requeue_fetch_requests(ids)
+      .await
+  }
+
+  async fn finish_fetch_request(
+    &self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .writer
+      .finish_fetch_request(
+        id,
+        status,
+        http_status,
+        error,
+        finished_at_ms
+      )
+      .await
+  }
 }
@@ -1,7 +1,10 @@
 //! Row structs and conversions between
 //! SQLx records and domain types.
 
-use crate::domain::model::FeedConfig;
+use crate::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
 use crate::ports::repo::StateRow;
 
 #[derive(Debug, sqlx::FromRow)]
@@ -22,7 +25,8 @@ pub struct StateRowRecord {
   pub next_action_at_ms:       i64,
   pub jitter_seconds:          i64,
   pub note: Option<String>,
-  pub consecutive_error_count: i64
+  pub consecutive_error_count: i64,
+  pub get_only_since_ms: Option<i64>
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -32,7 +36,10 @@ pub struct DueFeedRow {
   pub url:               String,
   pub domain:            String,
   pub category:          String,
-  pub base_poll_seconds: i64
+  pub base_poll_seconds: i64,
+  pub dataset:           Option<String>,
+  pub priority:          i64,
+  pub next_action_at_ms: Option<i64>
 }
 
 impl From<StateRowRecord> for StateRow {
@@ -71,7 +78,9 @@ impl From<StateRowRecord> for StateRow {
       note:                    value
         .note,
       consecutive_error_count: value
-        .consecutive_error_count
+        .consecutive_error_count,
+      get_only_since_ms:       value
+        .get_only_since_ms
     }
   }
 }
@@ -90,7 +99,17 @@ impl From<DueFeedRow> for FeedConfig {
       provenance:        None,
       tags:              None,
       language:          None,
-      content_type:      None
+      content_type:      None,
+      dataset:           row
+        .dataset
+        .as_deref()
+        .and_then(|raw| {
+          serde_json::from_str(raw).ok()
+        }),
+      priority:
+        FeedPriority::from_rank(
+          row.priority
+        )
     }
   }
 }
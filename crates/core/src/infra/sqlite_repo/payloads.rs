@@ -2,11 +2,26 @@
 //! associated feed items in a single
 //! transaction.
 
-use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::{
+  SqliteConnection,
+  SqlitePool
+};
 use tracing::debug;
 
 use crate::feed::parser::ParsedFeed;
+use crate::ports::repo::{
+  PayloadFilter,
+  PayloadRef
+};
+
+#[derive(Debug, sqlx::FromRow)]
+
+struct PayloadRefRow {
+  id:            i64,
+  feed_id:       String,
+  fetched_at_ms: i64,
+  content_hash:  String
+}
 
 pub async fn insert_payload_with_items(
   pool: &SqlitePool,
@@ -15,14 +30,48 @@ pub async fn insert_payload_with_items(
   etag: Option<&str>,
   last_modified_ms: Option<i64>,
   content_hash: Option<&str>,
-  parsed: &ParsedFeed,
-  _zone: &Tz
+  parsed: &ParsedFeed
 ) -> Result<(), String> {
   let mut tx =
     pool.begin().await.map_err(
       |e| format!("tx begin: {e}")
     )?;
 
+  let payload_id = insert_in(
+    &mut tx,
+    feed_id,
+    fetched_at_ms,
+    etag,
+    last_modified_ms,
+    content_hash,
+    parsed
+  )
+  .await?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  debug!(
+    feed_id,
+    payload_id,
+    "Inserted payload + items"
+  );
+
+  Ok(())
+}
+
+/// Inserts the payload and its items
+/// inside the caller's transaction.
+pub(super) async fn insert_in(
+  conn: &mut SqliteConnection,
+  feed_id: &str,
+  fetched_at_ms: i64,
+  etag: Option<&str>,
+  last_modified_ms: Option<i64>,
+  content_hash: Option<&str>,
+  parsed: &ParsedFeed
+) -> Result<i64, String> {
   let payload_id: i64 = sqlx::query_scalar(
         r#"
   INSERT INTO feed_payloads(
@@ -49,7 +98,7 @@ pub async fn insert_payload_with_items(
     .bind(parsed.metadata.description.clone())
     .bind(parsed.metadata.language.clone())
     .bind(parsed.metadata.updated_at_ms)
-    .fetch_one(&mut *tx)
+    .fetch_one(&mut *conn)
     .await
     .map_err(|e| format!("insert payload: {e}"))?;
 
@@ -76,20 +125,62 @@ pub async fn insert_payload_with_items(
         .bind(it.category.clone())
         .bind(it.description.clone())
         .bind(it.summary.clone())
-        .execute(&mut *tx)
+        .execute(&mut *conn)
         .await
         .map_err(|e| format!("insert item: {e}"))?;
   }
 
-  tx.commit().await.map_err(|e| {
-    format!("tx commit: {e}")
-  })?;
+  Ok(payload_id)
+}
 
-  debug!(
-    feed_id,
-    payload_id,
-    "Inserted payload + items"
-  );
+pub async fn archived_payload_refs(
+  pool: &SqlitePool,
+  filter: &PayloadFilter,
+  after_id: i64,
+  limit: i64
+) -> Result<Vec<PayloadRef>, String> {
+  let rows = sqlx::query_as::<_, PayloadRefRow>(
+        r#"
+      SELECT p.id, p.feed_id, p.fetched_at_ms, p.content_hash
+      FROM feed_payloads p
+      JOIN feeds f ON f.id = p.feed_id
+      JOIN payload_archive a ON a.content_hash = p.content_hash
+      WHERE p.id > ?1
+        AND f.dataset IS NULL
+        AND NOT EXISTS (
+          SELECT 1 FROM dataset_versions v
+          WHERE v.feed_id = p.feed_id AND v.content_hash = p.content_hash
+        )
+        AND (?2 IS NULL OR p.feed_id = ?2)
+        AND (?3 IS NULL OR f.category = ?3)
+        AND (?4 IS NULL OR p.fetched_at_ms >= ?4)
+        AND (?5 IS NULL OR p.fetched_at_ms < ?5)
+      ORDER BY p.id
+      LIMIT ?6
+      "#,
+    )
+    .bind(after_id)
+    .bind(filter.feed_id.as_deref())
+    .bind(filter.category.as_deref())
+    .bind(filter.since_ms)
+    .bind(filter.until_ms)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("archived payload refs: {e}"))?;
 
-  Ok(())
+  Ok(
+    rows
+      .into_iter()
+      .map(|r| {
+        PayloadRef {
+          id:            r.id,
+          feed_id:       r.feed_id,
+          fetched_at_ms: r
+            .fetched_at_ms,
+          content_hash:  r.content_hash
+        }
+      })
+      .collect()
+  )
 }
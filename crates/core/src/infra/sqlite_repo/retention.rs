@@ -0,0 +1,166 @@
+//! Batched retention deletes for the
+//! history tables, scoped to one
+//! category.
+
+use sqlx::SqlitePool;
+
+use crate::ports::repo::{
+  PruneScope,
+  PruneTarget
+};
+
+/// Ranks rows per feed, newest first,
+/// restricted to the scope category.
+fn ranked(
+  table: &str,
+  ts: &str
+) -> String {
+  format!(
+    r#"
+    SELECT t.id AS id, t.{ts} AS ts,
+           ROW_NUMBER() OVER (
+             PARTITION BY t.feed_id
+             ORDER BY t.{ts} DESC, t.id DESC
+           ) AS rn
+    FROM {table} t
+    JOIN feeds f ON f.id = t.feed_id
+    WHERE f.category = ?1
+    "#
+  )
+}
+
+const EXPIRED: &str =
+  "(?2 IS NOT NULL AND ts < ?2) OR \
+   (?3 IS NOT NULL AND rn > ?3)";
+
+async fn table_exists(
+  pool: &SqlitePool,
+  name: &str
+) -> Result<bool, String> {
+  let found: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = ?1"
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!("table lookup: {e}")
+    })?;
+
+  Ok(found.is_some())
+}
+
+/// Server tables share the database
+/// file in SQLite; items they point at
+/// are kept.
+async fn item_guards(
+  pool: &SqlitePool
+) -> Result<String, String> {
+  let mut guards = String::new();
+
+  if table_exists(pool, "entry_states")
+    .await?
+  {
+    guards.push_str(
+      " AND NOT EXISTS (SELECT 1 FROM \
+       entry_states s WHERE s.item_id \
+       = i.id)"
+    );
+  }
+
+  if table_exists(pool, "favorites")
+    .await?
+  {
+    guards.push_str(
+      " AND NOT EXISTS (SELECT 1 FROM \
+       favorites v WHERE v.feed_id = \
+       i.feed_id)"
+    );
+  }
+
+  Ok(guards)
+}
+
+pub async fn prune_rows(
+  pool: &SqlitePool,
+  target: PruneTarget,
+  scope: &PruneScope
+) -> Result<u64, String> {
+  let sql = match target {
+    | PruneTarget::FetchEvents => {
+      format!(
+        "DELETE FROM fetch_events \
+         WHERE id IN (SELECT id FROM \
+         ({}) WHERE {EXPIRED} LIMIT \
+         ?4)",
+        ranked(
+          "fetch_events",
+          "event_time_ms"
+        )
+      )
+    }
+    | PruneTarget::StateHistory => {
+      format!(
+        "DELETE FROM \
+         feed_state_history WHERE id \
+         IN (SELECT id FROM ({}) \
+         WHERE {EXPIRED} LIMIT ?4)",
+        ranked(
+          "feed_state_history",
+          "recorded_at_ms"
+        )
+      )
+    }
+    | PruneTarget::Items => {
+      let guards =
+        item_guards(pool).await?;
+
+      format!(
+        "DELETE FROM feed_items WHERE \
+         id IN (SELECT i.id FROM \
+         feed_items i WHERE \
+         i.payload_id IN (SELECT id \
+         FROM ({}) WHERE \
+         {EXPIRED}){guards} LIMIT ?4)",
+        ranked(
+          "feed_payloads",
+          "fetched_at_ms"
+        )
+      )
+    }
+    | PruneTarget::Payloads => {
+      format!(
+        "DELETE FROM feed_payloads \
+         WHERE id IN (SELECT id FROM \
+         ({}) p WHERE ({EXPIRED}) AND \
+         NOT EXISTS (SELECT 1 FROM \
+         feed_items i WHERE \
+         i.payload_id = p.id) LIMIT \
+         ?4)",
+        ranked(
+          "feed_payloads",
+          "fetched_at_ms"
+        )
+      )
+    }
+  };
+
+  let res = sqlx::query(&sql)
+    .bind(&scope.category)
+    .bind(scope.cutoff_ms)
+    .bind(scope.keep_per_feed)
+    .bind(scope.limit)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "prune {}: {e}",
+        target.as_str()
+      )
+    })?;
+
+  Ok(res.rows_affected())
+}
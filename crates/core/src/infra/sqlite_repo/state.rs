@@ -1,12 +1,20 @@
 //! Persist/read current and historical
 //! link state snapshots.
 
-use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::query::Query;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{
+  Sqlite,
+  SqlitePool
+};
 
+use super::events::event_query;
 use super::models::StateRowRecord;
 use crate::domain::link_state::LinkState;
-use crate::ports::repo::StateRow;
+use crate::ports::repo::{
+  StateRow,
+  WriteBatch
+};
 
 pub async fn latest_state(
   pool: &SqlitePool,
@@ -33,7 +41,8 @@ pub async fn latest_state(
         next_action_at_ms,
         jitter_seconds,
         note,
-        consecutive_error_count
+        consecutive_error_count,
+        get_only_since_ms
       FROM feed_state_current
       WHERE feed_id = ?1
       "#
@@ -52,67 +61,164 @@ pub async fn insert_state(
   pool: &SqlitePool,
   state: &LinkState,
   recorded_at_ms: i64,
-  _zone: &Tz,
   record_history: bool
 ) -> Result<(), String> {
   if record_history {
-    sqlx::query(
-            r#"
-        INSERT INTO feed_state_history(
-          feed_id, recorded_at_ms, phase,
-          last_head_at_ms, last_head_status, last_head_error,
-          last_get_at_ms, last_get_status, last_get_error,
-          etag, last_modified_ms,
-          backoff_index, base_poll_seconds, next_action_at_ms,
-          jitter_seconds, note, consecutive_error_count
-        ) VALUES (
-          ?1, ?2, ?3,
-          ?4, ?5, ?6,
-          ?7, ?8, ?9,
-          ?10, ?11,
-          ?12, ?13, ?14,
-          ?15, ?16, ?17
+    history_query(
+      state,
+      recorded_at_ms
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "insert_state history error: \
+         {e}"
+      )
+    })?;
+  }
+
+  current_query(state)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "insert_state current error: \
+         {e}"
+      )
+    })?;
+
+  Ok(())
+}
+
+/// Events first, then states, in one
+/// transaction.
+pub async fn write_batch(
+  pool: &SqlitePool,
+  batch: &WriteBatch
+) -> Result<(), String> {
+  let mut tx = pool
+    .begin()
+    .await
+    .map_err(|e| {
+      format!("write_batch begin: {e}")
+    })?;
+
+  for event in &batch.events {
+    event_query(event)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch event: {e}"
+        )
+      })?;
+  }
+
+  for pending in &batch.states {
+    if pending.record_history {
+      history_query(
+        &pending.state,
+        pending.recorded_at_ms
+      )
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch history: {e}"
         )
-        "#,
+      })?;
+    }
+
+    current_query(&pending.state)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "write_batch state: {e}"
         )
-        .bind(&state.feed_id)
-        .bind(recorded_at_ms)
-        .bind(format!("{:?}", state.phase))
-        .bind(state.last_head_at_ms)
-        .bind(state.last_head_status.map(|x| x as i64))
-        .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-        .bind(state.last_get_at_ms)
-        .bind(state.last_get_status.map(|x| x as i64))
-        .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-        .bind(&state.etag)
-        .bind(state.last_modified_ms)
-        .bind(state.backoff_index as i64)
-        .bind(state.base_poll_seconds as i64)
-        .bind(state.next_action_at_ms)
-        .bind(state.jitter_seconds)
-        .bind(&state.note)
-        .bind(state.consecutive_error_count as i64)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("insert_state history error: {e}"))?;
+      })?;
   }
 
+  tx.commit().await.map_err(|e| {
+    format!("write_batch commit: {e}")
+  })
+}
+
+fn history_query(
+  state: &LinkState,
+  recorded_at_ms: i64
+) -> Query<
+  '_,
+  Sqlite,
+  SqliteArguments<'_>
+> {
+  sqlx::query(
+    r#"
+      INSERT INTO feed_state_history(
+        feed_id, recorded_at_ms, phase,
+        last_head_at_ms, last_head_status, last_head_error,
+        last_get_at_ms, last_get_status, last_get_error,
+        etag, last_modified_ms,
+        backoff_index, base_poll_seconds, next_action_at_ms,
+        jitter_seconds, note, consecutive_error_count,
+        get_only_since_ms
+      ) VALUES (
+        ?1, ?2, ?3,
+        ?4, ?5, ?6,
+        ?7, ?8, ?9,
+        ?10, ?11,
+        ?12, ?13, ?14,
+        ?15, ?16, ?17,
+        ?18
+      )
+      "#,
+  )
+    .bind(&state.feed_id)
+    .bind(recorded_at_ms)
+    .bind(format!("{:?}", state.phase))
+    .bind(state.last_head_at_ms)
+    .bind(state.last_head_status.map(|x| x as i64))
+    .bind(state.last_head_error.map(|e| format!("{:?}", e)))
+    .bind(state.last_get_at_ms)
+    .bind(state.last_get_status.map(|x| x as i64))
+    .bind(state.last_get_error.map(|e| format!("{:?}", e)))
+    .bind(&state.etag)
+    .bind(state.last_modified_ms)
+    .bind(state.backoff_index as i64)
+    .bind(state.base_poll_seconds as i64)
+    .bind(state.next_action_at_ms)
+    .bind(state.jitter_seconds)
+    .bind(&state.note)
+    .bind(state.consecutive_error_count as i64)
+    .bind(state.get_only_since_ms)
+}
+
+fn current_query(
+  state: &LinkState
+) -> Query<
+  '_,
+  Sqlite,
+  SqliteArguments<'_>
+> {
   sqlx::query(
-        r#"
+    r#"
       INSERT INTO feed_state_current(
         feed_id, phase,
         last_head_at_ms, last_head_status, last_head_error,
         last_get_at_ms, last_get_status, last_get_error,
         etag, last_modified_ms,
         backoff_index, base_poll_seconds, next_action_at_ms,
-        jitter_seconds, note, consecutive_error_count
+        jitter_seconds, note, consecutive_error_count,
+        get_only_since_ms
       ) VALUES (
         ?1, ?2,
         ?3, ?4, ?5,
         ?6, ?7, ?8,
         ?9, ?10,
         ?11, ?12, ?13,
-        ?14, ?15, ?16
+        ?14, ?15, ?16,
+        ?17
       )
       ON CONFLICT(feed_id) DO UPDATE SET
         phase = excluded.phase,
@@ -129,9 +235,10 @@ pub async fn insert_state(
         next_action_at_ms = excluded.next_action_at_ms,
         jitter_seconds = excluded.jitter_seconds,
         note = excluded.note,
-        consecutive_error_count = excluded.consecutive_error_count
+        consecutive_error_count = excluded.consecutive_error_count,
+        get_only_since_ms = excluded.get_only_since_ms
       "#,
-    )
+  )
     .bind(&state.feed_id)
     .bind(format!("{:?}", state.phase))
     .bind(state.last_head_at_ms)
@@ -148,9 +255,5 @@ pub async fn insert_state(
     .bind(state.jitter_seconds)
     .bind(&state.note)
     .bind(state.consecutive_error_count as i64)
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_state current error: {e}"))?;
-
-  Ok(())
+    .bind(state.get_only_since_ms)
 }
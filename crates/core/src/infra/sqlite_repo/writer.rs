@@ -0,0 +1,109 @@
+//! Single writer for the SQLite repo.
+//! Every write is queued as a job and
+//! run in order by one task on a
+//! dedicated connection, so concurrent
+//! fetches never race for SQLite's
+//! write lock. Reads stay on the pool.
+
+use std::future::Future;
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use sqlx::SqlitePool;
+use tokio::sync::{
+  mpsc,
+  oneshot
+};
+
+use crate::infra::metrics;
+
+/// Queued jobs before writers wait.
+const QUEUE_DEPTH: usize = 1024;
+
+type Job = Box<
+  dyn FnOnce(
+      SqlitePool
+    )
+      -> BoxFuture<'static, ()>
+    + Send
+>;
+
+#[derive(Clone)]
+
+pub struct Writer {
+  tx: mpsc::Sender<Job>
+}
+
+impl Writer {
+  /// Starts the writer task on `pool`,
+  /// a single-connection pool. The task
+  /// ends once every handle is dropped.
+  pub fn spawn(
+    pool: SqlitePool
+  ) -> Self {
+    let (tx, mut rx) =
+      mpsc::channel::<Job>(QUEUE_DEPTH);
+
+    tokio::spawn(async move {
+      while let Some(job) =
+        rx.recv().await
+      {
+        job(pool.clone()).await;
+      }
+    });
+
+    Self {
+      tx
+    }
+  }
+
+  /// Queues `job` and waits for its
+  /// result. Time spent queued is
+  /// recorded as `writer_wait`.
+  pub async fn run<T, F, Fut>(
+    &self,
+    job: F
+  ) -> Result<T, String>
+  where
+    T: Send + 'static,
+    F: FnOnce(SqlitePool) -> Fut
+      + Send
+      + 'static,
+    Fut: Future<Output = Result<T, String>>
+      + Send
+      + 'static
+  {
+    let (done, result) =
+      oneshot::channel();
+
+    let queued = Instant::now();
+
+    let job: Job =
+      Box::new(move |pool| {
+        Box::pin(async move {
+          metrics::record_db_time(
+            "writer_wait",
+            queued.elapsed().as_millis()
+              as u64
+          );
+
+          let _ =
+            done.send(job(pool).await);
+        })
+      });
+
+    self
+      .tx
+      .send(job)
+      .await
+      .map_err(|_| stopped())?;
+
+    result
+      .await
+      .map_err(|_| stopped())?
+  }
+}
+
+fn stopped() -> String {
+  "sqlite writer stopped".to_string()
+}
@@ -0,0 +1,453 @@
+//! Write commands queued on the
+//! [`Writer`]: each one takes owned
+//! copies of its arguments and runs the
+//! matching query on the writer's
+//! connection.
+
+use chrono_tz::Tz;
+
+use super::writer::Writer;
+use super::{
+  archive,
+  datasets,
+  error_feeds,
+  events,
+  feeds,
+  fetch_requests,
+  health,
+  items,
+  migrations,
+  payloads,
+  retention,
+  state
+};
+use crate::domain::link_state::LinkState;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+use crate::ports::repo::{
+  ArchivedPayload,
+  ClaimedFetch,
+  DatasetVersion,
+  FetchRequestStatus,
+  HealthRollup,
+  PendingEvent,
+  PruneScope,
+  PruneTarget,
+  StoredItem,
+  WriteBatch
+};
+
+impl Writer {
+  pub async fn migrate(
+    &self,
+    zone: Tz,
+    default_poll_seconds: u64
+  ) -> Result<(), String> {
+    self
+      .run(move |pool| {
+        async move {
+          migrations::migrate(
+            &pool,
+            &zone,
+            default_poll_seconds
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn upsert_feeds_bulk(
+    &self,
+    feeds: Vec<FeedConfig>,
+    chunk_size: usize,
+    zone: Tz
+  ) -> Result<(), String> {
+    self
+      .run(move |pool| {
+        async move {
+          feeds::upsert_feeds_bulk(
+            &pool, feeds, chunk_size,
+            &zone
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn upsert_categories(
+    &self,
+    categories: Vec<String>
+  ) -> Result<(), String> {
+    self
+      .run(move |pool| {
+        async move {
+          feeds::upsert_categories(
+            &pool,
+            &categories
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn insert_state(
+    &self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    record_history: bool
+  ) -> Result<(), String> {
+    let state = state.clone();
+
+    self
+      .run(move |pool| {
+        async move {
+          state::insert_state(
+            &pool,
+            &state,
+            recorded_at_ms,
+            record_history
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn insert_event(
+    &self,
+    event: PendingEvent
+  ) -> Result<(), String> {
+    self
+      .run(move |pool| {
+        async move {
+          events::insert_event(
+            &pool, &event
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn write_batch(
+    &self,
+    batch: &WriteBatch
+  ) -> Result<(), String> {
+    let batch = batch.clone();
+
+    self
+      .run(move |pool| {
+        async move {
+          state::write_batch(
+            &pool, &batch
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn insert_payload_with_items(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed
+  ) -> Result<(), String> {
+    let feed_id = feed_id.to_string();
+    let etag = etag.map(str::to_string);
+    let content_hash =
+      content_hash.map(str::to_string);
+    let parsed = parsed.clone();
+
+    self
+      .run(move |pool| async move {
+        payloads::insert_payload_with_items(
+          &pool,
+          &feed_id,
+          fetched_at_ms,
+          etag.as_deref(),
+          last_modified_ms,
+          content_hash.as_deref(),
+          &parsed
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn mark_feed_error(
+    &self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64
+  ) -> Result<(), String> {
+    let feed_id = feed_id.to_string();
+
+    self
+      .run(move |pool| {
+        async move {
+          error_feeds::mark_feed_error(
+            &pool,
+            &feed_id,
+            error_kind,
+            status,
+            error_count,
+            observed_at_ms
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn insert_dataset_version(
+    &self,
+    version: &DatasetVersion,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    items: &ParsedFeed
+  ) -> Result<(), String> {
+    let version = version.clone();
+    let etag = etag.map(str::to_string);
+    let items = items.clone();
+
+    self
+      .run(move |pool| async move {
+        datasets::insert_dataset_version(
+          &pool,
+          &version,
+          etag.as_deref(),
+          last_modified_ms,
+          &items
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn touch_archived_payload(
+    &self,
+    content_hash: &str,
+    seen_at_ms: i64
+  ) -> Result<bool, String> {
+    let content_hash =
+      content_hash.to_string();
+
+    self
+      .run(move |pool| async move {
+        archive::touch_archived_payload(
+          &pool,
+          &content_hash,
+          seen_at_ms
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn insert_archived_payload(
+    &self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64
+  ) -> Result<(), String> {
+    let payload = payload.clone();
+
+    self
+      .run(move |pool| async move {
+        archive::insert_archived_payload(
+          &pool, &payload, seen_at_ms
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn prune_archived_payloads(
+    &self,
+    seen_before_ms: i64,
+    limit: i64
+  ) -> Result<
+    Vec<ArchivedPayload>,
+    String
+  > {
+    self
+      .run(move |pool| async move {
+        archive::prune_archived_payloads(
+          &pool,
+          seen_before_ms,
+          limit
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn apply_payload_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    inserts: &[FeedItem],
+    updates: &[StoredItem]
+  ) -> Result<(), String> {
+    let feed_id = feed_id.to_string();
+    let inserts = inserts.to_vec();
+    let updates = updates.to_vec();
+
+    self
+      .run(move |pool| {
+        async move {
+          items::apply_payload_items(
+            &pool, payload_id,
+            &feed_id, &inserts,
+            &updates
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn prune_rows(
+    &self,
+    target: PruneTarget,
+    scope: &PruneScope
+  ) -> Result<u64, String> {
+    let scope = scope.clone();
+
+    self
+      .run(move |pool| {
+        async move {
+          retention::prune_rows(
+            &pool, target, &scope
+          )
+          .await
+        }
+      })
+      .await
+  }
+
+  pub async fn replace_health_rollups(
+    &self,
+    day: &str,
+    rows: &[HealthRollup],
+    now_ms: i64
+  ) -> Result<(), String> {
+    let day = day.to_string();
+    let rows = rows.to_vec();
+
+    self
+      .run(move |pool| async move {
+        health::replace_health_rollups(
+          &pool, &day, &rows, now_ms
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn enqueue_fetch_request(
+    &self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64
+  ) -> Result<i64, String> {
+    let feed_id = feed_id.to_string();
+    let requested_by =
+      requested_by.map(str::to_string);
+
+    self
+      .run(move |pool| async move {
+        fetch_requests::enqueue_fetch_request(
+          &pool,
+          &feed_id,
+          requested_by.as_deref(),
+          requested_at_ms
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn claim_fetch_requests(
+    &self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64
+  ) -> Result<Vec<ClaimedFetch>, String>
+  {
+    let category = category.to_string();
+    let busy = busy_feed_ids.to_vec();
+
+    self
+      .run(move |pool| async move {
+        fetch_requests::claim_fetch_requests(
+          &pool,
+          &category,
+          &busy,
+          limit,
+          now_ms,
+          stale_before_ms
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn requeue_fetch_requests(
+    &self,
+    ids: &[i64]
+  ) -> Result<(), String> {
+    let ids = ids.to_vec();
+
+    self
+      .run(move |pool| async move {
+        fetch_requests::requeue_fetch_requests(
+          &pool, &ids
+        )
+        .await
+      })
+      .await
+  }
+
+  pub async fn finish_fetch_request(
+    &self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64
+  ) -> Result<(), String> {
+    let error =
+      error.map(str::to_string);
+
+    self
+      .run(move |pool| async move {
+        fetch_requests::finish_fetch_request(
+          &pool,
+          id,
+          status,
+          http_status,
+          error.as_deref(),
+          finished_at_ms
+        )
+        .await
+      })
+      .await
+  }
+}
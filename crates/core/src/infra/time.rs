@@ -4,6 +4,7 @@
 
 use chrono::{
   DateTime,
+  NaiveDate,
   TimeZone,
   Utc
 };
@@ -49,3 +50,49 @@ pub fn epoch_ms_to_iso(
     .with_timezone(zone)
     .to_rfc3339()
 }
+
+/// Parses a user-supplied instant:
+/// RFC 3339, a `YYYY-MM-DD` date
+/// (midnight in `zone`), or epoch
+/// milliseconds.
+pub fn parse_time_arg(
+  raw: &str,
+  zone: &Tz
+) -> Result<i64, String> {
+  let raw = raw.trim();
+
+  if let Ok(ms) = raw.parse::<i64>() {
+    return Ok(ms);
+  }
+
+  if let Ok(dt) =
+    DateTime::parse_from_rfc3339(raw)
+  {
+    return Ok(dt.timestamp_millis());
+  }
+
+  let date = NaiveDate::parse_from_str(
+    raw, "%Y-%m-%d"
+  )
+  .map_err(|_| {
+    format!(
+      "invalid time '{raw}', expected \
+       RFC 3339, YYYY-MM-DD or epoch \
+       millis"
+    )
+  })?;
+
+  date
+    .and_hms_opt(0, 0, 0)
+    .and_then(|naive| {
+      zone
+        .from_local_datetime(&naive)
+        .earliest()
+    })
+    .map(|dt| dt.timestamp_millis())
+    .ok_or_else(|| {
+      format!(
+        "invalid local date '{raw}'"
+      )
+    })
+}
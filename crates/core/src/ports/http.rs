@@ -18,4 +18,18 @@ pub trait Http: Send + Sync {
     &self,
     url: &str
   ) -> GetResult;
+
+  /// GET with `If-None-Match` /
+  /// `If-Modified-Since` from the given
+  /// validators; a 304 has no body.
+  async fn get_conditional(
+    &self,
+    url: &str,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>
+  ) -> GetResult {
+    let _ = (etag, last_modified_ms);
+
+    self.get(url).await
+  }
 }
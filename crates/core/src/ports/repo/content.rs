@@ -0,0 +1,44 @@
+//! Stored content a fetch is compared
+//! against, and dataset versions.
+
+use chrono_tz::Tz;
+
+use super::DatasetVersion;
+use crate::feed::parser::ParsedFeed;
+
+/// Supertrait of
+/// [`Repo`](super::Repo), kept apart so
+/// each backend implements it in its
+/// own `content` module.
+#[async_trait::async_trait]
+
+pub trait ContentRepo:
+  Send + Sync
+{
+  /// Content hash of the feed's latest
+  /// stored payload.
+  async fn latest_content_hash(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>;
+
+  async fn latest_dataset_version(
+    &self,
+    feed_id: &str
+  ) -> Result<
+    Option<DatasetVersion>,
+    String
+  >;
+
+  /// Stores the version and a payload
+  /// with one item per changed row in a
+  /// single transaction.
+  async fn insert_dataset_version(
+    &self,
+    version: &DatasetVersion,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    items: &ParsedFeed,
+    zone: &Tz
+  ) -> Result<(), String>;
+}
@@ -0,0 +1,137 @@
+//! Operator control storage: pauses
+//! shared by every fetcher, plus the
+//! read-only views the admin API
+//! serves.
+
+use chrono_tz::Tz;
+
+use crate::domain::model::FeedConfig;
+
+/// What a pause applies to.
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  Hash,
+)]
+
+pub enum PauseScope {
+  Feed,
+  Domain,
+  Category
+}
+
+impl PauseScope {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | PauseScope::Feed => "feed",
+      | PauseScope::Domain => "domain",
+      | PauseScope::Category => {
+        "category"
+      }
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "feed" => Ok(Self::Feed),
+      | "domain" => Ok(Self::Domain),
+      | "category" => {
+        Ok(Self::Category)
+      }
+      | other => {
+        Err(format!(
+          "invalid pause scope \
+           '{other}'"
+        ))
+      }
+    }
+  }
+}
+
+/// A row of `paused_targets`.
+#[derive(Debug, Clone)]
+
+pub struct PausedTarget {
+  pub scope:        PauseScope,
+  pub name:         String,
+  pub reason:       Option<String>,
+  pub paused_at_ms: i64
+}
+
+/// A row of `fetch_events`.
+#[derive(Debug, Clone)]
+
+pub struct FetchEvent {
+  pub id: i64,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<String>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+/// A feed parked in `error_feeds` after
+/// too many consecutive errors.
+#[derive(Debug, Clone)]
+
+pub struct ErrorFeed {
+  pub feed_id:           String,
+  pub error_count:       i64,
+  pub last_error_kind:   Option<String>,
+  pub last_error_status: Option<i64>,
+  pub last_error_at_ms:  i64,
+  pub note:              Option<String>
+}
+
+/// Supertrait of
+/// [`Repo`](super::Repo), kept apart so
+/// each backend implements it in its
+/// own `controls` module.
+#[async_trait::async_trait]
+
+pub trait ControlRepo:
+  Send + Sync
+{
+  async fn feed(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<FeedConfig>, String>;
+
+  /// Inserts or replaces the pause for
+  /// `target.scope`/`target.name`.
+  async fn pause_target(
+    &self,
+    target: &PausedTarget,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// `false` when nothing was paused.
+  async fn resume_target(
+    &self,
+    scope: PauseScope,
+    name: &str
+  ) -> Result<bool, String>;
+
+  async fn paused_targets(
+    &self
+  ) -> Result<Vec<PausedTarget>, String>;
+
+  /// Newest first.
+  async fn recent_fetch_events(
+    &self,
+    feed_id: &str,
+    limit: usize
+  ) -> Result<Vec<FetchEvent>, String>;
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeed>, String>;
+}
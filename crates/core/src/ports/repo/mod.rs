@@ -0,0 +1,373 @@
+//! Repository abstraction for feed
+//! definitions, state snapshots, fetch
+//! events, and parsed payloads.
+
+use chrono_tz::Tz;
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+
+mod content;
+mod control;
+mod types;
+
+pub use content::ContentRepo;
+pub use control::{
+  ControlRepo,
+  ErrorFeed,
+  FetchEvent,
+  PauseScope,
+  PausedTarget
+};
+pub use types::{
+  ArchivedPayload,
+  ClaimedFetch,
+  DatasetVersion,
+  FetchRequest,
+  FetchRequestStatus,
+  HealthEvent,
+  HealthPayload,
+  HealthQuery,
+  HealthRollup,
+  HealthScope,
+  PartitionPlan,
+  PartitionReport,
+  PayloadFilter,
+  PayloadRef,
+  PendingEvent,
+  PendingState,
+  PruneScope,
+  PruneTarget,
+  ScheduledFeed,
+  SchemaMigration,
+  SchemaStatus,
+  StateRow,
+  StoredItem,
+  WriteBatch
+};
+
+#[async_trait::async_trait]
+
+pub trait Repo:
+  ControlRepo + ContentRepo + Send + Sync
+{
+  async fn migrate(
+    &self,
+    zone: &Tz,
+    default_poll_seconds: u64
+  ) -> Result<(), String>;
+
+  /// Applied and pending schema
+  /// migrations; read-only, so it works
+  /// on a database `migrate` refuses.
+  async fn schema_status(
+    &self
+  ) -> Result<SchemaStatus, String>;
+
+  async fn upsert_feeds_bulk(
+    &self,
+    feeds: Vec<FeedConfig>,
+    chunk_size: usize,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn upsert_categories(
+    &self,
+    categories: Vec<String>,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn latest_state(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<StateRow>, String>;
+
+  /// Every feed in `category` not
+  /// parked in `error_feeds`, seeding
+  /// the scheduler's in-memory due
+  /// queue.
+  async fn scheduled_feeds_for_category(
+    &self,
+    category: &str
+  ) -> Result<Vec<ScheduledFeed>, String>;
+
+  async fn insert_state(
+    &self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    zone: &Tz,
+    record_history: bool
+  ) -> Result<(), String>;
+
+  async fn insert_event(
+    &self,
+    feed_id: &str,
+    method: &str,
+    status: Option<i64>,
+    error_kind: Option<ErrorKind>,
+    latency_ms: Option<i64>,
+    backoff_index: i64,
+    scheduled_next_action_at_ms: i64,
+    debug: Option<&str>,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Writes a batch of events and
+  /// state snapshots in a single
+  /// transaction.
+  async fn write_batch(
+    &self,
+    batch: &WriteBatch,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn insert_payload_with_items(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn mark_feed_error(
+    &self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Bumps `last_seen` for an archived
+  /// body; returns false when the hash
+  /// is not archived yet.
+  async fn touch_archived_payload(
+    &self,
+    content_hash: &str,
+    seen_at_ms: i64,
+    zone: &Tz
+  ) -> Result<bool, String>;
+
+  async fn insert_archived_payload(
+    &self,
+    payload: &ArchivedPayload,
+    seen_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn archived_payload(
+    &self,
+    content_hash: &str
+  ) -> Result<
+    Option<ArchivedPayload>,
+    String
+  >;
+
+  /// Deletes up to `limit` archived
+  /// bodies last seen before the cutoff
+  /// and returns the removed rows
+  /// (without bodies).
+  async fn prune_archived_payloads(
+    &self,
+    seen_before_ms: i64,
+    limit: i64,
+    zone: &Tz
+  ) -> Result<
+    Vec<ArchivedPayload>,
+    String
+  >;
+
+  /// Payloads whose body is still in
+  /// the archive, ordered by id and
+  /// paged with `after_id`. Dataset
+  /// feeds, and payloads recorded as a
+  /// dataset version of a feed that is
+  /// no longer one, are excluded.
+  async fn archived_payload_refs(
+    &self,
+    filter: &PayloadFilter,
+    after_id: i64,
+    limit: i64,
+    zone: &Tz
+  ) -> Result<Vec<PayloadRef>, String>;
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItem>, String>;
+
+  /// Inserts new items and rewrites
+  /// updated ones for a payload in a
+  /// single transaction.
+  async fn apply_payload_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    inserts: &[FeedItem],
+    updates: &[StoredItem],
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Deletes up to `scope.limit`
+  /// expired rows and returns how many
+  /// were removed. Items with server
+  /// read state or in favorited feeds
+  /// are never deleted.
+  async fn prune_rows(
+    &self,
+    target: PruneTarget,
+    scope: &PruneScope,
+    zone: &Tz
+  ) -> Result<u64, String>;
+
+  /// Creates upcoming time partitions
+  /// of the history tables and drops
+  /// expired ones; a no-op for stores
+  /// without partitions.
+  async fn maintain_partitions(
+    &self,
+    plan: &PartitionPlan
+  ) -> Result<PartitionReport, String>;
+
+  /// Fetch events in `[start_ms,
+  /// end_ms)`.
+  async fn health_events(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<HealthEvent>, String>;
+
+  /// Payloads fetched in `[start_ms,
+  /// end_ms)`.
+  async fn health_payloads(
+    &self,
+    start_ms: i64,
+    end_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<HealthPayload>, String>;
+
+  /// Replaces every rollup stored for
+  /// `day` with `rows`.
+  async fn replace_health_rollups(
+    &self,
+    day: &str,
+    rows: &[HealthRollup],
+    now_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Newest day first, then subject.
+  async fn health_rollups(
+    &self,
+    query: &HealthQuery
+  ) -> Result<Vec<HealthRollup>, String>;
+
+  /// Claims work leases on `feed_ids`
+  /// for `owner`, returning the ids
+  /// claimed. Free, expired and
+  /// already-owned leases are taken;
+  /// SQLite runs a single fetcher and
+  /// claims everything. The in-memory
+  /// repo expires leases on its clock.
+  async fn claim_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String],
+    ttl_seconds: u64
+  ) -> Result<Vec<String>, String>;
+
+  /// Extends a held lease (and its
+  /// domain token). `false` means it
+  /// was lost to another fetcher.
+  async fn renew_feed_lease(
+    &self,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String>;
+
+  /// Frees leases and the domain tokens
+  /// taken under them.
+  async fn release_feed_leases(
+    &self,
+    owner: &str,
+    feed_ids: &[String]
+  ) -> Result<(), String>;
+
+  /// Takes one of `limit` slots shared
+  /// by every fetcher for `domain`.
+  async fn acquire_domain_token(
+    &self,
+    domain: &str,
+    limit: usize,
+    owner: &str,
+    feed_id: &str,
+    ttl_seconds: u64
+  ) -> Result<bool, String>;
+
+  /// Queues a "refresh now" for
+  /// `feed_id`, reusing a request that
+  /// is still pending for it. Unknown
+  /// feeds are an error.
+  async fn enqueue_fetch_request(
+    &self,
+    feed_id: &str,
+    requested_by: Option<&str>,
+    requested_at_ms: i64,
+    zone: &Tz
+  ) -> Result<i64, String>;
+
+  async fn fetch_request(
+    &self,
+    id: i64
+  ) -> Result<
+    Option<FetchRequest>,
+    String
+  >;
+
+  /// Marks up to `limit` requests for
+  /// `category` running, at most one
+  /// per feed and none for
+  /// `busy_feed_ids`. Requests left
+  /// running since before
+  /// `stale_before_ms` are claimed
+  /// again.
+  async fn claim_fetch_requests(
+    &self,
+    category: &str,
+    busy_feed_ids: &[String],
+    limit: usize,
+    now_ms: i64,
+    stale_before_ms: i64,
+    zone: &Tz
+  ) -> Result<Vec<ClaimedFetch>, String>;
+
+  /// Returns claimed requests to
+  /// pending.
+  async fn requeue_fetch_requests(
+    &self,
+    ids: &[i64]
+  ) -> Result<(), String>;
+
+  async fn finish_fetch_request(
+    &self,
+    id: i64,
+    status: FetchRequestStatus,
+    http_status: Option<i64>,
+    error: Option<&str>,
+    finished_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+}
@@ -0,0 +1,521 @@
+//! Records exchanged with [`Repo`]
+//! implementations.
+//!
+//! [`Repo`]: super::Repo
+
+use std::collections::BTreeMap;
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::feed::parser::FeedItem;
+
+#[derive(Debug, Clone)]
+
+pub struct StateRow {
+  pub feed_id:                 String,
+  pub phase:                   String,
+  pub last_head_at_ms: Option<i64>,
+  pub last_head_status: Option<i64>,
+  pub last_head_error: Option<String>,
+  pub last_get_at_ms: Option<i64>,
+  pub last_get_status: Option<i64>,
+  pub last_get_error: Option<String>,
+  pub etag: Option<String>,
+  pub last_modified_ms: Option<i64>,
+  pub backoff_index:           i64,
+  pub base_poll_seconds:       i64,
+  pub next_action_at_ms:       i64,
+  pub jitter_seconds:          i64,
+  pub note: Option<String>,
+  pub consecutive_error_count: i64,
+  pub get_only_since_ms: Option<i64>
+}
+
+/// A fetch event queued for
+/// [`Repo::write_batch`], timestamped
+/// when it was recorded.
+///
+/// [`Repo::write_batch`]: super::Repo::write_batch
+#[derive(Debug, Clone)]
+
+pub struct PendingEvent {
+  pub feed_id: String,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<ErrorKind>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+/// A state snapshot queued for
+/// [`Repo::write_batch`].
+///
+/// [`Repo::write_batch`]: super::Repo::write_batch
+#[derive(Debug, Clone)]
+
+pub struct PendingState {
+  pub state:          LinkState,
+  pub recorded_at_ms: i64,
+  pub record_history: bool
+}
+
+/// Events and state snapshots written
+/// in one transaction, events first.
+#[derive(Debug, Clone, Default)]
+
+pub struct WriteBatch {
+  pub events: Vec<PendingEvent>,
+  pub states: Vec<PendingState>
+}
+
+impl WriteBatch {
+  pub fn len(&self) -> usize {
+    self.events.len()
+      + self.states.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// A feed eligible for scheduling and
+/// when it is next due. `None` means no
+/// state has been recorded yet, so the
+/// feed is due immediately.
+#[derive(Debug, Clone)]
+
+pub struct ScheduledFeed {
+  pub feed:              FeedConfig,
+  pub next_action_at_ms: Option<i64>
+}
+
+/// One stored version of a structured
+/// dataset feed. The body is kept
+/// content-addressed by `content_hash`.
+#[derive(Debug, Clone)]
+
+pub struct DatasetVersion {
+  pub feed_id:       String,
+  pub fetched_at_ms: i64,
+  pub content_hash:  String,
+  pub body:          Vec<u8>,
+  pub row_count:     i64,
+  pub added_count:   i64,
+  pub removed_count: i64,
+  pub changed_count: i64
+}
+
+/// A raw response body kept for later
+/// reparsing. `body` holds the encoded
+/// bytes for database storage;
+/// `location` is a path relative to the
+/// archive directory otherwise.
+#[derive(Debug, Clone)]
+
+pub struct ArchivedPayload {
+  pub content_hash: String,
+  pub encoding:     String,
+  pub body:         Option<Vec<u8>>,
+  pub location:     Option<String>,
+  pub raw_size:     i64,
+  pub stored_size:  i64
+}
+
+/// Selects stored payloads by feed,
+/// category and fetch time (inclusive
+/// `since`, exclusive `until`).
+#[derive(Debug, Clone, Default)]
+
+pub struct PayloadFilter {
+  pub feed_id:  Option<String>,
+  pub category: Option<String>,
+  pub since_ms: Option<i64>,
+  pub until_ms: Option<i64>
+}
+
+#[derive(Debug, Clone)]
+
+pub struct PayloadRef {
+  pub id:            i64,
+  pub feed_id:       String,
+  pub fetched_at_ms: i64,
+  pub content_hash:  String
+}
+
+#[derive(Debug, Clone)]
+
+pub struct StoredItem {
+  pub id:   i64,
+  pub item: FeedItem
+}
+
+/// History tables pruned by the
+/// retention task. `Items` and
+/// `Payloads` share the payload policy:
+/// items go first, then payloads left
+/// without items.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum PruneTarget {
+  FetchEvents,
+  StateHistory,
+  Items,
+  Payloads
+}
+
+impl PruneTarget {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | PruneTarget::FetchEvents => {
+        "fetch_events"
+      }
+      | PruneTarget::StateHistory => {
+        "feed_state_history"
+      }
+      | PruneTarget::Items => {
+        "feed_items"
+      }
+      | PruneTarget::Payloads => {
+        "feed_payloads"
+      }
+    }
+  }
+}
+
+/// One batch of a retention pass for a
+/// category. Rows older than
+/// `cutoff_ms` or beyond the newest
+/// `keep_per_feed` per feed expire.
+#[derive(Debug, Clone)]
+
+pub struct PruneScope {
+  pub category:      String,
+  pub cutoff_ms:     Option<i64>,
+  pub keep_per_feed: Option<i64>,
+  pub limit:         i64,
+  pub server_schema: String
+}
+
+/// One partition maintenance pass:
+/// monthly partitions are kept
+/// `premake_months` ahead of `now_ms`,
+/// and those ending at or before a
+/// table's cutoff are dropped.
+#[derive(Debug, Clone)]
+
+pub struct PartitionPlan {
+  pub now_ms:                  i64,
+  pub premake_months:          u32,
+  /// Turn unpartitioned tables into
+  /// partitioned ones in place.
+  pub convert_existing:        bool,
+  pub fetch_events_before_ms:
+    Option<i64>,
+  pub state_history_before_ms:
+    Option<i64>
+}
+
+/// Partitions touched by a pass.
+#[derive(Debug, Clone, Default)]
+
+pub struct PartitionReport {
+  pub created:   Vec<String>,
+  pub dropped:   Vec<String>,
+  pub converted: Vec<String>
+}
+
+/// A fetch event with its feed's
+/// domain, as read for health rollups.
+#[derive(Debug, Clone)]
+
+pub struct HealthEvent {
+  pub feed_id:       String,
+  pub domain:        String,
+  pub event_time_ms: i64,
+  pub error_kind:    Option<String>,
+  pub latency_ms:    Option<i64>
+}
+
+/// A stored payload; `changed` is set
+/// when its content hash differs from
+/// the feed's previous payload.
+#[derive(Debug, Clone)]
+
+pub struct HealthPayload {
+  pub feed_id:       String,
+  pub domain:        String,
+  pub fetched_at_ms: i64,
+  pub changed:       bool,
+  pub item_count:    i64
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum HealthScope {
+  Feed,
+  Domain
+}
+
+impl HealthScope {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | HealthScope::Feed => "feed",
+      | HealthScope::Domain => "domain"
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "feed" => Ok(HealthScope::Feed),
+      | "domain" => {
+        Ok(HealthScope::Domain)
+      }
+      | other => {
+        Err(format!(
+          "invalid health scope \
+           '{other}' (expected feed \
+           or domain)"
+        ))
+      }
+    }
+  }
+}
+
+/// Daily health for one feed or
+/// domain. `day` is `YYYY-MM-DD` in the
+/// configured timezone; `error_counts`
+/// is keyed by `ErrorKind` name.
+#[derive(Debug, Clone, PartialEq)]
+
+pub struct HealthRollup {
+  pub day:            String,
+  pub scope:          HealthScope,
+  pub subject:        String,
+  pub fetches:        i64,
+  pub successes:      i64,
+  pub error_counts:
+    BTreeMap<String, i64>,
+  pub p50_latency_ms: Option<i64>,
+  pub p95_latency_ms: Option<i64>,
+  pub changes:        i64,
+  pub items:          i64
+}
+
+/// Filter for stored rollups; days are
+/// inclusive `YYYY-MM-DD` bounds.
+#[derive(Debug, Clone)]
+
+pub struct HealthQuery {
+  pub scope:     HealthScope,
+  pub subject:   Option<String>,
+  pub since_day: Option<String>,
+  pub until_day: Option<String>,
+  pub limit:     i64
+}
+
+/// Lifecycle of an on-demand refresh
+/// in `fetch_requests`.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum FetchRequestStatus {
+  Pending,
+  Running,
+  Done,
+  Failed
+}
+
+impl FetchRequestStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      | FetchRequestStatus::Pending => {
+        "pending"
+      }
+      | FetchRequestStatus::Running => {
+        "running"
+      }
+      | FetchRequestStatus::Done => {
+        "done"
+      }
+      | FetchRequestStatus::Failed => {
+        "failed"
+      }
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "pending" => Ok(Self::Pending),
+      | "running" => Ok(Self::Running),
+      | "done" => Ok(Self::Done),
+      | "failed" => Ok(Self::Failed),
+      | other => {
+        Err(format!(
+          "invalid fetch request \
+           status '{other}'"
+        ))
+      }
+    }
+  }
+
+  pub fn is_finished(&self) -> bool {
+    matches!(
+      self,
+      Self::Done | Self::Failed
+    )
+  }
+}
+
+/// A "refresh now" command and, once
+/// finished, its outcome.
+#[derive(Debug, Clone)]
+
+pub struct FetchRequest {
+  pub id:              i64,
+  pub feed_id:         String,
+  pub requested_by:    Option<String>,
+  pub requested_at_ms: i64,
+  pub status: FetchRequestStatus,
+  pub started_at_ms:   Option<i64>,
+  pub finished_at_ms:  Option<i64>,
+  pub http_status:     Option<i64>,
+  pub error:           Option<String>
+}
+
+/// A request claimed by a category
+/// loop, with the feed it targets.
+#[derive(Debug, Clone)]
+
+pub struct ClaimedFetch {
+  pub request_id: i64,
+  pub feed:       FeedConfig
+}
+
+/// A schema migration known to this
+/// binary or recorded in the database.
+/// `applied_at_ms` is unset while it is
+/// pending.
+#[derive(Debug, Clone)]
+
+pub struct SchemaMigration {
+  pub version:       i64,
+  pub name:          String,
+  pub applied_at_ms: Option<i64>
+}
+
+/// Where a database's schema stands
+/// against the migrations this binary
+/// ships, `latest` being the newest.
+#[derive(Debug, Clone)]
+
+pub struct SchemaStatus {
+  pub latest:     i64,
+  pub migrations: Vec<SchemaMigration>
+}
+
+impl SchemaStatus {
+  /// Merges the binary's migrations
+  /// with the `(version, name,
+  /// applied_at_ms)` rows recorded in
+  /// the database, ordered by version.
+  pub fn new(
+    known: &[(i64, &str)],
+    applied: Vec<(i64, String, i64)>
+  ) -> Self {
+    let mut by_version: BTreeMap<
+      i64,
+      SchemaMigration
+    > = known
+      .iter()
+      .map(|(version, name)| {
+        (*version, SchemaMigration {
+          version:       *version,
+          name:          name
+            .to_string(),
+          applied_at_ms: None
+        })
+      })
+      .collect();
+
+    for (version, name, at) in applied {
+      by_version
+        .entry(version)
+        .or_insert(SchemaMigration {
+          version,
+          name,
+          applied_at_ms: None
+        })
+        .applied_at_ms = Some(at);
+    }
+
+    Self {
+      latest:     known
+        .iter()
+        .map(|(version, _)| *version)
+        .max()
+        .unwrap_or(0),
+      migrations: by_version
+        .into_values()
+        .collect()
+    }
+  }
+
+  /// Newest applied version, 0 for an
+  /// empty database.
+  pub fn current(&self) -> i64 {
+    self
+      .migrations
+      .iter()
+      .filter(|m| {
+        m.applied_at_ms.is_some()
+      })
+      .map(|m| m.version)
+      .max()
+      .unwrap_or(0)
+  }
+
+  pub fn pending(
+    &self
+  ) -> impl Iterator<Item = &SchemaMigration>
+  {
+    self.migrations.iter().filter(|m| {
+      m.applied_at_ms.is_none()
+    })
+  }
+
+  /// Refuses a database migrated by a
+  /// newer binary.
+  pub fn check(
+    &self
+  ) -> Result<(), String> {
+    let current = self.current();
+
+    if current > self.latest {
+      return Err(format!(
+        "database schema is at \
+         version {current}, newer \
+         than this binary supports \
+         ({}); upgrade pulsewire",
+        self.latest
+      ));
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,541 @@
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use pulsewire_core::app::simulate::VirtualClock;
+use pulsewire_core::infra::admin::{
+  self,
+  Controls
+};
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::infra::memory_repo::MemoryRepo;
+use pulsewire_core::ports::repo::{
+  ControlRepo,
+  Repo
+};
+use serde_json::{
+  Value,
+  json
+};
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+use tokio::net::TcpStream;
+
+const START_MS: i64 = 1_767_225_600_000;
+
+const TOKEN: &str = "secret";
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// An admin API on an ephemeral port
+/// over one stored feed, `f1` on
+/// `a.example`, and the controls it
+/// serves.
+async fn start(
+  tag: &str
+) -> (
+  SocketAddr,
+  Arc<MemoryRepo>,
+  Arc<Controls>
+) {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-admin-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(&dir, "config.toml", &[
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 300",
+    "max_seconds = 300",
+    "jitter_fraction = 0.0",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]"
+  ]);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 4"
+  ]);
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\"]"
+  ]);
+
+  write(&dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"f1\"",
+    "url = \"https://a.example/f1\""
+  ]);
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  fs::remove_dir_all(&dir).unwrap();
+
+  let mut cfg = loaded.app.clone();
+
+  cfg.admin.enabled = true;
+  cfg.admin.bind =
+    "127.0.0.1:0".to_string();
+  cfg.admin.token = TOKEN.to_string();
+
+  let clock = Arc::new(
+    VirtualClock::new(START_MS)
+  );
+
+  let repo =
+    Arc::new(MemoryRepo::with_clock(
+      clock.clone()
+    ));
+
+  repo
+    .upsert_feeds_bulk(
+      loaded.feeds.clone(),
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let controls =
+    Arc::new(Controls::default());
+
+  let server = admin::init(
+    Arc::new(cfg),
+    repo.clone(),
+    clock,
+    controls.clone()
+  )
+  .await
+  .unwrap()
+  .unwrap();
+
+  (server.addr(), repo, controls)
+}
+
+/// Sends `head` (and `body`) and reads
+/// the status and JSON reply.
+async fn send(
+  addr: SocketAddr,
+  head: &[&str],
+  body: &str
+) -> (u16, Value) {
+  let mut stream =
+    TcpStream::connect(addr)
+      .await
+      .unwrap();
+
+  let request = head.join("\r\n")
+    + "\r\n\r\n"
+    + body;
+
+  stream
+    .write_all(request.as_bytes())
+    .await
+    .unwrap();
+
+  let mut raw = String::new();
+
+  stream
+    .read_to_string(&mut raw)
+    .await
+    .unwrap();
+
+  let (head, body) =
+    raw.split_once("\r\n\r\n").unwrap();
+
+  let status = head
+    .split_whitespace()
+    .nth(1)
+    .unwrap()
+    .parse()
+    .unwrap();
+
+  (
+    status,
+    serde_json::from_str(body).unwrap()
+  )
+}
+
+/// An authorized request.
+async fn call(
+  addr: SocketAddr,
+  method: &str,
+  path: &str,
+  body: &str
+) -> (u16, Value) {
+  let line =
+    format!("{method} {path} HTTP/1.1");
+
+  let auth = format!(
+    "Authorization: Bearer {TOKEN}"
+  );
+
+  let length = format!(
+    "Content-Length: {}",
+    body.len()
+  );
+
+  send(
+    addr,
+    &[&line, &auth, &length],
+    body
+  )
+  .await
+}
+
+#[tokio::test]
+
+async fn missing_or_wrong_token_is_rejected_before_the_body()
+ {
+  let (addr, repo, _) =
+    start("auth").await;
+
+  // The body is never sent; a server
+  // waiting for it would not answer.
+  for auth in [
+    "X-Other: 1",
+    "Authorization: Bearer secreT",
+    "Authorization: secret"
+  ] {
+    let (status, body) =
+      tokio::time::timeout(
+        Duration::from_secs(5),
+        send(
+          addr,
+          &[
+            "PUT /v1/pauses/feed/f1 \
+             HTTP/1.1",
+            auth,
+            "Content-Length: 1000"
+          ],
+          ""
+        )
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(status, 401);
+    assert!(body["error"].is_string());
+  }
+
+  assert!(
+    repo
+      .paused_targets()
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn silent_client_times_out() {
+  let (addr, _, _) =
+    start("timeout").await;
+
+  let (status, body) = send(
+    addr,
+    &[
+      "PUT /v1/pauses/feed/f1 HTTP/1.1",
+      "Authorization: Bearer secret",
+      "Content-Length: 10"
+    ],
+    ""
+  )
+  .await;
+
+  assert_eq!(status, 400);
+  assert!(
+    body["error"]
+      .as_str()
+      .unwrap()
+      .contains("timed out")
+  );
+}
+
+#[tokio::test]
+
+async fn feed_is_paused_and_resumed() {
+  let (addr, repo, _) =
+    start("pause").await;
+
+  let (status, paused) = call(
+    addr,
+    "PUT",
+    "/v1/pauses/feed/f1",
+    r#"{"reason": "maintenance"}"#
+  )
+  .await;
+
+  assert_eq!(status, 200);
+  assert_eq!(
+    paused,
+    json!({
+      "scope": "feed",
+      "name": "f1",
+      "reason": "maintenance",
+      "paused_at_ms": START_MS
+    })
+  );
+
+  let (_, listed) =
+    call(addr, "GET", "/v1/pauses", "")
+      .await;
+
+  assert_eq!(
+    listed["pauses"],
+    json!([paused])
+  );
+
+  let (_, feed) = call(
+    addr,
+    "GET",
+    "/v1/feeds/f1",
+    ""
+  )
+  .await;
+
+  assert_eq!(feed["paused"], true);
+
+  assert_eq!(
+    call(
+      addr,
+      "PUT",
+      "/v1/pauses/feed/nope",
+      ""
+    )
+    .await
+    .0,
+    404
+  );
+
+  assert_eq!(
+    call(
+      addr,
+      "PUT",
+      "/v1/pauses/shelf/f1",
+      ""
+    )
+    .await
+    .0,
+    400
+  );
+
+  let (status, resumed) = call(
+    addr,
+    "DELETE",
+    "/v1/pauses/feed/f1",
+    ""
+  )
+  .await;
+
+  assert_eq!(status, 200);
+  assert_eq!(resumed["resumed"], true);
+
+  assert!(
+    repo
+      .paused_targets()
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  assert_eq!(
+    call(
+      addr,
+      "DELETE",
+      "/v1/pauses/feed/f1",
+      ""
+    )
+    .await
+    .0,
+    404
+  );
+}
+
+#[tokio::test]
+
+async fn feed_domain_and_unknown_routes()
+ {
+  let (addr, _, controls) =
+    start("routes").await;
+
+  let (status, feed) = call(
+    addr,
+    "GET",
+    "/v1/feeds/f1?events=5",
+    ""
+  )
+  .await;
+
+  assert_eq!(status, 200);
+  assert_eq!(
+    feed["domain"],
+    "a.example"
+  );
+  assert_eq!(feed["events"], json!([]));
+
+  assert_eq!(
+    call(
+      addr,
+      "GET",
+      "/v1/feeds/f1?events=x",
+      ""
+    )
+    .await
+    .0,
+    400
+  );
+
+  let (status, parked) = call(
+    addr,
+    "GET",
+    "/v1/error-feeds",
+    ""
+  )
+  .await;
+
+  assert_eq!(status, 200);
+  assert_eq!(
+    parked["error_feeds"],
+    json!([])
+  );
+
+  let (status, set) = call(
+    addr,
+    "PUT",
+    "/v1/domains/routes.example/\
+     concurrency",
+    r#"{"max_concurrent_requests": 2}"#
+  )
+  .await;
+
+  assert_eq!(status, 200);
+  assert_eq!(set["override"], 2);
+  assert_eq!(set["effective"], 2);
+  assert_eq!(
+    controls
+      .domain_limit("routes.example"),
+    Some(2)
+  );
+
+  let (_, domains) = call(
+    addr,
+    "GET",
+    "/v1/domains",
+    ""
+  )
+  .await;
+
+  assert!(
+    domains["domains"]
+      .as_array()
+      .unwrap()
+      .contains(&set)
+  );
+
+  assert_eq!(
+    call(
+      addr,
+      "PUT",
+      "/v1/domains/routes.example/concurrency",
+      r#"{"max_concurrent_requests": 0}"#
+    )
+    .await
+    .0,
+    400
+  );
+
+  let (_, cleared) = call(
+    addr,
+    "DELETE",
+    "/v1/domains/routes.example/\
+     concurrency",
+    ""
+  )
+  .await;
+
+  assert_eq!(
+    cleared["override"],
+    Value::Null
+  );
+  assert_eq!(cleared["effective"], 1);
+  assert_eq!(
+    controls
+      .domain_limit("routes.example"),
+    None
+  );
+
+  assert_eq!(
+    call(
+      addr,
+      "GET",
+      "/v1/nothing",
+      ""
+    )
+    .await
+    .0,
+    404
+  );
+}
@@ -0,0 +1,395 @@
+//! The payload body archive: storage,
+//! pruning and reparsing.
+
+mod prune;
+mod reparse;
+
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+use std::sync::Arc;
+
+use pulsewire_core::app::archive::{
+  archive_body,
+  load_body,
+  prune_once
+};
+use pulsewire_core::domain::model::{
+  AppConfig,
+  ArchiveStorage
+};
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+
+const DAY_MS: i64 = 86_400_000;
+
+const T0: i64 = 1_767_225_600_000;
+
+const HASH_A: &str = "ab01";
+
+const HASH_B: &str = "cd02";
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// A scratch directory holding the
+/// database and, for directory
+/// storage, the archive under
+/// `archive/`.
+fn scratch(tag: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-archive-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  dir
+}
+
+/// The archive enabled with
+/// `storage`, keeping bodies for
+/// `retention_days`.
+async fn setup(
+  dir: &Path,
+  storage: ArchiveStorage,
+  retention_days: u64
+) -> (AppConfig, Arc<SqliteRepo>) {
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(dir, "config.toml", &[
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 300",
+    "max_seconds = 300",
+    "jitter_fraction = 0.0",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]"
+  ]);
+
+  write(dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 1"
+  ]);
+
+  write(dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\"]"
+  ]);
+
+  write(dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"f1\"",
+    "url = \"https://a.example/f1\""
+  ]);
+
+  let mut cfg = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap()
+  .app;
+
+  cfg.archive.enabled = true;
+  cfg.archive.storage = storage;
+  cfg.archive.retention_days =
+    retention_days;
+
+  let repo = Arc::new(
+    SqliteRepo::new(&dir.join("p.db"))
+      .await
+      .unwrap()
+  );
+
+  repo
+    .migrate(&cfg.timezone, 300)
+    .await
+    .unwrap();
+
+  (cfg, repo)
+}
+
+/// The file holding `hash`'s body in
+/// the directory storage.
+async fn file_of(
+  repo: &SqliteRepo,
+  root: &Path,
+  hash: &str
+) -> PathBuf {
+  let rel = repo
+    .archived_payload(hash)
+    .await
+    .unwrap()
+    .unwrap()
+    .location
+    .unwrap();
+
+  root.join(rel)
+}
+
+fn body(n: usize) -> Vec<u8> {
+  b"<rss><channel><item>x</item>"
+    .repeat(n)
+}
+
+#[tokio::test]
+
+async fn database_body_round_trips_gzipped()
+ {
+  let dir = scratch("db");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Database,
+    0
+  )
+  .await;
+
+  let raw = body(50);
+
+  archive_body(
+    &cfg, &repo, HASH_A, &raw, T0
+  )
+  .await
+  .unwrap();
+
+  let stored = repo
+    .archived_payload(HASH_A)
+    .await
+    .unwrap()
+    .unwrap();
+
+  let gz = stored.body.unwrap();
+
+  assert_eq!(stored.encoding, "gzip");
+  assert_eq!(stored.location, None);
+  assert_eq!(&gz[..2], [0x1f, 0x8b]);
+  assert_eq!(
+    stored.raw_size,
+    raw.len() as i64
+  );
+  assert_eq!(
+    stored.stored_size,
+    gz.len() as i64
+  );
+  assert!(gz.len() < raw.len());
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_A
+    )
+    .await
+    .unwrap(),
+    Some(raw)
+  );
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_B
+    )
+    .await
+    .unwrap(),
+    None
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+
+async fn directory_storage_shards_by_hash()
+ {
+  let dir = scratch("dir");
+
+  let root = dir.join("archive");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Directory(
+      root.clone()
+    ),
+    0
+  )
+  .await;
+
+  let raw = body(10);
+
+  archive_body(
+    &cfg, &repo, HASH_A, &raw, T0
+  )
+  .await
+  .unwrap();
+
+  let stored = repo
+    .archived_payload(HASH_A)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(stored.body, None);
+
+  let rel = stored.location.unwrap();
+
+  assert!(
+    rel.starts_with("ab/ab01-")
+      && rel.ends_with(".gz"),
+    "{rel}"
+  );
+
+  let file = root.join(&rel);
+
+  assert_eq!(
+    fs::metadata(&file).unwrap().len()
+      as i64,
+    stored.stored_size
+  );
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_A
+    )
+    .await
+    .unwrap(),
+    Some(raw)
+  );
+
+  // The row points at a file only the
+  // directory storage can read.
+  let mut in_db = cfg.clone();
+
+  in_db.archive.storage =
+    ArchiveStorage::Database;
+
+  assert!(
+    load_body(
+      &in_db,
+      repo.as_ref(),
+      HASH_A
+    )
+    .await
+    .is_err()
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+
+async fn seen_body_is_touched_not_rewritten()
+ {
+  let dir = scratch("dedup");
+
+  let root = dir.join("archive");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Directory(
+      root.clone()
+    ),
+    7
+  )
+  .await;
+
+  let raw = body(10);
+
+  archive_body(
+    &cfg, &repo, HASH_A, &raw, T0
+  )
+  .await
+  .unwrap();
+
+  let file =
+    file_of(&repo, &root, HASH_A).await;
+
+  fs::remove_file(&file).unwrap();
+
+  // Seen again on day 5: no new file,
+  // but the retention clock restarts.
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_A,
+    &raw,
+    T0 + 5 * DAY_MS
+  )
+  .await
+  .unwrap();
+
+  assert!(!file.exists());
+
+  assert_eq!(
+    prune_once(
+      &cfg,
+      repo.as_ref(),
+      T0 + 10 * DAY_MS
+    )
+    .await
+    .unwrap(),
+    0
+  );
+
+  assert!(
+    repo
+      .archived_payload(HASH_A)
+      .await
+      .unwrap()
+      .is_some()
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
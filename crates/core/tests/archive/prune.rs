@@ -0,0 +1,253 @@
+//! Pruning archived bodies past the
+//! retention window.
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use pulsewire_core::app::archive::{
+  archive_body,
+  load_body,
+  prune_forever,
+  prune_once
+};
+use pulsewire_core::app::simulate::VirtualClock;
+use pulsewire_core::domain::model::ArchiveStorage;
+use pulsewire_core::ports::repo::Repo;
+
+use super::{
+  DAY_MS,
+  HASH_A,
+  HASH_B,
+  T0,
+  body,
+  file_of,
+  scratch,
+  setup
+};
+
+#[tokio::test]
+
+async fn prune_drops_bodies_past_retention()
+ {
+  let dir = scratch("prune");
+
+  let root = dir.join("archive");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Directory(
+      root.clone()
+    ),
+    7
+  )
+  .await;
+
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_A,
+    &body(3),
+    T0
+  )
+  .await
+  .unwrap();
+
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_B,
+    &body(4),
+    T0 + 6 * DAY_MS
+  )
+  .await
+  .unwrap();
+
+  let file_a =
+    file_of(&repo, &root, HASH_A).await;
+
+  let file_b =
+    file_of(&repo, &root, HASH_B).await;
+
+  // Retention 0 keeps everything.
+  let mut forever = cfg.clone();
+
+  forever.archive.retention_days = 0;
+
+  assert_eq!(
+    prune_once(
+      &forever,
+      repo.as_ref(),
+      T0 + 100 * DAY_MS
+    )
+    .await
+    .unwrap(),
+    0
+  );
+
+  assert_eq!(
+    prune_once(
+      &cfg,
+      repo.as_ref(),
+      T0 + 8 * DAY_MS
+    )
+    .await
+    .unwrap(),
+    1
+  );
+
+  assert!(!file_a.exists());
+  assert!(file_b.exists());
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_A
+    )
+    .await
+    .unwrap(),
+    None
+  );
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_B
+    )
+    .await
+    .unwrap(),
+    Some(body(4))
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+
+async fn prune_loop_runs_at_start() {
+  let dir = scratch("loop");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Database,
+    7
+  )
+  .await;
+
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_A,
+    &body(3),
+    T0
+  )
+  .await
+  .unwrap();
+
+  let clock = Arc::new(
+    VirtualClock::new(T0 + 8 * DAY_MS)
+  );
+
+  let pruning =
+    tokio::spawn(prune_forever(
+      Arc::new(cfg),
+      repo.clone(),
+      clock
+    ));
+
+  let mut left = 50;
+
+  while repo
+    .archived_payload(HASH_A)
+    .await
+    .unwrap()
+    .is_some()
+  {
+    left -= 1;
+
+    assert!(left > 0, "never pruned");
+
+    tokio::time::sleep(
+      Duration::from_millis(100)
+    )
+    .await;
+  }
+
+  pruning.abort();
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+
+async fn body_archived_again_survives_a_prune()
+ {
+  let dir = scratch("rewrite");
+
+  let root = dir.join("archive");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Directory(
+      root.clone()
+    ),
+    7
+  )
+  .await;
+
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_A,
+    &body(3),
+    T0
+  )
+  .await
+  .unwrap();
+
+  // A prune deletes the row; before it
+  // unlinks the file the body is seen
+  // again and archived anew.
+  let pruned = repo
+    .prune_archived_payloads(
+      T0 + DAY_MS,
+      10,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(pruned.len(), 1);
+
+  archive_body(
+    &cfg,
+    &repo,
+    HASH_A,
+    &body(3),
+    T0 + 8 * DAY_MS
+  )
+  .await
+  .unwrap();
+
+  for p in &pruned {
+    fs::remove_file(root.join(
+      p.location.as_deref().unwrap()
+    ))
+    .unwrap();
+  }
+
+  assert_eq!(
+    load_body(
+      &cfg,
+      repo.as_ref(),
+      HASH_A
+    )
+    .await
+    .unwrap(),
+    Some(body(3))
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,126 @@
+//! Reparsing archived bodies.
+
+use std::fs;
+
+use pulsewire_core::app::archive::archive_body;
+use pulsewire_core::app::reparse::reparse;
+use pulsewire_core::domain::model::ArchiveStorage;
+use pulsewire_core::feed::parser;
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::ports::repo::{
+  PayloadFilter,
+  Repo
+};
+
+use super::{
+  HASH_A,
+  HASH_B,
+  T0,
+  file_of,
+  scratch,
+  setup
+};
+
+const RSS: &[u8] = concat!(
+  "<rss version=\"2.0\">",
+  "<channel><title>t</title>",
+  "<item><guid>a</guid>",
+  "<title>a</title></item>",
+  "</channel></rss>"
+)
+.as_bytes();
+
+#[tokio::test]
+
+async fn unreadable_body_counts_as_missing()
+ {
+  let dir = scratch("reparse");
+
+  let root = dir.join("archive");
+
+  let (cfg, repo) = setup(
+    &dir,
+    ArchiveStorage::Directory(
+      root.clone()
+    ),
+    0
+  )
+  .await;
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      loaded.feeds,
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let parsed =
+    parser::parse(RSS).unwrap();
+
+  for (at, hash) in
+    [(T0, HASH_A), (T0 + 1, HASH_B)]
+  {
+    archive_body(
+      &cfg, &repo, hash, RSS, at
+    )
+    .await
+    .unwrap();
+
+    repo
+      .insert_payload_with_items(
+        "f1",
+        at,
+        None,
+        None,
+        Some(hash),
+        &parsed,
+        &cfg.timezone
+      )
+      .await
+      .unwrap();
+  }
+
+  // The first body's file is gone
+  // while its row remains.
+  fs::remove_file(
+    file_of(&repo, &root, HASH_A).await
+  )
+  .unwrap();
+
+  let report = reparse(
+    &cfg,
+    repo.as_ref(),
+    &PayloadFilter::default(),
+    true
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    (
+      report.payloads,
+      report.missing_bodies,
+      report.parse_errors,
+      report.unchanged
+    ),
+    (2, 1, 0, 1)
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
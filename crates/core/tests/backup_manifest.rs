@@ -0,0 +1,144 @@
+use pulsewire_core::domain::hashing::sha256_hex;
+use pulsewire_core::domain::model::SqlDialect;
+use pulsewire_core::infra::backup::{
+  BackupFile,
+  BackupManifest,
+  TableRows
+};
+
+#[tokio::test]
+
+async fn validates_before_restoring() {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-backup-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let data = b"snapshot bytes";
+
+  std::fs::write(
+    dir.join("database.sqlite"),
+    data
+  )
+  .unwrap();
+
+  let manifest = BackupManifest {
+    format:                1,
+    dialect:               "sqlite"
+      .to_string(),
+    created_at_ms:         0,
+    schema_version:        1,
+    server_schema_version: None,
+    compressed:            false,
+    tables:                vec![
+      TableRows {
+        name:    "feeds".to_string(),
+        rows:    2,
+        columns: Vec::new()
+      },
+    ],
+    files:                 vec![
+      BackupFile {
+        path:   "database.sqlite"
+          .to_string(),
+        bytes:  data.len() as u64,
+        sha256: sha256_hex(data)
+      },
+    ]
+  };
+
+  manifest.save(&dir).await.unwrap();
+
+  let loaded =
+    BackupManifest::load(&dir)
+      .await
+      .unwrap();
+
+  assert_eq!(
+    loaded.rows("feeds"),
+    Some(2)
+  );
+
+  loaded
+    .validate(
+      &dir,
+      SqlDialect::Sqlite,
+      1
+    )
+    .await
+    .unwrap();
+
+  let err =
+    |result: Result<(), String>| {
+      result.unwrap_err()
+    };
+
+  assert!(
+    err(
+      loaded
+        .validate(
+          &dir,
+          SqlDialect::Postgres,
+          1
+        )
+        .await
+    )
+    .contains("target is postgres")
+  );
+
+  assert!(
+    err(
+      loaded
+        .validate(
+          &dir,
+          SqlDialect::Sqlite,
+          0
+        )
+        .await
+    )
+    .contains("newer than this binary")
+  );
+
+  let mut escaping = loaded.clone();
+  escaping.files[0].path =
+    "../database.sqlite".to_string();
+
+  assert!(
+    err(
+      escaping
+        .validate(
+          &dir,
+          SqlDialect::Sqlite,
+          1
+        )
+        .await
+    )
+    .contains("leaves the backup")
+  );
+
+  std::fs::write(
+    dir.join("database.sqlite"),
+    b"snapshot bytez"
+  )
+  .unwrap();
+
+  assert!(
+    err(
+      loaded
+        .validate(
+          &dir,
+          SqlDialect::Sqlite,
+          1
+        )
+        .await
+    )
+    .contains("is damaged")
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
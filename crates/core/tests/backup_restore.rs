@@ -0,0 +1,308 @@
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::backup;
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig
+};
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// A config bundle with two feeds and
+/// its database at `p.db`.
+async fn setup(
+  tag: &str
+) -> (PathBuf, LoadedConfig) {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-restore-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(&dir, "config.toml", &[
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 300",
+    "max_seconds = 300",
+    "jitter_fraction = 0.0",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]"
+  ]);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 1"
+  ]);
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\"]"
+  ]);
+
+  write(&dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"f1\"",
+    "url = \"https://a.example/f1\"",
+    "[[feeds]]",
+    "id = \"f2\"",
+    "url = \"https://a.example/f2\""
+  ]);
+
+  let mut loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  loaded.app.sqlite_path =
+    dir.join("p.db");
+
+  (dir, loaded)
+}
+
+async fn feed_count(
+  cfg: &AppConfig
+) -> i64 {
+  let pool = sqlx::SqlitePool::connect(
+    &format!(
+      "sqlite://{}",
+      cfg.sqlite_path.display()
+    )
+  )
+  .await
+  .unwrap();
+
+  let count = sqlx::query_scalar(
+    "SELECT count(*) FROM feeds"
+  )
+  .fetch_one(&pool)
+  .await
+  .unwrap();
+
+  pool.close().await;
+
+  count
+}
+
+/// Backs up two feeds, adds a third,
+/// then restores over the live file.
+async fn round_trip(
+  tag: &str,
+  compress: bool
+) {
+  let (dir, loaded) = setup(tag).await;
+
+  let cfg = &loaded.app;
+
+  let repo =
+    SqliteRepo::new(&cfg.sqlite_path)
+      .await
+      .unwrap();
+
+  repo
+    .migrate(&cfg.timezone, 300)
+    .await
+    .unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      loaded.feeds.clone(),
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let out = dir.join("backup");
+
+  let manifest = backup::backup(
+    cfg,
+    &out,
+    compress,
+    |_, _| {}
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    manifest.compressed,
+    compress
+  );
+  assert_eq!(
+    manifest
+      .tables
+      .iter()
+      .find(|t| t.name == "feeds")
+      .unwrap()
+      .rows,
+    2
+  );
+
+  let mut extra =
+    loaded.feeds[0].clone();
+
+  extra.id = "f3".to_string();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![extra],
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  drop(repo);
+
+  assert_eq!(feed_count(cfg).await, 3);
+
+  assert!(
+    backup::restore(
+      cfg,
+      &out,
+      false,
+      |_, _| {}
+    )
+    .await
+    .unwrap_err()
+    .contains("--replace")
+  );
+
+  // A directory where the live file
+  // would be moved makes the swap fail;
+  // the live database must survive.
+  let blocker =
+    dir.join("p.db.replaced");
+
+  fs::create_dir_all(blocker.join("x"))
+    .unwrap();
+
+  assert!(
+    backup::restore(
+      cfg,
+      &out,
+      true,
+      |_, _| {}
+    )
+    .await
+    .is_err()
+  );
+
+  assert_eq!(feed_count(cfg).await, 3);
+
+  fs::remove_dir_all(&blocker).unwrap();
+
+  backup::restore(
+    cfg,
+    &out,
+    true,
+    |_, _| {}
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(feed_count(cfg).await, 2);
+
+  // Nothing of the replaced database is
+  // left beside it.
+  let leftovers: Vec<_> =
+    fs::read_dir(&dir)
+      .unwrap()
+      .map(|e| {
+        e.unwrap()
+          .file_name()
+          .to_string_lossy()
+          .into_owned()
+      })
+      .filter(|name| {
+        name.contains(".replaced")
+          || name.contains(".restore")
+      })
+      .collect();
+
+  assert!(
+    leftovers.is_empty(),
+    "{leftovers:?}"
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+
+async fn plain_backup_restores_the_snapshot()
+ {
+  round_trip("plain", false).await;
+}
+
+#[tokio::test]
+
+async fn compressed_backup_restores_the_snapshot()
+ {
+  round_trip("gzip", true).await;
+}
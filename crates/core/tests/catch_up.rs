@@ -0,0 +1,204 @@
+use pulsewire_core::app::scheduler::spread_overdue;
+use pulsewire_core::domain::model::{
+  CatchUpConfig,
+  FeedConfig,
+  FeedPriority
+};
+use pulsewire_core::ports::repo::ScheduledFeed;
+
+const NOW: i64 = 10_000_000;
+
+fn scheduled(
+  id: &str,
+  next_action_at_ms: Option<i64>
+) -> ScheduledFeed {
+  ScheduledFeed {
+    feed: FeedConfig {
+      id:                id.to_string(),
+      url:               format!(
+        "https://example.com/{id}"
+      ),
+      domain:            "example.com"
+        .to_string(),
+      category:          "news"
+        .to_string(),
+      base_poll_seconds: 60,
+      provenance:        None,
+      tags:              None,
+      language:          None,
+      content_type:      None,
+      dataset:           None,
+      priority:
+        FeedPriority::Normal
+    },
+    next_action_at_ms
+  }
+}
+
+fn policy() -> CatchUpConfig {
+  CatchUpConfig {
+    enabled:             true,
+    window_seconds:      100,
+    min_overdue_seconds: 60
+  }
+}
+
+#[test]
+
+fn spreads_most_overdue_first_within_window()
+ {
+  let mut feeds = vec![
+    scheduled(
+      "recent",
+      Some(NOW - 70_000)
+    ),
+    scheduled(
+      "oldest",
+      Some(NOW - 900_000)
+    ),
+    scheduled(
+      "older",
+      Some(NOW - 500_000)
+    ),
+    scheduled(
+      "barely",
+      Some(NOW - 30_000)
+    ),
+    scheduled(
+      "future",
+      Some(NOW + 5_000)
+    ),
+    scheduled("new", None),
+  ];
+
+  let spread = spread_overdue(
+    &mut feeds,
+    NOW,
+    &policy(),
+    || 0.5
+  );
+
+  assert_eq!(spread, 3);
+
+  let at = |id: &str| {
+    feeds
+      .iter()
+      .find(|f| f.feed.id == id)
+      .and_then(|f| f.next_action_at_ms)
+  };
+
+  // Three slots of ~33.3s, each feed
+  // placed mid-slot.
+  assert_eq!(
+    at("oldest"),
+    Some(NOW + 16_666)
+  );
+
+  assert_eq!(
+    at("older"),
+    Some(NOW + 50_000)
+  );
+
+  assert_eq!(
+    at("recent"),
+    Some(NOW + 83_333)
+  );
+
+  assert_eq!(
+    at("barely"),
+    Some(NOW - 30_000)
+  );
+
+  assert_eq!(
+    at("future"),
+    Some(NOW + 5_000)
+  );
+
+  assert_eq!(at("new"), None);
+}
+
+#[test]
+
+fn disabled_policy_leaves_feeds_alone()
+{
+  let mut feeds = vec![scheduled(
+    "oldest",
+    Some(NOW - 900_000)
+  )];
+
+  let cfg = CatchUpConfig {
+    enabled: false,
+    ..policy()
+  };
+
+  assert_eq!(
+    spread_overdue(
+      &mut feeds,
+      NOW,
+      &cfg,
+      || 0.0
+    ),
+    0
+  );
+
+  assert_eq!(
+    feeds[0].next_action_at_ms,
+    Some(NOW - 900_000)
+  );
+}
+
+#[test]
+
+fn slots_follow_the_original_due_order()
+{
+  // Loaded in an order unrelated to
+  // how overdue each feed is.
+  let overdue_ms =
+    [300, 900, 120, 600, 450, 750, 200];
+
+  let mut feeds = overdue_ms
+    .iter()
+    .map(|ms| {
+      scheduled(
+        &ms.to_string(),
+        Some(NOW - ms * 1000)
+      )
+    })
+    .collect::<Vec<_>>();
+
+  // Draws near both ends of each slot
+  // still never overtake a neighbour.
+  let mut draws =
+    [0.99, 0.01].into_iter().cycle();
+
+  spread_overdue(
+    &mut feeds,
+    NOW,
+    &policy(),
+    || draws.next().unwrap()
+  );
+
+  let mut by_slot = feeds
+    .iter()
+    .map(|f| {
+      (
+        f.next_action_at_ms.unwrap(),
+        f.feed
+          .id
+          .parse::<i64>()
+          .unwrap()
+      )
+    })
+    .collect::<Vec<_>>();
+
+  by_slot.sort();
+
+  let order = by_slot
+    .iter()
+    .map(|(_, ms)| *ms)
+    .collect::<Vec<_>>();
+
+  assert_eq!(order, [
+    900, 750, 600, 450, 300, 200, 120
+  ]);
+}
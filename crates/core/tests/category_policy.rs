@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  validate_semantic
+};
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// A config bundle whose `video`
+/// category overrides polling and
+/// dispatch; `max_seconds` is passed
+/// through so a test can break it.
+fn bundle(
+  tag: &str,
+  video_max_seconds: u64
+) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-policy-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(&dir, "config.toml", &[
+    "[app]",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 600",
+    "max_seconds = 3600",
+    "jitter_fraction = 0.1",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]",
+    "[scheduler]",
+    "parallelism = 8"
+  ]);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"video.example\"",
+    "max_concurrent_requests = 2"
+  ]);
+
+  let video_max = format!(
+    "max_seconds = {video_max_seconds}"
+  );
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"news.example\"]",
+    "[[categories.windows]]",
+    "days = [\"sat\", \"sun\"]",
+    "start = \"00:00\"",
+    "end = \"24:00\"",
+    "quiet = true",
+    "[[categories]]",
+    "name = \"video\"",
+    "domains = [\"video.example\"]",
+    "parallelism = 2",
+    "due_batch_size = 50",
+    "[categories.polling]",
+    "default_seconds = 1800",
+    &video_max,
+    "[categories.backoff]",
+    "max_consecutive_errors = 9"
+  ]);
+
+  write(&dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"clip\"",
+    "url = \"https://video.example/rss\"",
+    "[[feeds]]",
+    "id = \"daily\"",
+    "url = \"https://news.example/rss\""
+  ]);
+
+  dir
+}
+
+#[tokio::test]
+
+async fn category_overrides_merge_over_globals()
+ {
+  let dir = bundle("merge", 14_400);
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  let scheduling =
+    &loaded.app.scheduling;
+
+  let video =
+    scheduling.policy_for("video");
+
+  assert_eq!(
+    video.default_poll_seconds,
+    1800
+  );
+
+  assert_eq!(
+    video.max_poll_seconds,
+    14_400
+  );
+
+  assert_eq!(
+    video.jitter_fraction,
+    0.1
+  );
+
+  assert_eq!(
+    video.max_consecutive_errors,
+    9
+  );
+
+  assert_eq!(video.parallelism, 2);
+
+  assert_eq!(video.due_batch_size, 50);
+
+  assert_eq!(
+    scheduling.policy_for("news"),
+    scheduling.defaults
+  );
+
+  assert_eq!(
+    scheduling.defaults.parallelism,
+    8
+  );
+
+  assert_eq!(
+    scheduling.defaults.due_batch_size,
+    1000
+  );
+
+  let poll = |id: &str| {
+    loaded
+      .feeds
+      .iter()
+      .find(|f| f.id == id)
+      .map(|f| f.base_poll_seconds)
+  };
+
+  assert_eq!(poll("clip"), Some(1800));
+
+  assert_eq!(poll("daily"), Some(600));
+
+  let windows = &loaded.app.windows;
+
+  assert_eq!(
+    windows
+      .for_feed("daily", "news")
+      .len(),
+    1
+  );
+
+  assert!(
+    windows
+      .for_feed("clip", "video")
+      .is_empty()
+  );
+
+  assert!(
+    validate_semantic(
+      &loaded.app,
+      &loaded.categories
+    )
+    .is_ok()
+  );
+
+  let _ = fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+
+async fn semantic_check_rejects_inverted_bounds()
+ {
+  let dir = bundle("bounds", 900);
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  let err = validate_semantic(
+    &loaded.app,
+    &loaded.categories
+  )
+  .unwrap_err()
+  .to_string();
+
+  assert!(
+    err.contains("category 'video'"),
+    "{err}"
+  );
+
+  let _ = fs::remove_dir_all(&dir);
+}
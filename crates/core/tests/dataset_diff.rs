@@ -0,0 +1,89 @@
+use pulsewire_core::domain::model::{
+  DatasetConfig,
+  DatasetFormat
+};
+use pulsewire_core::feed::dataset::{
+  RowChangeKind,
+  changes_to_feed,
+  diff_rows,
+  parse_rows
+};
+
+#[test]
+
+fn csv_versions_diff_by_key() {
+  let cfg = DatasetConfig {
+    format:       DatasetFormat::Csv,
+    key:          "id".to_string(),
+    records_path: None
+  };
+
+  let prev = parse_rows(
+    b"id,name\n1,alpha\n2,beta\n",
+    &cfg
+  )
+  .unwrap();
+
+  let next = parse_rows(
+    b"id,name\n2,BETA\n3,gamma\n",
+    &cfg
+  )
+  .unwrap();
+
+  let changes = diff_rows(&prev, &next);
+
+  let kinds = changes
+    .iter()
+    .map(|c| (c.kind, c.key.as_str()))
+    .collect::<Vec<_>>();
+
+  assert_eq!(kinds, vec![
+    (RowChangeKind::Removed, "1"),
+    (RowChangeKind::Changed, "2"),
+    (RowChangeKind::Added, "3")
+  ]);
+
+  assert_eq!(
+    changes[1].changed_columns,
+    vec!["name".to_string()]
+  );
+
+  let feed = changes_to_feed(
+    &changes, "abc", 2, 1_000
+  );
+
+  assert_eq!(feed.items.len(), 3);
+
+  assert_eq!(
+    feed.items[1].guid.as_deref(),
+    Some("abc@1000:changed:2")
+  );
+}
+
+#[test]
+
+fn json_records_path_and_duplicate_keys()
+ {
+  let cfg = DatasetConfig {
+    format:       DatasetFormat::Json,
+    key:          "code".to_string(),
+    records_path: Some(
+      "/data".to_string()
+    )
+  };
+
+  let rows = parse_rows(
+    br#"{"data":[{"code":7,"v":1}]}"#,
+    &cfg
+  )
+  .unwrap();
+
+  assert!(rows.contains_key("7"));
+
+  let dup = parse_rows(
+    br#"{"data":[{"code":7},{"code":7}]}"#,
+    &cfg
+  );
+
+  assert!(dup.is_err());
+}
@@ -0,0 +1,216 @@
+use chrono_tz::Tz;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
+use pulsewire_core::infra::db_copy::{
+  self,
+  Store
+};
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+
+async fn migrated(
+  name: &str,
+  zone: &Tz
+) -> (std::path::PathBuf, SqliteRepo) {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-copy-{name}-{}.db",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_file(&db);
+
+  let repo =
+    SqliteRepo::new(&db).await.unwrap();
+
+  repo
+    .migrate(zone, 300)
+    .await
+    .unwrap();
+
+  (db, repo)
+}
+
+#[tokio::test]
+
+async fn copies_rows_with_their_ids() {
+  let zone: Tz = "UTC".parse().unwrap();
+
+  let (from_db, source) =
+    migrated("from", &zone).await;
+
+  let (to_db, target) =
+    migrated("to", &zone).await;
+
+  source
+    .upsert_categories(
+      vec!["news".to_string()],
+      &zone
+    )
+    .await
+    .unwrap();
+
+  source
+    .upsert_feeds_bulk(
+      ["f1", "f2", "f3"]
+        .into_iter()
+        .map(|id| {
+          FeedConfig {
+            id:                id
+              .to_string(),
+            url:               format!(
+              "http://example.com/{id}"
+            ),
+            domain:
+              "example.com".to_string(),
+            category:          "news"
+              .to_string(),
+            base_poll_seconds: 300,
+            provenance:        None,
+            tags:              Some(
+              vec![
+                "a".to_string(),
+                "b".to_string(),
+              ]
+            ),
+            language:          None,
+            content_type:      None,
+            dataset:           None,
+            priority:
+              FeedPriority::default()
+          }
+        })
+        .collect(),
+      100,
+      &zone
+    )
+    .await
+    .unwrap();
+
+  for at in 1..=3 {
+    source
+      .insert_state(
+        &LinkState::initial(
+          "f1".to_string(),
+          300,
+          3600,
+          0.0,
+          at * 1000
+        ),
+        at * 1000,
+        &zone,
+        true
+      )
+      .await
+      .unwrap();
+  }
+
+  let mut ids = Vec::new();
+
+  // One pending request per feed;
+  // repeats are folded together.
+  for (at, feed) in
+    [(1, "f1"), (2, "f2"), (3, "f3")]
+  {
+    ids.push(
+      source
+        .enqueue_fetch_request(
+          feed,
+          Some("test"),
+          at,
+          &zone
+        )
+        .await
+        .unwrap()
+    );
+  }
+
+  let from = Store::sqlite(&from_db)
+    .await
+    .unwrap();
+
+  let to = Store::sqlite(&to_db)
+    .await
+    .unwrap();
+
+  // Batches of one exercise paging.
+  let report = db_copy::copy(
+    &from,
+    &to,
+    1,
+    |_, _| {}
+  )
+  .await
+  .unwrap();
+
+  let rows = |name: &str| {
+    report
+      .tables
+      .iter()
+      .find(|(t, _)| t == name)
+      .map(|(_, n)| *n)
+  };
+
+  assert_eq!(rows("feeds"), Some(3));
+  assert_eq!(
+    rows("feed_state_history"),
+    Some(3)
+  );
+  assert_eq!(
+    rows("fetch_requests"),
+    Some(3)
+  );
+
+  let state = target
+    .latest_state("f1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    state.next_action_at_ms,
+    3000
+  );
+
+  for id in &ids {
+    assert!(
+      target
+        .fetch_request(*id)
+        .await
+        .unwrap()
+        .is_some()
+    );
+  }
+
+  // The copied pending request keeps
+  // absorbing repeats for its feed.
+  let again = target
+    .enqueue_fetch_request(
+      "f1", None, 4, &zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(again, ids[0]);
+
+  let err = db_copy::copy(
+    &from,
+    &to,
+    100,
+    |_, _| {}
+  )
+  .await
+  .unwrap_err();
+
+  assert!(
+    err.contains("not empty"),
+    "{err}"
+  );
+
+  for db in [from_db, to_db] {
+    let _ = std::fs::remove_file(&db);
+  }
+}
@@ -0,0 +1,146 @@
+use pulsewire_core::app::scheduler::DueQueue;
+use pulsewire_core::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
+
+fn feed(id: &str) -> FeedConfig {
+  FeedConfig {
+    id:                id.to_string(),
+    url:               format!(
+      "https://example.com/{id}"
+    ),
+    domain:            "example.com"
+      .to_string(),
+    category:          "news"
+      .to_string(),
+    base_poll_seconds: 60,
+    provenance:        None,
+    tags:              None,
+    language:          None,
+    content_type:      None,
+    dataset:           None,
+    priority:
+      FeedPriority::Normal
+  }
+}
+
+#[test]
+
+fn pops_due_feeds_in_order_and_honours_reschedule()
+ {
+  let mut queue = DueQueue::default();
+
+  queue.schedule(feed("a"), 300);
+
+  queue.schedule(feed("b"), 100);
+
+  queue.schedule(feed("c"), 200);
+
+  queue.schedule(feed("a"), 50);
+
+  assert_eq!(queue.len(), 3);
+
+  assert_eq!(
+    queue.next_due_ms(),
+    Some(50)
+  );
+
+  let due = queue.pop_due(200, 2);
+
+  let ids: Vec<_> = due
+    .iter()
+    .map(|f| f.id.as_str())
+    .collect();
+
+  assert_eq!(ids, ["a", "b"]);
+
+  assert!(queue.remove("c").is_some());
+
+  assert!(
+    queue.pop_due(1_000, 10).is_empty()
+  );
+
+  assert_eq!(queue.next_due_ms(), None);
+
+  assert!(queue.is_empty());
+}
+
+fn ranked(
+  id: &str,
+  priority: FeedPriority
+) -> FeedConfig {
+  FeedConfig {
+    priority,
+    ..feed(id)
+  }
+}
+
+fn ids(
+  due: &[FeedConfig]
+) -> Vec<&str> {
+  due
+    .iter()
+    .map(|f| f.id.as_str())
+    .collect()
+}
+
+#[test]
+
+fn serves_higher_priority_first_and_ages_low()
+ {
+  let mut strict = DueQueue::default();
+
+  strict.schedule(
+    ranked("low", FeedPriority::Low),
+    0
+  );
+
+  strict.schedule(feed("normal"), 500);
+
+  strict.schedule(
+    ranked("high", FeedPriority::High),
+    900
+  );
+
+  assert_eq!(
+    ids(&strict.pop_due(1_000, 3)),
+    ["high", "normal", "low"]
+  );
+
+  // One class per 300ms of waiting:
+  // "low" has waited 1000ms, so it
+  // ranks above "high" that just came
+  // due.
+  let mut aged =
+    DueQueue::with_aging(300);
+
+  aged.schedule(
+    ranked("low", FeedPriority::Low),
+    0
+  );
+
+  aged.schedule(
+    ranked("high", FeedPriority::High),
+    900
+  );
+
+  assert_eq!(
+    aged.lag_by_priority(1_000),
+    [
+      (FeedPriority::High, 100),
+      (FeedPriority::Normal, 0),
+      (FeedPriority::Low, 1_000)
+    ]
+  );
+
+  assert_eq!(
+    ids(&aged.pop_due(1_000, 1)),
+    ["low"]
+  );
+
+  assert_eq!(
+    ids(&aged.pop_due(1_000, 1)),
+    ["high"]
+  );
+}
@@ -0,0 +1,133 @@
+use pulsewire_core::app::scheduler::head_mode::HeadModes;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::HeadProbeConfig;
+
+const NOW_MS: i64 = 1_767_225_600_000;
+
+const CFG: HeadProbeConfig =
+  HeadProbeConfig {
+    retest_seconds:   3600,
+    domain_threshold: 2
+  };
+
+fn state(
+  id: &str,
+  get_only: bool
+) -> LinkState {
+  let mut state = LinkState::initial(
+    id.to_string(),
+    300,
+    300,
+    0.0,
+    NOW_MS
+  );
+
+  state.get_only_since_ms =
+    get_only.then_some(NOW_MS);
+
+  state
+}
+
+#[test]
+
+fn threshold_of_get_only_feeds_tips_the_domain()
+ {
+  let domain = "threshold.example";
+
+  let modes = HeadModes::default();
+
+  let fresh = state("other", false);
+
+  modes
+    .record(domain, &state("a", true));
+
+  assert!(!modes.domain_says_get_only(
+    &CFG, domain, &fresh, NOW_MS
+  ));
+
+  // Recording the same feed twice does
+  // not count it twice.
+  modes
+    .record(domain, &state("a", true));
+
+  assert!(!modes.domain_says_get_only(
+    &CFG, domain, &fresh, NOW_MS
+  ));
+
+  modes
+    .record(domain, &state("b", true));
+
+  assert!(modes.domain_says_get_only(
+    &CFG, domain, &fresh, NOW_MS
+  ));
+
+  // Another domain is unaffected.
+  assert!(!modes.domain_says_get_only(
+    &CFG,
+    "elsewhere.example",
+    &fresh,
+    NOW_MS
+  ));
+
+  // A feed whose HEAD works again
+  // leaves the count.
+  modes
+    .record(domain, &state("b", false));
+
+  assert!(!modes.domain_says_get_only(
+    &CFG, domain, &fresh, NOW_MS
+  ));
+}
+
+#[test]
+
+fn only_feeds_without_a_recent_head_follow_the_domain()
+ {
+  let domain = "recent.example";
+
+  let modes = HeadModes::default();
+
+  modes
+    .record(domain, &state("a", true));
+  modes
+    .record(domain, &state("b", true));
+
+  // Already GET-only: nothing to
+  // decide.
+  assert!(!modes.domain_says_get_only(
+    &CFG,
+    domain,
+    &state("a", true),
+    NOW_MS
+  ));
+
+  let mut probed = state("c", false);
+
+  probed.last_head_at_ms =
+    Some(NOW_MS - 60_000);
+
+  assert!(!modes.domain_says_get_only(
+    &CFG, domain, &probed, NOW_MS
+  ));
+
+  // Its HEAD is older than the re-test
+  // interval.
+  assert!(modes.domain_says_get_only(
+    &CFG,
+    domain,
+    &probed,
+    NOW_MS + 3_600_000
+  ));
+
+  let off = HeadProbeConfig {
+    domain_threshold: 0,
+    ..CFG
+  };
+
+  assert!(!modes.domain_says_get_only(
+    &off,
+    domain,
+    &state("d", false),
+    NOW_MS
+  ));
+}
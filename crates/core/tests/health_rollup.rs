@@ -0,0 +1,112 @@
+use pulsewire_core::app::health::build_rollups;
+use pulsewire_core::ports::repo::{
+  HealthEvent,
+  HealthPayload,
+  HealthScope
+};
+
+fn event(
+  feed_id: &str,
+  error_kind: Option<&str>,
+  latency_ms: i64
+) -> HealthEvent {
+  HealthEvent {
+    feed_id:       feed_id.to_string(),
+    domain:        "example.com"
+      .to_string(),
+    event_time_ms: 0,
+    error_kind:    error_kind
+      .map(str::to_string),
+    latency_ms:    Some(latency_ms)
+  }
+}
+
+#[test]
+
+fn rollups_group_errors_and_percentiles()
+ {
+  let events = vec![
+    event("a", None, 100),
+    event("a", None, 300),
+    event(
+      "a",
+      Some("Http5xx(503)"),
+      200
+    ),
+    event(
+      "a",
+      Some("Http5xx(500)"),
+      900
+    ),
+    event("b", Some("Timeout"), 50),
+  ];
+
+  let payloads = vec![
+    HealthPayload {
+      feed_id:       "a".to_string(),
+      domain:        "example.com"
+        .to_string(),
+      fetched_at_ms: 0,
+      changed:       true,
+      item_count:    4
+    },
+    HealthPayload {
+      feed_id:       "a".to_string(),
+      domain:        "example.com"
+        .to_string(),
+      fetched_at_ms: 1,
+      changed:       false,
+      item_count:    4
+    },
+  ];
+
+  let rows = build_rollups(
+    "2026-01-02",
+    &events,
+    &payloads
+  );
+
+  assert_eq!(rows.len(), 3);
+
+  let a = &rows[0];
+
+  assert_eq!(a.subject, "a");
+
+  assert_eq!(a.fetches, 4);
+
+  assert_eq!(a.successes, 2);
+
+  assert_eq!(
+    a.error_counts.get("Http5xx"),
+    Some(&2)
+  );
+
+  assert_eq!(
+    a.p50_latency_ms,
+    Some(200)
+  );
+
+  assert_eq!(
+    a.p95_latency_ms,
+    Some(900)
+  );
+
+  assert_eq!(
+    (a.changes, a.items),
+    (1, 4)
+  );
+
+  let domain = &rows[2];
+
+  assert_eq!(
+    domain.scope,
+    HealthScope::Domain
+  );
+
+  assert_eq!(domain.fetches, 5);
+
+  assert_eq!(
+    domain.error_counts.get("Timeout"),
+    Some(&1)
+  );
+}
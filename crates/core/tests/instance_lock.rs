@@ -0,0 +1,255 @@
+use pulsewire_core::infra::instance_lock::{
+  InstanceLock,
+  LockInfo
+};
+
+#[test]
+
+fn second_acquire_fails_until_released()
+{
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-lock-{}.db",
+      std::process::id()
+    ));
+
+  let lock =
+    InstanceLock::acquire(&db).unwrap();
+
+  let err = InstanceLock::acquire(&db)
+    .err()
+    .unwrap();
+
+  assert!(
+    err.contains("another fetcher")
+  );
+
+  lock.release();
+
+  assert!(
+    !InstanceLock::path_for(&db)
+      .exists()
+  );
+
+  InstanceLock::acquire(&db)
+    .unwrap()
+    .release();
+}
+
+#[test]
+
+fn old_heartbeat_is_stale() {
+  let raw = [
+    "pid=1",
+    "host=elsewhere",
+    "started_ms=0",
+    "heartbeat_ms=1000"
+  ]
+  .join("\n");
+
+  let info =
+    LockInfo::parse(&raw).unwrap();
+
+  assert!(
+    !info.is_stale(30_000, "here")
+  );
+
+  assert!(
+    info.is_stale(120_000, "here")
+  );
+}
+
+fn stale_lock(db: &std::path::Path) {
+  std::fs::write(
+    InstanceLock::path_for(db),
+    [
+      "pid=1",
+      "host=elsewhere",
+      "started_ms=0",
+      "heartbeat_ms=0"
+    ]
+    .join("\n")
+  )
+  .unwrap();
+}
+
+#[test]
+
+fn one_starter_takes_over_a_stale_lock()
+{
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-lock-race-{}.db",
+      std::process::id()
+    ));
+
+  let mut rounds_won = 0;
+
+  for _ in 0..200 {
+    stale_lock(&db);
+
+    let start = std::sync::Arc::new(
+      std::sync::Barrier::new(8)
+    );
+
+    let starters = (0..8)
+      .map(|_| {
+        let db = db.clone();
+
+        let start = start.clone();
+
+        std::thread::spawn(move || {
+          start.wait();
+
+          InstanceLock::acquire(&db)
+        })
+      })
+      .collect::<Vec<_>>();
+
+    let mut won = starters
+      .into_iter()
+      .filter_map(|t| {
+        t.join().unwrap().ok()
+      })
+      .collect::<Vec<_>>();
+
+    // Starters that see the file move
+    // under them back off, so a round
+    // may rarely have no winner, but
+    // never two.
+    assert!(won.len() <= 1);
+
+    if let Some(lock) = won.pop() {
+      lock.release();
+
+      rounds_won += 1;
+    }
+  }
+
+  assert!(rounds_won > 100);
+
+  let _ = std::fs::remove_file(
+    InstanceLock::path_for(&db)
+  );
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn losing_the_lock_signals_shutdown()
+ {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-lock-lost-{}.db",
+      std::process::id()
+    ));
+
+  let mut lock =
+    InstanceLock::acquire(&db).unwrap();
+
+  let (tx, mut rx) =
+    tokio::sync::watch::channel(false);
+
+  lock.spawn_heartbeat(tx);
+
+  // A rival moves ours aside and
+  // creates its own file.
+  std::fs::remove_file(
+    InstanceLock::path_for(&db)
+  )
+  .unwrap();
+
+  stale_lock(&db);
+
+  tokio::time::timeout(
+    std::time::Duration::from_secs(30),
+    rx.changed()
+  )
+  .await
+  .unwrap()
+  .unwrap();
+
+  assert!(*rx.borrow());
+
+  lock.release();
+
+  // The rival's file is left as it
+  // wrote it.
+  let held = LockInfo::parse(
+    &std::fs::read_to_string(
+      InstanceLock::path_for(&db)
+    )
+    .unwrap()
+  )
+  .unwrap();
+
+  assert_eq!(held.host, "elsewhere");
+  assert_eq!(held.heartbeat_ms, 0);
+
+  std::fs::remove_file(
+    InstanceLock::path_for(&db)
+  )
+  .unwrap();
+}
+
+#[test]
+
+fn lock_left_with_our_pid_is_stale() {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-lock-pid-{}.db",
+      std::process::id()
+    ));
+
+  let path =
+    InstanceLock::path_for(&db);
+
+  let lock =
+    InstanceLock::acquire(&db).unwrap();
+
+  let ours = LockInfo::parse(
+    &std::fs::read_to_string(&path)
+      .unwrap()
+  )
+  .unwrap();
+
+  lock.release();
+
+  assert!(!ours.is_stale(
+    ours.heartbeat_ms,
+    &ours.host
+  ));
+
+  // An earlier process with the same
+  // pid, e.g. PID 1 in a restarted
+  // container, heartbeat moments ago.
+  let earlier = LockInfo {
+    instance: "earlier".to_string(),
+    ..ours.clone()
+  };
+
+  assert!(earlier.is_stale(
+    ours.heartbeat_ms,
+    &ours.host
+  ));
+
+  std::fs::write(
+    &path,
+    [
+      format!("pid={}", ours.pid),
+      format!("host={}", ours.host),
+      "started_ms=0".to_string(),
+      format!(
+        "heartbeat_ms={}",
+        ours.heartbeat_ms
+      )
+    ]
+    .join("\n")
+  )
+  .unwrap();
+
+  InstanceLock::acquire(&db)
+    .unwrap()
+    .release();
+
+  assert!(!path.exists());
+}
@@ -1,4 +1,8 @@
 use pulsewire_core::domain::link_state::{compute_delay_seconds, LinkPhase, LinkState, NextAction};
+use pulsewire_core::domain::model::{
+  GetResult,
+  HeadResult
+};
 
 #[test]
 
@@ -26,7 +30,7 @@ fn decide_action_respects_next_action_at()
   s.next_action_at_ms = 2_000;
 
   let a = LinkState::decide_next_action(
-    &s, 1_500
+    &s, 1_500, 60_000
   );
 
   match a {
@@ -36,3 +40,169 @@ fn decide_action_respects_next_action_at()
     | _ => panic!("expected sleep")
   }
 }
+
+#[test]
+
+fn refused_head_switches_to_get_until_retest()
+ {
+  let mut s = LinkState::initial(
+    "f1".to_string(),
+    10,
+    60,
+    0.1,
+    0
+  );
+
+  s.phase = LinkPhase::Sleeping;
+
+  s.consecutive_error_count = 1;
+
+  let head =
+    |status, etag: Option<&str>| {
+      HeadResult {
+        status:        Some(status),
+        etag:          etag
+          .map(str::to_string),
+        last_modified: None,
+        error:         None,
+        latency_ms:    5
+      }
+    };
+
+  let s = LinkState::apply_head_result(
+    s,
+    head(405, None),
+    1_000,
+    0.5
+  );
+
+  assert_eq!(
+    s.get_only_since_ms,
+    Some(1_000)
+  );
+  assert_eq!(
+    s.phase,
+    LinkPhase::NeedsGet
+  );
+  assert_eq!(
+    s.next_action_at_ms,
+    1_000
+  );
+  assert_eq!(
+    s.consecutive_error_count,
+    1
+  );
+
+  let mut s = s;
+
+  s.phase = LinkPhase::Sleeping;
+
+  assert!(matches!(
+    LinkState::decide_next_action(
+      &s, 30_000, 60_000
+    ),
+    NextAction::DoGet { .. }
+  ));
+
+  // Re-test is due: HEAD again, and a
+  // well-behaved answer restores it.
+  assert!(matches!(
+    LinkState::decide_next_action(
+      &s, 61_000, 60_000
+    ),
+    NextAction::DoHead { .. }
+  ));
+
+  let s = LinkState::apply_head_result(
+    s,
+    head(200, Some("\"v2\"")),
+    61_000,
+    0.5
+  );
+
+  assert_eq!(s.get_only_since_ms, None);
+  assert!(
+    s.note.as_deref().is_some_and(
+      |n| {
+        n.starts_with("head-restored")
+      }
+    )
+  );
+}
+
+#[test]
+
+fn errors_back_off_from_the_error_policy()
+ {
+  let s = LinkState::initial(
+    "f1".to_string(),
+    300,
+    3_600,
+    0.0,
+    0
+  )
+  .with_error_backoff(30, 90);
+
+  let get = |status| {
+    GetResult {
+      status:        Some(status),
+      body:          None,
+      etag:          None,
+      last_modified: None,
+      error:         None,
+      latency_ms:    5
+    }
+  };
+
+  let failed = |s| {
+    LinkState::apply_get_result(
+      s,
+      get(503),
+      0,
+      false,
+      0.5
+    )
+  };
+
+  let s = failed(s);
+
+  assert_eq!(
+    s.phase,
+    LinkPhase::ErrorBackoff
+  );
+  assert_eq!(
+    s.next_action_at_ms,
+    30_000
+  );
+
+  let s = failed(s);
+
+  assert_eq!(
+    s.next_action_at_ms,
+    60_000
+  );
+
+  // Capped at the error maximum, not
+  // the poll maximum.
+  let s = failed(failed(s));
+
+  assert_eq!(
+    s.next_action_at_ms,
+    90_000
+  );
+
+  // Recovery polls from the poll
+  // interval again.
+  let s = LinkState::apply_get_result(
+    s,
+    get(200),
+    0,
+    true,
+    0.5
+  );
+
+  assert_eq!(
+    s.next_action_at_ms,
+    300_000
+  );
+}
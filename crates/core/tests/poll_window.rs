@@ -0,0 +1,206 @@
+use chrono::{
+  NaiveDate,
+  TimeZone
+};
+use chrono_tz::Tz;
+use pulsewire_core::domain::poll_window::{
+  PollWindow,
+  adjust_next_action,
+  poll_seconds_at,
+  quiet_until
+};
+
+const WEEKDAYS: u8 = 0b001_1111;
+
+const WEEKEND: u8 = 0b110_0000;
+
+const EVERY_DAY: u8 = 0b111_1111;
+
+fn at(
+  tz: &Tz,
+  (y, mo, d): (i32, u32, u32),
+  (h, mi): (u32, u32)
+) -> i64 {
+  tz.from_local_datetime(
+    &NaiveDate::from_ymd_opt(y, mo, d)
+      .unwrap()
+      .and_hms_opt(h, mi, 0)
+      .unwrap()
+  )
+  .unwrap()
+  .timestamp_millis()
+}
+
+/// Quiet weekends and nights, with
+/// fast polling during market hours.
+fn finance() -> Vec<PollWindow> {
+  vec![
+    PollWindow {
+      days:         WEEKEND,
+      start_minute: 0,
+      end_minute:   1440,
+      poll_seconds: None
+    },
+    PollWindow {
+      days:         EVERY_DAY,
+      start_minute: 22 * 60,
+      end_minute:   6 * 60,
+      poll_seconds: None
+    },
+    PollWindow {
+      days:         WEEKDAYS,
+      start_minute: 9 * 60 + 30,
+      end_minute:   16 * 60,
+      poll_seconds: Some(60)
+    },
+  ]
+}
+
+#[test]
+
+fn quiet_windows_chain_and_wrap() {
+  let tz: Tz =
+    "Europe/Berlin".parse().unwrap();
+
+  let windows = finance();
+
+  // Saturday noon: the weekend ends at
+  // midnight, inside the nightly window
+  // Sunday opened, so Monday 06:00.
+  let saturday =
+    at(&tz, (2026, 10, 17), (12, 0));
+
+  assert_eq!(
+    quiet_until(
+      &windows, saturday, &tz
+    ),
+    Some(at(
+      &tz,
+      (2026, 10, 19),
+      (6, 0)
+    ))
+  );
+
+  // Tuesday 02:00 is still inside the
+  // window Monday opened.
+  let tuesday =
+    at(&tz, (2026, 10, 20), (2, 0));
+
+  assert_eq!(
+    quiet_until(&windows, tuesday, &tz),
+    Some(at(
+      &tz,
+      (2026, 10, 20),
+      (6, 0)
+    ))
+  );
+
+  let noon =
+    at(&tz, (2026, 10, 20), (12, 0));
+
+  assert_eq!(
+    quiet_until(&windows, noon, &tz),
+    None
+  );
+
+  assert_eq!(
+    poll_seconds_at(
+      &windows, noon, &tz
+    ),
+    Some(60)
+  );
+}
+
+#[test]
+
+fn next_action_is_capped_and_deferred()
+{
+  let tz: Tz = "UTC".parse().unwrap();
+
+  let windows = finance();
+
+  let noon =
+    at(&tz, (2026, 10, 20), (12, 0));
+
+  let hour_later = noon + 3_600_000;
+
+  assert_eq!(
+    adjust_next_action(
+      &windows, &tz, noon, hour_later,
+      true, 300, 0.0
+    ),
+    noon + 60_000
+  );
+
+  // Error backoff keeps its delay.
+  assert_eq!(
+    adjust_next_action(
+      &windows, &tz, noon, hour_later,
+      false, 300, 0.0
+    ),
+    hour_later
+  );
+
+  // Friday 21:30 plus an hour lands in
+  // the weekend; it moves to Monday
+  // 06:00 plus half the base poll
+  // interval.
+  let friday =
+    at(&tz, (2026, 10, 23), (21, 30));
+
+  assert_eq!(
+    adjust_next_action(
+      &windows,
+      &tz,
+      friday,
+      friday + 3_600_000,
+      true,
+      300,
+      0.5
+    ),
+    at(&tz, (2026, 10, 26), (6, 0))
+      + 150_000
+  );
+}
+
+#[test]
+
+fn deferred_action_wakes_within_one_base_poll()
+ {
+  let tz: Tz = "UTC".parse().unwrap();
+
+  let windows = finance();
+
+  let friday =
+    at(&tz, (2026, 10, 23), (21, 30));
+
+  let open =
+    at(&tz, (2026, 10, 26), (6, 0));
+
+  // However long the original delay,
+  // e.g. a day of error backoff, the
+  // spread past the window's end is
+  // bounded by the base poll interval.
+  for delay_ms in
+    [3_600_000, 86_400_000]
+  {
+    for rand01 in [0.0, 0.5, 0.99, 1.0]
+    {
+      let next = adjust_next_action(
+        &windows,
+        &tz,
+        friday,
+        friday + delay_ms,
+        false,
+        300,
+        rand01
+      );
+
+      assert!(
+        (open..=open + 300_000)
+          .contains(&next),
+        "{delay_ms} {rand01}: {next}"
+      );
+    }
+  }
+}
@@ -0,0 +1,109 @@
+//! Permits go to higher priority
+//! classes first, with aging lifting a
+//! long-waiting lower class.
+
+use std::sync::{
+  Arc,
+  Mutex
+};
+use std::time::Duration;
+
+use pulsewire_core::app::scheduler::ClassedSemaphore;
+use pulsewire_core::domain::model::FeedPriority;
+
+/// Queues one waiter per entry of
+/// `order`, a millisecond apart, while
+/// the only permit is held, then
+/// returns the order they were served.
+async fn served(
+  aging: Option<Duration>,
+  order: &[(
+    &'static str,
+    FeedPriority
+  )]
+) -> Vec<&'static str> {
+  let sem =
+    ClassedSemaphore::new(1, aging);
+
+  let held = sem
+    .acquire(FeedPriority::High)
+    .await;
+
+  let log =
+    Arc::new(Mutex::new(Vec::new()));
+
+  let mut waiters = Vec::new();
+
+  for &(name, priority) in order {
+    let sem = sem.clone();
+
+    let log = log.clone();
+
+    waiters.push(tokio::spawn(
+      async move {
+        let _permit =
+          sem.acquire(priority).await;
+
+        log.lock().unwrap().push(name);
+
+        tokio::time::sleep(
+          Duration::from_millis(10)
+        )
+        .await;
+      }
+    ));
+
+    tokio::time::sleep(
+      Duration::from_millis(1)
+    )
+    .await;
+  }
+
+  tokio::time::sleep(
+    Duration::from_secs(60)
+  )
+  .await;
+
+  drop(held);
+
+  for w in waiters {
+    w.await.unwrap();
+  }
+
+  log.lock().unwrap().clone()
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn higher_class_is_served_before_earlier_lower()
+ {
+  assert_eq!(
+    served(None, &[
+      ("low", FeedPriority::Low),
+      ("normal", FeedPriority::Normal),
+      ("high", FeedPriority::High),
+      ("low2", FeedPriority::Low)
+    ])
+    .await,
+    ["high", "normal", "low", "low2"]
+  );
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn aging_lifts_a_long_waiting_low()
+ {
+  // By the release `low` has aged two
+  // classes and arrived first.
+  assert_eq!(
+    served(
+      Some(Duration::from_secs(20)),
+      &[
+        ("low", FeedPriority::Low),
+        ("high", FeedPriority::High)
+      ]
+    )
+    .await,
+    ["low", "high"]
+  );
+}
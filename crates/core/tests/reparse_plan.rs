@@ -0,0 +1,168 @@
+use pulsewire_core::app::reparse::plan_items;
+use pulsewire_core::feed::parser::FeedItem;
+use pulsewire_core::ports::repo::StoredItem;
+
+fn item(
+  guid: &str,
+  title: &str
+) -> FeedItem {
+  FeedItem {
+    title:           Some(
+      title.to_string()
+    ),
+    link:            None,
+    guid:            Some(
+      guid.to_string()
+    ),
+    published_at_ms: None,
+    category:        None,
+    description:     None,
+    summary:         None
+  }
+}
+
+#[test]
+
+fn plan_matches_by_guid_and_is_idempotent()
+ {
+  let existing = vec![
+    StoredItem {
+      id:   1,
+      item: item("a", "old")
+    },
+    StoredItem {
+      id:   2,
+      item: item("b", "same")
+    },
+  ];
+
+  let parsed = vec![
+    item("a", "new"),
+    item("b", "same"),
+    item("c", "added"),
+  ];
+
+  let plan =
+    plan_items(&existing, &parsed);
+
+  assert_eq!(plan.unchanged, 1);
+
+  assert_eq!(plan.updates.len(), 1);
+
+  assert_eq!(plan.updates[0].id, 1);
+
+  assert_eq!(plan.inserts.len(), 1);
+
+  let applied = vec![
+    StoredItem {
+      id:   1,
+      item: item("a", "new")
+    },
+    StoredItem {
+      id:   2,
+      item: item("b", "same")
+    },
+    StoredItem {
+      id:   3,
+      item: item("c", "added")
+    },
+  ];
+
+  let again =
+    plan_items(&applied, &parsed);
+
+  assert_eq!(again.unchanged, 3);
+
+  assert!(
+    again.inserts.is_empty()
+      && again.updates.is_empty()
+  );
+}
+
+fn linked(
+  guid: Option<&str>,
+  link: &str,
+  title: &str
+) -> FeedItem {
+  FeedItem {
+    guid: guid.map(str::to_string),
+    link: Some(link.to_string()),
+    ..item("", title)
+  }
+}
+
+#[test]
+
+fn duplicate_guids_match_each_stored_copy()
+ {
+  let existing = vec![
+    StoredItem {
+      id:   1,
+      item: item("a", "first")
+    },
+    StoredItem {
+      id:   2,
+      item: item("a", "second")
+    },
+  ];
+
+  let parsed = vec![
+    item("a", "first"),
+    item("a", "second"),
+  ];
+
+  let plan =
+    plan_items(&existing, &parsed);
+
+  assert_eq!(plan.unchanged, 2);
+  assert!(
+    plan.inserts.is_empty()
+      && plan.updates.is_empty()
+  );
+}
+
+#[test]
+
+fn item_gaining_a_guid_matches_by_link()
+{
+  let existing = vec![StoredItem {
+    id:   1,
+    item: linked(
+      None,
+      "https://a.example/1",
+      "one"
+    )
+  }];
+
+  let parsed = vec![linked(
+    Some("g1"),
+    "https://a.example/1",
+    "one"
+  )];
+
+  let plan =
+    plan_items(&existing, &parsed);
+
+  assert!(plan.inserts.is_empty());
+  assert_eq!(plan.updates.len(), 1);
+  assert_eq!(plan.updates[0].id, 1);
+}
+
+#[test]
+
+fn shared_title_with_other_guid_is_new()
+{
+  let existing = vec![StoredItem {
+    id:   1,
+    item: item("a", "Daily update")
+  }];
+
+  let parsed =
+    vec![item("b", "Daily update")];
+
+  let plan =
+    plan_items(&existing, &parsed);
+
+  assert_eq!(plan.inserts.len(), 1);
+  assert!(plan.updates.is_empty());
+}
@@ -0,0 +1,237 @@
+//! Refresh requests and operator
+//! pauses.
+
+use chrono_tz::Tz;
+use pulsewire_core::ports::repo::{
+  FetchRequestStatus,
+  PauseScope,
+  PausedTarget,
+  Repo
+};
+
+pub async fn fetch_requests(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  let err = repo
+    .enqueue_fetch_request(
+      "nope", None, 1, zone
+    )
+    .await
+    .unwrap_err();
+
+  assert!(err.contains("unknown feed"));
+
+  let low = repo
+    .enqueue_fetch_request(
+      "r1",
+      Some("ops"),
+      1,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(
+    repo
+      .enqueue_fetch_request(
+        "r1", None, 2, zone
+      )
+      .await
+      .unwrap(),
+    low
+  );
+
+  let high = repo
+    .enqueue_fetch_request(
+      "r2", None, 3, zone
+    )
+    .await
+    .unwrap();
+
+  let claimed = repo
+    .claim_fetch_requests(
+      "req",
+      &[],
+      1,
+      10,
+      0,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(claimed.len(), 1);
+  assert_eq!(
+    claimed[0].request_id,
+    high
+  );
+  assert_eq!(claimed[0].feed.id, "r2");
+
+  // Busy feeds are skipped.
+  assert!(
+    repo
+      .claim_fetch_requests(
+        "req",
+        &["r1".to_string()],
+        5,
+        10,
+        0,
+        zone
+      )
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  repo
+    .requeue_fetch_requests(&[high])
+    .await
+    .unwrap();
+
+  let again = repo
+    .fetch_request(high)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    again.status,
+    FetchRequestStatus::Pending
+  );
+  assert_eq!(again.started_at_ms, None);
+
+  // Stale running requests are claimed
+  // again.
+  let claimed = repo
+    .claim_fetch_requests(
+      "req",
+      &[],
+      5,
+      20,
+      0,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(claimed.len(), 2);
+
+  let stale = repo
+    .claim_fetch_requests(
+      "req",
+      &[],
+      5,
+      30,
+      21,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(stale.len(), 2);
+
+  repo
+    .finish_fetch_request(
+      low,
+      FetchRequestStatus::Done,
+      Some(200),
+      None,
+      40,
+      zone
+    )
+    .await
+    .unwrap();
+
+  let done = repo
+    .fetch_request(low)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    done.status,
+    FetchRequestStatus::Done
+  );
+  assert_eq!(
+    done.requested_by.as_deref(),
+    Some("ops")
+  );
+  assert_eq!(
+    done.started_at_ms,
+    Some(30)
+  );
+  assert_eq!(
+    done.finished_at_ms,
+    Some(40)
+  );
+  assert_eq!(
+    done.http_status,
+    Some(200)
+  );
+}
+
+pub async fn pauses(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  for (scope, name) in [
+    (PauseScope::Feed, "r1"),
+    (
+      PauseScope::Domain,
+      "req.example.com"
+    ),
+    (PauseScope::Feed, "r1")
+  ] {
+    repo
+      .pause_target(
+        &PausedTarget {
+          scope,
+          name: name.to_string(),
+          reason: None,
+          paused_at_ms: 1
+        },
+        zone
+      )
+      .await
+      .unwrap();
+  }
+
+  let paused = repo
+    .paused_targets()
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|p| (p.scope, p.name))
+    .collect::<Vec<_>>();
+
+  assert_eq!(paused, [
+    (
+      PauseScope::Domain,
+      "req.example.com".to_string()
+    ),
+    (
+      PauseScope::Feed,
+      "r1".to_string()
+    )
+  ]);
+
+  assert!(
+    repo
+      .resume_target(
+        PauseScope::Feed,
+        "r1"
+      )
+      .await
+      .unwrap()
+  );
+  assert!(
+    !repo
+      .resume_target(
+        PauseScope::Feed,
+        "r1"
+      )
+      .await
+      .unwrap()
+  );
+}
@@ -0,0 +1,245 @@
+//! Work leases and shared domain
+//! tokens. SQLite runs one fetcher and
+//! grants everything; the shared
+//! backends hand each lease to one
+//! owner until it is released or
+//! lapses.
+
+use std::time::Duration;
+
+use pulsewire_core::ports::repo::Repo;
+
+fn ids(ids: &[&str]) -> Vec<String> {
+  ids
+    .iter()
+    .map(|id| id.to_string())
+    .collect()
+}
+
+pub async fn single_owner(
+  repo: &dyn Repo
+) {
+  let feeds = ids(&["l1", "l2"]);
+
+  for owner in ["a", "b"] {
+    assert_eq!(
+      repo
+        .claim_feed_leases(
+          owner, &feeds, 60
+        )
+        .await
+        .unwrap(),
+      feeds
+    );
+
+    assert!(
+      repo
+        .renew_feed_lease(
+          owner, "l1", 60
+        )
+        .await
+        .unwrap()
+    );
+
+    assert!(
+      repo
+        .acquire_domain_token(
+          "l.example",
+          1,
+          owner,
+          "l1",
+          60
+        )
+        .await
+        .unwrap()
+    );
+  }
+
+  repo
+    .release_feed_leases("a", &feeds)
+    .await
+    .unwrap();
+}
+
+pub async fn shared(repo: &dyn Repo) {
+  let feeds = ids(&["l1", "l2"]);
+
+  let mut claimed = repo
+    .claim_feed_leases("a", &feeds, 60)
+    .await
+    .unwrap();
+
+  claimed.sort();
+
+  assert_eq!(claimed, feeds);
+
+  // Held leases are skipped; claiming
+  // again as the owner keeps them.
+  assert_eq!(
+    repo
+      .claim_feed_leases(
+        "b",
+        &ids(&["l1", "l3"]),
+        60
+      )
+      .await
+      .unwrap(),
+    ids(&["l3"])
+  );
+
+  assert_eq!(
+    repo
+      .claim_feed_leases(
+        "a",
+        &ids(&["l1"]),
+        60
+      )
+      .await
+      .unwrap(),
+    ids(&["l1"])
+  );
+
+  assert!(
+    repo
+      .renew_feed_lease("a", "l1", 60)
+      .await
+      .unwrap()
+  );
+  assert!(
+    !repo
+      .renew_feed_lease("b", "l1", 60)
+      .await
+      .unwrap()
+  );
+
+  // One shared slot: a second holder
+  // waits until the first releases.
+  assert!(
+    repo
+      .acquire_domain_token(
+        "l.example",
+        1,
+        "a",
+        "l1",
+        60
+      )
+      .await
+      .unwrap()
+  );
+  assert!(
+    !repo
+      .acquire_domain_token(
+        "l.example",
+        1,
+        "b",
+        "l3",
+        60
+      )
+      .await
+      .unwrap()
+  );
+
+  repo
+    .release_feed_leases(
+      "a",
+      &ids(&["l1"])
+    )
+    .await
+    .unwrap();
+
+  assert!(
+    repo
+      .acquire_domain_token(
+        "l.example",
+        1,
+        "b",
+        "l3",
+        60
+      )
+      .await
+      .unwrap()
+  );
+  assert_eq!(
+    repo
+      .claim_feed_leases(
+        "b",
+        &ids(&["l1"]),
+        60
+      )
+      .await
+      .unwrap(),
+    ids(&["l1"])
+  );
+
+  // Releasing someone else's lease
+  // leaves it alone.
+  repo
+    .release_feed_leases(
+      "a",
+      &ids(&["l1"])
+    )
+    .await
+    .unwrap();
+
+  assert!(
+    repo
+      .renew_feed_lease("b", "l1", 60)
+      .await
+      .unwrap()
+  );
+
+  // A replica that died holding `l2`
+  // stops renewing; once the lease
+  // lapses another owner takes it and
+  // the dead one cannot renew.
+  repo
+    .claim_feed_leases(
+      "a",
+      &ids(&["l2"]),
+      0
+    )
+    .await
+    .unwrap();
+
+  tokio::time::sleep(
+    Duration::from_millis(20)
+  )
+  .await;
+
+  assert_eq!(
+    repo
+      .claim_feed_leases(
+        "b",
+        &ids(&["l2"]),
+        60
+      )
+      .await
+      .unwrap(),
+    ids(&["l2"])
+  );
+  assert!(
+    !repo
+      .renew_feed_lease("a", "l2", 60)
+      .await
+      .unwrap()
+  );
+
+  repo
+    .release_feed_leases(
+      "b",
+      &ids(&["l1", "l2", "l3"])
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(
+    repo
+      .claim_feed_leases(
+        "a", &feeds, 60
+      )
+      .await
+      .unwrap()
+      .len(),
+    2
+  );
+}
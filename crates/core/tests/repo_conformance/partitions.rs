@@ -0,0 +1,209 @@
+//! Postgres partition maintenance:
+//! rows stranded in the default
+//! partition and conversions retried
+//! after a failed index build.
+
+use chrono::{
+  TimeZone,
+  Utc
+};
+use pulsewire_core::ports::repo::{
+  PartitionPlan,
+  Repo
+};
+use sqlx::PgPool;
+
+use super::now_ms;
+
+fn plan(
+  convert_existing: bool,
+  fetch_events_before_ms: Option<i64>
+) -> PartitionPlan {
+  PartitionPlan {
+    now_ms: now_ms(),
+    premake_months: 1,
+    convert_existing,
+    fetch_events_before_ms,
+    state_history_before_ms: None
+  }
+}
+
+async fn exec(
+  pool: &PgPool,
+  sql: &str
+) {
+  sqlx::query(sql)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn count(
+  pool: &PgPool,
+  table: &str
+) -> i64 {
+  sqlx::query_scalar(&format!(
+    "SELECT count(*) FROM {table}"
+  ))
+  .fetch_one(pool)
+  .await
+  .unwrap()
+}
+
+fn insert_event(
+  id: Option<i64>,
+  at: &str
+) -> String {
+  format!(
+    "INSERT INTO fetch_events(id, \
+     feed_id, event_time, method, \
+     backoff_index, \
+     scheduled_next_action_at) VALUES \
+     ({}, 'p1', '{at}', 'GET', 0, \
+     '{at}')",
+    id.map_or(
+      "DEFAULT".to_string(),
+      |id| id.to_string()
+    )
+  )
+}
+
+pub async fn default_rows_move_out(
+  repo: &dyn Repo,
+  pool: &PgPool
+) {
+  exec(
+    pool,
+    &insert_event(
+      None,
+      "2020-01-15 00:00:00+00"
+    )
+  )
+  .await;
+
+  // Events recorded earlier in the
+  // run are there too.
+  assert!(
+    count(pool, "fetch_events_default")
+      .await
+      > 1
+  );
+
+  let report = repo
+    .maintain_partitions(&plan(
+      false, None
+    ))
+    .await
+    .unwrap();
+
+  assert!(
+    report.created.contains(
+      &"fetch_events_p2020_01"
+        .to_string()
+    ),
+    "{report:?}"
+  );
+  assert_eq!(
+    count(pool, "fetch_events_default")
+      .await,
+    0
+  );
+  assert_eq!(
+    count(
+      pool,
+      "fetch_events_p2020_01"
+    )
+    .await,
+    1
+  );
+
+  // Once there, retention drops it.
+  let march = Utc
+    .with_ymd_and_hms(
+      2020, 3, 1, 0, 0, 0
+    )
+    .unwrap()
+    .timestamp_millis();
+
+  let report = repo
+    .maintain_partitions(&plan(
+      false,
+      Some(march)
+    ))
+    .await
+    .unwrap();
+
+  assert!(
+    report.dropped.contains(
+      &"fetch_events_p2020_01"
+        .to_string()
+    ),
+    "{report:?}"
+  );
+}
+
+pub async fn conversion_retries_invalid_index(
+  repo: &dyn Repo,
+  pool: &PgPool
+) {
+  // A pre-partitioning table.
+  for sql in [
+    "ALTER SEQUENCE \
+     fetch_events_id_seq OWNED BY NONE",
+    "CREATE TABLE fetch_events_plain \
+     (LIKE fetch_events INCLUDING \
+     DEFAULTS)",
+    "DROP TABLE fetch_events",
+    "ALTER TABLE fetch_events_plain \
+     RENAME TO fetch_events"
+  ] {
+    exec(pool, sql).await;
+  }
+
+  // A duplicate fails the unique index
+  // build, leaving it INVALID.
+  let at = "2026-01-01 00:00:00+00";
+
+  exec(
+    pool,
+    &insert_event(Some(1), at)
+  )
+  .await;
+  exec(
+    pool,
+    &insert_event(Some(1), at)
+  )
+  .await;
+
+  assert!(
+    repo
+      .maintain_partitions(&plan(
+        true, None
+      ))
+      .await
+      .is_err()
+  );
+
+  exec(
+    pool,
+    "DELETE FROM fetch_events WHERE \
+     ctid IN (SELECT max(ctid) FROM \
+     fetch_events)"
+  )
+  .await;
+
+  let report = repo
+    .maintain_partitions(&plan(
+      true, None
+    ))
+    .await
+    .unwrap();
+
+  assert_eq!(report.converted, [
+    "fetch_events".to_string()
+  ]);
+  assert_eq!(
+    count(pool, "fetch_events").await,
+    1
+  );
+}
@@ -0,0 +1,359 @@
+//! Payloads, items, the body archive,
+//! payload retention and dataset
+//! versions.
+
+use chrono_tz::Tz;
+use pulsewire_core::ports::repo::{
+  ArchivedPayload,
+  DatasetVersion,
+  PayloadFilter,
+  PruneTarget,
+  Repo
+};
+
+use super::{
+  item,
+  parsed,
+  scope
+};
+
+pub async fn payloads(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  for (at, hash, guids) in [
+    (1_000, "h1", &["a", "b"][..]),
+    (2_000, "h1", &["a", "b"][..]),
+    (3_000, "h2", &["c"][..])
+  ] {
+    repo
+      .insert_payload_with_items(
+        "p1",
+        at,
+        None,
+        None,
+        Some(hash),
+        &parsed(guids),
+        zone
+      )
+      .await
+      .unwrap();
+  }
+
+  let health = repo
+    .health_payloads(0, 10_000, zone)
+    .await
+    .unwrap();
+
+  let mut seen = health
+    .iter()
+    .map(|p| {
+      (
+        p.fetched_at_ms,
+        p.changed,
+        p.item_count
+      )
+    })
+    .collect::<Vec<_>>();
+  seen.sort();
+
+  assert_eq!(seen, [
+    (1_000, true, 2),
+    (2_000, false, 2),
+    (3_000, true, 1)
+  ]);
+
+  assert_eq!(
+    repo
+      .latest_content_hash("p1")
+      .await
+      .unwrap()
+      .as_deref(),
+    Some("h2")
+  );
+  assert_eq!(
+    repo
+      .latest_content_hash("p2")
+      .await
+      .unwrap(),
+    None
+  );
+
+  let archived = ArchivedPayload {
+    content_hash: "h1".to_string(),
+    encoding:     "identity"
+      .to_string(),
+    body:         Some(
+      b"<rss/>".to_vec()
+    ),
+    location:     None,
+    raw_size:     6,
+    stored_size:  6
+  };
+
+  assert!(
+    !repo
+      .touch_archived_payload(
+        "h1", 5, zone
+      )
+      .await
+      .unwrap()
+  );
+
+  repo
+    .insert_archived_payload(
+      &archived, 10, zone
+    )
+    .await
+    .unwrap();
+
+  assert!(
+    repo
+      .touch_archived_payload(
+        "h1", 20, zone
+      )
+      .await
+      .unwrap()
+  );
+
+  let refs = repo
+    .archived_payload_refs(
+      &PayloadFilter {
+        feed_id: Some("p1".to_string()),
+        ..PayloadFilter::default()
+      },
+      0,
+      10,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(
+    refs
+      .iter()
+      .map(|r| r.fetched_at_ms)
+      .collect::<Vec<_>>(),
+    [1_000, 2_000]
+  );
+
+  let items = repo
+    .payload_items(refs[0].id)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    items
+      .iter()
+      .map(|i| i.item.guid.as_deref())
+      .collect::<Vec<_>>(),
+    [Some("a"), Some("b")]
+  );
+
+  let mut updated = items[0].clone();
+  updated.item.title =
+    Some("A".to_string());
+
+  repo
+    .apply_payload_items(
+      refs[0].id,
+      "p1",
+      &[item("d")],
+      &[updated],
+      zone
+    )
+    .await
+    .unwrap();
+
+  let items = repo
+    .payload_items(refs[0].id)
+    .await
+    .unwrap();
+
+  assert_eq!(items.len(), 3);
+  assert_eq!(
+    items[0].item.title.as_deref(),
+    Some("A")
+  );
+
+  // The archive keeps the newest
+  // `last_seen`.
+  assert!(
+    repo
+      .prune_archived_payloads(
+        20, 10, zone
+      )
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  let pruned = repo
+    .prune_archived_payloads(
+      21, 10, zone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(pruned.len(), 1);
+  assert!(pruned[0].body.is_none());
+
+  // Payloads go only once their items
+  // are gone.
+  let keep_one = scope("pay", 1);
+
+  assert_eq!(
+    repo
+      .prune_rows(
+        PruneTarget::Payloads,
+        &keep_one,
+        zone
+      )
+      .await
+      .unwrap(),
+    0
+  );
+  assert_eq!(
+    repo
+      .prune_rows(
+        PruneTarget::Items,
+        &keep_one,
+        zone
+      )
+      .await
+      .unwrap(),
+    5
+  );
+  assert_eq!(
+    repo
+      .prune_rows(
+        PruneTarget::Payloads,
+        &keep_one,
+        zone
+      )
+      .await
+      .unwrap(),
+    2
+  );
+}
+
+pub async fn datasets(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  assert!(
+    repo
+      .latest_dataset_version("ds1")
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  let version = DatasetVersion {
+    feed_id:       "ds1".to_string(),
+    fetched_at_ms: 5_000,
+    content_hash:  "d1".to_string(),
+    body:          b"id\n1\n".to_vec(),
+    row_count:     1,
+    added_count:   1,
+    removed_count: 0,
+    changed_count: 0
+  };
+
+  repo
+    .insert_dataset_version(
+      &version,
+      None,
+      None,
+      &parsed(&["d1@5000:added:1"]),
+      zone
+    )
+    .await
+    .unwrap();
+
+  let latest = repo
+    .latest_dataset_version("ds1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    (
+      latest.fetched_at_ms,
+      latest.content_hash.as_str(),
+      latest.body.as_slice(),
+      latest.added_count
+    ),
+    (5_000, "d1", &b"id\n1\n"[..], 1)
+  );
+
+  // The version's payload and items
+  // are written with it.
+  assert_eq!(
+    repo
+      .latest_content_hash("ds1")
+      .await
+      .unwrap()
+      .as_deref(),
+    Some("d1")
+  );
+
+  let health = repo
+    .health_payloads(5_000, 5_001, zone)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    health
+      .iter()
+      .map(|p| {
+        (
+          p.feed_id.as_str(),
+          p.item_count
+        )
+      })
+      .collect::<Vec<_>>(),
+    [("ds1", 1)]
+  );
+
+  // Even with its body archived and
+  // the feed no longer a dataset, the
+  // version's payload is not
+  // reparsed.
+  repo
+    .insert_archived_payload(
+      &ArchivedPayload {
+        content_hash: "d1".to_string(),
+        encoding:     "identity"
+          .to_string(),
+        body:         Some(
+          version.body.clone()
+        ),
+        location:     None,
+        raw_size:     5,
+        stored_size:  5
+      },
+      5_000,
+      zone
+    )
+    .await
+    .unwrap();
+
+  assert!(
+    repo
+      .archived_payload_refs(
+        &PayloadFilter {
+          feed_id: Some(
+            "ds1".to_string()
+          ),
+          ..PayloadFilter::default()
+        },
+        0,
+        10,
+        zone
+      )
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
@@ -0,0 +1,366 @@
+//! Due-feed selection, state upserts,
+//! fetch events and error feeds.
+
+use chrono_tz::Tz;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::{
+  ErrorKind,
+  FeedPriority
+};
+use pulsewire_core::ports::repo::{
+  PendingEvent,
+  PendingState,
+  PruneTarget,
+  Repo,
+  WriteBatch
+};
+
+use super::{
+  feed,
+  now_ms,
+  scope
+};
+
+pub async fn due_feeds(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  let mut moved = feed(
+    "d-normal",
+    "due",
+    FeedPriority::Normal
+  );
+  moved.url =
+    "http://due.example.com/moved"
+      .to_string();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![moved],
+      10,
+      zone
+    )
+    .await
+    .unwrap();
+
+  let stored = repo
+    .feed("d-normal")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    stored.url,
+    "http://due.example.com/moved"
+  );
+  assert!(stored.tags.is_none());
+  assert!(
+    repo
+      .feed("nope")
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  repo
+    .insert_state(
+      &LinkState::initial(
+        "d-normal".to_string(),
+        300,
+        3600,
+        0.0,
+        5_000
+      ),
+      5_000,
+      zone,
+      false
+    )
+    .await
+    .unwrap();
+
+  repo
+    .mark_feed_error(
+      "d-parked",
+      Some(ErrorKind::Timeout),
+      Some(504),
+      7,
+      9_000,
+      zone
+    )
+    .await
+    .unwrap();
+
+  let due = repo
+    .scheduled_feeds_for_category("due")
+    .await
+    .unwrap();
+
+  // Unordered: the due queue sorts by
+  // priority itself.
+  let mut seen = due
+    .iter()
+    .map(|s| {
+      (
+        s.feed.id.as_str(),
+        s.feed.priority,
+        s.next_action_at_ms
+      )
+    })
+    .collect::<Vec<_>>();
+
+  seen.sort_by_key(|s| s.0);
+
+  assert_eq!(seen, [
+    (
+      "d-high",
+      FeedPriority::High,
+      None
+    ),
+    ("d-low", FeedPriority::Low, None),
+    (
+      "d-normal",
+      FeedPriority::Normal,
+      Some(5_000)
+    )
+  ]);
+
+  let parked =
+    repo.error_feeds().await.unwrap();
+
+  assert_eq!(parked.len(), 1);
+  assert_eq!(
+    parked[0].feed_id,
+    "d-parked"
+  );
+  assert_eq!(parked[0].error_count, 7);
+  assert_eq!(
+    parked[0]
+      .last_error_kind
+      .as_deref(),
+    Some("Timeout")
+  );
+  assert_eq!(
+    parked[0].last_error_status,
+    Some(504)
+  );
+  assert_eq!(
+    parked[0].last_error_at_ms,
+    9_000
+  );
+  assert_eq!(
+    parked[0].note.as_deref(),
+    Some("max-consecutive-errors")
+  );
+}
+
+pub async fn states(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  assert!(
+    repo
+      .latest_state("s1")
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  let mut state = LinkState::initial(
+    "s1".to_string(),
+    300,
+    3600,
+    0.0,
+    1_000
+  );
+
+  repo
+    .insert_state(
+      &state, 1_000, zone, true
+    )
+    .await
+    .unwrap();
+
+  let row = repo
+    .latest_state("s1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.phase,
+    "NeedsInitialGet"
+  );
+  assert_eq!(
+    row.note.as_deref(),
+    Some("initial")
+  );
+
+  state.last_get_status = Some(200);
+  state.last_get_error =
+    Some(ErrorKind::Timeout);
+  state.etag =
+    Some("\"v1\"".to_string());
+  state.backoff_index = 2;
+  state.consecutive_error_count = 3;
+  state.next_action_at_ms = 2_000;
+
+  repo
+    .write_batch(
+      &WriteBatch {
+        events: Vec::new(),
+        states: vec![PendingState {
+          state:          state.clone(),
+          recorded_at_ms: 2_000,
+          record_history: true
+        }]
+      },
+      zone
+    )
+    .await
+    .unwrap();
+
+  let row = repo
+    .latest_state("s1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.last_get_status,
+    Some(200)
+  );
+  assert_eq!(
+    row.last_get_error.as_deref(),
+    Some("Timeout")
+  );
+  assert_eq!(
+    row.etag.as_deref(),
+    Some("\"v1\"")
+  );
+  assert_eq!(row.backoff_index, 2);
+  assert_eq!(
+    row.consecutive_error_count,
+    3
+  );
+  assert_eq!(
+    row.next_action_at_ms,
+    2_000
+  );
+
+  // Two history rows; keeping one per
+  // feed drops the older.
+  assert_eq!(
+    repo
+      .prune_rows(
+        PruneTarget::StateHistory,
+        &scope("state", 1),
+        zone
+      )
+      .await
+      .unwrap(),
+    1
+  );
+}
+
+pub async fn events(
+  repo: &dyn Repo,
+  zone: &Tz
+) {
+  let before = now_ms();
+
+  for method in ["HEAD", "GET"] {
+    repo
+      .insert_event(
+        "s1",
+        method,
+        Some(200),
+        None,
+        Some(40),
+        0,
+        60_000,
+        None,
+        zone
+      )
+      .await
+      .unwrap();
+  }
+
+  repo
+    .write_batch(
+      &WriteBatch {
+        events: vec![PendingEvent {
+          feed_id: "s1".to_string(),
+          event_time_ms: 1_234,
+          method: "GET".to_string(),
+          status: None,
+          error_kind: Some(
+            ErrorKind::Timeout
+          ),
+          latency_ms: None,
+          backoff_index: 1,
+          scheduled_next_action_at_ms:
+            9_999,
+          debug: Some(
+            "slow".to_string()
+          )
+        }],
+        states: Vec::new()
+      },
+      zone
+    )
+    .await
+    .unwrap();
+
+  let recent = repo
+    .recent_fetch_events("s1", 10)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    recent
+      .iter()
+      .map(|e| e.method.as_str())
+      .collect::<Vec<_>>(),
+    ["GET", "GET", "HEAD"]
+  );
+  assert_eq!(
+    recent[0].event_time_ms,
+    1_234
+  );
+  assert_eq!(
+    recent[0].error_kind.as_deref(),
+    Some("Timeout")
+  );
+  assert_eq!(
+    recent[0].debug.as_deref(),
+    Some("slow")
+  );
+  assert!(
+    (before..=now_ms()).contains(
+      &recent[1].event_time_ms
+    )
+  );
+  assert_eq!(
+    repo
+      .recent_fetch_events("s1", 1)
+      .await
+      .unwrap()
+      .len(),
+    1
+  );
+
+  let health = repo
+    .health_events(1_234, 1_235, zone)
+    .await
+    .unwrap();
+
+  assert_eq!(health.len(), 1);
+  assert_eq!(
+    health[0].domain,
+    "state.example.com"
+  );
+  assert_eq!(
+    health[0].latency_ms,
+    None
+  );
+}
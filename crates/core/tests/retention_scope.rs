@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use pulsewire_core::app::partitions::drop_cutoff;
+use pulsewire_core::app::retention::scope_for;
+use pulsewire_core::domain::model::{
+  RetentionConfig,
+  RetentionLimits,
+  RetentionPolicy
+};
+use pulsewire_core::ports::repo::PruneTarget;
+
+const DAY_MS: i64 = 86_400_000;
+
+#[test]
+
+fn category_overrides_win_and_unbounded_skips()
+ {
+  let defaults = RetentionPolicy {
+    fetch_events:  RetentionLimits {
+      max_age_days:      Some(30),
+      max_rows_per_feed: None
+    },
+    state_history:
+      RetentionLimits::default(),
+    payloads:      RetentionLimits {
+      max_age_days:      None,
+      max_rows_per_feed: Some(100)
+    }
+  };
+
+  let mut categories = HashMap::new();
+
+  categories.insert(
+    "news".to_string(),
+    RetentionPolicy {
+      fetch_events: RetentionLimits {
+        max_age_days:      Some(7),
+        max_rows_per_feed: None
+      },
+      ..defaults
+    }
+  );
+
+  let cfg = RetentionConfig {
+    enabled: true,
+    interval_seconds: 3600,
+    batch_size: 500,
+    server_schema: "server".to_string(),
+    defaults,
+    categories
+  };
+
+  let now_ms = 100 * DAY_MS;
+
+  let news = scope_for(
+    &cfg,
+    "news",
+    PruneTarget::FetchEvents,
+    now_ms
+  )
+  .unwrap();
+
+  assert_eq!(
+    news.cutoff_ms,
+    Some(93 * DAY_MS)
+  );
+
+  assert_eq!(news.limit, 500);
+
+  let other = scope_for(
+    &cfg,
+    "other",
+    PruneTarget::FetchEvents,
+    now_ms
+  )
+  .unwrap();
+
+  assert_eq!(
+    other.cutoff_ms,
+    Some(70 * DAY_MS)
+  );
+
+  let items = scope_for(
+    &cfg,
+    "other",
+    PruneTarget::Items,
+    now_ms
+  )
+  .unwrap();
+
+  assert_eq!(items.cutoff_ms, None);
+
+  assert_eq!(
+    items.keep_per_feed,
+    Some(100)
+  );
+
+  assert!(
+    scope_for(
+      &cfg,
+      "news",
+      PruneTarget::StateHistory,
+      now_ms
+    )
+    .is_none()
+  );
+}
+
+#[test]
+
+fn partitions_drop_only_when_every_category_ages_out()
+ {
+  let defaults = RetentionPolicy {
+    fetch_events:  RetentionLimits {
+      max_age_days:      Some(30),
+      max_rows_per_feed: Some(10)
+    },
+    state_history: RetentionLimits {
+      max_age_days:      None,
+      max_rows_per_feed: Some(10)
+    },
+    payloads:
+      RetentionLimits::default()
+  };
+
+  let mut categories = HashMap::new();
+
+  categories.insert(
+    "slow".to_string(),
+    RetentionPolicy {
+      fetch_events: RetentionLimits {
+        max_age_days:      Some(90),
+        max_rows_per_feed: None
+      },
+      ..defaults
+    }
+  );
+
+  let cfg = RetentionConfig {
+    enabled: true,
+    interval_seconds: 3600,
+    batch_size: 500,
+    server_schema: "server".to_string(),
+    defaults,
+    categories
+  };
+
+  let now_ms = 200 * DAY_MS;
+
+  let both = [
+    "news".to_string(),
+    "slow".to_string()
+  ];
+
+  assert_eq!(
+    drop_cutoff(
+      &cfg,
+      &both,
+      PruneTarget::FetchEvents,
+      now_ms
+    ),
+    Some(110 * DAY_MS)
+  );
+
+  // Row caps alone never expire a
+  // whole month.
+  assert_eq!(
+    drop_cutoff(
+      &cfg,
+      &both,
+      PruneTarget::StateHistory,
+      now_ms
+    ),
+    None
+  );
+}
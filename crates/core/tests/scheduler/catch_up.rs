@@ -0,0 +1,131 @@
+//! The catch-up spread survives the
+//! periodic resync.
+
+use std::time::Duration;
+
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::ports::repo::{
+  ControlRepo,
+  Repo
+};
+
+use super::{
+  START_MS,
+  context,
+  load,
+  spawn_loop
+};
+
+#[tokio::test(start_paused = true)]
+
+async fn spread_outlives_the_resync() {
+  let ids = (0..20)
+    .map(|i| format!("f{i:02}"))
+    .collect::<Vec<_>>();
+
+  let urls = ids
+    .iter()
+    .map(|id| {
+      format!("https://a.example/{id}")
+    })
+    .collect::<Vec<_>>();
+
+  let feeds = ids
+    .iter()
+    .zip(&urls)
+    .map(|(id, url)| {
+      (id.as_str(), url.as_str())
+    })
+    .collect::<Vec<_>>();
+
+  let loaded =
+    load("catch-up", &[], &feeds).await;
+
+  let mut cfg = loaded.app.clone();
+
+  cfg.catch_up.enabled = true;
+  cfg.catch_up.window_seconds = 600;
+  cfg.catch_up.min_overdue_seconds = 60;
+
+  let ctx = context(&loaded, cfg, &[
+    "[defaults]",
+    "error_rate = 0.0"
+  ])
+  .await;
+
+  // Every feed is an hour overdue after
+  // downtime.
+  for id in &ids {
+    let mut state = LinkState::initial(
+      id.clone(),
+      300,
+      300,
+      0.0,
+      START_MS - 7_200_000
+    );
+
+    state.next_action_at_ms =
+      START_MS - 3_600_000;
+
+    ctx
+      .repo
+      .insert_state(
+        &state,
+        START_MS - 7_200_000,
+        &ctx.cfg.timezone,
+        false
+      )
+      .await
+      .unwrap();
+  }
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  // Past the first resync at 300 s and
+  // the end of the window.
+  tokio::time::sleep(
+    Duration::from_secs(620)
+  )
+  .await;
+
+  stop.send(true).unwrap();
+
+  handle.await.unwrap().unwrap();
+
+  ctx.writes.flush().await.unwrap();
+
+  let mut first = Vec::new();
+
+  for id in &ids {
+    let events = ctx
+      .repo
+      .recent_fetch_events(id, 100)
+      .await
+      .unwrap();
+
+    first.push(
+      events
+        .last()
+        .unwrap()
+        .event_time_ms
+        - START_MS
+    );
+  }
+
+  first.sort_unstable();
+
+  // One feed per 30 s slot: a herd at
+  // the resync would bunch the second
+  // half together.
+  assert!(
+    *first.last().unwrap() > 570_000,
+    "{first:?}"
+  );
+
+  for pair in first.windows(3) {
+    assert!(
+      pair[2] - pair[0] > 1_000,
+      "{first:?}"
+    );
+  }
+}
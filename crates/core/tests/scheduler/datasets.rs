@@ -0,0 +1,156 @@
+//! Dataset ingest: versions, their
+//! change items and repeated bodies.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use pulsewire_core::app::scheduler::ingest_dataset;
+use pulsewire_core::domain::model::{
+  DatasetConfig,
+  DatasetFormat,
+  GetResult
+};
+use pulsewire_core::infra::memory_repo::MemoryRepo;
+use pulsewire_core::ports::repo::{
+  ContentRepo,
+  Repo
+};
+
+use super::{
+  START_MS,
+  load
+};
+
+#[tokio::test]
+
+async fn returning_bodies_make_new_items()
+ {
+  let loaded = load(
+    "dataset-ingest",
+    &[],
+    &[("d1", "https://a.example/d1")]
+  )
+  .await;
+
+  let cfg = &loaded.app;
+
+  let mut feed =
+    loaded.feeds[0].clone();
+
+  feed.dataset = Some(DatasetConfig {
+    format:       DatasetFormat::Csv,
+    key:          "id".to_string(),
+    records_path: None
+  });
+
+  let repo =
+    Arc::new(MemoryRepo::new());
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![feed.clone()],
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let res = GetResult {
+    status:        Some(200),
+    body:          None,
+    etag:          None,
+    last_modified: None,
+    error:         None,
+    latency_ms:    5
+  };
+
+  let a: &[u8] = b"id,v\n1,a\n";
+  let b: &[u8] = b"id,v\n1,b\n";
+
+  // A, B, A, B, then B unchanged.
+  for (i, (body, hash)) in [
+    (a, "ha"),
+    (b, "hb"),
+    (a, "ha"),
+    (b, "hb"),
+    (b, "hb")
+  ]
+  .into_iter()
+  .enumerate()
+  {
+    ingest_dataset(
+      cfg,
+      &repo,
+      &feed,
+      &res,
+      body,
+      hash,
+      START_MS + i as i64 * 60_000
+    )
+    .await
+    .unwrap();
+  }
+
+  let payloads = repo
+    .health_payloads(
+      START_MS,
+      START_MS + 3_600_000,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  // The baseline has no items; each
+  // later version one changed row.
+  assert_eq!(
+    payloads
+      .iter()
+      .map(|p| p.item_count)
+      .collect::<Vec<_>>(),
+    [0, 1, 1, 1]
+  );
+
+  let mut guids = Vec::new();
+
+  for id in 1..=4 {
+    for stored in repo
+      .payload_items(id)
+      .await
+      .unwrap()
+    {
+      guids.push(
+        stored.item.guid.unwrap()
+      );
+    }
+  }
+
+  assert_eq!(guids.len(), 3);
+  assert_eq!(
+    guids
+      .iter()
+      .collect::<HashSet<_>>()
+      .len(),
+    3,
+    "{guids:?}"
+  );
+
+  let latest = repo
+    .latest_dataset_version("d1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(latest.content_hash, "hb");
+  assert_eq!(
+    latest.fetched_at_ms,
+    START_MS + 180_000
+  );
+}
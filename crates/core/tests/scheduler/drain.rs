@@ -0,0 +1,152 @@
+//! Actions still running at the drain
+//! deadline are abandoned without
+//! stranding their refresh requests or
+//! leases.
+
+use std::time::Duration;
+
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::ports::repo::{
+  ControlRepo,
+  FetchRequestStatus,
+  Repo
+};
+
+use super::{
+  START_MS,
+  context,
+  load,
+  spawn_loop
+};
+
+#[tokio::test(start_paused = true)]
+
+async fn deadline_requeues_requests_and_releases_leases()
+ {
+  let loaded = load("drain", &[], &[
+    ("f1", "https://a.example/f1"),
+    ("f2", "https://a.example/f2")
+  ])
+  .await;
+
+  let mut cfg = loaded.app.clone();
+
+  cfg.shutdown_drain_seconds = 5;
+  cfg.leasing.enabled = true;
+  cfg.leasing.owner = "a".to_string();
+  cfg.leasing.shared_domain_limits =
+    false;
+
+  // Every fetch outlasts the drain.
+  let ctx = context(&loaded, cfg, &[
+    "[defaults]",
+    "error_rate = 0.0",
+    "latency_ms = 600000"
+  ])
+  .await;
+
+  // `f2` is not due, so it only runs
+  // for its refresh request.
+  let mut state = LinkState::initial(
+    "f2".to_string(),
+    300,
+    300,
+    0.0,
+    START_MS
+  );
+
+  state.next_action_at_ms =
+    START_MS + 3_600_000;
+
+  ctx
+    .repo
+    .insert_state(
+      &state,
+      START_MS,
+      &ctx.cfg.timezone,
+      false
+    )
+    .await
+    .unwrap();
+
+  let request = ctx
+    .repo
+    .enqueue_fetch_request(
+      "f2",
+      None,
+      START_MS,
+      &ctx.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  tokio::time::sleep(
+    Duration::from_secs(5)
+  )
+  .await;
+
+  assert_eq!(
+    ctx
+      .repo
+      .fetch_request(request)
+      .await
+      .unwrap()
+      .unwrap()
+      .status,
+    FetchRequestStatus::Running
+  );
+
+  stop.send(true).unwrap();
+
+  let err =
+    handle.await.unwrap().unwrap_err();
+
+  assert!(
+    err.contains("2 action(s)"),
+    "{err}"
+  );
+
+  assert_eq!(
+    ctx
+      .repo
+      .fetch_request(request)
+      .await
+      .unwrap()
+      .unwrap()
+      .status,
+    FetchRequestStatus::Pending
+  );
+
+  let ids = vec![
+    "f1".to_string(),
+    "f2".to_string(),
+  ];
+
+  assert_eq!(
+    ctx
+      .repo
+      .claim_feed_leases("b", &ids, 60)
+      .await
+      .unwrap(),
+    ids
+  );
+
+  // Nothing abandoned is written later.
+  tokio::time::sleep(
+    Duration::from_secs(1200)
+  )
+  .await;
+
+  ctx.writes.flush().await.unwrap();
+
+  assert!(
+    ctx
+      .repo
+      .recent_fetch_events("f1", 10)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
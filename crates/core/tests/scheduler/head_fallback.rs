@@ -0,0 +1,211 @@
+//! A feed whose origin answers HEAD
+//! with 405 moves to conditional GETs,
+//! which get 304s while it is
+//! unchanged, or the same body from an
+//! origin that ignores the validators.
+
+use std::time::Duration;
+
+use pulsewire_core::ports::repo::{
+  ControlRepo,
+  Repo
+};
+
+use super::{
+  START_MS,
+  context,
+  load,
+  spawn_loop
+};
+
+#[tokio::test(start_paused = true)]
+
+async fn unchanged_feed_gets_304s_after_head_fails()
+ {
+  let loaded = load(
+    "head-fallback",
+    &[],
+    &[("f1", "https://a.example/f1")]
+  )
+  .await;
+
+  let ctx = context(
+    &loaded,
+    loaded.app.clone(),
+    &[
+      "[feeds.f1]",
+      "head = false",
+      "change_every_minutes = 0",
+      "error_rate = 0.0"
+    ]
+  )
+  .await;
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  // Four polls five minutes apart.
+  tokio::time::sleep(
+    Duration::from_secs(1000)
+  )
+  .await;
+
+  stop.send(true).unwrap();
+
+  handle.await.unwrap().unwrap();
+
+  ctx.writes.flush().await.unwrap();
+
+  let mut events = ctx
+    .repo
+    .recent_fetch_events("f1", 100)
+    .await
+    .unwrap();
+
+  events.reverse();
+
+  let seen = events
+    .iter()
+    .map(|e| {
+      (e.method.as_str(), e.status)
+    })
+    .collect::<Vec<_>>();
+
+  // A new feed starts with a GET; HEAD
+  // is not retried within the re-test
+  // interval once it has failed.
+  assert_eq!(
+    seen[..3],
+    [
+      ("GET", Some(200)),
+      ("HEAD", Some(405)),
+      ("GET", Some(304))
+    ],
+    "{seen:?}"
+  );
+  assert!(
+    seen[2..].iter().all(|s| {
+      *s == ("GET", Some(304))
+    }),
+    "{seen:?}"
+  );
+
+  let state = ctx
+    .repo
+    .latest_state("f1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert!(
+    state.get_only_since_ms.is_some()
+  );
+  assert!(state.etag.is_some());
+
+  let (_, traffic) =
+    ctx.http.feed_traffic().remove(0);
+
+  assert_eq!(traffic.heads, 1);
+  assert_eq!(
+    traffic.not_modified as usize,
+    seen.len() - 2
+  );
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn resent_body_is_not_a_change_when_304s_never_come()
+ {
+  let loaded = load(
+    "head-no-304",
+    &[],
+    &[("f1", "https://a.example/f1")]
+  )
+  .await;
+
+  let ctx = context(
+    &loaded,
+    loaded.app.clone(),
+    &[
+      "[feeds.f1]",
+      "head = false",
+      "conditional = false",
+      "change_every_minutes = 0",
+      "error_rate = 0.0"
+    ]
+  )
+  .await;
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  tokio::time::sleep(
+    Duration::from_secs(1000)
+  )
+  .await;
+
+  stop.send(true).unwrap();
+
+  handle.await.unwrap().unwrap();
+
+  ctx.writes.flush().await.unwrap();
+
+  let mut events = ctx
+    .repo
+    .recent_fetch_events("f1", 100)
+    .await
+    .unwrap();
+
+  events.reverse();
+
+  let gets = events
+    .iter()
+    .filter(|e| e.method == "GET")
+    .collect::<Vec<_>>();
+
+  assert!(
+    gets.len() >= 3,
+    "{events:?}"
+  );
+  assert!(gets.iter().all(|e| {
+    e.status == Some(200)
+  }));
+  assert_eq!(
+    gets[0].debug.as_deref(),
+    Some("get-body-changed")
+  );
+  assert!(
+    gets[1..].iter().all(|e| {
+      e.debug.as_deref().is_some_and(
+        |note| {
+          note
+            .ends_with("get-unchanged")
+        }
+      )
+    }),
+    "{gets:?}"
+  );
+
+  // Only the first body is stored.
+  let payloads = ctx
+    .repo
+    .health_payloads(
+      START_MS,
+      ctx.clock.now_ms() + 1,
+      &ctx.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(payloads.len(), 1);
+
+  let state = ctx
+    .repo
+    .latest_state("f1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    state.backoff_index as usize,
+    gets.len() - 1
+  );
+}
@@ -0,0 +1,118 @@
+//! A feed's lease is only released
+//! once its new state is written.
+
+use pulsewire_core::app::scheduler::run_leased;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::ports::repo::{
+  PendingState,
+  Repo
+};
+
+use super::{
+  START_MS,
+  context,
+  load
+};
+
+#[tokio::test(start_paused = true)]
+
+async fn lease_is_released_after_buffered_state_is_written()
+ {
+  // A long interval keeps the
+  // snapshot buffered unless the
+  // release flushes it.
+  let loaded = load(
+    "lease",
+    &[
+      "[write_behind]",
+      "enabled = true",
+      "flush_ms = 600000"
+    ],
+    &[("f1", "https://a.example/f1")]
+  )
+  .await;
+
+  let mut cfg = loaded.app.clone();
+
+  cfg.leasing.enabled = true;
+  cfg.leasing.owner = "a".to_string();
+  cfg.leasing.shared_domain_limits =
+    false;
+
+  let ctx =
+    context(&loaded, cfg, &[]).await;
+
+  let feed = loaded.feeds[0].clone();
+
+  let ids = vec![feed.id.clone()];
+
+  assert_eq!(
+    ctx
+      .repo
+      .claim_feed_leases("a", &ids, 60)
+      .await
+      .unwrap(),
+    ids
+  );
+
+  let next_ms = START_MS + 300_000;
+
+  let mut state = LinkState::initial(
+    feed.id.clone(),
+    300,
+    300,
+    0.0,
+    START_MS
+  );
+
+  state.next_action_at_ms = next_ms;
+
+  let work = async {
+    ctx
+      .writes
+      .state(PendingState {
+        state,
+        recorded_at_ms: START_MS,
+        record_history: false
+      })
+      .await?;
+
+    Ok(Some(next_ms))
+  };
+
+  assert_eq!(
+    run_leased(
+      &ctx.cfg,
+      ctx.repo.as_ref(),
+      &ctx.writes,
+      &ctx.controls,
+      &feed,
+      work
+    )
+    .await,
+    Ok(Some(next_ms))
+  );
+
+  // Another replica claims the freed
+  // lease and sees the new schedule.
+  assert_eq!(
+    ctx
+      .repo
+      .claim_feed_leases("b", &ids, 60)
+      .await
+      .unwrap(),
+    ids
+  );
+
+  let row = ctx
+    .repo
+    .latest_state(&feed.id)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.next_action_at_ms,
+    next_ms
+  );
+}
@@ -0,0 +1,253 @@
+//! Scheduler behavior against the
+//! in-memory repo, a virtual clock and
+//! the simulator's scripted origin, on
+//! paused tokio time.
+
+mod catch_up;
+mod datasets;
+mod drain;
+mod head_fallback;
+mod leases;
+mod requests;
+
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use pulsewire_core::app::context::AppContext;
+use pulsewire_core::app::scheduler::Scheduler;
+use pulsewire_core::app::simulate::{
+  Scenario,
+  ScriptedHttp,
+  VirtualClock
+};
+use pulsewire_core::app::write_behind::WriteBehind;
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig
+};
+use pulsewire_core::infra::memory_repo::MemoryRepo;
+use pulsewire_core::infra::random::MutexRng;
+use pulsewire_core::ports::repo::Repo;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// 2026-01-01T00:00:00Z.
+pub const START_MS: i64 =
+  1_767_225_600_000;
+
+pub type Ctx = AppContext<
+  MemoryRepo,
+  ScriptedHttp,
+  VirtualClock,
+  MutexRng
+>;
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// Loads a bundle of `feeds` (id, url)
+/// in category `news`, polled every
+/// five minutes; `extra` is appended
+/// to config.toml.
+pub async fn load(
+  tag: &str,
+  extra: &[&str],
+  feeds: &[(&str, &str)]
+) -> LoadedConfig {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-scheduler-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  let mut config = vec![
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 300",
+    "max_seconds = 300",
+    "jitter_fraction = 0.0",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]",
+  ];
+
+  config.extend_from_slice(extra);
+
+  write(&dir, "config.toml", &config);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 4"
+  ]);
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\"]"
+  ]);
+
+  let feeds = feeds
+    .iter()
+    .flat_map(|(id, url)| {
+      [
+        "[[feeds]]".to_string(),
+        format!("id = \"{id}\""),
+        format!("url = \"{url}\"")
+      ]
+    })
+    .collect::<Vec<_>>();
+
+  fs::write(
+    dir.join("feeds/all.toml"),
+    feeds.join("\n")
+  )
+  .unwrap();
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap();
+
+  fs::remove_dir_all(&dir).unwrap();
+
+  loaded
+}
+
+/// A context over `loaded` with its
+/// feeds and categories stored, the
+/// origin following `scenario`.
+pub async fn context(
+  loaded: &LoadedConfig,
+  cfg: AppConfig,
+  scenario: &[&str]
+) -> Ctx {
+  let clock = Arc::new(
+    VirtualClock::new(START_MS)
+  );
+
+  let repo =
+    Arc::new(MemoryRepo::with_clock(
+      clock.clone()
+    ));
+
+  repo
+    .upsert_categories(
+      loaded
+        .categories
+        .iter()
+        .map(|c| c.name.clone())
+        .collect(),
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      loaded.feeds.clone(),
+      100,
+      &cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let http =
+    Arc::new(ScriptedHttp::new(
+      clock.clone(),
+      i64::MAX,
+      &loaded.feeds,
+      &Scenario::parse(
+        &scenario.join("\n")
+      )
+      .unwrap(),
+      1
+    ));
+
+  let writes = WriteBehind::spawn(
+    repo.clone(),
+    &cfg.write_behind,
+    cfg.timezone
+  );
+
+  AppContext {
+    cfg: Arc::new(cfg),
+    repo,
+    http,
+    clock,
+    rng: Arc::new(MutexRng::seeded(1)),
+    writes,
+    controls: Arc::default(),
+    head_modes: Arc::default()
+  }
+}
+
+/// Runs the `news` loop until the
+/// sender flips to `true`.
+pub fn spawn_loop(
+  ctx: &Ctx
+) -> (
+  watch::Sender<bool>,
+  JoinHandle<Result<(), String>>
+) {
+  let (tx, rx) = watch::channel(false);
+
+  let handle = tokio::spawn(
+    Scheduler::run_forever_category(
+      ctx.clone(),
+      "news".to_string(),
+      rx
+    )
+  );
+
+  (tx, handle)
+}
@@ -0,0 +1,259 @@
+//! `fetch_requests` rows move from
+//! pending through running to done or
+//! failed as the loop serves them.
+
+use std::time::Duration;
+
+use pulsewire_core::ports::repo::{
+  FetchRequest,
+  FetchRequestStatus,
+  Repo
+};
+
+use super::{
+  context,
+  load,
+  spawn_loop
+};
+
+/// Serves one refresh request after the
+/// feed's first poll and returns the
+/// statuses it went through with the
+/// final row.
+async fn serve_request(
+  tag: &str,
+  flush_ms: &str,
+  scenario: &[&str]
+) -> (
+  Vec<FetchRequestStatus>,
+  FetchRequest
+) {
+  let loaded = load(
+    tag,
+    &[
+      "[write_behind]",
+      "enabled = true",
+      flush_ms
+    ],
+    &[("f1", "https://a.example/f1")]
+  )
+  .await;
+
+  let ctx = context(
+    &loaded,
+    loaded.app.clone(),
+    scenario
+  )
+  .await;
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  tokio::time::sleep(
+    Duration::from_secs(10)
+  )
+  .await;
+
+  let now_ms = ctx.clock.now_ms();
+
+  let id = ctx
+    .repo
+    .enqueue_fetch_request(
+      "f1",
+      Some("test"),
+      now_ms,
+      &ctx.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let mut seen = Vec::new();
+
+  let row = loop {
+    let row = ctx
+      .repo
+      .fetch_request(id)
+      .await
+      .unwrap()
+      .unwrap();
+
+    if seen.last() != Some(&row.status)
+    {
+      seen.push(row.status);
+    }
+
+    if matches!(
+      row.status,
+      FetchRequestStatus::Done
+        | FetchRequestStatus::Failed
+    ) {
+      break row;
+    }
+
+    tokio::time::sleep(
+      Duration::from_millis(50)
+    )
+    .await;
+  };
+
+  stop.send(true).unwrap();
+
+  handle.await.unwrap().unwrap();
+
+  (seen, row)
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn refresh_request_runs_to_done()
+{
+  // Written at once, then left
+  // buffered in write-behind.
+  for flush_ms in [
+    "flush_ms = 1",
+    "flush_ms = 600000"
+  ] {
+    let (seen, row) = serve_request(
+      "request-done",
+      flush_ms,
+      &[
+        "[defaults]",
+        "error_rate = 0.0"
+      ]
+    )
+    .await;
+
+    assert_eq!(seen, vec![
+      FetchRequestStatus::Pending,
+      FetchRequestStatus::Running,
+      FetchRequestStatus::Done
+    ]);
+
+    assert_eq!(
+      row.http_status,
+      Some(200)
+    );
+    assert_eq!(row.error, None);
+    assert!(
+      row.started_at_ms.unwrap()
+        <= row.finished_at_ms.unwrap()
+    );
+  }
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn refresh_request_fails_with_the_get_error()
+ {
+  let (seen, row) = serve_request(
+    "request-failed",
+    "flush_ms = 600000",
+    &[
+      "[defaults]",
+      "error_rate = 1.0",
+      "error_status = 503"
+    ]
+  )
+  .await;
+
+  assert_eq!(
+    seen.last(),
+    Some(&FetchRequestStatus::Failed)
+  );
+  assert!(seen.contains(
+    &FetchRequestStatus::Running
+  ));
+  assert_eq!(
+    row.http_status,
+    Some(503)
+  );
+  assert!(row.error.is_some());
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn request_for_a_feed_leased_elsewhere_waits_pending()
+ {
+  let loaded = load(
+    "request-leased",
+    &[],
+    &[("f1", "https://a.example/f1")]
+  )
+  .await;
+
+  let mut cfg = loaded.app.clone();
+
+  cfg.leasing.enabled = true;
+  cfg.leasing.owner = "a".to_string();
+
+  let ctx = context(&loaded, cfg, &[
+    "[defaults]",
+    "error_rate = 0.0"
+  ])
+  .await;
+
+  let ids = vec!["f1".to_string()];
+
+  ctx
+    .repo
+    .claim_feed_leases("b", &ids, 3600)
+    .await
+    .unwrap();
+
+  let id = ctx
+    .repo
+    .enqueue_fetch_request(
+      "f1",
+      None,
+      ctx.clock.now_ms(),
+      &ctx.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  let (stop, handle) = spawn_loop(&ctx);
+
+  tokio::time::sleep(
+    Duration::from_secs(5)
+  )
+  .await;
+
+  let row = ctx
+    .repo
+    .fetch_request(id)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.status,
+    FetchRequestStatus::Pending
+  );
+  assert_eq!(row.started_at_ms, None);
+
+  ctx
+    .repo
+    .release_feed_leases("b", &ids)
+    .await
+    .unwrap();
+
+  tokio::time::sleep(
+    Duration::from_secs(10)
+  )
+  .await;
+
+  let row = ctx
+    .repo
+    .fetch_request(id)
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.status,
+    FetchRequestStatus::Done
+  );
+
+  stop.send(true).unwrap();
+
+  handle.await.unwrap().unwrap();
+}
@@ -0,0 +1,250 @@
+use std::fs;
+use std::path::Path;
+
+use chrono_tz::Tz;
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::infra::database::server_schema_migrations;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+#[tokio::test]
+
+async fn refuses_a_newer_schema() {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-migrations-{}.db",
+      std::process::id()
+    ));
+
+  let zone: Tz = "UTC".parse().unwrap();
+
+  let repo =
+    SqliteRepo::new(&db).await.unwrap();
+
+  let fresh =
+    repo.schema_status().await.unwrap();
+
+  assert_eq!(fresh.current(), 0);
+  assert_eq!(
+    fresh.pending().count() as i64,
+    fresh.latest
+  );
+
+  repo
+    .migrate(&zone, 300)
+    .await
+    .unwrap();
+
+  // A second run is a no-op.
+  repo
+    .migrate(&zone, 300)
+    .await
+    .unwrap();
+
+  let migrated =
+    repo.schema_status().await.unwrap();
+
+  assert_eq!(
+    migrated.current(),
+    migrated.latest
+  );
+  assert_eq!(
+    migrated.pending().count(),
+    0
+  );
+
+  let pool = sqlx::SqlitePool::connect(
+    &format!(
+      "sqlite://{}",
+      db.display()
+    )
+  )
+  .await
+  .unwrap();
+
+  sqlx::query(
+    "INSERT INTO \
+     schema_migrations(version, name, \
+     applied_at) VALUES (?, 'future', \
+     0)"
+  )
+  .bind(migrated.latest + 1)
+  .execute(&pool)
+  .await
+  .unwrap();
+
+  let err = repo
+    .migrate(&zone, 300)
+    .await
+    .unwrap_err();
+
+  assert!(
+    err.contains("newer"),
+    "{err}"
+  );
+
+  assert!(
+    repo
+      .schema_status()
+      .await
+      .unwrap()
+      .check()
+      .is_err()
+  );
+
+  let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+
+async fn lists_the_server_migrations() {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-server-migrations-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(&dir, "config.toml", &[
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 300",
+    "max_seconds = 300",
+    "jitter_fraction = 0.0",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]"
+  ]);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 1"
+  ]);
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\"]"
+  ]);
+
+  write(&dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"f1\"",
+    "url = \"https://a.example/f1\""
+  ]);
+
+  let mut cfg = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await
+  .unwrap()
+  .app;
+
+  cfg.sqlite_path = dir.join("p.db");
+
+  assert!(
+    server_schema_migrations(&cfg)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  let pool = sqlx::SqlitePool::connect(
+    &format!(
+      "sqlite://{}",
+      cfg.sqlite_path.display()
+    )
+  )
+  .await
+  .unwrap();
+
+  // As `pulsewire-server` records them.
+  sqlx::query(
+    "CREATE TABLE \
+     server_schema_migrations(version \
+     INTEGER PRIMARY KEY, name TEXT \
+     NOT NULL, applied_at TEXT NOT \
+     NULL DEFAULT (datetime('now')))"
+  )
+  .execute(&pool)
+  .await
+  .unwrap();
+
+  sqlx::query(
+    "INSERT INTO \
+     server_schema_migrations(version, \
+     name, applied_at) VALUES (1, \
+     'baseline', '2026-01-01 \
+     00:00:00')"
+  )
+  .execute(&pool)
+  .await
+  .unwrap();
+
+  let listed =
+    server_schema_migrations(&cfg)
+      .await
+      .unwrap();
+
+  assert_eq!(listed.len(), 1);
+  assert_eq!(listed[0].version, 1);
+  assert_eq!(
+    listed[0].name,
+    "baseline"
+  );
+  assert_eq!(
+    listed[0].applied_at_ms,
+    Some(1_767_225_600_000)
+  );
+
+  fs::remove_dir_all(&dir).unwrap();
+}
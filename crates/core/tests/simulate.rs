@@ -0,0 +1,321 @@
+use std::fs;
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use pulsewire_core::app::simulate::{
+  self,
+  Scenario,
+  SimReport,
+  SimulationPlan
+};
+use pulsewire_core::domain::model::ArchiveStorage;
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig
+};
+
+fn write(
+  dir: &Path,
+  name: &str,
+  lines: &[&str]
+) {
+  fs::write(
+    dir.join(name),
+    lines.join("\n")
+  )
+  .unwrap();
+}
+
+/// Hourly polling of three feeds on
+/// two domains.
+fn bundle(tag: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-simulate-{tag}-{}",
+      std::process::id()
+    ));
+
+  let _ = fs::remove_dir_all(&dir);
+
+  fs::create_dir_all(dir.join("feeds"))
+    .unwrap();
+
+  fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  let schemas = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ))
+  .join("../fetcher/res/schemas");
+
+  for entry in
+    fs::read_dir(schemas).unwrap()
+  {
+    let path = entry.unwrap().path();
+
+    fs::copy(
+      &path,
+      dir.join("schemas").join(
+        path.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  write(&dir, "config.toml", &[
+    "[app]",
+    "timezone = \"UTC\"",
+    "[database]",
+    "[postgres]",
+    "schema = \"fetcher\"",
+    "[polling]",
+    "default_seconds = 3600",
+    "max_seconds = 3600",
+    "jitter_fraction = 0.1",
+    "[backoff]",
+    "error_base_seconds = 30",
+    "max_error_seconds = 600",
+    "max_consecutive_errors = 3",
+    "[requests]",
+    "user_agent = \"test\"",
+    "[logging]"
+  ]);
+
+  write(&dir, "domains.toml", &[
+    "[[domains]]",
+    "name = \"a.example\"",
+    "max_concurrent_requests = 2"
+  ]);
+
+  write(&dir, "categories.toml", &[
+    "[[categories]]",
+    "name = \"news\"",
+    "domains = [\"a.example\", \
+     \"b.example\"]"
+  ]);
+
+  write(&dir, "feeds/all.toml", &[
+    "[[feeds]]",
+    "id = \"busy\"",
+    "url = \"https://a.example/rss\"",
+    "[[feeds]]",
+    "id = \"still\"",
+    "url = \"https://a.example/still\"",
+    "[[feeds]]",
+    "id = \"down\"",
+    "url = \"https://b.example/rss\""
+  ]);
+
+  dir
+}
+
+const SCENARIO: &[&str] = &[
+  "[defaults]",
+  "change_every_minutes = 30",
+  "error_rate = 0.0",
+  "[domains.\"b.example\"]",
+  "error_rate = 1.0",
+  "[feeds.still]",
+  "change_every_minutes = 0"
+];
+
+fn run(
+  loaded: &LoadedConfig,
+  seed: u64
+) -> SimReport {
+  let scenario = Scenario::parse(
+    &SCENARIO.join("\n")
+  )
+  .unwrap();
+
+  simulate::run(
+    loaded.app.clone(),
+    loaded
+      .categories
+      .iter()
+      .map(|c| c.name.clone())
+      .collect(),
+    loaded.feeds.clone(),
+    &scenario,
+    SimulationPlan {
+      start_ms: 1_767_225_600_000,
+      days: 1,
+      seed
+    }
+  )
+  .unwrap()
+}
+
+#[test]
+
+fn simulation_is_reproducible_and_follows_the_script()
+ {
+  let dir = bundle("run");
+
+  let loaded =
+    tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(ConfigLoader::load(
+        &dir.join("config.toml")
+      ))
+      .unwrap();
+
+  let first = run(&loaded, 7);
+
+  let again = run(&loaded, 7);
+
+  assert_eq!(
+    format!("{:?}", first.feeds),
+    format!("{:?}", again.feeds)
+  );
+
+  assert_eq!(
+    first.end_ms - first.start_ms,
+    86_400_000
+  );
+
+  let feed = |id: &str| {
+    first
+      .feeds
+      .iter()
+      .find(|f| f.feed_id == id)
+      .unwrap()
+      .clone()
+  };
+
+  let busy = feed("busy");
+
+  assert!(busy.changes > 0);
+  assert!(busy.detected > 0);
+  assert!(
+    busy.detected <= busy.changes
+  );
+  assert_eq!(busy.errors, 0);
+  assert!(
+    busy.detection.p50_ms.unwrap()
+      <= busy.detection.max_ms.unwrap()
+  );
+
+  let still = feed("still");
+
+  assert_eq!(still.changes, 0);
+  assert!(still.requests() > 0);
+
+  // Every request fails, so the feed
+  // is parked after three errors.
+  let down = feed("down");
+
+  assert_eq!(down.requests(), 3);
+  assert_eq!(down.errors, 3);
+  assert!(down.parked);
+
+  let domains = first
+    .domains
+    .iter()
+    .map(|d| {
+      (d.domain.as_str(), d.requests)
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(domains, vec![
+    (
+      "a.example",
+      busy.requests()
+        + still.requests()
+    ),
+    ("b.example", 3)
+  ]);
+
+  fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+
+fn scenario_feed_entries_override_their_domain()
+ {
+  let scenario = Scenario::parse(
+    &[
+      "[defaults]",
+      "latency_ms = 100",
+      "[domains.\"a.example\"]",
+      "latency_ms = 900",
+      "error_rate = 0.5",
+      "[feeds.x]",
+      "error_rate = 0.0"
+    ]
+    .join("\n")
+  )
+  .unwrap();
+
+  let dir = bundle("scenario");
+
+  let loaded =
+    tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(ConfigLoader::load(
+        &dir.join("config.toml")
+      ))
+      .unwrap();
+
+  fs::remove_dir_all(&dir).unwrap();
+
+  let mut feed =
+    loaded.feeds[0].clone();
+
+  feed.id = "x".to_string();
+
+  let model = scenario.model_for(&feed);
+
+  assert_eq!(model.latency_ms, 900);
+  assert_eq!(model.error_rate, 0.0);
+
+  assert!(
+    Scenario::parse(
+      "[defaults]\nerror_status = 200"
+    )
+    .is_err()
+  );
+
+  assert!(
+    Scenario::parse(
+      "[feeds.x]\nrate = 1"
+    )
+    .is_err()
+  );
+}
+
+#[test]
+
+fn simulation_leaves_the_archive_directory_alone()
+ {
+  let dir = bundle("archive");
+
+  let mut loaded =
+    tokio::runtime::Runtime::new()
+      .unwrap()
+      .block_on(ConfigLoader::load(
+        &dir.join("config.toml")
+      ))
+      .unwrap();
+
+  let archive = dir.join("archive");
+
+  loaded.app.archive.enabled = true;
+  loaded.app.archive.storage =
+    ArchiveStorage::Directory(
+      archive.clone()
+    );
+
+  assert_eq!(
+    run(&loaded, 3).feeds.len(),
+    3
+  );
+
+  assert!(!archive.exists());
+
+  fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::{
+  FeedConfig,
+  FeedPriority
+};
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+
+fn feed(id: &str) -> FeedConfig {
+  FeedConfig {
+    id:                id.to_string(),
+    url:               format!(
+      "http://example.com/{id}"
+    ),
+    domain:            "example.com"
+      .to_string(),
+    category:          "news"
+      .to_string(),
+    base_poll_seconds: 300,
+    provenance:        None,
+    tags:              None,
+    language:          None,
+    content_type:      None,
+    dataset:           None,
+    priority:
+      FeedPriority::default()
+  }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+
+async fn concurrent_writes_are_serialized()
+ {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-writer-{}.db",
+      std::process::id()
+    ));
+
+  let zone: Tz = "UTC".parse().unwrap();
+
+  let repo = Arc::new(
+    SqliteRepo::new(&db).await.unwrap()
+  );
+
+  repo
+    .migrate(&zone, 300)
+    .await
+    .unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &zone
+    )
+    .await
+    .unwrap();
+
+  let ids: Vec<String> = (0..32)
+    .map(|i| format!("f{i}"))
+    .collect();
+
+  repo
+    .upsert_feeds_bulk(
+      ids
+        .iter()
+        .map(|id| feed(id))
+        .collect(),
+      8,
+      &zone
+    )
+    .await
+    .unwrap();
+
+  let tasks: Vec<_> = ids
+    .iter()
+    .map(|id| {
+      let repo = repo.clone();
+      let id = id.clone();
+
+      tokio::spawn(async move {
+        for at in 0..10 {
+          let state =
+            LinkState::initial(
+              id.clone(),
+              300,
+              3600,
+              0.0,
+              at
+            );
+
+          repo
+            .insert_state(
+              &state, at, &zone, true
+            )
+            .await?;
+        }
+
+        Ok::<_, String>(())
+      })
+    })
+    .collect();
+
+  for task in tasks {
+    task.await.unwrap().unwrap();
+  }
+
+  for id in &ids {
+    let row = repo
+      .latest_state(id)
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(
+      row.next_action_at_ms,
+      9
+    );
+  }
+
+  let _ = std::fs::remove_file(&db);
+}
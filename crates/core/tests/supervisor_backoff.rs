@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use pulsewire_core::app::scheduler::{
+  is_transient_error,
+  restart_delay
+};
+use pulsewire_core::domain::model::{
+  FatalPolicy,
+  SupervisorConfig
+};
+
+#[test]
+
+fn restart_delay_doubles_up_to_cap() {
+  let cfg = SupervisorConfig {
+    restart_base_seconds: 2,
+    restart_max_seconds:  30,
+    max_restarts:         5,
+    on_fatal:
+      FatalPolicy::Exit
+  };
+
+  let delays: Vec<_> = (1..=6)
+    .map(|n| restart_delay(&cfg, n))
+    .collect();
+
+  assert_eq!(
+    delays,
+    [2, 4, 8, 16, 30, 30]
+      .map(Duration::from_secs)
+  );
+}
+
+#[test]
+
+fn lock_and_connection_errors_are_transient()
+ {
+  assert!(is_transient_error(
+    "scheduled_feeds error: error \
+     returned from database: (code: \
+     5) database is locked"
+  ));
+
+  assert!(is_transient_error(
+    "pool timed out while waiting for \
+     an open connection"
+  ));
+
+  assert!(!is_transient_error(
+    "scheduled_feeds error: no such \
+     table: feeds"
+  ));
+}
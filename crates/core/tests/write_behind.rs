@@ -0,0 +1,250 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono_tz::Tz;
+use pulsewire_core::app::write_behind::WriteBehind;
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::{
+  FeedConfig,
+  FeedPriority,
+  WriteBehindConfig
+};
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::{
+  PendingState,
+  Repo
+};
+
+/// A migrated database with feed `f1`.
+async fn setup(
+  tag: &str
+) -> (PathBuf, Arc<SqliteRepo>) {
+  let db =
+    std::env::temp_dir().join(format!(
+      "pulsewire-wb-{tag}-{}.db",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_file(&db);
+
+  let zone: Tz = "UTC".parse().unwrap();
+
+  let repo = Arc::new(
+    SqliteRepo::new(&db).await.unwrap()
+  );
+
+  repo
+    .migrate(&zone, 300)
+    .await
+    .unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &zone
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![FeedConfig {
+        id:                "f1"
+          .to_string(),
+        url:
+          "http://example.com/f1"
+            .to_string(),
+        domain:
+          "example.com".to_string(),
+        category:          "news"
+          .to_string(),
+        base_poll_seconds: 300,
+        provenance:        None,
+        tags:              None,
+        language:          None,
+        content_type:      None,
+        dataset:           None,
+        priority:
+          FeedPriority::default()
+      }],
+      100,
+      &zone
+    )
+    .await
+    .unwrap();
+
+  (db, repo)
+}
+
+fn snapshot(
+  etag: &str
+) -> PendingState {
+  let mut state = LinkState::initial(
+    "f1".to_string(),
+    300,
+    3600,
+    0.0,
+    1_000
+  );
+
+  state.etag = Some(etag.to_string());
+
+  PendingState {
+    state,
+    recorded_at_ms: 1_000,
+    record_history: true
+  }
+}
+
+#[tokio::test]
+
+async fn pending_state_is_served_until_flushed()
+ {
+  let (db, repo) =
+    setup("served").await;
+
+  let zone: Tz = "UTC".parse().unwrap();
+
+  // A long interval keeps the batch
+  // queued until the explicit flush.
+  let writes = WriteBehind::spawn(
+    repo.clone(),
+    &WriteBehindConfig {
+      enabled:    true,
+      batch_size: 500,
+      flush_ms:   60_000
+    },
+    zone
+  );
+
+  writes
+    .state(snapshot("v2"))
+    .await
+    .unwrap();
+
+  assert_eq!(
+    writes
+      .pending_state("f1")
+      .and_then(|s| s.etag),
+    Some("v2".to_string())
+  );
+
+  writes.flush().await.unwrap();
+
+  assert!(
+    writes
+      .pending_state("f1")
+      .is_none()
+  );
+
+  let row = repo
+    .latest_state("f1")
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(
+    row.etag,
+    Some("v2".to_string())
+  );
+
+  let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+
+async fn failed_batch_stays_queued() {
+  let (db, repo) =
+    setup("failed").await;
+
+  let zone: Tz = "UTC".parse().unwrap();
+
+  let writes = WriteBehind::spawn(
+    repo.clone(),
+    &WriteBehindConfig {
+      enabled:    true,
+      batch_size: 500,
+      flush_ms:   60_000
+    },
+    zone
+  );
+
+  // With the history table gone every
+  // write of the batch fails.
+  let side = sqlx::SqlitePool::connect(
+    &format!(
+      "sqlite://{}",
+      db.display()
+    )
+  )
+  .await
+  .unwrap();
+
+  sqlx::query(
+    "ALTER TABLE feed_state_history \
+     RENAME TO feed_state_history_off"
+  )
+  .execute(&side)
+  .await
+  .unwrap();
+
+  writes
+    .state(snapshot("v2"))
+    .await
+    .unwrap();
+
+  let err =
+    writes.flush().await.unwrap_err();
+
+  assert!(
+    err.contains("held"),
+    "{err}"
+  );
+
+  // The failed snapshot is still served
+  // and stays queued.
+  assert_eq!(
+    writes
+      .pending_state("f1")
+      .and_then(|s| s.etag),
+    Some("v2".to_string())
+  );
+
+  assert!(
+    repo
+      .latest_state("f1")
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  sqlx::query(
+    "ALTER TABLE \
+     feed_state_history_off RENAME TO \
+     feed_state_history"
+  )
+  .execute(&side)
+  .await
+  .unwrap();
+
+  side.close().await;
+
+  writes.flush().await.unwrap();
+
+  assert!(
+    writes
+      .pending_state("f1")
+      .is_none()
+  );
+
+  assert_eq!(
+    repo
+      .latest_state("f1")
+      .await
+      .unwrap()
+      .and_then(|s| s.etag),
+    Some("v2".to_string())
+  );
+
+  let _ = std::fs::remove_file(&db);
+}
@@ -1,11 +1,14 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use pulsewire_core::app::context::AppContext;
 use pulsewire_core::app::scheduler::Scheduler;
+use pulsewire_core::app::write_behind::WriteBehind;
 use pulsewire_core::domain::model::{
   AppConfig,
   AppMode,
+  ArchiveStorage,
   FeedConfig,
   SqlDialect
 };
@@ -13,39 +16,64 @@ use pulsewire_core::infra::config::{
   ConfigLoader,
   LoadedConfig
 };
+use pulsewire_core::infra::instance_lock::InstanceLock;
 use pulsewire_core::infra::logging::{
   BootError,
+  flush_logs,
   init_logging
 };
+use pulsewire_core::infra::memory_repo::MemoryRepo;
 use pulsewire_core::infra::random::MutexRng;
 use pulsewire_core::infra::reqwest_http::ReqwestHttp;
 use pulsewire_core::infra::system_clock::SystemClock;
 use pulsewire_core::infra::{
+  admin,
   database,
   metrics
 };
 use pulsewire_core::ports::repo::Repo;
+use tokio::sync::watch;
 use tracing::{
   error,
   info,
   warn
 };
 
+/// How long a second signal waits for
+/// buffered writes before exiting.
+const EXIT_FLUSH_TIMEOUT: Duration =
+  Duration::from_secs(5);
+
 /// Binary entrypoint:
-/// - parses CLI args (`CONFIG_PATH` or
-///   `--ingest-benchmark N`)
+/// - parses CLI args (`CONFIG_PATH`,
+///   `--ingest-benchmark N`,
+///   `--dry-run`)
 /// - loads TOML config bundle
 ///   (app/domains/feeds), initializes
 ///   logging
+/// - takes the `<sqlite.path>.lock`
+///   single-instance lock (SQLite)
 /// - optionally wipes the DB in dev,
-///   opens SQLite + runs migrations
+///   opens SQLite + runs migrations;
+///   `--dry-run` skips all three and
+///   keeps everything in memory,
+///   archived bodies included
 /// - bulk upserts feeds, then either
 ///   runs the ingest benchmark
 ///   (HEAD/GET skipped) or starts the
 ///   scheduler loop with
 ///   HTTP/clock/rng/repo adapters
+/// - on SIGINT/SIGTERM stops
+///   scheduling, drains in-flight
+///   actions for up to `[shutdown]
+///   drain_seconds`, then flushes logs
+///   and exits 0; a second signal
+///   flushes buffered writes (for up to
+///   five seconds) and exits with 130
 /// - exits with `BootError` on fatal
-///   startup/ingest errors
+///   startup/ingest errors, when the
+///   drain deadline is missed or when
+///   buffered writes cannot be flushed
 #[tokio::main]
 
 async fn main() -> Result<(), BootError>
@@ -56,7 +84,7 @@ async fn main() -> Result<(), BootError>
     pick_config_path(args.config_path);
 
   let LoadedConfig {
-    app: app_cfg,
+    app: mut app_cfg,
     feeds,
     categories
   } = ConfigLoader::load(&cfg_path)
@@ -67,6 +95,33 @@ async fn main() -> Result<(), BootError>
 
   init_logging(&app_cfg);
 
+  let (shutdown_tx, shutdown_rx) =
+    watch::channel(false);
+
+  // Taken before the metrics listener
+  // binds and before dev mode may
+  // delete the database file. Losing
+  // it later shuts the fetcher down.
+  let instance_lock =
+    match app_cfg.db_dialect {
+      | SqlDialect::Sqlite
+        if !args.dry_run =>
+      {
+        let mut lock =
+          InstanceLock::acquire(
+            &app_cfg.sqlite_path
+          )
+          .map_err(BootError::Fatal)?;
+
+        lock.spawn_heartbeat(
+          shutdown_tx.clone()
+        );
+
+        Some(lock)
+      }
+      | _ => None
+    };
+
   metrics::init(
     &app_cfg.metrics,
     &categories
@@ -97,10 +152,12 @@ async fn main() -> Result<(), BootError>
 
   info!(feeds = feeds.len(), db = %db_desc, dialect = ?app_cfg.db_dialect, mode = ?app_cfg.mode, "Loaded config");
 
-  if matches!(
-    app_cfg.mode,
-    AppMode::Dev
-  ) {
+  if !args.dry_run
+    && matches!(
+      app_cfg.mode,
+      AppMode::Dev
+    )
+  {
     match app_cfg.db_dialect {
       | SqlDialect::Sqlite => {
         warn!(
@@ -131,12 +188,26 @@ async fn main() -> Result<(), BootError>
     }
   }
 
-  let repo = database::create_repo(
-    app_cfg.db_dialect,
-    &app_cfg
-  )
-  .await
-  .map_err(BootError::Fatal)?;
+  let repo: Arc<dyn Repo> = if args
+    .dry_run
+  {
+    warn!(db = %db_desc, "Dry run: state, events and payloads stay in memory, nothing is written to the database");
+
+    // Archived bodies stay in the
+    // in-memory repo instead of the
+    // archive directory.
+    app_cfg.archive.storage =
+      ArchiveStorage::Database;
+
+    Arc::new(MemoryRepo::new())
+  } else {
+    database::create_repo(
+      app_cfg.db_dialect,
+      &app_cfg
+    )
+    .await
+    .map_err(BootError::Fatal)?
+  };
 
   repo
     .migrate(
@@ -196,6 +267,11 @@ async fn main() -> Result<(), BootError>
         "Ingest benchmark finished"
       );
 
+      if let Some(lock) = instance_lock
+      {
+        lock.release();
+      }
+
       return Ok(());
     }
     | RunMode::Scheduler => {}
@@ -230,31 +306,160 @@ async fn main() -> Result<(), BootError>
 
   let rng = Arc::new(MutexRng::new());
 
+  let writes = WriteBehind::spawn(
+    repo.clone(),
+    &cfg.write_behind,
+    cfg.timezone
+  );
+
   let ctx = AppContext {
-    cfg:   cfg.clone(),
-    repo:  repo.clone(),
-    http:  http.clone(),
-    clock: clock.clone(),
-    rng:   rng.clone()
+    cfg:        cfg.clone(),
+    repo:       repo.clone(),
+    http:       http.clone(),
+    clock:      clock.clone(),
+    rng:        rng.clone(),
+    writes:     writes.clone(),
+    controls:   Arc::default(),
+    head_modes: Arc::default()
   };
 
-  if let Err(e) =
+  let admin_server = admin::init(
+    cfg.clone(),
+    repo.clone(),
+    clock.clone(),
+    ctx.controls.clone()
+  )
+  .await
+  .map_err(BootError::Fatal)?;
+
+  tokio::spawn(watch_signals(
+    shutdown_tx,
+    writes.clone(),
+    cfg.shutdown_drain_seconds
+  ));
+
+  let result =
     Scheduler::run_forever_by_category(
       ctx,
-      category_names
+      category_names,
+      shutdown_rx
     )
-    .await
-  {
+    .await;
+
+  // Drained actions may still have
+  // buffered writes.
+  let flushed = writes.flush().await;
+
+  metrics::shutdown();
+
+  if let Some(server) = admin_server {
+    server.shutdown();
+  }
+
+  if let Some(lock) = instance_lock {
+    lock.release();
+  }
+
+  if let Err(e) = result {
     error!(error = %e, "Fatal error");
 
-    return Err(BootError::Fatal(
-      e.to_string()
-    ));
+    flush_logs();
+
+    return Err(BootError::Fatal(e));
   }
 
+  if let Err(e) = flushed {
+    error!(error = %e, "Final write flush failed");
+
+    flush_logs();
+
+    return Err(BootError::Fatal(e));
+  }
+
+  info!("Shutdown complete");
+
+  flush_logs();
+
   Ok(())
 }
 
+/// Flips `shutdown` on the first
+/// SIGINT/SIGTERM. A second signal
+/// skips the drain, writes what is
+/// buffered within
+/// [`EXIT_FLUSH_TIMEOUT`] and exits
+/// with status 130.
+async fn watch_signals(
+  shutdown: watch::Sender<bool>,
+  writes: WriteBehind,
+  drain_seconds: u64
+) {
+  let signal = next_signal().await;
+
+  info!(
+    signal,
+    drain_seconds,
+    "Shutdown requested, draining \
+     in-flight actions"
+  );
+
+  let _ = shutdown.send(true);
+
+  let signal = next_signal().await;
+
+  warn!(
+    signal,
+    "Second signal received, exiting \
+     without draining"
+  );
+
+  match tokio::time::timeout(
+    EXIT_FLUSH_TIMEOUT,
+    writes.flush()
+  )
+  .await
+  {
+    | Ok(Ok(())) => {}
+    | Ok(Err(e)) => {
+      error!(error = %e, "Final write flush failed");
+    }
+    | Err(_) => {
+      error!(
+        timeout_seconds =
+          EXIT_FLUSH_TIMEOUT.as_secs(),
+        "Final write flush timed out"
+      );
+    }
+  }
+
+  flush_logs();
+
+  std::process::exit(130);
+}
+
+async fn next_signal() -> &'static str {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{
+      SignalKind,
+      signal
+    };
+
+    if let Ok(mut term) =
+      signal(SignalKind::terminate())
+    {
+      return tokio::select! {
+          _ = tokio::signal::ctrl_c() => "SIGINT",
+          _ = term.recv() => "SIGTERM",
+      };
+    }
+  }
+
+  let _ = tokio::signal::ctrl_c().await;
+
+  "SIGINT"
+}
+
 fn pick_config_path(
   arg1: Option<String>
 ) -> PathBuf {
@@ -305,7 +510,10 @@ enum RunMode {
 
 struct Args {
   config_path: Option<String>,
-  mode:        RunMode
+  mode:        RunMode,
+  /// Use an in-memory repo instead of
+  /// the configured database.
+  dry_run:     bool
 }
 
 fn parse_args() -> Args {
@@ -316,6 +524,8 @@ fn parse_args() -> Args {
 
   let mut mode = RunMode::Scheduler;
 
+  let mut dry_run = false;
+
   while let Some(arg) = args.next() {
     if arg == "--ingest-benchmark" {
       if let Some(n) = args.next() {
@@ -328,6 +538,8 @@ fn parse_args() -> Args {
             feeds_to_insert
           };
       }
+    } else if arg == "--dry-run" {
+      dry_run = true;
     } else {
       config_path = Some(arg);
     }
@@ -335,7 +547,8 @@ fn parse_args() -> Args {
 
   Args {
     config_path,
-    mode
+    mode,
+    dry_run
   }
 }
 
@@ -383,5 +596,7 @@ fn benchmark_feed_stream(
         tags: None,
         language: None,
         content_type: None,
+        dataset: None,
+        priority: Default::default(),
     })
 }
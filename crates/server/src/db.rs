@@ -1,6 +1,5 @@
 use std::path::Path;
 
-use sqlx::PgPool;
 use sqlx::postgres::PgPoolOptions;
 
 use crate::app_state::AppState;
@@ -47,8 +46,6 @@ pub async fn connect_db(
             )
           })?;
 
-      ensure_fetcher_tags_column_sqlite(&pool).await?;
-
       Ok(AppState {
         sqlite:            Some(pool),
         postgres:          None,
@@ -104,12 +101,6 @@ pub async fn connect_db(
           ))
         })?;
 
-      ensure_fetcher_tags_column_postgres(
-        &pool,
-        &fetcher_schema
-      )
-      .await?;
-
       Ok(AppState {
         sqlite:            None,
         postgres:          Some(pool),
@@ -396,97 +387,3 @@ pub async fn ensure_default_user(
 
   Ok(())
 }
-
-async fn ensure_fetcher_tags_column_postgres(
-  pool: &PgPool,
-  schema: &str
-) -> Result<(), ConfigError> {
-  let has_column: Option<i32> =
-    sqlx::query_scalar(
-      "SELECT 1 FROM \
-       information_schema.columns \
-       WHERE table_schema = $1 AND \
-       table_name = 'feeds' AND \
-       column_name = 'tags'"
-    )
-    .bind(schema)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column check \
-         failed: {e}"
-      ))
-    })?;
-
-  if has_column.is_some() {
-    return Ok(());
-  }
-
-  let ddl = format!(
-    "ALTER TABLE {}.feeds ADD COLUMN \
-     IF NOT EXISTS tags TEXT[]",
-    quote_ident(schema)
-  );
-
-  sqlx::query(&ddl)
-    .execute(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column add \
-         failed: {e}"
-      ))
-    })?;
-
-  Ok(())
-}
-
-async fn ensure_fetcher_tags_column_sqlite(
-  pool: &sqlx::SqlitePool
-) -> Result<(), ConfigError> {
-  let has_table: Option<i32> = sqlx::query_scalar(
-        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags table check failed: {e}"
-      ))
-    })?;
-
-  if has_table.is_none() {
-    return Ok(());
-  }
-
-  let has_column: Option<i32> = sqlx::query_scalar(
-        r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'tags' LIMIT 1"#
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column check failed: {e}"
-      ))
-    })?;
-
-  if has_column.is_some() {
-    return Ok(());
-  }
-
-  sqlx::query(
-    "ALTER TABLE feeds ADD COLUMN \
-     tags TEXT NULL"
-  )
-  .execute(pool)
-  .await
-  .map_err(|e| {
-    ConfigError::Invalid(format!(
-      "fetcher tags column add \
-       failed: {e}"
-    ))
-  })?;
-
-  Ok(())
-}
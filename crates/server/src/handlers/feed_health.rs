@@ -0,0 +1,200 @@
+use axum::Json;
+use axum::extract::{
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use chrono::NaiveDate;
+
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::db::quote_ident;
+use crate::errors::ServerError;
+use crate::models::{
+  FeedHealthQuery,
+  FeedHealthRow
+};
+
+#[derive(Debug, sqlx::FromRow)]
+struct HealthRow {
+  day:            String,
+  subject:        String,
+  fetches:        i64,
+  successes:      i64,
+  error_counts:   String,
+  p50_latency_ms: Option<i64>,
+  p95_latency_ms: Option<i64>,
+  changes:        i64,
+  items:          i64
+}
+
+fn parse_day(
+  raw: Option<&str>,
+  name: &str
+) -> Result<Option<String>, ServerError>
+{
+  let Some(raw) = raw else {
+    return Ok(None);
+  };
+
+  NaiveDate::parse_from_str(
+    raw, "%Y-%m-%d"
+  )
+  .map(|d| {
+    Some(
+      d.format("%Y-%m-%d").to_string()
+    )
+  })
+  .map_err(|_| {
+    ServerError::new(
+      StatusCode::BAD_REQUEST,
+      format!(
+        "{name} must be YYYY-MM-DD"
+      )
+    )
+  })
+}
+
+/// Daily rollups written by the
+/// fetcher's health task, newest day
+/// first.
+pub async fn list_feed_health(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<FeedHealthQuery>
+) -> Result<
+  Json<Vec<FeedHealthRow>>,
+  ServerError
+> {
+  auth_user_id(&state, &headers)
+    .await?;
+
+  let scope = query
+    .scope
+    .as_deref()
+    .unwrap_or("feed")
+    .to_string();
+
+  if scope != "feed"
+    && scope != "domain"
+  {
+    return Err(ServerError::new(
+      StatusCode::BAD_REQUEST,
+      "scope must be feed or domain"
+    ));
+  }
+
+  let since = parse_day(
+    query.since.as_deref(),
+    "since"
+  )?;
+
+  let until = parse_day(
+    query.until.as_deref(),
+    "until"
+  )?;
+
+  let limit = query
+    .limit
+    .unwrap_or(100)
+    .min(1000) as i64;
+
+  let subject =
+    query.subject.as_deref();
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    let schema = state
+      .fetcher_schema
+      .as_deref()
+      .unwrap_or("fetcher");
+
+    let sql = format!(
+      "SELECT day::text AS day, \
+       subject, fetches, successes, \
+       error_counts::text AS \
+       error_counts, p50_latency_ms, \
+       p95_latency_ms, changes, items \
+       FROM {}.feed_health_daily \
+       WHERE scope = $1 AND ($2::text \
+       IS NULL OR subject = $2) AND \
+       ($3::date IS NULL OR day >= \
+       $3::date) AND ($4::date IS \
+       NULL OR day <= $4::date) ORDER \
+       BY day DESC, subject LIMIT $5",
+      quote_ident(schema)
+    );
+
+    sqlx::query_as::<_, HealthRow>(&sql)
+      .bind(&scope)
+      .bind(subject)
+      .bind(since.as_deref())
+      .bind(until.as_deref())
+      .bind(limit)
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
+            .sqlite
+            .as_ref()
+            .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, HealthRow>(
+      "SELECT day, subject, fetches, \
+       successes, error_counts, \
+       p50_latency_ms, p95_latency_ms, \
+       changes, items FROM \
+       feed_health_daily WHERE scope = \
+       ?1 AND (?2 IS NULL OR subject = \
+       ?2) AND (?3 IS NULL OR day >= \
+       ?3) AND (?4 IS NULL OR day <= \
+       ?4) ORDER BY day DESC, subject \
+       LIMIT ?5"
+    )
+    .bind(&scope)
+    .bind(subject)
+    .bind(since.as_deref())
+    .bind(until.as_deref())
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      format!(
+        "feed health query failed: {e}"
+      )
+    )
+  })?;
+
+  let rows = rows
+    .into_iter()
+    .map(|r| {
+      FeedHealthRow {
+        day:            r.day,
+        scope:          scope.clone(),
+        subject:        r.subject,
+        fetches:        r.fetches,
+        successes:      r.successes,
+        error_counts:
+          serde_json::from_str(
+            &r.error_counts
+          )
+          .unwrap_or_default(),
+        p50_latency_ms: r
+          .p50_latency_ms,
+        p95_latency_ms: r
+          .p95_latency_ms,
+        changes:        r.changes,
+        items:          r.items
+      }
+    })
+    .collect();
+
+  Ok(Json(rows))
+}
@@ -2,9 +2,11 @@ mod auth;
 mod docs;
 mod entries;
 mod favorites;
+mod feed_health;
 mod feeds;
 mod folders;
 mod health;
+mod refresh;
 mod subscriptions;
 mod users;
 
@@ -58,7 +60,10 @@ pub fn router(
         .route("/v1/entries/unread/count", get(entries::unread_count))
         .route("/v1/feeds/unread/counts", get(entries::feed_unread_counts))
         .route("/v1/feeds/counts", get(entries::feed_entry_counts))
+        .route("/v1/feeds/health", get(feed_health::list_feed_health))
         .route("/v1/feeds/:feed_id/entries", get(entries::list_feed_entries))
+        .route("/v1/feeds/:feed_id/refresh", post(refresh::request_refresh))
+        .route("/v1/feeds/:feed_id/refresh/:request_id", get(refresh::refresh_status))
         .route("/v1/entries/read", post(entries::mark_entries_read))
         .route("/v1/entries/read", delete(entries::mark_entries_unread))
         .route("/v1/entries/:item_id/read", get(entries::read_state))
@@ -0,0 +1,288 @@
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::db::quote_ident;
+use crate::errors::ServerError;
+use crate::models::RefreshRequest;
+
+fn db_error(
+  e: sqlx::Error,
+  what: &str
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    format!("{what} failed: {e}")
+  )
+}
+
+fn not_found(
+  what: &str
+) -> ServerError {
+  ServerError::new(
+    StatusCode::NOT_FOUND,
+    format!("{what} not found")
+  )
+}
+
+async fn load_request(
+  state: &AppState,
+  request_id: i64
+) -> Result<
+  Option<RefreshRequest>,
+  ServerError
+> {
+  if let Some(pool) = &state.postgres {
+    let schema = state
+      .fetcher_schema
+      .as_deref()
+      .unwrap_or("fetcher");
+
+    let sql = format!(
+      "SELECT id, feed_id, status, \
+       CAST(EXTRACT(EPOCH FROM \
+       requested_at) * 1000 AS \
+       BIGINT) AS requested_at_ms, \
+       CAST(EXTRACT(EPOCH FROM \
+       started_at) * 1000 AS BIGINT) \
+       AS started_at_ms, \
+       CAST(EXTRACT(EPOCH FROM \
+       finished_at) * 1000 AS BIGINT) \
+       AS finished_at_ms, \
+       http_status::bigint AS \
+       http_status, error FROM \
+       {}.fetch_requests WHERE id = $1",
+      quote_ident(schema)
+    );
+
+    return sqlx::query_as::<
+      _,
+      RefreshRequest
+    >(&sql)
+    .bind(request_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      db_error(
+        e,
+        "refresh request query"
+      )
+    });
+  }
+
+  let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+  sqlx::query_as::<_, RefreshRequest>(
+    "SELECT id, feed_id, status, \
+     requested_at_ms, started_at_ms, \
+     finished_at_ms, http_status, \
+     error FROM fetch_requests WHERE \
+     id = ?1"
+  )
+  .bind(request_id)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    db_error(e, "refresh request query")
+  })
+}
+
+/// Queues a "refresh now" for the
+/// fetcher, reusing a request that is
+/// still pending for the feed. Poll the
+/// returned request for the outcome.
+pub async fn request_refresh(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(feed_id): AxumPath<String>
+) -> Result<
+  (StatusCode, Json<RefreshRequest>),
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let requested_by =
+    format!("user:{user_id}");
+
+  let now = chrono::Utc::now()
+    .timestamp_millis();
+
+  let request_id = if let Some(pool) =
+    &state.postgres
+  {
+    let schema = quote_ident(
+      state
+        .fetcher_schema
+        .as_deref()
+        .unwrap_or("fetcher")
+    );
+
+    let exists: Option<String> =
+      sqlx::query_scalar(&format!(
+        "SELECT id FROM \
+         {schema}.feeds WHERE id = $1"
+      ))
+      .bind(&feed_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(|e| {
+        db_error(e, "feed query")
+      })?;
+
+    if exists.is_none() {
+      return Err(not_found("feed"));
+    }
+
+    let pending: Option<i64> =
+      sqlx::query_scalar(&format!(
+        "SELECT id FROM \
+         {schema}.fetch_requests \
+         WHERE feed_id = $1 AND \
+         status = 'pending' ORDER BY \
+         id LIMIT 1"
+      ))
+      .bind(&feed_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(|e| {
+        db_error(
+          e,
+          "refresh request query"
+        )
+      })?;
+
+    match pending {
+      | Some(id) => id,
+      | None => {
+        sqlx::query_scalar(&format!(
+          "INSERT INTO \
+           {schema}.fetch_requests \
+           (feed_id, requested_by, \
+           requested_at, status) \
+           VALUES ($1, $2, NOW(), \
+           'pending') RETURNING id"
+        ))
+        .bind(&feed_id)
+        .bind(&requested_by)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+          db_error(
+            e,
+            "refresh request insert"
+          )
+        })?
+      }
+    }
+  } else {
+    let pool = state
+            .sqlite
+            .as_ref()
+            .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let exists: Option<String> =
+      sqlx::query_scalar(
+        "SELECT id FROM feeds WHERE \
+         id = ?1"
+      )
+      .bind(&feed_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(|e| {
+        db_error(e, "feed query")
+      })?;
+
+    if exists.is_none() {
+      return Err(not_found("feed"));
+    }
+
+    let pending: Option<i64> =
+      sqlx::query_scalar(
+        "SELECT id FROM \
+         fetch_requests WHERE feed_id \
+         = ?1 AND status = 'pending' \
+         ORDER BY id LIMIT 1"
+      )
+      .bind(&feed_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(|e| {
+        db_error(
+          e,
+          "refresh request query"
+        )
+      })?;
+
+    match pending {
+      | Some(id) => id,
+      | None => {
+        sqlx::query_scalar(
+          "INSERT INTO fetch_requests \
+           (feed_id, requested_by, \
+           requested_at_ms, status) \
+           VALUES (?1, ?2, ?3, \
+           'pending') RETURNING id"
+        )
+        .bind(&feed_id)
+        .bind(&requested_by)
+        .bind(now)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+          db_error(
+            e,
+            "refresh request insert"
+          )
+        })?
+      }
+    }
+  };
+
+  let request =
+    load_request(&state, request_id)
+      .await?
+      .ok_or_else(|| {
+        not_found("refresh request")
+      })?;
+
+  Ok((
+    StatusCode::ACCEPTED,
+    Json(request)
+  ))
+}
+
+pub async fn refresh_status(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath((feed_id, request_id)): AxumPath<(
+    String,
+    i64
+  )>
+) -> Result<
+  Json<RefreshRequest>,
+  ServerError
+> {
+  auth_user_id(&state, &headers)
+    .await?;
+
+  load_request(&state, request_id)
+    .await?
+    .filter(|r| r.feed_id == feed_id)
+    .map(Json)
+    .ok_or_else(|| {
+      not_found("refresh request")
+    })
+}
@@ -283,3 +283,47 @@ pub struct SubscriptionRequest {
 pub struct SubscriptionRow {
   pub feed_id: String
 }
+
+#[derive(Debug, Deserialize)]
+
+pub struct FeedHealthQuery {
+  pub scope:   Option<String>,
+  pub subject: Option<String>,
+  pub since:   Option<String>,
+  pub until:   Option<String>,
+  pub limit:   Option<u32>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct FeedHealthRow {
+  pub day:            String,
+  pub scope:          String,
+  pub subject:        String,
+  pub fetches:        i64,
+  pub successes:      i64,
+  pub error_counts:   serde_json::Value,
+  pub p50_latency_ms: Option<i64>,
+  pub p95_latency_ms: Option<i64>,
+  pub changes:        i64,
+  pub items:          i64
+}
+
+/// A queued "refresh now" and, once the
+/// fetcher has run it, its outcome.
+/// `status` is `pending`, `running`,
+/// `done` or `failed`.
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct RefreshRequest {
+  pub id:              i64,
+  pub feed_id:         String,
+  pub status:          String,
+  pub requested_at_ms: i64,
+  pub started_at_ms:   Option<i64>,
+  pub finished_at_ms:  Option<i64>,
+  pub http_status:     Option<i64>,
+  pub error:           Option<String>
+}
@@ -1,11 +1,18 @@
-use std::path::Path;
+//! Server schema migrations: numbered
+//! steps recorded in
+//! `server_schema_migrations`, apart
+//! from the fetcher's own table so both
+//! can share a database. Each step runs
+//! in a transaction.
 
 use sqlx::{
+  Connection,
+  PgConnection,
   Pool,
   Postgres,
-  Sqlite
+  Sqlite,
+  SqliteConnection
 };
-use tokio::fs;
 
 use crate::app_state::AppState;
 use crate::config::{
@@ -16,20 +23,40 @@ use crate::config::{
 };
 use crate::db::quote_ident;
 
+/// Every migration, oldest first. An
+/// applied step is never edited: new
+/// DDL goes in a new numbered step,
+/// appended here with its files under
+/// `res/sql/*/migrations/` and handled
+/// in both `apply_*`.
+const MIGRATIONS: [(i64, &str); 1] =
+  [(1, "baseline")];
+
+/// The DDL of migration 1, frozen as
+/// shipped.
+const SQLITE_BASELINE: &str =
+  include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/res/sql/sqlite/migrations/\
+     0001_baseline.sql"
+  ));
+
+const POSTGRES_BASELINE: &str =
+  include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/res/sql/postgres/migrations/\
+     0001_baseline.sql"
+  ));
+
+const LOCK_SQL: &str =
+  "SELECT pg_advisory_xact_lock(\
+   hashtext('pulsewire.server_migrations'\
+   ))";
+
 pub async fn apply_server_schema(
   config: &ServerConfig,
-  state: &AppState,
-  config_path: &Path
+  state: &AppState
 ) -> Result<(), ConfigError> {
-  let base_dir = config_path
-    .parent()
-    .ok_or_else(|| {
-    ConfigError::Invalid(
-      "config path has no parent"
-        .into()
-    )
-  })?;
-
   match config.dialect()? {
     | SqlDialect::Sqlite => {
       let pool = state
@@ -42,26 +69,7 @@ pub async fn apply_server_schema(
           )
         })?;
 
-      let schema_path = base_dir
-        .join("sql")
-        .join("sqlite")
-        .join("schema.sql");
-
-      let content = fs::read_to_string(
-        &schema_path
-      )
-      .await
-      .map_err(|_| {
-        ConfigError::Invalid(format!(
-          "schema not found at {}",
-          schema_path.display()
-        ))
-      })?;
-
-      execute_schema_sqlite(
-        pool, &content
-      )
-      .await?;
+      migrate_sqlite(pool).await?;
     }
     | SqlDialect::Postgres => {
       let pool = state
@@ -94,25 +102,8 @@ pub async fn apply_server_schema(
           &pg.fetcher_schema
         )?;
 
-      let schema_path = base_dir
-        .join("sql")
-        .join("postgres")
-        .join("schema.sql");
-
-      let content = fs::read_to_string(
-        &schema_path
-      )
-      .await
-      .map_err(|_| {
-        ConfigError::Invalid(format!(
-          "schema not found at {}",
-          schema_path.display()
-        ))
-      })?;
-
-      execute_schema_postgres(
+      migrate_postgres(
         pool,
-        &content,
         &schema,
         &fetcher_schema
       )
@@ -123,44 +114,165 @@ pub async fn apply_server_schema(
   Ok(())
 }
 
-async fn execute_schema_sqlite(
-  pool: &Pool<Sqlite>,
+fn apply_error(
+  e: impl std::fmt::Display
+) -> ConfigError {
+  ConfigError::Invalid(format!(
+    "schema apply error: {e}"
+  ))
+}
+
+/// Versions from `MIGRATIONS` not yet
+/// in `applied`; refuses a database
+/// migrated by a newer server.
+fn pending(
+  applied: &[i64]
+) -> Result<
+  Vec<(i64, &'static str)>,
+  ConfigError
+> {
+  let latest = MIGRATIONS
+    .iter()
+    .map(|(version, _)| *version)
+    .max()
+    .unwrap_or(0);
+
+  if let Some(current) = applied
+    .iter()
+    .copied()
+    .max()
+    .filter(|v| *v > latest)
+  {
+    return Err(ConfigError::Invalid(
+      format!(
+        "server schema is at version \
+         {current}, newer than this \
+         binary supports ({latest}); \
+         upgrade pulsewire-server"
+      )
+    ));
+  }
+
+  Ok(
+    MIGRATIONS
+      .iter()
+      .filter(|(version, _)| {
+        !applied.contains(version)
+      })
+      .copied()
+      .collect()
+  )
+}
+
+fn statements(
   content: &str
+) -> impl Iterator<Item = &str> {
+  content
+    .split(';')
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+}
+
+async fn migrate_sqlite(
+  pool: &Pool<Sqlite>
 ) -> Result<(), ConfigError> {
-  for stmt in content.split(';') {
-    let trimmed = stmt.trim();
+  sqlx::query(
+    "CREATE TABLE IF NOT EXISTS \
+     server_schema_migrations(version \
+     INTEGER PRIMARY KEY, name TEXT \
+     NOT NULL, applied_at TEXT NOT \
+     NULL DEFAULT (datetime('now')))"
+  )
+  .execute(pool)
+  .await
+  .map_err(apply_error)?;
 
-    if trimmed.is_empty() {
-      continue;
-    }
+  let applied: Vec<i64> =
+    sqlx::query_scalar(
+      "SELECT version FROM \
+       server_schema_migrations"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(apply_error)?;
 
-    sqlx::query(trimmed)
-      .execute(pool)
+  for (version, name) in
+    pending(&applied)?
+  {
+    let mut tx = pool
+      .begin()
       .await
-      .map_err(|e| {
-        ConfigError::Invalid(format!(
-          "schema apply error: {e}"
-        ))
-      })?;
+      .map_err(apply_error)?;
+
+    apply_sqlite(&mut tx, version)
+      .await?;
+
+    sqlx::query(
+      "INSERT INTO \
+       server_schema_migrations(\
+       version, name) VALUES (?1, ?2)"
+    )
+    .bind(version)
+    .bind(name)
+    .execute(&mut *tx)
+    .await
+    .map_err(apply_error)?;
+
+    tx.commit()
+      .await
+      .map_err(apply_error)?;
+
+    tracing::info!(
+      version,
+      name,
+      "applied server migration"
+    );
   }
 
   Ok(())
 }
 
-async fn execute_schema_postgres(
+async fn apply_sqlite(
+  conn: &mut SqliteConnection,
+  version: i64
+) -> Result<(), ConfigError> {
+  match version {
+    | 1 => {
+      for stmt in
+        statements(SQLITE_BASELINE)
+      {
+        sqlx::query(stmt)
+          .execute(&mut *conn)
+          .await
+          .map_err(apply_error)?;
+      }
+
+      ensure_fetcher_tags_column_sqlite(
+        conn
+      )
+      .await
+    }
+    | _ => {
+      Err(ConfigError::Invalid(
+        format!(
+          "unknown server migration \
+           {version}"
+        )
+      ))
+    }
+  }
+}
+
+async fn migrate_postgres(
   pool: &Pool<Postgres>,
-  content: &str,
   schema: &str,
   fetcher_schema: &str
 ) -> Result<(), ConfigError> {
-  let mut conn = pool
-    .acquire()
-    .await
-    .map_err(|e| {
-    ConfigError::Invalid(format!(
-      "schema apply error: {e}"
-    ))
-  })?;
+  let mut conn =
+    pool
+      .acquire()
+      .await
+      .map_err(apply_error)?;
 
   let search_stmt = format!(
     "SET search_path TO {}, {}",
@@ -169,30 +281,216 @@ async fn execute_schema_postgres(
   );
 
   sqlx::query(&search_stmt)
+    .execute(&mut *conn)
+    .await
+    .map_err(apply_error)?;
+
+  sqlx::query(
+    "CREATE TABLE IF NOT EXISTS \
+     server_schema_migrations(version \
+     BIGINT PRIMARY KEY, name TEXT \
+     NOT NULL, applied_at TIMESTAMPTZ \
+     NOT NULL DEFAULT NOW())"
+  )
+  .execute(&mut *conn)
+  .await
+  .map_err(apply_error)?;
+
+  let applied: Vec<i64> =
+    sqlx::query_scalar(
+      "SELECT version FROM \
+       server_schema_migrations"
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(apply_error)?;
+
+  for (version, name) in
+    pending(&applied)?
+  {
+    let mut tx = conn
+      .begin()
+      .await
+      .map_err(apply_error)?;
+
+    // Held until commit, so servers
+    // starting together apply each
+    // step once.
+    sqlx::query(LOCK_SQL)
+      .execute(&mut *tx)
+      .await
+      .map_err(apply_error)?;
+
+    let done: Option<i64> =
+      sqlx::query_scalar(
+        "SELECT version FROM \
+         server_schema_migrations \
+         WHERE version = $1"
+      )
+      .bind(version)
+      .fetch_optional(&mut *tx)
+      .await
+      .map_err(apply_error)?;
+
+    if done.is_some() {
+      continue;
+    }
+
+    apply_postgres(
+      &mut tx,
+      version,
+      fetcher_schema
+    )
+    .await?;
+
+    sqlx::query(
+      "INSERT INTO \
+       server_schema_migrations(\
+       version, name) VALUES ($1, $2)"
+    )
+    .bind(version)
+    .bind(name)
+    .execute(&mut *tx)
+    .await
+    .map_err(apply_error)?;
+
+    tx.commit()
+      .await
+      .map_err(apply_error)?;
+
+    tracing::info!(
+      version,
+      name,
+      "applied server migration"
+    );
+  }
+
+  Ok(())
+}
+
+async fn apply_postgres(
+  conn: &mut PgConnection,
+  version: i64,
+  fetcher_schema: &str
+) -> Result<(), ConfigError> {
+  match version {
+    | 1 => {
+      for stmt in
+        statements(POSTGRES_BASELINE)
+      {
+        sqlx::query(stmt)
+          .execute(&mut *conn)
+          .await
+          .map_err(apply_error)?;
+      }
+
+      ensure_fetcher_tags_column_postgres(
+        conn,
+        fetcher_schema
+      )
+      .await
+    }
+    | _ => {
+      Err(ConfigError::Invalid(
+        format!(
+          "unknown server migration \
+           {version}"
+        )
+      ))
+    }
+  }
+}
+
+async fn ensure_fetcher_tags_column_postgres(
+  conn: &mut PgConnection,
+  schema: &str
+) -> Result<(), ConfigError> {
+  let has_column: Option<i32> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM \
+       information_schema.columns \
+       WHERE table_schema = $1 AND \
+       table_name = 'feeds' AND \
+       column_name = 'tags'"
+    )
+    .bind(schema)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+      ConfigError::Invalid(format!(
+        "fetcher tags column check \
+         failed: {e}"
+      ))
+    })?;
+
+  if has_column.is_some() {
+    return Ok(());
+  }
+
+  let ddl = format!(
+    "ALTER TABLE {}.feeds ADD COLUMN \
+     IF NOT EXISTS tags TEXT[]",
+    quote_ident(schema)
+  );
+
+  sqlx::query(&ddl)
     .execute(&mut *conn)
     .await
     .map_err(|e| {
       ConfigError::Invalid(format!(
-        "schema apply error: {e}"
+        "fetcher tags column add \
+         failed: {e}"
       ))
     })?;
 
-  for stmt in content.split(';') {
-    let trimmed = stmt.trim();
+  Ok(())
+}
 
-    if trimmed.is_empty() {
-      continue;
-    }
+async fn ensure_fetcher_tags_column_sqlite(
+  conn: &mut SqliteConnection
+) -> Result<(), ConfigError> {
+  let has_table: Option<i32> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+      ConfigError::Invalid(format!(
+        "fetcher tags table check failed: {e}"
+      ))
+    })?;
 
-    sqlx::query(trimmed)
-      .execute(&mut *conn)
-      .await
-      .map_err(|e| {
-        ConfigError::Invalid(format!(
-          "schema apply error: {e}"
-        ))
-      })?;
+  if has_table.is_none() {
+    return Ok(());
+  }
+
+  let has_column: Option<i32> = sqlx::query_scalar(
+        r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'tags' LIMIT 1"#
+    )
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+      ConfigError::Invalid(format!(
+        "fetcher tags column check failed: {e}"
+      ))
+    })?;
+
+  if has_column.is_some() {
+    return Ok(());
   }
 
+  sqlx::query(
+    "ALTER TABLE feeds ADD COLUMN \
+     tags TEXT NULL"
+  )
+  .execute(&mut *conn)
+  .await
+  .map_err(|e| {
+    ConfigError::Invalid(format!(
+      "fetcher tags column add \
+       failed: {e}"
+    ))
+  })?;
+
   Ok(())
 }
@@ -58,9 +58,7 @@ pub async fn run()
   .await?;
 
   schema::apply_server_schema(
-    &config,
-    &state,
-    Path::new(&config_path)
+    &config, &state
   )
   .await?;
 